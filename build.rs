@@ -32,12 +32,16 @@ fn main() {
                 "WGL_ARB_extensions_string",
                 "WGL_ARB_framebuffer_sRGB",
                 "WGL_ARB_multisample",
+                "WGL_NV_multisample_coverage",
                 "WGL_ARB_pixel_format",
                 "WGL_ARB_pixel_format_float",
                 "WGL_EXT_create_context_es2_profile",
                 "WGL_EXT_extensions_string",
                 "WGL_EXT_framebuffer_sRGB",
                 "WGL_EXT_swap_control",
+                "WGL_NV_DX_interop",
+                "WGL_NV_DX_interop2",
+                "WGL_NV_swap_group",
             ],
         )
         .write_bindings(gl_generator::StructGenerator, &mut file)
@@ -53,6 +57,7 @@ fn main() {
                 "EGL_KHR_create_context",
                 "EGL_EXT_create_context_robustness",
                 "EGL_KHR_create_context_no_error",
+                "EGL_KHR_context_flush_control",
                 "EGL_KHR_platform_x11",
                 "EGL_KHR_platform_android",
                 "EGL_KHR_platform_wayland",
@@ -62,6 +67,14 @@ fn main() {
                 "EGL_MESA_platform_gbm",
                 "EGL_EXT_platform_wayland",
                 "EGL_EXT_platform_device",
+                "EGL_MESA_platform_surfaceless",
+                "EGL_EXT_buffer_age",
+                "EGL_NV_coverage_sample",
+                "EGL_ANDROID_front_buffer_auto_refresh",
+                "EGL_KHR_mutable_render_buffer",
+                "EGL_KHR_display_reference",
+                "EGL_EXT_surface_SMPTE2086_metadata",
+                "EGL_EXT_surface_CTA861_3_metadata",
             ],
         )
         .write_bindings(gl_generator::StructGenerator, &mut file)
@@ -91,12 +104,17 @@ fn main() {
                 "GLX_ARB_create_context_profile",
                 "GLX_ARB_create_context_robustness",
                 "GLX_ARB_context_flush_control",
+                "GLX_EXT_create_context_es2_profile",
                 "GLX_ARB_fbconfig_float",
                 "GLX_ARB_framebuffer_sRGB",
                 "GLX_EXT_framebuffer_sRGB",
                 "GLX_ARB_multisample",
+                "GLX_NV_multisample_coverage",
+                "GLX_EXT_buffer_age",
                 "GLX_EXT_swap_control",
                 "GLX_SGI_swap_control",
+                "GLX_NV_swap_group",
+                "GLX_OML_sync_control",
             ],
         )
         .write_bindings(gl_generator::StructGenerator, &mut file)
@@ -112,6 +130,7 @@ fn main() {
                 "EGL_KHR_create_context",
                 "EGL_EXT_create_context_robustness",
                 "EGL_KHR_create_context_no_error",
+                "EGL_KHR_context_flush_control",
                 "EGL_KHR_platform_x11",
                 "EGL_KHR_platform_android",
                 "EGL_KHR_platform_wayland",
@@ -121,6 +140,14 @@ fn main() {
                 "EGL_MESA_platform_gbm",
                 "EGL_EXT_platform_wayland",
                 "EGL_EXT_platform_device",
+                "EGL_MESA_platform_surfaceless",
+                "EGL_EXT_buffer_age",
+                "EGL_NV_coverage_sample",
+                "EGL_ANDROID_front_buffer_auto_refresh",
+                "EGL_KHR_mutable_render_buffer",
+                "EGL_KHR_display_reference",
+                "EGL_EXT_surface_SMPTE2086_metadata",
+                "EGL_EXT_surface_CTA861_3_metadata",
             ],
         )
         .write_bindings(gl_generator::StructGenerator, &mut file)
@@ -138,6 +165,7 @@ fn main() {
                 "EGL_KHR_create_context",
                 "EGL_EXT_create_context_robustness",
                 "EGL_KHR_create_context_no_error",
+                "EGL_KHR_context_flush_control",
                 "EGL_KHR_platform_x11",
                 "EGL_KHR_platform_android",
                 "EGL_KHR_platform_wayland",
@@ -147,6 +175,14 @@ fn main() {
                 "EGL_MESA_platform_gbm",
                 "EGL_EXT_platform_wayland",
                 "EGL_EXT_platform_device",
+                "EGL_MESA_platform_surfaceless",
+                "EGL_EXT_buffer_age",
+                "EGL_NV_coverage_sample",
+                "EGL_ANDROID_front_buffer_auto_refresh",
+                "EGL_KHR_mutable_render_buffer",
+                "EGL_KHR_display_reference",
+                "EGL_EXT_surface_SMPTE2086_metadata",
+                "EGL_EXT_surface_CTA861_3_metadata",
             ],
         )
         .write_bindings(gl_generator::StaticStructGenerator, &mut file)
@@ -164,6 +200,7 @@ fn main() {
                 "EGL_KHR_create_context",
                 "EGL_EXT_create_context_robustness",
                 "EGL_KHR_create_context_no_error",
+                "EGL_KHR_context_flush_control",
                 "EGL_KHR_platform_x11",
                 "EGL_KHR_platform_android",
                 "EGL_KHR_platform_wayland",
@@ -173,6 +210,14 @@ fn main() {
                 "EGL_MESA_platform_gbm",
                 "EGL_EXT_platform_wayland",
                 "EGL_EXT_platform_device",
+                "EGL_MESA_platform_surfaceless",
+                "EGL_EXT_buffer_age",
+                "EGL_NV_coverage_sample",
+                "EGL_ANDROID_front_buffer_auto_refresh",
+                "EGL_KHR_mutable_render_buffer",
+                "EGL_KHR_display_reference",
+                "EGL_EXT_surface_SMPTE2086_metadata",
+                "EGL_EXT_surface_CTA861_3_metadata",
             ],
         )
         .write_bindings(gl_generator::StaticStructGenerator, &mut file)
@@ -195,12 +240,46 @@ fn main() {
         )
         .write_bindings(gl_generator::GlobalGenerator, &mut file)
         .unwrap();
+
+        // for the `bundled-egl` feature
+        let mut file = File::create(&dest.join("egl_bindings.rs")).unwrap();
+        Registry::new(
+            Api::Egl,
+            (1, 5),
+            Profile::Core,
+            Fallbacks::All,
+            [
+                "EGL_KHR_create_context",
+                "EGL_EXT_create_context_robustness",
+                "EGL_KHR_create_context_no_error",
+                "EGL_KHR_context_flush_control",
+                "EGL_KHR_platform_x11",
+                "EGL_KHR_platform_android",
+                "EGL_KHR_platform_wayland",
+                "EGL_KHR_platform_gbm",
+                "EGL_EXT_platform_base",
+                "EGL_EXT_platform_x11",
+                "EGL_MESA_platform_gbm",
+                "EGL_EXT_platform_wayland",
+                "EGL_EXT_platform_device",
+                "EGL_MESA_platform_surfaceless",
+                "EGL_EXT_buffer_age",
+                "EGL_NV_coverage_sample",
+                "EGL_ANDROID_front_buffer_auto_refresh",
+                "EGL_KHR_mutable_render_buffer",
+                "EGL_KHR_display_reference",
+                "EGL_EXT_surface_SMPTE2086_metadata",
+                "EGL_EXT_surface_CTA861_3_metadata",
+            ],
+        )
+        .write_bindings(gl_generator::StructGenerator, &mut file)
+        .unwrap();
     }
 
     // TODO: only build the bindings below if we run tests/examples
 
     let mut file = File::create(&dest.join("test_gl_bindings.rs")).unwrap();
-    Registry::new(Api::Gles2, (3, 0), Profile::Core, Fallbacks::All, [])
+    Registry::new(Api::Gles2, (3, 1), Profile::Core, Fallbacks::All, [])
         .write_bindings(gl_generator::StructGenerator, &mut file)
         .unwrap();
 }
@@ -0,0 +1,87 @@
+/* The C interface of glutin, built with the `capi` feature. See the
+ * documentation of the `capi` module for the rules the pointers follow. */
+
+#ifndef GLUTIN_H
+#define GLUTIN_H
+
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum GlutinStatus {
+    GLUTIN_OK = 0,
+    GLUTIN_INVALID_ARGUMENT = 1,
+    GLUTIN_NOT_SUPPORTED = 2,
+    GLUTIN_NO_AVAILABLE_PIXEL_FORMAT = 3,
+    GLUTIN_VERSION_NOT_SUPPORTED = 4,
+    GLUTIN_CONTEXT_LOST = 5,
+    GLUTIN_DISPLAY_LOST = 6,
+    GLUTIN_NOT_CURRENT = 7,
+    GLUTIN_FUNCTION_UNAVAILABLE = 8,
+    GLUTIN_OS_ERROR = 9,
+    GLUTIN_PANIC = 10
+} GlutinStatus;
+
+#define GLUTIN_API_OPENGL 0
+#define GLUTIN_API_OPENGL_ES 1
+
+typedef struct GlutinEventsLoop GlutinEventsLoop;
+typedef struct GlutinBuilder GlutinBuilder;
+typedef struct GlutinContext GlutinContext;
+typedef struct GlutinWindowContext GlutinWindowContext;
+
+typedef struct GlutinEvents {
+    int close_requested;
+    int resized;
+    uint32_t width;
+    uint32_t height;
+} GlutinEvents;
+
+const char *glutin_last_error_message(void);
+
+GlutinStatus glutin_events_loop_new(GlutinEventsLoop **out);
+void glutin_events_loop_free(GlutinEventsLoop *el);
+GlutinStatus glutin_events_loop_poll(GlutinEventsLoop *el,
+                                     const GlutinWindowContext *window,
+                                     GlutinEvents *out);
+
+GlutinStatus glutin_builder_new(GlutinBuilder **out);
+void glutin_builder_free(GlutinBuilder *builder);
+GlutinStatus glutin_builder_set_gl_version(GlutinBuilder *builder, int api,
+                                           uint8_t major, uint8_t minor);
+GlutinStatus glutin_builder_set_vsync(GlutinBuilder *builder, int vsync);
+GlutinStatus glutin_builder_set_multisampling(GlutinBuilder *builder,
+                                              uint16_t samples);
+GlutinStatus glutin_builder_set_depth_stencil(GlutinBuilder *builder,
+                                              uint8_t depth_bits,
+                                              uint8_t stencil_bits);
+GlutinStatus glutin_builder_set_srgb(GlutinBuilder *builder, int srgb);
+
+GlutinStatus glutin_context_new(const GlutinEventsLoop *el,
+                                const GlutinBuilder *builder,
+                                GlutinContext **out);
+void glutin_context_free(GlutinContext *context);
+GlutinStatus glutin_context_make_current(const GlutinContext *context);
+GlutinStatus glutin_context_make_not_current(const GlutinContext *context);
+GlutinStatus glutin_context_get_proc_address(const GlutinContext *context,
+                                             const char *name,
+                                             const void **out);
+
+GlutinStatus glutin_window_context_new(const GlutinEventsLoop *el,
+                                       const GlutinBuilder *builder,
+                                       const char *title, uint32_t width,
+                                       uint32_t height,
+                                       GlutinWindowContext **out);
+void glutin_window_context_free(GlutinWindowContext *context);
+const GlutinContext *
+glutin_window_context_context(const GlutinWindowContext *context);
+GlutinStatus
+glutin_window_context_swap_buffers(const GlutinWindowContext *context);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,122 @@
+//! Squares numbers with a compute shader, from a thread that never creates
+//! an events loop.
+
+extern crate glutin;
+
+mod gl {
+    pub use self::Gles2 as Gl;
+    include!(concat!(env!("OUT_DIR"), "/test_gl_bindings.rs"));
+}
+
+use glutin::ContextTrait;
+use std::{mem, ptr, slice, thread};
+
+const COUNT: usize = 16;
+
+fn main() {
+    let squares = thread::spawn(|| {
+        let context = glutin::ContextBuilder::for_compute()
+            .build_compute()
+            .map_err(|err| err.to_string())?;
+        unsafe { context.make_current().unwrap() };
+        let gl =
+            gl::Gl::load_with(|s| context.get_proc_address(s) as *const _);
+        unsafe { square(&gl, context.get_api()) }
+    })
+    .join()
+    .unwrap();
+
+    match squares {
+        Ok(squares) => println!("squares: {:?}", squares),
+        Err(err) => println!("couldn't dispatch the compute shader: {}", err),
+    }
+}
+
+/// Squares the integers below `COUNT` on the GPU.
+unsafe fn square(gl: &gl::Gl, api: glutin::Api) -> Result<Vec<u32>, String> {
+    if !gl.DispatchCompute.is_loaded() {
+        return Err("compute shaders are unavailable".into());
+    }
+
+    let source = match api {
+        glutin::Api::OpenGlEs => CS_SRC_GLES,
+        _ => CS_SRC_GL,
+    };
+    let shader = gl.CreateShader(gl::COMPUTE_SHADER);
+    gl.ShaderSource(
+        shader,
+        1,
+        [source.as_ptr() as *const _].as_ptr(),
+        ptr::null(),
+    );
+    gl.CompileShader(shader);
+    let mut status = 0;
+    gl.GetShaderiv(shader, gl::COMPILE_STATUS, &mut status);
+    if status == 0 {
+        return Err("the compute shader didn't compile".into());
+    }
+
+    let program = gl.CreateProgram();
+    gl.AttachShader(program, shader);
+    gl.LinkProgram(program);
+    gl.UseProgram(program);
+
+    let values = (0..COUNT as u32).collect::<Vec<_>>();
+    let size = (COUNT * mem::size_of::<u32>()) as gl::types::GLsizeiptr;
+    let mut buffer = 0;
+    gl.GenBuffers(1, &mut buffer);
+    gl.BindBuffer(gl::SHADER_STORAGE_BUFFER, buffer);
+    gl.BufferData(
+        gl::SHADER_STORAGE_BUFFER,
+        size,
+        values.as_ptr() as *const _,
+        gl::DYNAMIC_READ,
+    );
+    gl.BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, buffer);
+
+    gl.DispatchCompute(1, 1, 1);
+    gl.MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+
+    let mapped = gl.MapBufferRange(
+        gl::SHADER_STORAGE_BUFFER,
+        0,
+        size,
+        gl::MAP_READ_BIT,
+    ) as *const u32;
+    if mapped.is_null() {
+        return Err("the buffer couldn't be mapped".into());
+    }
+    let squares = slice::from_raw_parts(mapped, COUNT).to_vec();
+    gl.UnmapBuffer(gl::SHADER_STORAGE_BUFFER);
+    Ok(squares)
+}
+
+const CS_SRC_GLES: &'static [u8] = b"
+#version 310 es
+
+layout(local_size_x = 16) in;
+
+layout(std430, binding = 0) buffer Values {
+    uint values[];
+};
+
+void main() {
+    uint i = gl_GlobalInvocationID.x;
+    values[i] = values[i] * values[i];
+}
+\0";
+
+const CS_SRC_GL: &'static [u8] = b"
+#version 430
+
+layout(local_size_x = 16) in;
+
+layout(std430, binding = 0) buffer Values {
+    uint values[];
+};
+
+void main() {
+    uint i = gl_GlobalInvocationID.x;
+    values[i] = values[i] * values[i];
+}
+\0";
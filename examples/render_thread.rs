@@ -0,0 +1,75 @@
+//! Renders from a dedicated thread while the events loop runs on the main
+//! thread, which forwards the resizes to the rendering thread.
+
+extern crate glutin;
+
+mod support;
+
+use glutin::ContextTrait;
+use std::sync::{mpsc, Arc};
+use std::thread;
+
+enum Message {
+    Resized(glutin::dpi::PhysicalSize),
+    Quit,
+}
+
+fn main() {
+    let mut el = glutin::EventsLoop::new();
+    let wb = glutin::WindowBuilder::new().with_title("Rendering thread");
+    let combined_context = glutin::ContextBuilder::new()
+        .with_vsync(true)
+        .build_combined(wb, &el)
+        .unwrap();
+    let combined_context = Arc::new(combined_context);
+
+    let (sender, receiver) = mpsc::channel();
+    let renderer = {
+        let combined_context = combined_context.clone();
+        thread::spawn(move || {
+            unsafe { combined_context.make_current().unwrap() };
+            let gl = support::load(&combined_context.context());
+
+            let mut frame = 0u32;
+            loop {
+                for message in receiver.try_iter() {
+                    match message {
+                        // resized here, where the context is current
+                        Message::Resized(size) => combined_context.resize(size),
+                        Message::Quit => {
+                            // the context is dropped on the main thread
+                            unsafe { combined_context.make_not_current() }
+                                .unwrap();
+                            return;
+                        }
+                    }
+                }
+
+                let red = (frame % 120) as f32 / 120.0;
+                gl.draw_frame([red, 0.5, 0.7, 1.0]);
+                let _ = combined_context.swap_buffers();
+                frame += 1;
+            }
+        })
+    };
+
+    el.run_forever(|event| {
+        if let glutin::Event::WindowEvent { event, .. } = event {
+            match event {
+                glutin::WindowEvent::CloseRequested => {
+                    let _ = sender.send(Message::Quit);
+                    return glutin::ControlFlow::Break;
+                }
+                glutin::WindowEvent::Resized(logical_size) => {
+                    let dpi_factor = combined_context.get_hidpi_factor();
+                    let size = logical_size.to_physical(dpi_factor);
+                    let _ = sender.send(Message::Resized(size));
+                }
+                _ => (),
+            }
+        }
+        glutin::ControlFlow::Continue
+    });
+
+    renderer.join().unwrap();
+}
@@ -0,0 +1,81 @@
+//! Compares switching between native contexts with switching between
+//! virtual contexts sharing a single native context.
+
+extern crate glutin;
+
+mod support;
+
+use glutin::ContextTrait;
+use std::time::{Duration, Instant};
+
+const CONTEXTS: usize = 4;
+const FRAMES: usize = 500;
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000.0
+        + f64::from(duration.subsec_nanos()) / 1_000_000.0
+}
+
+fn main() {
+    let el = glutin::EventsLoop::new();
+
+    // one native context per plugin
+    let main_context =
+        glutin::ContextBuilder::new().build_headless(&el).unwrap();
+    let contexts = (0..CONTEXTS)
+        .map(|_| {
+            glutin::ContextBuilder::new()
+                .with_shared_lists(&main_context)
+                .build_headless(&el)
+                .unwrap()
+        })
+        .collect::<Vec<_>>();
+    let gls = contexts
+        .iter()
+        .map(|context| {
+            unsafe { context.make_current().unwrap() };
+            support::load(context)
+        })
+        .collect::<Vec<_>>();
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        for (context, gl) in contexts.iter().zip(gls.iter()) {
+            unsafe { context.make_current().unwrap() };
+            gl.draw_frame([1.0, 0.5, 0.7, 1.0]);
+        }
+    }
+    let native = start.elapsed();
+
+    // one virtual context per plugin
+    let host = glutin::VirtualContextHost::new(main_context);
+    let virtual_contexts =
+        (0..CONTEXTS).map(|_| host.create_context()).collect::<Vec<_>>();
+    unsafe { virtual_contexts[0].make_current().unwrap() };
+    let gl = support::load(host.context());
+    host.reset_stats();
+
+    let start = Instant::now();
+    for _ in 0..FRAMES {
+        for context in &virtual_contexts {
+            unsafe { context.make_current().unwrap() };
+            gl.draw_frame([1.0, 0.5, 0.7, 1.0]);
+        }
+    }
+    let virtualized = start.elapsed();
+
+    let switches = CONTEXTS * FRAMES;
+    println!(
+        "native contexts: {} MakeCurrent calls in {:.1} ms",
+        switches,
+        millis(native)
+    );
+    let stats = host.get_stats();
+    println!(
+        "virtual contexts: {} native MakeCurrent calls and {} state \
+         switches in {:.1} ms",
+        stats.native_make_current_calls,
+        stats.state_switches,
+        millis(virtualized)
+    );
+}
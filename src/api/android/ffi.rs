@@ -17,6 +17,15 @@ pub type AAssetManager = raw::c_void;
 /// native_window.h
 pub type ANativeWindow = raw::c_void;
 
+pub const ANATIVEWINDOW_TRANSFORM_IDENTITY: libc::int32_t = 0x00;
+pub const ANATIVEWINDOW_TRANSFORM_ROTATE_90: libc::int32_t = 0x04;
+pub const ANATIVEWINDOW_TRANSFORM_ROTATE_180: libc::int32_t = 0x03;
+pub const ANATIVEWINDOW_TRANSFORM_ROTATE_270: libc::int32_t = 0x07;
+
+/// Only available since API level 26, so it has to be looked up at runtime.
+pub type ANativeWindow_setBuffersTransform =
+    unsafe extern "C" fn(*mut ANativeWindow, libc::int32_t) -> libc::int32_t;
+
 extern "C" {
     pub fn ANativeWindow_getHeight(
         window: *const ANativeWindow,
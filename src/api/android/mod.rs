@@ -9,24 +9,60 @@ use CreationError::{self, OsError};
 use winit;
 
 use Api;
+use BackendInfo;
 use ContextError;
+use DisplayToken;
+use DriverWorkarounds;
 use GlAttributes;
+use Ownership;
+use PbufferLimits;
 use PixelFormat;
 use PixelFormatRequirements;
+use PresentStats;
+use Rect;
+use ReleaseBehavior;
+use RenderBuffer;
+use SurfaceDowngrade;
+use SurfaceInfo;
+use SurfaceVisibility;
+use SwapBehavior;
+use SwapIntervalRange;
 
 use api::egl;
 use api::egl::Context as EglContext;
-use std::cell::Cell;
+use os::android::SurfaceTransform;
+use std::mem;
+use std::os::raw;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
 use winit::os::android::EventsLoopExt;
 
 mod ffi;
 
 struct AndroidContext {
     egl_context: EglContext,
-    stopped: Option<Cell<bool>>,
+    /// Set from the thread of the activity when the window goes away, hence
+    /// atomic. `None` if the context doesn't render to the window of the
+    /// activity.
+    stopped: Option<AtomicBool>,
+    /// The address of the `ANativeWindow` rendered to if it isn't the one of
+    /// the activity, such as the input surface of a video encoder.
+    native_window: Option<usize>,
 }
 
+impl AndroidContext {
+    /// Whether the context renders to a window, rather than to a pbuffer.
+    fn has_window(&self) -> bool {
+        self.stopped.is_some() || self.native_window.is_some()
+    }
+}
+
+/// What was current on a thread before a context was made current by
+/// `Context::make_current_scoped`.
+pub use api::egl::CurrentState;
+
 pub struct Context(Arc<AndroidContext>);
 
 struct AndroidSyncEventHandler(Arc<AndroidContext>);
@@ -42,7 +78,7 @@ impl android_glue::SyncEventHandler for AndroidSyncEventHandler {
             // this situation. Set stop to true to prevent
             // swap_buffer call race conditions.
             android_glue::Event::TermWindow => {
-                self.0.stopped.as_ref().unwrap().set(true);
+                self.0.stopped.as_ref().unwrap().store(true, Ordering::SeqCst);
             }
             _ => {
                 return;
@@ -51,6 +87,27 @@ impl android_glue::SyncEventHandler for AndroidSyncEventHandler {
     }
 }
 
+/// See the docs in the crate root file.
+pub fn probe() -> Vec<BackendInfo> {
+    egl::probe().into_iter().collect()
+}
+
+/// See the docs in the crate root file. Returns the number of contexts
+/// alive if there are any.
+pub unsafe fn reload_symbols() -> Result<(), usize> {
+    let live = egl::live_contexts();
+    if live != 0 {
+        return Err(live);
+    }
+    egl::reload_symbols();
+    Ok(())
+}
+
+/// See the docs in the crate root file.
+pub fn preload() {
+    egl::preload();
+}
+
 impl Context {
     #[inline]
     pub fn new(
@@ -70,7 +127,8 @@ impl Context {
             .and_then(|p| p.finish(native_window as *const _)));
         let ctx = Arc::new(AndroidContext {
             egl_context: context,
-            stopped: Some(Cell::new(false)),
+            stopped: Some(AtomicBool::new(false)),
+            native_window: None,
         });
 
         let handler = Box::new(AndroidSyncEventHandler(ctx.clone()));
@@ -78,7 +136,10 @@ impl Context {
         let context = Context(ctx.clone());
 
         el.set_suspend_callback(Some(Box::new(move |suspended| {
-            ctx.stopped.as_ref().unwrap().set(suspended);
+            ctx.stopped
+                .as_ref()
+                .unwrap()
+                .store(suspended, Ordering::SeqCst);
             if suspended {
                 // Android has stopped the activity or sent it to background.
                 // Release the EGL surface and stop the animation loop.
@@ -104,14 +165,73 @@ impl Context {
         _el: &winit::EventsLoop,
         pf_reqs: &PixelFormatRequirements,
         gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        Self::new_compute(pf_reqs, gl_attr)
+    }
+
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn new_compute(
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
     ) -> Result<Self, CreationError> {
         let gl_attr = gl_attr.clone().map_sharing(|c| &c.0.egl_context);
         let context =
             EglContext::new(pf_reqs, &gl_attr, egl::NativeDisplay::Android)?;
-        let context = context.finish_pbuffer((1, 1))?; // TODO:
+        let context = if pf_reqs.compute_only {
+            context.finish_surfaceless()?
+        } else {
+            context.finish_pbuffer((1, 1))? // TODO:
+        };
+        let ctx = Arc::new(AndroidContext {
+            egl_context: context,
+            stopped: None,
+            native_window: None,
+        });
+        Ok(Context(ctx))
+    }
+
+    /// Builds a context rendering to an `ANativeWindow` that isn't the one
+    /// of the activity, such as one obtained from a `SurfaceTexture` or from
+    /// the input surface of a `MediaCodec` encoder.
+    #[inline]
+    pub unsafe fn new_native_window(
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+        native_window: *const raw::c_void,
+    ) -> Result<Self, CreationError> {
+        if native_window.is_null() {
+            return Err(OsError("the native window is null".to_string()));
+        }
+        let gl_attr = gl_attr.clone().map_sharing(|c| &c.0.egl_context);
+        let context =
+            EglContext::new(pf_reqs, &gl_attr, egl::NativeDisplay::Android)?
+                .finish(native_window as *const _)?;
+        // the window doesn't follow the lifecycle of the activity, so it is
+        // never stopped
+        let ctx = Arc::new(AndroidContext {
+            egl_context: context,
+            stopped: None,
+            native_window: Some(native_window as usize),
+        });
+        Ok(Context(ctx))
+    }
+
+    /// Wraps an EGL context created by another library.
+    #[inline]
+    pub unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: *const raw::c_void,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        let context =
+            EglContext::from_raw(display, context, config, surface, ownership)?;
         let ctx = Arc::new(AndroidContext {
             egl_context: context,
             stopped: None,
+            native_window: None,
         });
         Ok(Context(ctx))
     }
@@ -127,10 +247,20 @@ impl Context {
         unimplemented!()
     }
 
+    #[inline]
+    pub unsafe fn current_state(&self) -> CurrentState {
+        self.0.egl_context.current_state()
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        self.0.egl_context.restore_current(state)
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         if let Some(ref stopped) = self.0.stopped {
-            if stopped.get() {
+            if stopped.load(Ordering::SeqCst) {
                 return Err(ContextError::ContextLost);
             }
         }
@@ -138,9 +268,261 @@ impl Context {
         self.0.egl_context.make_current()
     }
 
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        self.0.egl_context.make_not_current()
+    }
+
     #[inline]
     pub fn resize(&self, _: u32, _: u32) {}
 
+    #[inline]
+    pub fn needs_resize(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn resize_pbuffer(
+        &self,
+        width: u32,
+        height: u32,
+        largest: bool,
+    ) -> Result<(u32, u32), ContextError> {
+        if self.0.has_window() {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        self.0.egl_context.resize_pbuffer((width, height), largest)
+    }
+
+    #[inline]
+    pub fn get_pbuffer_size(&self) -> Option<(u32, u32)> {
+        if self.0.has_window() {
+            return None;
+        }
+        self.0
+            .egl_context
+            .get_surface_info()
+            .ok()
+            .map(|info| (info.width, info.height))
+    }
+
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        self.0.egl_context.get_pbuffer_limits()
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        self.0.egl_context.get_surface_info()
+    }
+
+    #[inline]
+    pub fn get_surface_visibility(
+        &self,
+        _window: &winit::Window,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn is_vsync_forced(
+        &self,
+        _window: &winit::Window,
+    ) -> Result<bool, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        self.0.egl_context.display_token()
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        self.0.egl_context.get_release_behavior()
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        self.0.egl_context.get_driver_workarounds()
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        self.0.egl_context.get_surface_downgrades()
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        self.0.egl_context.destroy()
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        self.0.egl_context.set_swap_behavior(behavior)
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        self.0.egl_context.is_render_buffer_mutable()
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        self.0.egl_context.set_render_buffer(render_buffer)
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        self.0.egl_context.set_hdr_metadata(metadata)
+    }
+
+    #[inline]
+    pub fn join_swap_group(
+        &self,
+        _group: u32,
+        _barrier: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn copy_state_from(
+        &self,
+        _other: &Context,
+        _mask: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_swap_msc(
+        &self,
+        _divisor: u32,
+        _remainder: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        level: i32,
+    ) -> Result<(), ContextError> {
+        self.0.egl_context.set_pbuffer_mipmap_level(level)
+    }
+
+    #[inline]
+    pub fn bind_pbuffer_tex_image(
+        &self,
+        bind: bool,
+    ) -> Result<(), ContextError> {
+        self.0.egl_context.bind_pbuffer_tex_image(bind)
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        self.0.egl_context.get_swap_interval_range()
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        self.0.egl_context.set_swap_interval(interval)
+    }
+
+    #[inline]
+    pub fn set_front_buffer_auto_refresh(
+        &self,
+        enabled: bool,
+    ) -> Result<(), ContextError> {
+        if !self.0.has_window() {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        self.0.egl_context.set_front_buffer_auto_refresh(enabled)
+    }
+
+    pub fn set_buffers_transform(
+        &self,
+        transform: SurfaceTransform,
+    ) -> Result<(), ContextError> {
+        if !self.0.has_window() {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        let symbol = unsafe {
+            libc::dlsym(
+                libc::RTLD_DEFAULT,
+                b"ANativeWindow_setBuffersTransform\0".as_ptr() as *const _,
+            )
+        };
+        if symbol.is_null() {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        let set_buffers_transform: ffi::ANativeWindow_setBuffersTransform =
+            unsafe { mem::transmute(symbol) };
+
+        let native_window = match self.0.native_window {
+            Some(native_window) => native_window as *const raw::c_void,
+            None => unsafe { android_glue::get_native_window() as *const _ },
+        };
+        if native_window.is_null() {
+            return Err(ContextError::ContextLost);
+        }
+
+        let transform = match transform {
+            SurfaceTransform::Identity => {
+                ffi::ANATIVEWINDOW_TRANSFORM_IDENTITY
+            }
+            SurfaceTransform::Rotate90 => {
+                ffi::ANATIVEWINDOW_TRANSFORM_ROTATE_90
+            }
+            SurfaceTransform::Rotate180 => {
+                ffi::ANATIVEWINDOW_TRANSFORM_ROTATE_180
+            }
+            SurfaceTransform::Rotate270 => {
+                ffi::ANATIVEWINDOW_TRANSFORM_ROTATE_270
+            }
+        };
+
+        let ret = unsafe {
+            set_buffers_transform(native_window as *mut _, transform)
+        };
+        if ret != 0 {
+            return Err(ContextError::OsError(format!(
+                "ANativeWindow_setBuffersTransform failed with {}",
+                ret
+            )));
+        }
+
+        Ok(())
+    }
+
     #[inline]
     pub fn is_current(&self) -> bool {
         self.0.egl_context.is_current()
@@ -154,13 +536,33 @@ impl Context {
     #[inline]
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
         if let Some(ref stopped) = self.0.stopped {
-            if stopped.get() {
+            if stopped.load(Ordering::SeqCst) {
                 return Err(ContextError::ContextLost);
             }
         }
         self.0.egl_context.swap_buffers()
     }
 
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        if let Some(ref stopped) = self.0.stopped {
+            if stopped.load(Ordering::SeqCst) {
+                return Err(ContextError::ContextLost);
+            }
+        }
+        self.0.egl_context.swap_buffers_with_damage(rects)
+    }
+
+    /// Waits for about `timeout`, in place of a swap while rendering is
+    /// paused.
+    #[inline]
+    pub fn wait_paused_frame(&self, timeout: Duration) {
+        thread::sleep(timeout)
+    }
+
     #[inline]
     pub fn get_api(&self) -> Api {
         self.0.egl_context.get_api()
@@ -180,4 +582,14 @@ impl Context {
     pub unsafe fn get_egl_display(&self) -> egl::ffi::EGLDisplay {
         self.0.egl_context.get_egl_display()
     }
+
+    #[inline]
+    pub fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        self.0.egl_context.set_label(label)
+    }
+
+    #[inline]
+    pub fn egl_display_tracks_references(&self) -> bool {
+        self.0.egl_context.egl_display_tracks_references()
+    }
 }
@@ -0,0 +1,172 @@
+#![cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+
+use std::os::raw::c_int;
+
+/// The value of an attribute in an `AttribList`.
+pub trait AttribValue {
+    fn into_attrib(self) -> c_int;
+}
+
+impl AttribValue for c_int {
+    #[inline]
+    fn into_attrib(self) -> c_int {
+        self
+    }
+}
+
+/// Enums and bitmasks, which the bindings declare as `u32`.
+impl AttribValue for u32 {
+    #[inline]
+    fn into_attrib(self) -> c_int {
+        self as c_int
+    }
+}
+
+impl AttribValue for u16 {
+    #[inline]
+    fn into_attrib(self) -> c_int {
+        c_int::from(self)
+    }
+}
+
+impl AttribValue for u8 {
+    #[inline]
+    fn into_attrib(self) -> c_int {
+        c_int::from(self)
+    }
+}
+
+/// `TRUE` and `FALSE` are `1` and `0` in EGL, GLX and WGL.
+impl AttribValue for bool {
+    #[inline]
+    fn into_attrib(self) -> c_int {
+        self as c_int
+    }
+}
+
+/// A list of key-value pairs ended by a terminator, as taken by
+/// `eglChooseConfig`, `glXCreateContextAttribsARB`,
+/// `wglChoosePixelFormatARB` and the like.
+///
+/// The keys are the enums of the bindings, and the list can only grow by
+/// pairs, so it is never left with an odd length or without its terminator.
+#[derive(Debug, Clone)]
+pub struct AttribList {
+    /// The pairs, followed by the terminator.
+    list: Vec<c_int>,
+}
+
+impl AttribList {
+    /// Builds an empty list ended by `terminator`, which is `EGL_NONE` for
+    /// EGL and `0` for GLX and WGL.
+    pub fn new(terminator: u32) -> Self {
+        AttribList {
+            list: vec![terminator as c_int],
+        }
+    }
+
+    /// Appends a pair.
+    pub fn push<V: AttribValue>(&mut self, key: u32, value: V) {
+        let terminator = self.list.pop().unwrap();
+        debug_assert!(key as c_int != terminator, "pushed the terminator");
+        self.list.push(key as c_int);
+        self.list.push(value.into_attrib());
+        self.list.push(terminator);
+    }
+
+    /// Removes the pair of `key`, returning whether there was one.
+    pub fn remove(&mut self, key: u32) -> bool {
+        let pairs = (self.list.len() - 1) / 2;
+        match (0..pairs).find(|i| self.list[i * 2] == key as c_int) {
+            Some(i) => {
+                self.list.drain(i * 2..i * 2 + 2);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Returns the pairs, without the terminator.
+    pub fn pairs<'a>(&'a self) -> impl Iterator<Item = (u32, c_int)> + 'a {
+        self.list[..self.list.len() - 1]
+            .chunks(2)
+            .map(|pair| (pair[0] as u32, pair[1]))
+    }
+
+    /// Returns the list, ended by the terminator.
+    pub fn as_ptr(&self) -> *const c_int {
+        self.list.as_ptr()
+    }
+
+    /// Returns the list with its values widened to `EGLAttrib`s, as taken by
+    /// the functions added in EGL 1.5.
+    pub fn to_egl_attribs(&self) -> Vec<isize> {
+        self.list.iter().map(|&value| value as isize).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `EGL_NONE` and `EGL_DONT_CARE`
+    const NONE: u32 = 0x3038;
+    const DONT_CARE: c_int = -1;
+
+    fn check_invariants(attribs: &AttribList) {
+        assert_eq!(attribs.list.len() % 2, 1);
+        assert_eq!(*attribs.list.last().unwrap(), NONE as c_int);
+    }
+
+    #[test]
+    fn terminated_with_odd_length() {
+        let mut attribs = AttribList::new(NONE);
+        check_invariants(&attribs);
+        attribs.push(0x3024, 8u8);
+        check_invariants(&attribs);
+        attribs.push(0x3025, true);
+        check_invariants(&attribs);
+        assert!(attribs.remove(0x3024));
+        check_invariants(&attribs);
+        assert!(!attribs.remove(0x3024));
+        check_invariants(&attribs);
+        assert!(attribs.remove(0x3025));
+        check_invariants(&attribs);
+        assert_eq!(attribs.list, [NONE as c_int]);
+    }
+
+    #[test]
+    fn remove_keeps_pairs() {
+        let mut attribs = AttribList::new(NONE);
+        attribs.push(1, 10);
+        // a value equal to a key mustn't be taken for a key
+        attribs.push(2, 3);
+        attribs.push(3, 30);
+
+        assert!(attribs.remove(2));
+        assert_eq!(attribs.pairs().collect::<Vec<_>>(), [(1, 10), (3, 30)]);
+        assert!(!attribs.remove(10));
+        assert!(attribs.remove(3));
+        assert_eq!(attribs.pairs().collect::<Vec<_>>(), [(1, 10)]);
+    }
+
+    #[test]
+    fn widened_to_egl_attribs() {
+        let mut attribs = AttribList::new(NONE);
+        attribs.push(0x3021, DONT_CARE);
+        attribs.push(0x309D, 0x3089u32);
+        assert_eq!(
+            attribs.to_egl_attribs(),
+            [0x3021, -1, 0x309D, 0x3089, NONE as isize],
+        );
+    }
+}
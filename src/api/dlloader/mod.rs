@@ -1,12 +1,13 @@
 #![cfg(any(
     target_os = "windows",
+    target_os = "macos",
     target_os = "linux",
     target_os = "dragonfly",
     target_os = "freebsd",
     target_os = "netbsd",
     target_os = "openbsd"
 ))]
-use std::ffi::CString;
+use std::ffi::{CString, OsStr};
 use std::ops::{Deref, DerefMut};
 use std::sync::Arc;
 
@@ -25,7 +26,7 @@ pub trait SymTrait {
 }
 
 impl<T: SymTrait> SymWrapper<T> {
-    pub fn new(lib_paths: Vec<&str>) -> Result<Self, ()> {
+    pub fn new<P: AsRef<OsStr>>(lib_paths: Vec<P>) -> Result<Self, ()> {
         for path in lib_paths {
             let lib = Library::new(path);
             if lib.is_ok() {
@@ -0,0 +1,182 @@
+//! `EGL_KHR_debug`, loaded through `eglGetProcAddress` since the generator
+//! of the bindings doesn't know the types of its callback.
+
+use super::{ffi, get_client_extensions, EGL};
+use {ContextError, EglDebugMessage, EglMessageType};
+
+use std::collections::HashSet;
+use std::ffi::CStr;
+use std::mem;
+use std::os::raw::{c_char, c_void};
+use std::sync::{Arc, Mutex};
+
+const OBJECT_CONTEXT_KHR: ffi::egl::types::EGLenum = 0x33B2;
+const OBJECT_SURFACE_KHR: ffi::egl::types::EGLenum = 0x33B3;
+const DEBUG_MSG_CRITICAL_KHR: ffi::egl::types::EGLint = 0x33B9;
+const DEBUG_MSG_ERROR_KHR: ffi::egl::types::EGLint = 0x33BA;
+const DEBUG_MSG_WARN_KHR: ffi::egl::types::EGLint = 0x33BB;
+const DEBUG_MSG_INFO_KHR: ffi::egl::types::EGLint = 0x33BC;
+
+type DebugProc = extern "system" fn(
+    ffi::egl::types::EGLenum,
+    *const c_char,
+    ffi::egl::types::EGLint,
+    *const c_void,
+    *const c_void,
+    *const c_char,
+);
+
+type DebugMessageControlFn = unsafe extern "system" fn(
+    Option<DebugProc>,
+    *const ffi::egl::types::EGLAttrib,
+) -> ffi::egl::types::EGLint;
+
+type LabelObjectFn = unsafe extern "system" fn(
+    ffi::egl::types::EGLDisplay,
+    ffi::egl::types::EGLenum,
+    *const c_void,
+    *const c_void,
+) -> ffi::egl::types::EGLint;
+
+type DebugFn = dyn Fn(&EglDebugMessage) + Send + Sync;
+
+lazy_static! {
+    static ref CALLBACK: Mutex<Option<Arc<DebugFn>>> = Mutex::new(None);
+    /// The labels glutin gave to objects, the only ones that are known to
+    /// point to strings.
+    static ref LABELS: Mutex<HashSet<usize>> = Mutex::new(HashSet::new());
+}
+
+/// Loads `name` if EGL supports `EGL_KHR_debug`.
+unsafe fn load<T: Copy>(name: &str) -> Option<T> {
+    let egl = EGL.as_ref()?;
+    if !get_client_extensions(egl).iter().any(|s| s == "EGL_KHR_debug") {
+        return None;
+    }
+    let p = egl.GetProcAddress(name.as_ptr() as *const _) as *const c_void;
+    if p.is_null() {
+        None
+    } else {
+        Some(mem::transmute_copy::<*const c_void, T>(&p))
+    }
+}
+
+/// Sets the function the messages up to `max_type` are reported to, or
+/// goes back to the default reporting without one.
+pub fn set_callback(
+    max_type: EglMessageType,
+    callback: Option<Arc<DebugFn>>,
+) -> Result<(), ContextError> {
+    let control = unsafe {
+        load::<DebugMessageControlFn>("eglDebugMessageControlKHR\0")
+    }
+    .ok_or(ContextError::FunctionUnavailable)?;
+
+    let types = [
+        (DEBUG_MSG_CRITICAL_KHR, EglMessageType::Critical),
+        (DEBUG_MSG_ERROR_KHR, EglMessageType::Error),
+        (DEBUG_MSG_WARN_KHR, EglMessageType::Warning),
+        (DEBUG_MSG_INFO_KHR, EglMessageType::Info),
+    ];
+    let mut attribs = Vec::with_capacity(types.len() * 2 + 1);
+    for &(attrib, message_type) in &types {
+        attribs.push(attrib as ffi::egl::types::EGLAttrib);
+        attribs.push((message_type <= max_type) as ffi::egl::types::EGLAttrib);
+    }
+    attribs.push(ffi::egl::NONE as ffi::egl::types::EGLAttrib);
+
+    // the callback is in place before EGL can call the trampoline
+    let enabled = callback.is_some();
+    let previous = mem::replace(&mut *CALLBACK.lock().unwrap(), callback);
+    let trampoline = if enabled { Some(report as DebugProc) } else { None };
+    let ret = unsafe { control(trampoline, attribs.as_ptr()) };
+    if ret != ffi::egl::SUCCESS as ffi::egl::types::EGLint {
+        *CALLBACK.lock().unwrap() = previous;
+        return Err(ContextError::OsError(format!(
+            "eglDebugMessageControlKHR failed: 0x{:x}",
+            ret
+        )));
+    }
+    Ok(())
+}
+
+/// Labels `context` and `surface`, unless it is `EGL_NO_SURFACE`, with
+/// `label`, which must stay alive until it is unregistered.
+pub unsafe fn label_objects(
+    display: ffi::egl::types::EGLDisplay,
+    context: ffi::egl::types::EGLContext,
+    surface: ffi::egl::types::EGLSurface,
+    label: &CStr,
+) -> Result<(), ContextError> {
+    let label_object = load::<LabelObjectFn>("eglLabelObjectKHR\0")
+        .ok_or(ContextError::FunctionUnavailable)?;
+
+    let label = label.as_ptr() as *const c_void;
+    LABELS.lock().unwrap().insert(label as usize);
+    let success = ffi::egl::SUCCESS as ffi::egl::types::EGLint;
+    if label_object(display, OBJECT_CONTEXT_KHR, context, label) != success
+        || !surface.is_null()
+            && label_object(display, OBJECT_SURFACE_KHR, surface, label)
+                != success
+    {
+        return Err(ContextError::OsError(
+            "eglLabelObjectKHR failed".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Forgets a label that is about to be freed.
+pub fn unregister_label(label: &CStr) {
+    LABELS.lock().unwrap().remove(&(label.as_ptr() as usize));
+}
+
+/// Reads a label, if it is one of glutin.
+unsafe fn read_label(label: *const c_void) -> Option<String> {
+    let labels = LABELS.lock().unwrap();
+    if label.is_null() || !labels.contains(&(label as usize)) {
+        return None;
+    }
+    let label = CStr::from_ptr(label as *const c_char);
+    Some(label.to_string_lossy().into_owned())
+}
+
+unsafe fn read_string(string: *const c_char) -> String {
+    if string.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(string).to_string_lossy().into_owned()
+    }
+}
+
+extern "system" fn report(
+    error: ffi::egl::types::EGLenum,
+    command: *const c_char,
+    message_type: ffi::egl::types::EGLint,
+    _thread_label: *const c_void,
+    object_label: *const c_void,
+    message: *const c_char,
+) {
+    // cloned, so that the lock isn't held if the callback makes EGL report
+    // something else
+    let callback = match *CALLBACK.lock().unwrap() {
+        Some(ref callback) => callback.clone(),
+        None => return,
+    };
+    let message_type = match message_type {
+        DEBUG_MSG_CRITICAL_KHR => EglMessageType::Critical,
+        DEBUG_MSG_ERROR_KHR => EglMessageType::Error,
+        DEBUG_MSG_WARN_KHR => EglMessageType::Warning,
+        _ => EglMessageType::Info,
+    };
+    let message = unsafe {
+        EglDebugMessage {
+            error,
+            command: read_string(command),
+            message_type,
+            object_label: read_label(object_label),
+            message: read_string(message),
+        }
+    };
+    callback(&message);
+}
@@ -0,0 +1,370 @@
+#![cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+
+use ContextError;
+
+use api::attribs::AttribList;
+
+use super::{ffi, Context, EGL};
+
+use std::marker::PhantomData;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::RawFd;
+use std::{mem, ptr};
+
+use self::ffi::egl::types::{
+    EGLBoolean, EGLClientBuffer, EGLContext, EGLDisplay, EGLImageKHR, EGLenum,
+    EGLint, EGLuint64KHR,
+};
+
+// from `EGL_EXT_image_dma_buf_import` and
+// `EGL_EXT_image_dma_buf_import_modifiers`
+const LINUX_DMA_BUF_EXT: EGLenum = 0x3270;
+const LINUX_DRM_FOURCC_EXT: EGLenum = 0x3271;
+const DMA_BUF_PLANE_FD_EXT: [EGLenum; 4] = [0x3272, 0x3275, 0x3278, 0x3440];
+const DMA_BUF_PLANE_OFFSET_EXT: [EGLenum; 4] = [0x3273, 0x3276, 0x3279, 0x3441];
+const DMA_BUF_PLANE_PITCH_EXT: [EGLenum; 4] = [0x3274, 0x3277, 0x327A, 0x3442];
+const DMA_BUF_PLANE_MODIFIER_LO_EXT: [EGLenum; 4] =
+    [0x3443, 0x3445, 0x3447, 0x3449];
+const DMA_BUF_PLANE_MODIFIER_HI_EXT: [EGLenum; 4] =
+    [0x3444, 0x3446, 0x3448, 0x344A];
+
+/// `DRM_FORMAT_MOD_INVALID`, meaning that the modifier is implicit.
+const DRM_FORMAT_MOD_INVALID: u64 = 0x00ff_ffff_ffff_ffff;
+
+type CreateImageFn = unsafe extern "system" fn(
+    EGLDisplay,
+    EGLContext,
+    EGLenum,
+    EGLClientBuffer,
+    *const EGLint,
+) -> EGLImageKHR;
+type DestroyImageFn =
+    unsafe extern "system" fn(EGLDisplay, EGLImageKHR) -> EGLBoolean;
+type ExportDmaBufImageQueryFn = unsafe extern "system" fn(
+    EGLDisplay,
+    EGLImageKHR,
+    *mut c_int,
+    *mut c_int,
+    *mut EGLuint64KHR,
+) -> EGLBoolean;
+type ExportDmaBufImageFn = unsafe extern "system" fn(
+    EGLDisplay,
+    EGLImageKHR,
+    *mut c_int,
+    *mut EGLint,
+    *mut EGLint,
+) -> EGLBoolean;
+
+/// A plane of a `DmaBuf`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DmaBufPlane {
+    /// The DMA-BUF file descriptor holding the plane.
+    pub fd: RawFd,
+
+    /// The offset of the plane in the buffer, in bytes.
+    pub offset: u32,
+
+    /// The number of bytes between two rows of the plane.
+    pub stride: u32,
+}
+
+/// An image exported as Linux DMA-BUF file descriptors, which can be sent to
+/// another process over a Unix socket and imported there.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DmaBuf {
+    /// The width of the image, in pixels.
+    pub width: u32,
+
+    /// The height of the image, in pixels.
+    pub height: u32,
+
+    /// The DRM fourcc code of the format of the image.
+    pub fourcc: u32,
+
+    /// The DRM format modifier, which describes the tiling and compression
+    /// of the image. `0x00ff_ffff_ffff_ffff` means that it's implicit.
+    pub modifier: u64,
+
+    /// The planes of the image, up to four.
+    pub planes: Vec<DmaBufPlane>,
+}
+
+/// How the texels of an imported image are interpreted, from
+/// `EGL_EXT_image_gl_colorspace`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageColorspace {
+    /// Leaves the choice to the driver, as without the extension.
+    Default,
+
+    /// Samples the texels as they are stored, for example for video frames
+    /// whose shaders convert the colors themselves, so that they aren't
+    /// converted twice.
+    Linear,
+
+    /// Decodes the texels from sRGB when sampling and encodes them when
+    /// rendering, like an sRGB texture.
+    Srgb,
+}
+
+/// Describes how to import a `DmaBuf` as an `EglImage`.
+#[derive(Debug, Clone)]
+pub struct EglImageBuilder<'b> {
+    buf: &'b DmaBuf,
+    colorspace: ImageColorspace,
+}
+
+impl<'b> EglImageBuilder<'b> {
+    /// Initializes a new `EglImageBuilder` importing `buf` with the default
+    /// parameters.
+    pub fn new(buf: &'b DmaBuf) -> Self {
+        EglImageBuilder {
+            buf,
+            colorspace: ImageColorspace::Default,
+        }
+    }
+
+    /// Sets how the texels of the image are interpreted.
+    ///
+    /// Anything but `ImageColorspace::Default` requires
+    /// `EGL_EXT_image_gl_colorspace`.
+    pub fn with_colorspace(mut self, colorspace: ImageColorspace) -> Self {
+        self.colorspace = colorspace;
+        self
+    }
+}
+
+/// An `EGLImage` imported from a `DmaBuf`.
+///
+/// Bind it to a texture with `glEGLImageTargetTexture2DOES` from
+/// `GL_OES_EGL_image`. The image is destroyed when this is dropped, the
+/// texture keeps its contents alive.
+pub struct EglImage<'a> {
+    display: EGLDisplay,
+    image: EGLImageKHR,
+    destroy: DestroyImageFn,
+    marker: PhantomData<&'a ()>,
+}
+
+impl<'a> EglImage<'a> {
+    /// Returns the `EGLImageKHR`.
+    pub fn as_ptr(&self) -> *const c_void {
+        self.image
+    }
+}
+
+impl<'a> Drop for EglImage<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            (self.destroy)(self.display, self.image);
+        }
+    }
+}
+
+/// Loads an extension function of EGL.
+unsafe fn get_proc_address<T: Copy>(name: &str) -> Option<T> {
+    assert_eq!(mem::size_of::<T>(), mem::size_of::<*const c_void>());
+    let egl = EGL.as_ref().unwrap();
+    let p = egl.GetProcAddress(name.as_ptr() as *const _) as *const c_void;
+    if p.is_null() {
+        None
+    } else {
+        Some(mem::transmute_copy::<*const c_void, T>(&p))
+    }
+}
+
+/// Builds the error of an EGL function that failed.
+fn image_error(function: &str) -> ContextError {
+    let egl = EGL.as_ref().unwrap();
+    let err = unsafe { egl.GetError() };
+    ContextError::OsError(format!(
+        "{} failed (eglGetError returned 0x{:x})",
+        function, err
+    ))
+}
+
+impl Context {
+    fn has_extensions(&self, extensions: &[&str]) -> bool {
+        extensions
+            .iter()
+            .all(|ext| self.extensions.iter().any(|s| s == ext))
+    }
+
+    /// See the docs of `ContextExt::export_dmabuf`.
+    pub unsafe fn export_dmabuf(
+        &self,
+        texture: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<DmaBuf, ContextError> {
+        if !self.has_extensions(&[
+            "EGL_KHR_image_base",
+            "EGL_KHR_gl_texture_2D_image",
+            "EGL_MESA_image_dma_buf_export",
+        ]) {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        let (create, destroy, query, export) = match (
+            get_proc_address::<CreateImageFn>("eglCreateImageKHR\0"),
+            get_proc_address::<DestroyImageFn>("eglDestroyImageKHR\0"),
+            get_proc_address::<ExportDmaBufImageQueryFn>(
+                "eglExportDMABUFImageQueryMESA\0",
+            ),
+            get_proc_address::<ExportDmaBufImageFn>(
+                "eglExportDMABUFImageMESA\0",
+            ),
+        ) {
+            (Some(c), Some(d), Some(q), Some(e)) => (c, d, q, e),
+            _ => return Err(ContextError::FunctionUnavailable),
+        };
+
+        let image = create(
+            self.display,
+            self.context,
+            ffi::egl::GL_TEXTURE_2D,
+            texture as usize as EGLClientBuffer,
+            AttribList::new(ffi::egl::NONE).as_ptr(),
+        );
+        if image.is_null() {
+            return Err(image_error("eglCreateImageKHR"));
+        }
+        let image = EglImage {
+            display: self.display,
+            image,
+            destroy,
+            marker: PhantomData,
+        };
+
+        let mut fourcc = 0;
+        let mut num_planes = 0;
+        let mut modifier = 0;
+        if query(
+            self.display,
+            image.image,
+            &mut fourcc,
+            &mut num_planes,
+            &mut modifier,
+        ) == 0
+        {
+            return Err(image_error("eglExportDMABUFImageQueryMESA"));
+        }
+
+        let num_planes = num_planes as usize;
+        let mut fds = [-1; 4];
+        let mut strides = [0; 4];
+        let mut offsets = [0; 4];
+        if num_planes > fds.len()
+            || export(
+                self.display,
+                image.image,
+                fds.as_mut_ptr(),
+                strides.as_mut_ptr(),
+                offsets.as_mut_ptr(),
+            ) == 0
+        {
+            return Err(image_error("eglExportDMABUFImageMESA"));
+        }
+
+        let planes = (0..num_planes)
+            .map(|i| DmaBufPlane {
+                fd: fds[i],
+                offset: offsets[i] as u32,
+                stride: strides[i] as u32,
+            })
+            .collect();
+        Ok(DmaBuf {
+            width,
+            height,
+            fourcc: fourcc as u32,
+            modifier,
+            planes,
+        })
+    }
+
+    /// See the docs of `ContextExt::import_image`.
+    pub unsafe fn import_image(
+        &self,
+        builder: &EglImageBuilder<'_>,
+    ) -> Result<EglImage<'_>, ContextError> {
+        let buf = builder.buf;
+        let with_modifier = buf.modifier != DRM_FORMAT_MOD_INVALID;
+        let mut required =
+            vec!["EGL_KHR_image_base", "EGL_EXT_image_dma_buf_import"];
+        if with_modifier {
+            required.push("EGL_EXT_image_dma_buf_import_modifiers");
+        }
+        if builder.colorspace != ImageColorspace::Default {
+            required.push("EGL_EXT_image_gl_colorspace");
+        }
+        if !self.has_extensions(&required) {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        let (create, destroy) = match (
+            get_proc_address::<CreateImageFn>("eglCreateImageKHR\0"),
+            get_proc_address::<DestroyImageFn>("eglDestroyImageKHR\0"),
+        ) {
+            (Some(c), Some(d)) => (c, d),
+            _ => return Err(ContextError::FunctionUnavailable),
+        };
+        if buf.planes.is_empty() || buf.planes.len() > 4 {
+            return Err(ContextError::OsError(format!(
+                "a DMA-BUF has between 1 and 4 planes, not {}",
+                buf.planes.len()
+            )));
+        }
+
+        let mut attribs = AttribList::new(ffi::egl::NONE);
+        attribs.push(ffi::egl::WIDTH, buf.width as EGLint);
+        attribs.push(ffi::egl::HEIGHT, buf.height as EGLint);
+        attribs.push(LINUX_DRM_FOURCC_EXT, buf.fourcc);
+        for (i, plane) in buf.planes.iter().enumerate() {
+            attribs.push(DMA_BUF_PLANE_FD_EXT[i], plane.fd);
+            attribs.push(DMA_BUF_PLANE_OFFSET_EXT[i], plane.offset);
+            attribs.push(DMA_BUF_PLANE_PITCH_EXT[i], plane.stride);
+            if with_modifier {
+                attribs.push(
+                    DMA_BUF_PLANE_MODIFIER_LO_EXT[i],
+                    buf.modifier as u32,
+                );
+                attribs.push(
+                    DMA_BUF_PLANE_MODIFIER_HI_EXT[i],
+                    (buf.modifier >> 32) as u32,
+                );
+            }
+        }
+        match builder.colorspace {
+            ImageColorspace::Default => (),
+            ImageColorspace::Linear => attribs.push(
+                ffi::egl::GL_COLORSPACE,
+                ffi::egl::GL_COLORSPACE_LINEAR,
+            ),
+            ImageColorspace::Srgb => attribs.push(
+                ffi::egl::GL_COLORSPACE,
+                ffi::egl::GL_COLORSPACE_SRGB,
+            ),
+        }
+
+        // the target isn't bound to a context
+        let image = create(
+            self.display,
+            ffi::egl::NO_CONTEXT,
+            LINUX_DMA_BUF_EXT,
+            ptr::null(),
+            attribs.as_ptr(),
+        );
+        if image.is_null() {
+            return Err(image_error("eglCreateImageKHR"));
+        }
+        Ok(EglImage {
+            display: self.display,
+            image,
+            destroy,
+            marker: PhantomData,
+        })
+    }
+}
@@ -36,6 +36,9 @@ pub type EGLNativeWindowType = winapi::shared::windef::HWND;
 pub type EGLNativeWindowType = *const libc::c_void;
 #[cfg(target_os = "android")]
 pub type EGLNativeWindowType = *const libc::c_void;
+/// The `CALayer` of a view, with ANGLE.
+#[cfg(target_os = "macos")]
+pub type EGLNativeWindowType = *const libc::c_void;
 #[cfg(any(
     target_os = "dragonfly",
     target_os = "freebsd",
@@ -1,5 +1,6 @@
 #![cfg(any(
     target_os = "windows",
+    target_os = "macos",
     target_os = "linux",
     target_os = "android",
     target_os = "dragonfly",
@@ -10,23 +11,72 @@
 #![allow(unused_variables)]
 
 use Api;
+use Backend;
+use BackendInfo;
 use ContextError;
 use CreationError;
+use DisplayToken;
+use DriverWorkarounds;
 use GlAttributes;
+use GlProfile;
 use GlRequest;
+use HdrMetadata;
+use MultisampleResolve;
+use Ownership;
+use PbufferLimits;
+use PbufferTexture;
 use PixelFormat;
 use PixelFormatRequirements;
+use Rect;
 use ReleaseBehavior;
+use RenderBuffer;
 use Robustness;
+use SurfaceDowngrade;
+use SurfaceInfo;
+use SwapIntervalRange;
+use SwapBehavior;
+use TransparentPixel;
+use TransparentType;
+use X11VisualType;
+
+use api::attribs::AttribList;
+use api::proc_cache::ProcCache;
+use api::reloadable::{LibraryUser, Reloadable};
+use api::swap_interval::SwapIntervalTracker;
 
-use std::cell::Cell;
+#[cfg(not(target_os = "android"))]
+use std::env;
 use std::ffi::{CStr, CString};
+#[cfg(not(target_os = "android"))]
+use std::ffi::OsStr;
 use std::ops::{Deref, DerefMut};
+#[cfg(not(target_os = "android"))]
+use std::os::raw::c_ulong;
 use std::os::raw::{c_int, c_void};
+#[cfg(not(target_os = "android"))]
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, MutexGuard};
 use std::{mem, ptr};
 
+pub mod debug;
 pub mod ffi;
 
+const GL_EXTENSIONS: u32 = 0x1F03;
+
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+pub use self::dmabuf::{
+    DmaBuf, DmaBufPlane, EglImage, EglImageBuilder, ImageColorspace,
+};
+
+mod dmabuf;
+
 #[cfg(not(target_os = "android"))]
 mod egl {
     use super::ffi;
@@ -52,11 +102,21 @@ mod egl {
             #[cfg(target_os = "windows")]
             let paths = vec!["libEGL.dll", "atioglxx.dll"];
 
-            #[cfg(not(target_os = "windows"))]
+            // macOS doesn't have an EGL implementation of its own
+            #[cfg(target_os = "macos")]
+            let paths = Vec::<&str>::new();
+
+            #[cfg(not(any(target_os = "windows", target_os = "macos")))]
             let paths = vec!["libEGL.so.1", "libEGL.so"];
 
             SymWrapper::new(paths).map(|i| Egl(i))
         }
+
+        /// Loads the implementation shipped with the executable.
+        pub fn new_bundled() -> Result<Self, ()> {
+            SymWrapper::new(super::bundled_paths(super::BUNDLED_EGL_NAME))
+                .map(Egl)
+        }
     }
 }
 
@@ -71,6 +131,11 @@ mod egl {
         pub fn new() -> Result<Self, ()> {
             Ok(Egl(ffi::egl::Egl))
         }
+
+        /// EGL is linked statically, so nothing can be bundled.
+        pub fn new_bundled() -> Result<Self, ()> {
+            Err(())
+        }
     }
 }
 pub use self::egl::Egl;
@@ -90,7 +155,197 @@ impl DerefMut for Egl {
 }
 
 lazy_static! {
-    pub static ref EGL: Option<Egl> = Egl::new().ok();
+    /// The implementation shipped with the executable, such as ANGLE or
+    /// SwiftShader, if the `bundled-egl` feature is enabled.
+    static ref BUNDLED_EGL: Reloadable<Egl> =
+        Reloadable::new(Egl::new_bundled().ok());
+    pub static ref EGL: Reloadable<Egl> = Reloadable::new(load_egl());
+    /// The functions resolved through `eglGetProcAddress` alone, then those
+    /// looked up in the client library of OpenGL and of OpenGL ES first.
+    static ref PROC_ADDRESSES: [ProcCache; 3] = Default::default();
+}
+
+fn load_egl() -> Option<Egl> {
+    BUNDLED_EGL.as_ref().cloned().or_else(|| Egl::new().ok())
+}
+
+/// Loads EGL and the client libraries, unless they already are.
+pub fn preload() {
+    lazy_static::initialize(&EGL);
+    #[cfg(not(target_os = "android"))]
+    {
+        lazy_static::initialize(&GL_LIBRARY);
+        lazy_static::initialize(&GLES_LIBRARY);
+    }
+}
+
+/// The addresses resolved for the contexts of `api`, which are the same for
+/// all of them.
+fn proc_addresses(
+    api: Api,
+    get_all_proc_addresses: bool,
+) -> &'static ProcCache {
+    let index = match (get_all_proc_addresses, api) {
+        (true, _) => 0,
+        (false, Api::OpenGl) => 1,
+        (false, Api::OpenGlEs) | (false, Api::WebGl) => 2,
+    };
+    &PROC_ADDRESSES[index]
+}
+
+/// The number of EGL contexts alive, which keep the libraries loaded.
+pub fn live_contexts() -> usize {
+    EGL.users()
+}
+
+/// Unloads and loads again EGL and the client libraries.
+///
+/// # Safety
+///
+/// No EGL context may be alive or being built.
+pub unsafe fn reload_symbols() {
+    // everything is unloaded first, otherwise the dynamic loader would hand
+    // back the libraries that are still loaded
+    EGL.replace(None);
+    BUNDLED_EGL.replace(None);
+    #[cfg(not(target_os = "android"))]
+    {
+        GL_LIBRARY.replace(None);
+        GLES_LIBRARY.replace(None);
+    }
+
+    BUNDLED_EGL.replace(Egl::new_bundled().ok());
+    EGL.replace(load_egl());
+    #[cfg(not(target_os = "android"))]
+    {
+        GL_LIBRARY.replace(load_gl_library());
+        GLES_LIBRARY.replace(load_gles_library());
+    }
+    for cache in PROC_ADDRESSES.iter() {
+        cache.clear();
+    }
+}
+
+/// Whether `EGL` was shipped with the executable, in which case it is
+/// preferred over the other backends so that every machine renders the
+/// same way.
+pub fn is_bundled() -> bool {
+    BUNDLED_EGL.is_some()
+}
+
+/// The names under which ANGLE and SwiftShader ship their libraries.
+#[cfg(target_os = "windows")]
+const BUNDLED_EGL_NAME: &str = "libEGL.dll";
+#[cfg(target_os = "windows")]
+const BUNDLED_GLES_NAME: &str = "libGLESv2.dll";
+#[cfg(target_os = "macos")]
+const BUNDLED_EGL_NAME: &str = "libEGL.dylib";
+#[cfg(target_os = "macos")]
+const BUNDLED_GLES_NAME: &str = "libGLESv2.dylib";
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "android"
+)))]
+const BUNDLED_EGL_NAME: &str = "libEGL.so";
+#[cfg(not(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "android"
+)))]
+const BUNDLED_GLES_NAME: &str = "libGLESv2.so";
+
+/// Returns where the library `name` is shipped with the executable: next to
+/// it, or in the `Frameworks` directory of a macOS app bundle. Nothing is
+/// looked for without the `bundled-egl` feature.
+#[cfg(not(target_os = "android"))]
+fn bundled_paths(name: &str) -> Vec<PathBuf> {
+    if !cfg!(feature = "bundled-egl") {
+        return Vec::new();
+    }
+    let dir = match env::current_exe() {
+        Ok(ref exe) => match exe.parent() {
+            Some(dir) => dir.to_path_buf(),
+            None => return Vec::new(),
+        },
+        Err(_) => return Vec::new(),
+    };
+
+    let mut paths = vec![dir.join(name)];
+    if cfg!(target_os = "macos") {
+        paths.push(dir.join("../Frameworks").join(name));
+    }
+    paths.into_iter().filter(|path| path.is_file()).collect()
+}
+
+#[cfg(not(target_os = "android"))]
+lazy_static! {
+    // The client libraries export the core functions of their API.
+    static ref GL_LIBRARY: Reloadable<libloading::Library> =
+        Reloadable::new(load_gl_library());
+    static ref GLES_LIBRARY: Reloadable<libloading::Library> =
+        Reloadable::new(load_gles_library());
+}
+
+#[cfg(not(target_os = "android"))]
+fn load_gl_library() -> Option<libloading::Library> {
+    open_library(&[
+        #[cfg(target_os = "windows")]
+        "opengl32.dll",
+        #[cfg(not(target_os = "windows"))]
+        "libGL.so.1",
+        #[cfg(not(target_os = "windows"))]
+        "libGL.so",
+    ])
+}
+
+/// A bundled EGL must be used with its own client library.
+#[cfg(not(target_os = "android"))]
+fn load_gles_library() -> Option<libloading::Library> {
+    if is_bundled() {
+        open_library(&bundled_paths(BUNDLED_GLES_NAME))
+    } else {
+        open_library(&[
+            #[cfg(target_os = "windows")]
+            "libGLESv2.dll",
+            #[cfg(not(target_os = "windows"))]
+            "libGLESv2.so.2",
+            #[cfg(not(target_os = "windows"))]
+            "libGLESv2.so",
+        ])
+    }
+}
+
+#[cfg(not(target_os = "android"))]
+fn open_library<P: AsRef<OsStr>>(
+    paths: &[P],
+) -> Option<libloading::Library> {
+    paths
+        .iter()
+        .filter_map(|path| libloading::Library::new(path).ok())
+        .next()
+}
+
+#[cfg(not(target_os = "android"))]
+fn get_client_proc_address(api: Api, addr: &CStr) -> *const () {
+    let library = match api {
+        Api::OpenGl => GL_LIBRARY.as_ref(),
+        Api::OpenGlEs | Api::WebGl => GLES_LIBRARY.as_ref(),
+    };
+    library
+        .and_then(|library| unsafe {
+            library
+                .get::<*const ()>(addr.to_bytes_with_nul())
+                .ok()
+                .map(|symbol| *symbol)
+        })
+        .unwrap_or(ptr::null())
+}
+
+// `libGLESv2` is linked on Android.
+#[cfg(target_os = "android")]
+fn get_client_proc_address(_api: Api, addr: &CStr) -> *const () {
+    unsafe { libc::dlsym(libc::RTLD_DEFAULT, addr.as_ptr()) as *const _ }
 }
 
 /// Specifies the type of display passed as `native_display`.
@@ -106,38 +361,181 @@ pub enum NativeDisplay {
     Android,
     // TODO: should be `EGLDeviceEXT`
     Device(ffi::EGLNativeDisplayType),
+    /// No display server, through `EGL_MESA_platform_surfaceless`. Falls
+    /// back to `EGL_DEFAULT_DISPLAY`.
+    Surfaceless,
     /// Don't specify any display type. Useful on windows. `None` means
     /// `EGL_DEFAULT_DISPLAY`.
     Other(Option<ffi::EGLNativeDisplayType>),
 }
 
+/// `eglSwapBuffersWithDamageKHR` and `eglSwapBuffersWithDamageEXT` share
+/// this signature.
+type SwapBuffersWithDamageFn = unsafe extern "system" fn(
+    ffi::egl::types::EGLDisplay,
+    ffi::egl::types::EGLSurface,
+    *const ffi::egl::types::EGLint,
+    ffi::egl::types::EGLint,
+) -> ffi::egl::types::EGLBoolean;
+
+/// The display, surfaces and context current on a thread for the client
+/// API of a context, to restore them afterwards.
+pub struct CurrentState {
+    display: ffi::egl::types::EGLDisplay,
+    draw: ffi::egl::types::EGLSurface,
+    read: ffi::egl::types::EGLSurface,
+    context: ffi::egl::types::EGLContext,
+    /// The client API bound on the thread, which the current context is
+    /// queried and restored for.
+    bound_api: ffi::egl::types::EGLenum,
+}
+
 pub struct Context {
     display: ffi::egl::types::EGLDisplay,
     context: ffi::egl::types::EGLContext,
-    surface: Cell<ffi::egl::types::EGLSurface>,
+    /// Locked while the surface is in use, so that it can't be replaced or
+    /// destroyed from another thread in the meantime.
+    surface: Mutex<ffi::egl::types::EGLSurface>,
+    /// Locked after `surface` when both are needed.
+    swap_interval: Mutex<SwapIntervalTracker<ffi::egl::types::EGLSurface>>,
     api: Api,
+    /// The client API bound with `eglBindAPI` before the context is used,
+    /// since EGL tracks the current context of each thread per API. `None`
+    /// before EGL 1.2, which only has OpenGL ES.
+    client_api: Option<ffi::egl::types::EGLenum>,
     pixel_format: PixelFormat,
-    #[cfg(target_os = "android")]
+    release_behavior: ReleaseBehavior,
+    /// The workarounds that were needed to create the context.
+    driver_workarounds: DriverWorkarounds,
+    extensions: Vec<String>,
+    /// Whether `eglGetProcAddress` also returns the core functions.
+    get_all_proc_addresses: bool,
+    swap_buffers_with_damage: Option<SwapBuffersWithDamageFn>,
     config_id: ffi::egl::types::EGLConfig,
+    /// Whether the context was created without a surface, in which case
+    /// `surface` is always `EGL_NO_SURFACE`.
+    surfaceless: bool,
+    /// Whether the context and its surface are destroyed on drop, which is
+    /// always the case of the contexts created by glutin.
+    ownership: Ownership,
+    /// Whether the display counts its references, in which case it is
+    /// terminated on drop.
+    tracks_references: bool,
+    /// The labels given to the context and its surface, kept alive since
+    /// EGL only stores their pointers.
+    labels: Mutex<Vec<CString>>,
+    /// What was given up to create the surface.
+    downgrades: Vec<SurfaceDowngrade>,
+    /// How the pbuffer can be bound to a texture, `None` if it can't.
+    pbuffer_texture: Option<PbufferTexture>,
+    /// The attributes added to the ones of the window surface, to create it
+    /// again once the application is resumed.
+    #[cfg(target_os = "android")]
+    surface_attribs: Vec<(u32, c_int)>,
+    /// Whether `destroy` was called, after which the drop does nothing.
+    destroyed: AtomicBool,
+    /// Keeps `EGL` from being reloaded while the context exists.
+    _user: LibraryUser,
 }
 
-#[cfg(target_os = "android")]
-#[inline]
-fn get_native_display(
-    egl: &Egl,
-    native_display: NativeDisplay,
-) -> *const c_void {
-    unsafe { egl.GetDisplay(ffi::egl::DEFAULT_DISPLAY as *mut _) }
+/// Whether an error returned by `eglMakeCurrent` or `eglSwapBuffers` means
+/// that the native display went away, which can't be recovered from without
+/// recreating everything.
+fn is_display_lost(err: u32) -> bool {
+    err == ffi::egl::BAD_DISPLAY || err == ffi::egl::NOT_INITIALIZED
 }
 
-#[cfg(not(target_os = "android"))]
-fn get_native_display(
-    egl: &Egl,
-    native_display: NativeDisplay,
-) -> *const c_void {
-    // the first step is to query the list of extensions without any display, if
-    // supported
-    let dp_extensions = unsafe {
+/// The client API to bind with `eglBindAPI` for the contexts of `api`, if
+/// the display supports binding one.
+fn client_api(
+    egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+    api: Api,
+) -> Option<ffi::egl::types::EGLenum> {
+    if *egl_version < (1, 2) {
+        return None;
+    }
+    match api {
+        Api::OpenGl => Some(ffi::egl::OPENGL_API),
+        _ => Some(ffi::egl::OPENGL_ES_API),
+    }
+}
+
+/// Checks that `version` of `api` exists and can be requested from the
+/// display, and returns the `EGL_RENDERABLE_TYPE` its configs need, if the
+/// display knows it.
+///
+/// `None` is the version picked when creating the context: OpenGL ES 2.0,
+/// or 1.0 as a fallback, and the latest version of OpenGL.
+fn renderable_type(
+    egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+    extensions: &[String],
+    api: Api,
+    version: Option<(u8, u8)>,
+) -> Result<Option<ffi::egl::types::EGLenum>, CreationError> {
+    // the minor version can only be requested with `EGL_KHR_create_context`,
+    // otherwise the driver picks it
+    let create_context = *egl_version >= (1, 5)
+        || extensions.iter().any(|s| s == "EGL_KHR_create_context");
+    let renderable = match (api, version) {
+        (Api::OpenGlEs, None) | (Api::OpenGlEs, Some((2, 0))) => {
+            ffi::egl::OPENGL_ES2_BIT
+        }
+        (Api::OpenGlEs, Some((1, minor))) if minor <= 1 => {
+            ffi::egl::OPENGL_ES_BIT
+        }
+        (Api::OpenGlEs, Some((3, minor))) if minor <= 2 => {
+            if minor > 0 && !create_context {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
+            ffi::egl::OPENGL_ES3_BIT
+        }
+        (Api::OpenGl, None) => ffi::egl::OPENGL_BIT,
+        (Api::OpenGl, Some((major, minor)))
+            if match major {
+                1 => minor <= 5,
+                2 => minor <= 1,
+                3 => minor <= 3,
+                4 => minor <= 6,
+                _ => false,
+            } =>
+        {
+            if *egl_version < (1, 4) {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
+            ffi::egl::OPENGL_BIT
+        }
+        // versions that don't exist, and WebGL, which EGL doesn't provide
+        _ => return Err(CreationError::OpenGlVersionNotSupported),
+    };
+    // `EGL_RENDERABLE_TYPE` only exists since EGL 1.3, before which only
+    // OpenGL ES 1 contexts can be created
+    if *egl_version >= (1, 3) {
+        Ok(Some(renderable))
+    } else if api == Api::OpenGlEs
+        && (version.is_none() || renderable == ffi::egl::OPENGL_ES_BIT)
+    {
+        Ok(None)
+    } else {
+        Err(CreationError::OpenGlVersionNotSupported)
+    }
+}
+
+/// Turns the error of a failed swap into a `ContextError`.
+fn swap_buffers_error(function: &str) -> ContextError {
+    let egl = EGL.as_ref().unwrap();
+    match unsafe { egl.GetError() } as u32 {
+        ffi::egl::CONTEXT_LOST => ContextError::ContextLost,
+        err if is_display_lost(err) => ContextError::DisplayLost,
+        err => ContextError::OsError(format!(
+            "{} failed (eglGetError returned 0x{:x})",
+            function, err
+        )),
+    }
+}
+
+/// Queries the list of extensions without any display.
+fn get_client_extensions(egl: &Egl) -> Vec<String> {
+    unsafe {
         let p =
             egl.QueryString(ffi::egl::NO_DISPLAY, ffi::egl::EXTENSIONS as i32);
 
@@ -152,11 +550,125 @@ fn get_native_display(
                 .unwrap_or_else(|_| format!(""));
             list.split(' ').map(|e| e.to_string()).collect::<Vec<_>>()
         }
+    }
+}
+
+/// Queries the default display for `glutin::probe`.
+pub fn probe() -> Option<BackendInfo> {
+    let egl = EGL.as_ref()?;
+    let client_extensions = get_client_extensions(egl);
+
+    let display =
+        unsafe { egl.GetDisplay(ffi::egl::DEFAULT_DISPLAY as *mut _) };
+    if display.is_null() {
+        return None;
+    }
+
+    // The display is not terminated afterwards, since contexts of the
+    // application may be using it.
+    let (mut major, mut minor) = (0, 0);
+    if unsafe { egl.Initialize(display, &mut major, &mut minor) } == 0 {
+        return None;
+    }
+
+    let display_extensions = unsafe {
+        let p = egl.QueryString(display, ffi::egl::EXTENSIONS as i32);
+        if p.is_null() {
+            vec![]
+        } else {
+            CStr::from_ptr(p)
+                .to_string_lossy()
+                .split(' ')
+                .filter(|e| !e.is_empty())
+                .map(|e| e.to_string())
+                .collect()
+        }
     };
 
+    let mut num_configs = 0;
+    unsafe {
+        egl.GetConfigs(display, ptr::null_mut(), 0, &mut num_configs);
+    }
+
+    Some(BackendInfo {
+        backend: Backend::Egl,
+        version: (major as u8, minor as u8),
+        client_extensions,
+        display_extensions,
+        num_configs: num_configs as usize,
+    })
+}
+
+/// Whether displays can be obtained without a display server, through
+/// `EGL_MESA_platform_surfaceless`.
+pub fn has_surfaceless_platform() -> bool {
+    match EGL.as_ref() {
+        Some(egl) => get_client_extensions(egl)
+            .iter()
+            .any(|s| s == "EGL_MESA_platform_surfaceless"),
+        None => false,
+    }
+}
+
+/// The function a display was obtained with, which decides the one its
+/// window surfaces are created with.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum PlatformDisplay {
+    /// `eglGetDisplay`, whose window surfaces are created from an
+    /// `EGLNativeWindowType` by `eglCreateWindowSurface`.
+    Native,
+    /// `eglGetPlatformDisplay` of EGL 1.5, for the given platform.
+    Khr(ffi::egl::types::EGLenum),
+    /// `eglGetPlatformDisplayEXT` of `EGL_EXT_platform_base`, for the given
+    /// platform.
+    Ext(ffi::egl::types::EGLenum),
+}
+
+#[cfg(target_os = "android")]
+#[inline]
+fn get_native_display(
+    egl: &Egl,
+    native_display: NativeDisplay,
+) -> (*const c_void, PlatformDisplay) {
+    let display =
+        unsafe { egl.GetDisplay(ffi::egl::DEFAULT_DISPLAY as *mut _) };
+    (display, PlatformDisplay::Native)
+}
+
+#[cfg(not(target_os = "android"))]
+fn get_native_display(
+    egl: &Egl,
+    native_display: NativeDisplay,
+) -> (*const c_void, PlatformDisplay) {
+    // the first step is to query the list of extensions without any display, if
+    // supported
+    let dp_extensions = get_client_extensions(egl);
+
     let has_dp_extension =
         |e: &str| dp_extensions.iter().find(|s| s == &e).is_some();
 
+    // a display counting its references can be terminated on drop without
+    // breaking the other contexts of the same native display
+    let mut attribs = AttribList::new(ffi::egl::NONE);
+    if has_dp_extension("EGL_KHR_display_reference") {
+        attribs.push(ffi::egl::TRACK_REFERENCES_KHR, true);
+    }
+    let khr = |platform, display: Option<*const c_void>| unsafe {
+        let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
+        let display = egl.GetPlatformDisplay(
+            platform,
+            d as *mut _,
+            attribs.to_egl_attribs().as_ptr(),
+        );
+        (display, PlatformDisplay::Khr(platform))
+    };
+    let ext = |platform, display: Option<*const c_void>| unsafe {
+        let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
+        let display =
+            egl.GetPlatformDisplayEXT(platform, d as *mut _, attribs.as_ptr());
+        (display, PlatformDisplay::Ext(platform))
+    };
+
     match native_display {
         // Note: Some EGL implementations are missing the
         // `eglGetPlatformDisplay(EXT)` symbol       despite reporting
@@ -167,86 +679,44 @@ fn get_native_display(
             if has_dp_extension("EGL_KHR_platform_x11")
                 && egl.GetPlatformDisplay.is_loaded() =>
         {
-            let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
             // TODO: `PLATFORM_X11_SCREEN_KHR`
-            unsafe {
-                egl.GetPlatformDisplay(
-                    ffi::egl::PLATFORM_X11_KHR,
-                    d as *mut _,
-                    ptr::null(),
-                )
-            }
+            khr(ffi::egl::PLATFORM_X11_KHR, display)
         }
 
         NativeDisplay::X11(display)
             if has_dp_extension("EGL_EXT_platform_x11")
                 && egl.GetPlatformDisplayEXT.is_loaded() =>
         {
-            let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
             // TODO: `PLATFORM_X11_SCREEN_EXT`
-            unsafe {
-                egl.GetPlatformDisplayEXT(
-                    ffi::egl::PLATFORM_X11_EXT,
-                    d as *mut _,
-                    ptr::null(),
-                )
-            }
+            ext(ffi::egl::PLATFORM_X11_EXT, display)
         }
 
         NativeDisplay::Gbm(display)
             if has_dp_extension("EGL_KHR_platform_gbm")
                 && egl.GetPlatformDisplay.is_loaded() =>
         {
-            let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
-            unsafe {
-                egl.GetPlatformDisplay(
-                    ffi::egl::PLATFORM_GBM_KHR,
-                    d as *mut _,
-                    ptr::null(),
-                )
-            }
+            khr(ffi::egl::PLATFORM_GBM_KHR, display)
         }
 
         NativeDisplay::Gbm(display)
             if has_dp_extension("EGL_MESA_platform_gbm")
                 && egl.GetPlatformDisplayEXT.is_loaded() =>
         {
-            let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
-            unsafe {
-                egl.GetPlatformDisplayEXT(
-                    ffi::egl::PLATFORM_GBM_KHR,
-                    d as *mut _,
-                    ptr::null(),
-                )
-            }
+            ext(ffi::egl::PLATFORM_GBM_KHR, display)
         }
 
         NativeDisplay::Wayland(display)
             if has_dp_extension("EGL_KHR_platform_wayland")
                 && egl.GetPlatformDisplay.is_loaded() =>
         {
-            let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
-            unsafe {
-                egl.GetPlatformDisplay(
-                    ffi::egl::PLATFORM_WAYLAND_KHR,
-                    d as *mut _,
-                    ptr::null(),
-                )
-            }
+            khr(ffi::egl::PLATFORM_WAYLAND_KHR, display)
         }
 
         NativeDisplay::Wayland(display)
             if has_dp_extension("EGL_EXT_platform_wayland")
                 && egl.GetPlatformDisplayEXT.is_loaded() =>
         {
-            let d = display.unwrap_or(ffi::egl::DEFAULT_DISPLAY as *const _);
-            unsafe {
-                egl.GetPlatformDisplayEXT(
-                    ffi::egl::PLATFORM_WAYLAND_EXT,
-                    d as *mut _,
-                    ptr::null(),
-                )
-            }
+            ext(ffi::egl::PLATFORM_WAYLAND_EXT, display)
         }
 
         // TODO: This will never be reached right now, as the android egl
@@ -255,23 +725,29 @@ fn get_native_display(
         NativeDisplay::Android
             if has_dp_extension("EGL_KHR_platform_android")
                 && egl.GetPlatformDisplay.is_loaded() =>
-        unsafe {
-            egl.GetPlatformDisplay(
-                ffi::egl::PLATFORM_ANDROID_KHR,
-                ffi::egl::DEFAULT_DISPLAY as *mut _,
-                ptr::null(),
-            )
+        {
+            khr(ffi::egl::PLATFORM_ANDROID_KHR, None)
         }
 
         NativeDisplay::Device(display)
             if has_dp_extension("EGL_EXT_platform_device")
                 && egl.GetPlatformDisplay.is_loaded() =>
-        unsafe {
-            egl.GetPlatformDisplay(
-                ffi::egl::PLATFORM_DEVICE_EXT,
-                display as *mut _,
-                ptr::null(),
-            )
+        {
+            khr(ffi::egl::PLATFORM_DEVICE_EXT, Some(display))
+        }
+
+        NativeDisplay::Surfaceless
+            if has_dp_extension("EGL_MESA_platform_surfaceless")
+                && egl.GetPlatformDisplay.is_loaded() =>
+        {
+            khr(ffi::egl::PLATFORM_SURFACELESS_MESA, None)
+        }
+
+        NativeDisplay::Surfaceless
+            if has_dp_extension("EGL_MESA_platform_surfaceless")
+                && egl.GetPlatformDisplayEXT.is_loaded() =>
+        {
+            ext(ffi::egl::PLATFORM_SURFACELESS_MESA, None)
         }
 
         NativeDisplay::X11(Some(display))
@@ -279,15 +755,17 @@ fn get_native_display(
         | NativeDisplay::Wayland(Some(display))
         | NativeDisplay::Device(display)
         | NativeDisplay::Other(Some(display)) => unsafe {
-            egl.GetDisplay(display as *mut _)
+            (egl.GetDisplay(display as *mut _), PlatformDisplay::Native)
         },
 
         NativeDisplay::X11(None)
         | NativeDisplay::Gbm(None)
         | NativeDisplay::Wayland(None)
         | NativeDisplay::Android
+        | NativeDisplay::Surfaceless
         | NativeDisplay::Other(None) => unsafe {
-            egl.GetDisplay(ffi::egl::DEFAULT_DISPLAY as *mut _)
+            let display = egl.GetDisplay(ffi::egl::DEFAULT_DISPLAY as *mut _);
+            (display, PlatformDisplay::Native)
         },
     }
 }
@@ -305,8 +783,11 @@ impl Context {
         native_display: NativeDisplay,
     ) -> Result<ContextPrototype<'a>, CreationError> {
         let egl = EGL.as_ref().unwrap();
+        // the configs of X11 displays have the visual class of X11 as their
+        // native visual type
+        let x11 = matches!(native_display, NativeDisplay::X11(_));
         // calling `eglGetDisplay` or equivalent
-        let display = get_native_display(egl, native_display);
+        let (display, platform) = get_native_display(egl, native_display);
 
         if display.is_null() {
             return Err(CreationError::OsError(
@@ -314,6 +795,25 @@ impl Context {
             ));
         }
 
+        // contexts of different displays, such as the ones of two Wayland
+        // compositors, can't share their objects
+        if let Some(share) = opengl.sharing {
+            if share.display != display {
+                return Err(CreationError::IncompatibleSharing);
+            }
+        }
+
+        // the surfaceless platform has no window surfaces, so its configs
+        // are only usable with pbuffers
+        let pbuffer_only = pf_reqs.compute_only
+            || match platform {
+                PlatformDisplay::Khr(platform)
+                | PlatformDisplay::Ext(platform) => {
+                    platform == ffi::egl::PLATFORM_SURFACELESS_MESA
+                }
+                PlatformDisplay::Native => false,
+            };
+
         let egl_version = unsafe {
             let mut major: ffi::egl::types::EGLint = mem::uninitialized();
             let mut minor: ffi::egl::types::EGLint = mem::uninitialized();
@@ -327,21 +827,18 @@ impl Context {
             (major, minor)
         };
 
-        // the list of extensions supported by the client once initialized is
-        // different from the list of extensions obtained earlier
-        let extensions = if egl_version >= (1, 2) {
-            let p = unsafe {
-                CStr::from_ptr(
-                    egl.QueryString(display, ffi::egl::EXTENSIONS as i32),
-                )
-            };
-            let list = String::from_utf8(p.to_bytes().to_vec())
-                .unwrap_or_else(|_| format!(""));
-            list.split(' ').map(|e| e.to_string()).collect::<Vec<_>>()
-        } else {
-            vec![]
+        // `eglCreatePlatformWindowSurface` needs an EGL 1.5 display, even if
+        // the client library could get one with `eglGetPlatformDisplay`
+        let platform = match platform {
+            PlatformDisplay::Khr(_) if egl_version < (1, 5) => {
+                PlatformDisplay::Native
+            }
+            platform => platform,
         };
 
+        let extensions = get_display_extensions(egl, display, &egl_version);
+        let tracks_references = unsafe { tracks_references(egl, display) };
+
         // binding the right API and choosing the version
         let (version, api) = unsafe {
             match opengl.version {
@@ -404,73 +901,479 @@ impl Context {
         };
 
         let (config_id, pixel_format) = unsafe {
-            choose_fbconfig(egl, display, &egl_version, api, version, pf_reqs)?
+            choose_fbconfig(
+                egl,
+                display,
+                &egl_version,
+                &extensions,
+                api,
+                version,
+                pf_reqs,
+                pbuffer_only,
+                x11,
+            )?
+        };
+
+        // the release behavior is set when creating the context
+        let release_behavior = if pf_reqs.release_behavior
+            == ReleaseBehavior::None
+            && extensions
+                .iter()
+                .any(|s| s == "EGL_KHR_context_flush_control")
+        {
+            ReleaseBehavior::None
+        } else {
+            ReleaseBehavior::Flush
         };
 
         Ok(ContextPrototype {
             opengl: opengl,
             display: display,
+            platform,
+            x11,
             egl_version: egl_version,
             extensions: extensions,
             api: api,
             version: version,
             config_id: config_id,
             pixel_format: pixel_format,
+            double_buffer: pf_reqs.double_buffer != Some(false),
+            srgb: pf_reqs.srgb,
+            release_behavior,
+            driver_workarounds: pf_reqs.driver_workarounds,
+            tracks_references,
+            downgrade_on_alloc_failure: pf_reqs.downgrade_on_alloc_failure,
+            downgrades: Vec::new(),
+            pbuffer_texture: pf_reqs.pbuffer_texture,
+            config_attribs: pf_reqs.egl_config_attribs.clone(),
+            surface_attribs: pf_reqs.egl_surface_attribs.clone(),
         })
     }
 
-    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
-        let egl = EGL.as_ref().unwrap();
-        let ret = egl.MakeCurrent(
-            self.display,
-            self.surface.get(),
-            self.surface.get(),
-            self.context,
-        );
+    /// Wraps a context created by another library.
+    ///
+    /// The config is looked up from the context if `config` is null, and
+    /// `surface` may be `EGL_NO_SURFACE` if the context is made current
+    /// without one.
+    pub unsafe fn from_raw(
+        display: ffi::egl::types::EGLDisplay,
+        context: ffi::egl::types::EGLContext,
+        config: ffi::egl::types::EGLConfig,
+        surface: ffi::egl::types::EGLSurface,
+        ownership: Ownership,
+    ) -> Result<Context, CreationError> {
+        let egl = match EGL.as_ref() {
+            Some(egl) => egl,
+            None => {
+                return Err(CreationError::NotSupported("libEGL not available"))
+            }
+        };
 
-        if ret == 0 {
-            match egl.GetError() as u32 {
-                ffi::egl::CONTEXT_LOST => return Err(ContextError::ContextLost),
-                err => panic!(
-                    "eglMakeCurrent failed (eglGetError returned 0x{:x})",
-                    err
-                ),
+        // initializing a display again only returns its version
+        let mut major = 0;
+        let mut minor = 0;
+        if egl.Initialize(display, &mut major, &mut minor) == 0 {
+            let msg = "eglInitialize failed".to_string();
+            return Err(CreationError::OsError(msg));
+        }
+        let egl_version = (major, minor);
+        let extensions = get_display_extensions(egl, display, &egl_version);
+        let tracks_references = tracks_references(egl, display);
+
+        let query_context = |attrib| {
+            let mut value = 0;
+            if egl.QueryContext(
+                display,
+                context,
+                attrib as ffi::egl::types::EGLint,
+                &mut value,
+            ) == 0
+            {
+                let msg = "eglQueryContext failed".to_string();
+                Err(CreationError::OsError(msg))
+            } else {
+                Ok(value)
+            }
+        };
+
+        let api = match query_context(ffi::egl::CONTEXT_CLIENT_TYPE)? as u32 {
+            ffi::egl::OPENGL_API => Api::OpenGl,
+            ffi::egl::OPENGL_ES_API => Api::OpenGlEs,
+            _ => {
+                return Err(CreationError::NotSupported(
+                    "the context is neither an OpenGL nor an OpenGL ES one",
+                ));
+            }
+        };
+
+        let config_id = if config.is_null() {
+            let id = query_context(ffi::egl::CONFIG_ID)?;
+            let mut attribs = AttribList::new(ffi::egl::NONE);
+            attribs.push(ffi::egl::CONFIG_ID, id);
+            let mut config = ptr::null();
+            let mut num_configs = 0;
+            if egl.ChooseConfig(
+                display,
+                attribs.as_ptr(),
+                &mut config,
+                1,
+                &mut num_configs,
+            ) == 0
+                || num_configs == 0
+            {
+                return Err(CreationError::NoAvailablePixelFormat);
             }
+            config
         } else {
-            Ok(())
+            config
+        };
+
+        let surfaceless = surface == ffi::egl::NO_SURFACE;
+        let pixel_format =
+            describe_config(egl, display, &extensions, config_id, false)?;
+        let pixel_format = if surfaceless {
+            pixel_format
+        } else {
+            query_surface_format(
+                display,
+                &egl_version,
+                &extensions,
+                surface,
+                pixel_format,
+            )
+        };
+
+        let mut context = Context {
+            display,
+            context,
+            surface: Mutex::new(surface),
+            swap_interval: Mutex::new(SwapIntervalTracker::new(None)),
+            api,
+            client_api: client_api(&egl_version, api),
+            pixel_format,
+            // the flush control can't be queried
+            release_behavior: ReleaseBehavior::Flush,
+            driver_workarounds: DriverWorkarounds::none(),
+            get_all_proc_addresses: get_all_proc_addresses(
+                &egl_version,
+                &extensions,
+            ),
+            swap_buffers_with_damage: load_swap_buffers_with_damage(
+                &extensions,
+            ),
+            extensions,
+            config_id,
+            surfaceless,
+            ownership,
+            tracks_references,
+            labels: Mutex::new(Vec::new()),
+            downgrades: Vec::new(),
+            pbuffer_texture: None,
+            #[cfg(target_os = "android")]
+            surface_attribs: Vec::new(),
+            destroyed: AtomicBool::new(false),
+            _user: EGL.user(),
+        };
+        if surfaceless && !context.binds_without_surface() {
+            // the handles are left to the caller
+            context.ownership = Ownership::Borrowed;
+            return Err(CreationError::NotSupported(
+                "the OpenGL ES context can't be made current without a \
+                 surface, which requires GL_OES_surfaceless_context",
+            ));
         }
+        Ok(context)
     }
 
-    #[inline]
-    pub fn is_current(&self) -> bool {
-        let egl = EGL.as_ref().unwrap();
-        unsafe { egl.GetCurrentContext() == self.context }
+    /// Locks the surface. The lock only protects a pointer, so it is still
+    /// usable if another thread panicked while holding it.
+    fn surface(&self) -> MutexGuard<'_, ffi::egl::types::EGLSurface> {
+        self.surface.lock().unwrap_or_else(|err| err.into_inner())
     }
 
-    pub fn get_proc_address(&self, addr: &str) -> *const () {
+    /// The error returned when the surface is `EGL_NO_SURFACE`, which means
+    /// it was destroyed unless the context never had one.
+    fn no_surface_error(&self) -> ContextError {
+        if self.surfaceless {
+            ContextError::FunctionUnavailable
+        } else {
+            ContextError::ContextLost
+        }
+    }
+
+    /// Binds the client API of the context on the calling thread, which
+    /// another context of the process may have changed, before the current
+    /// context is queried or changed.
+    unsafe fn bind_api(&self) {
+        if let Some(client_api) = self.client_api {
+            EGL.as_ref().unwrap().BindAPI(client_api);
+        }
+    }
+
+    /// Returns what is current on the calling thread for the client API of
+    /// the context, along with the client API bound on the thread.
+    pub unsafe fn current_state(&self) -> CurrentState {
         let egl = EGL.as_ref().unwrap();
-        let addr = CString::new(addr.as_bytes()).unwrap();
-        let addr = addr.as_ptr();
-        unsafe { egl.GetProcAddress(addr) as *const _ }
+        let bound_api = egl.QueryAPI();
+        self.bind_api();
+        CurrentState {
+            bound_api,
+            display: egl.GetCurrentDisplay(),
+            draw: egl
+                .GetCurrentSurface(ffi::egl::DRAW as ffi::egl::types::EGLint),
+            read: egl
+                .GetCurrentSurface(ffi::egl::READ as ffi::egl::types::EGLint),
+            context: egl.GetCurrentContext(),
+        }
     }
 
-    #[inline]
-    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+    /// Makes `state` current again, then binds the client API that was bound
+    /// back. Releasing the current context needs a display, so that of this
+    /// context is used if none was current.
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
         let egl = EGL.as_ref().unwrap();
-        if self.surface.get() == ffi::egl::NO_SURFACE {
-            return Err(ContextError::ContextLost);
+        self.bind_api();
+        if state.context == ffi::egl::NO_CONTEXT {
+            egl.MakeCurrent(
+                self.display,
+                ffi::egl::NO_SURFACE,
+                ffi::egl::NO_SURFACE,
+                ffi::egl::NO_CONTEXT,
+            );
+        } else {
+            egl.MakeCurrent(
+                state.display,
+                state.draw,
+                state.read,
+                state.context,
+            );
         }
+        egl.BindAPI(state.bound_api);
+    }
 
-        let ret = unsafe { egl.SwapBuffers(self.display, self.surface.get()) };
+    pub unsafe fn make_current(&self) -> Result<(), ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        self.bind_api();
+        let surface = self.surface();
+        let ret =
+            egl.MakeCurrent(self.display, *surface, *surface, self.context);
 
         if ret == 0 {
-            match unsafe { egl.GetError() } as u32 {
+            match egl.GetError() as u32 {
                 ffi::egl::CONTEXT_LOST => return Err(ContextError::ContextLost),
-                err => panic!(
-                    "eglSwapBuffers failed (eglGetError returned 0x{:x})",
+                err if is_display_lost(err) => Err(ContextError::DisplayLost),
+                err => Err(ContextError::OsError(format!(
+                    "eglMakeCurrent failed (eglGetError returned 0x{:x})",
                     err
-                ),
+                ))),
             }
+        } else {
+            // a failure is reported by `set_swap_interval`, and the interval
+            // is tried again on the next call
+            let _ = self.apply_swap_interval(*surface);
+            Ok(())
+        }
+    }
+
+    /// Applies the wanted swap interval to `surface`, which must be bound to
+    /// the context on the calling thread, unless it was applied already.
+    unsafe fn apply_swap_interval(
+        &self,
+        surface: ffi::egl::types::EGLSurface,
+    ) -> Result<(), ContextError> {
+        if surface == ffi::egl::NO_SURFACE {
+            return Ok(());
+        }
+        let mut tracker =
+            self.swap_interval.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(interval) = tracker.pending(surface) {
+            let egl = EGL.as_ref().unwrap();
+            if egl.SwapInterval(self.display, interval) == 0 {
+                return Err(ContextError::OsError(format!(
+                    "`eglSwapInterval` failed (eglGetError returned 0x{:x})",
+                    egl.GetError()
+                )));
+            }
+            tracker.applied(surface, interval);
+        }
+        Ok(())
+    }
+
+    /// `eglSwapInterval` sets the interval of the surface bound to the
+    /// current context, so it is applied right away if the context is
+    /// current on the calling thread, and when it is next made current
+    /// otherwise.
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        let surface = self.surface();
+        if *surface == ffi::egl::NO_SURFACE {
+            return Err(self.no_surface_error());
+        }
+        self.swap_interval
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .set(interval as c_int);
+        if self.is_current() {
+            unsafe { self.apply_swap_interval(*surface) }
+        } else {
+            Ok(())
+        }
+    }
+
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if !self.is_current() {
+            return Ok(());
+        }
+
+        let egl = EGL.as_ref().unwrap();
+        let ret = egl.MakeCurrent(
+            self.display,
+            ffi::egl::NO_SURFACE,
+            ffi::egl::NO_SURFACE,
+            ffi::egl::NO_CONTEXT,
+        );
+        if ret == 0 {
+            Err(ContextError::OsError(format!(
+                "`eglMakeCurrent` failed (eglGetError returned 0x{:x})",
+                egl.GetError()
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Whether the context is current on the calling thread. The client API
+    /// bound on the thread is left as it was.
+    #[inline]
+    pub fn is_current(&self) -> bool {
+        let egl = EGL.as_ref().unwrap();
+        unsafe {
+            let bound_api = egl.QueryAPI();
+            self.bind_api();
+            let current = egl.GetCurrentContext() == self.context;
+            egl.BindAPI(bound_api);
+            current
+        }
+    }
+
+    /// Whether the context can be made current without a surface.
+    ///
+    /// `EGL_KHR_surfaceless_context` only allows it for OpenGL ES contexts
+    /// supporting `GL_OES_surfaceless_context`, which can only be queried
+    /// once current, so the context is made current briefly and the one
+    /// current before is restored afterwards.
+    unsafe fn binds_without_surface(&self) -> bool {
+        if self.api != Api::OpenGlEs {
+            return true;
+        }
+
+        let egl = EGL.as_ref().unwrap();
+        let previous = self.current_state();
+        if egl.MakeCurrent(
+            self.display,
+            ffi::egl::NO_SURFACE,
+            ffi::egl::NO_SURFACE,
+            self.context,
+        ) == 0
+        {
+            // `EGL_BAD_MATCH`, the previous context is still current
+            egl.GetError();
+            return false;
+        }
+
+        let get_string = self.get_proc_address("glGetString");
+        let supported = !get_string.is_null() && {
+            let get_string: unsafe extern "system" fn(u32) -> *const u8 =
+                mem::transmute(get_string);
+            let p = get_string(GL_EXTENSIONS);
+            !p.is_null()
+                && CStr::from_ptr(p as *const _)
+                    .to_string_lossy()
+                    .split(' ')
+                    .any(|ext| ext == "GL_OES_surfaceless_context")
+        };
+
+        self.restore_current(&previous);
+        supported
+    }
+
+    /// Before EGL 1.5, `eglGetProcAddress` is only required to return
+    /// extension functions and may return garbage for core ones, so those are
+    /// looked up in the client library first.
+    pub fn get_proc_address(&self, addr: &str) -> *const () {
+        let get_all = self.get_all_proc_addresses;
+        proc_addresses(self.api, get_all).get(addr, |addr| {
+            let egl = EGL.as_ref().unwrap();
+            let addr = CString::new(addr.as_bytes()).unwrap();
+            if !get_all {
+                let p = get_client_proc_address(self.api, &addr);
+                if !p.is_null() {
+                    return p;
+                }
+            }
+            unsafe { egl.GetProcAddress(addr.as_ptr()) as *const _ }
+        })
+    }
+
+    #[inline]
+    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        let surface = self.surface();
+        if *surface == ffi::egl::NO_SURFACE {
+            return Err(self.no_surface_error());
+        }
+
+        let ret = unsafe {
+            self.bind_api();
+            egl.SwapBuffers(self.display, *surface)
+        };
+
+        if ret == 0 {
+            Err(swap_buffers_error("eglSwapBuffers"))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Falls back to `eglSwapBuffers` if neither
+    /// `EGL_KHR_swap_buffers_with_damage` nor
+    /// `EGL_EXT_swap_buffers_with_damage` is available.
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        let swap = match self.swap_buffers_with_damage {
+            Some(swap) => swap,
+            None => return self.swap_buffers(),
+        };
+
+        let surface = self.surface();
+        if *surface == ffi::egl::NO_SURFACE {
+            return Err(self.no_surface_error());
+        }
+
+        let rects = rects
+            .iter()
+            .flat_map(|rect| {
+                vec![
+                    rect.x as ffi::egl::types::EGLint,
+                    rect.y as ffi::egl::types::EGLint,
+                    rect.width as ffi::egl::types::EGLint,
+                    rect.height as ffi::egl::types::EGLint,
+                ]
+            })
+            .collect::<Vec<_>>();
+        let ret = unsafe {
+            self.bind_api();
+            swap(
+                self.display,
+                *surface,
+                rects.as_ptr(),
+                (rects.len() / 4) as ffi::egl::types::EGLint,
+            )
+        };
+
+        if ret == 0 {
+            Err(swap_buffers_error("eglSwapBuffersWithDamage"))
         } else {
             Ok(())
         }
@@ -486,6 +1389,60 @@ impl Context {
         self.pixel_format.clone()
     }
 
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        DisplayToken::new("egl", self.display as usize as u64)
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        self.release_behavior
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        self.driver_workarounds
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        self.downgrades.clone()
+    }
+
+    /// The range is a property of the config the surface was created with.
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        let query = |attrib| {
+            let mut value = 0;
+            let ret = unsafe {
+                egl.GetConfigAttrib(
+                    self.display,
+                    self.config_id,
+                    attrib as ffi::egl::types::EGLint,
+                    &mut value,
+                )
+            };
+            if ret == 0 {
+                Err(ContextError::OsError(format!(
+                    "`eglGetConfigAttrib` failed (eglGetError returned 0x{:x})",
+                    unsafe { egl.GetError() }
+                )))
+            } else {
+                Ok(value)
+            }
+        };
+
+        let min = query(ffi::egl::MIN_SWAP_INTERVAL)?;
+        let max = query(ffi::egl::MAX_SWAP_INTERVAL)?;
+        Ok(SwapIntervalRange {
+            min: min.max(0) as u32,
+            max: max.max(0) as u32,
+            adaptive: false,
+        })
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::egl::types::EGLContext {
         self.context
@@ -496,6 +1453,490 @@ impl Context {
         self.display
     }
 
+    /// Whether the display counts its references, in which case it is
+    /// terminated when the context is dropped.
+    #[inline]
+    pub fn egl_display_tracks_references(&self) -> bool {
+        self.tracks_references
+    }
+
+    /// Labels the context and its current surface for `EGL_KHR_debug`.
+    pub fn set_label(&self, label: &str) -> Result<(), ContextError> {
+        let label = CString::new(label).map_err(|_| {
+            ContextError::OsError("the label contains a nul byte".to_string())
+        })?;
+        let surface = self.surface();
+        let mut labels = self.labels.lock().unwrap();
+        let res = unsafe {
+            debug::label_objects(self.display, self.context, *surface, &label)
+        };
+        if res.is_ok() {
+            labels.push(label);
+        } else {
+            debug::unregister_label(&label);
+        }
+        res
+    }
+
+    /// Queries the largest pbuffer the config of the context allows.
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        let query = |attrib| {
+            let mut value = 0;
+            let ret = unsafe {
+                egl.GetConfigAttrib(
+                    self.display,
+                    self.config_id,
+                    attrib as ffi::egl::types::EGLint,
+                    &mut value,
+                )
+            };
+            if ret == 0 {
+                return Err(ContextError::OsError(format!(
+                    "eglGetConfigAttrib failed (eglGetError returned 0x{:x})",
+                    unsafe { egl.GetError() }
+                )));
+            }
+            Ok(value as u32)
+        };
+
+        Ok(PbufferLimits {
+            max_width: query(ffi::egl::MAX_PBUFFER_WIDTH)?,
+            max_height: query(ffi::egl::MAX_PBUFFER_HEIGHT)?,
+            max_pixels: query(ffi::egl::MAX_PBUFFER_PIXELS)?,
+        })
+    }
+
+    /// Queries the properties of the surface from the driver.
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        let guard = self.surface();
+        let surface = *guard;
+        if surface == ffi::egl::NO_SURFACE {
+            return Err(self.no_surface_error());
+        }
+
+        let query = |attrib| {
+            // Attributes that don't apply to the surface are left untouched.
+            let mut value = 0;
+            let ret = unsafe {
+                egl.QuerySurface(
+                    self.display,
+                    surface,
+                    attrib as ffi::egl::types::EGLint,
+                    &mut value,
+                )
+            };
+            if ret == 0 {
+                return Err(ContextError::OsError(format!(
+                    "eglQuerySurface failed (eglGetError returned 0x{:x})",
+                    unsafe { egl.GetError() }
+                )));
+            }
+            Ok(value as u32)
+        };
+
+        let swap_behavior = match query(ffi::egl::SWAP_BEHAVIOR)? {
+            ffi::egl::BUFFER_PRESERVED => Some(SwapBehavior::Preserved),
+            ffi::egl::BUFFER_DESTROYED => Some(SwapBehavior::Destroyed),
+            _ => None,
+        };
+        let render_buffer = match query(ffi::egl::RENDER_BUFFER)? {
+            ffi::egl::BACK_BUFFER => Some(RenderBuffer::Back),
+            ffi::egl::SINGLE_BUFFER => Some(RenderBuffer::Single),
+            _ => None,
+        };
+        let multisample_resolve = match query(ffi::egl::MULTISAMPLE_RESOLVE)? {
+            ffi::egl::MULTISAMPLE_RESOLVE_DEFAULT => {
+                Some(MultisampleResolve::Default)
+            }
+            ffi::egl::MULTISAMPLE_RESOLVE_BOX => Some(MultisampleResolve::Box),
+            _ => None,
+        };
+
+        // The buffer age is only defined for the surface of the current
+        // context.
+        let buffer_age = if self.is_current()
+            && self.extensions.iter().any(|s| s == "EGL_EXT_buffer_age")
+        {
+            Some(query(ffi::egl::BUFFER_AGE_EXT)?)
+        } else {
+            None
+        };
+
+        Ok(SurfaceInfo {
+            width: query(ffi::egl::WIDTH)?,
+            height: query(ffi::egl::HEIGHT)?,
+            swap_behavior,
+            render_buffer,
+            multisample_resolve,
+            buffer_age,
+            largest_pbuffer: query(ffi::egl::LARGEST_PBUFFER)? != 0,
+        })
+    }
+
+    /// Sets what happens to the color buffer of the surface after a swap.
+    ///
+    /// Preserving the color buffer requires a config that was chosen with
+    /// `SWAP_BEHAVIOR_PRESERVED_BIT`, otherwise
+    /// `ContextError::FunctionUnavailable` is returned.
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        let guard = self.surface();
+        let surface = *guard;
+        if surface == ffi::egl::NO_SURFACE {
+            return Err(self.no_surface_error());
+        }
+
+        let value = match behavior {
+            SwapBehavior::Preserved => ffi::egl::BUFFER_PRESERVED,
+            SwapBehavior::Destroyed => ffi::egl::BUFFER_DESTROYED,
+        };
+        let ret = unsafe {
+            egl.SurfaceAttrib(
+                self.display,
+                surface,
+                ffi::egl::SWAP_BEHAVIOR as ffi::egl::types::EGLint,
+                value as ffi::egl::types::EGLint,
+            )
+        };
+
+        if ret == 0 {
+            match unsafe { egl.GetError() } as u32 {
+                ffi::egl::BAD_MATCH => Err(ContextError::FunctionUnavailable),
+                ffi::egl::CONTEXT_LOST => Err(ContextError::ContextLost),
+                err => Err(ContextError::OsError(format!(
+                    "eglSurfaceAttrib failed (eglGetError returned 0x{:x})",
+                    err
+                ))),
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Chooses the mipmap level of the pbuffer rendered to, with
+    /// `EGL_MIPMAP_LEVEL`.
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        level: i32,
+    ) -> Result<(), ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        let guard = self.surface();
+        match self.pbuffer_texture {
+            Some(texture) if texture.mipmap => (),
+            _ => return Err(ContextError::FunctionUnavailable),
+        }
+
+        let ret = unsafe {
+            egl.SurfaceAttrib(
+                self.display,
+                *guard,
+                ffi::egl::MIPMAP_LEVEL as ffi::egl::types::EGLint,
+                level,
+            )
+        };
+        if ret == 0 {
+            return Err(texture_error("eglSurfaceAttrib"));
+        }
+        Ok(())
+    }
+
+    /// Binds the color buffer of the pbuffer to the texture bound to
+    /// `GL_TEXTURE_2D` in the current context with `eglBindTexImage`, or
+    /// releases it with `eglReleaseTexImage`.
+    pub fn bind_pbuffer_tex_image(
+        &self,
+        bind: bool,
+    ) -> Result<(), ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        let guard = self.surface();
+        if self.pbuffer_texture.is_none() {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        // the call is meant to be ignored then, but crashes some drivers
+        if unsafe { egl.GetCurrentContext() } == ffi::egl::NO_CONTEXT {
+            return Err(ContextError::NotCurrent);
+        }
+
+        let buffer = ffi::egl::BACK_BUFFER as ffi::egl::types::EGLint;
+        let (ret, function) = unsafe {
+            if bind {
+                (
+                    egl.BindTexImage(self.display, *guard, buffer),
+                    "eglBindTexImage",
+                )
+            } else {
+                (
+                    egl.ReleaseTexImage(self.display, *guard, buffer),
+                    "eglReleaseTexImage",
+                )
+            }
+        };
+        if ret == 0 {
+            return Err(texture_error(function));
+        }
+        Ok(())
+    }
+
+    /// Returns whether the render buffer of the surface can be switched with
+    /// `set_render_buffer`, its config supporting
+    /// `EGL_KHR_mutable_render_buffer`.
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        let egl = EGL.as_ref().unwrap();
+        if *self.surface() == ffi::egl::NO_SURFACE {
+            return false;
+        }
+        let mut surface_type = 0;
+        let ret = unsafe {
+            egl.GetConfigAttrib(
+                self.display,
+                self.config_id,
+                ffi::egl::SURFACE_TYPE as ffi::egl::types::EGLint,
+                &mut surface_type,
+            )
+        };
+        ret != 0
+            && surface_type as ffi::egl::types::EGLenum
+                & ffi::egl::MUTABLE_RENDER_BUFFER_BIT_KHR
+                != 0
+    }
+
+    /// Switches the surface between single and double buffering with
+    /// `EGL_KHR_mutable_render_buffer`, which takes effect after the next
+    /// swap.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the config of the
+    /// surface doesn't support it.
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        if !self.is_render_buffer_mutable() {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        let egl = EGL.as_ref().unwrap();
+        let guard = self.surface();
+        let value = match render_buffer {
+            RenderBuffer::Back => ffi::egl::BACK_BUFFER,
+            RenderBuffer::Single => ffi::egl::SINGLE_BUFFER,
+        };
+        let ret = unsafe {
+            egl.SurfaceAttrib(
+                self.display,
+                *guard,
+                ffi::egl::RENDER_BUFFER as ffi::egl::types::EGLint,
+                value as ffi::egl::types::EGLint,
+            )
+        };
+
+        if ret == 0 {
+            match unsafe { egl.GetError() } as u32 {
+                ffi::egl::CONTEXT_LOST => Err(ContextError::ContextLost),
+                err => Err(ContextError::OsError(format!(
+                    "eglSurfaceAttrib failed (eglGetError returned 0x{:x})",
+                    err
+                ))),
+            }
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Sets the metadata of `EGL_EXT_surface_SMPTE2086_metadata` and
+    /// `EGL_EXT_surface_CTA861_3_metadata` that are supported.
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &HdrMetadata,
+    ) -> Result<(), ContextError> {
+        let has_extension = |ext| self.extensions.iter().any(|s| s == ext);
+        let smpte2086 = has_extension("EGL_EXT_surface_SMPTE2086_metadata");
+        let cta861_3 = has_extension("EGL_EXT_surface_CTA861_3_metadata");
+        if !smpte2086 && !cta861_3 {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        let guard = self.surface();
+        let surface = *guard;
+        if surface == ffi::egl::NO_SURFACE {
+            return Err(self.no_surface_error());
+        }
+
+        let mut attribs = Vec::new();
+        if smpte2086 {
+            let [red, green, blue] = metadata.display_primaries;
+            let (white_x, white_y) = metadata.white_point;
+            attribs.extend_from_slice(&[
+                (ffi::egl::SMPTE2086_DISPLAY_PRIMARY_RX_EXT, red.0),
+                (ffi::egl::SMPTE2086_DISPLAY_PRIMARY_RY_EXT, red.1),
+                (ffi::egl::SMPTE2086_DISPLAY_PRIMARY_GX_EXT, green.0),
+                (ffi::egl::SMPTE2086_DISPLAY_PRIMARY_GY_EXT, green.1),
+                (ffi::egl::SMPTE2086_DISPLAY_PRIMARY_BX_EXT, blue.0),
+                (ffi::egl::SMPTE2086_DISPLAY_PRIMARY_BY_EXT, blue.1),
+                (ffi::egl::SMPTE2086_WHITE_POINT_X_EXT, white_x),
+                (ffi::egl::SMPTE2086_WHITE_POINT_Y_EXT, white_y),
+                (ffi::egl::SMPTE2086_MAX_LUMINANCE_EXT, metadata.max_luminance),
+                (ffi::egl::SMPTE2086_MIN_LUMINANCE_EXT, metadata.min_luminance),
+            ]);
+        }
+        if cta861_3 {
+            attribs.extend_from_slice(&[
+                (
+                    ffi::egl::CTA861_3_MAX_CONTENT_LIGHT_LEVEL_EXT,
+                    metadata.max_content_light_level,
+                ),
+                (
+                    ffi::egl::CTA861_3_MAX_FRAME_AVERAGE_LEVEL_EXT,
+                    metadata.max_frame_average_light_level,
+                ),
+            ]);
+        }
+
+        let egl = EGL.as_ref().unwrap();
+        for &(attrib, value) in &attribs {
+            // the values are fixed-point numbers
+            let scaling = ffi::egl::METADATA_SCALING_EXT as f32;
+            let value = (value * scaling).round();
+            let ret = unsafe {
+                egl.SurfaceAttrib(
+                    self.display,
+                    surface,
+                    attrib as ffi::egl::types::EGLint,
+                    value as ffi::egl::types::EGLint,
+                )
+            };
+            if ret == 0 {
+                let err = unsafe { egl.GetError() } as u32;
+                if err == ffi::egl::CONTEXT_LOST {
+                    return Err(ContextError::ContextLost);
+                }
+                return Err(ContextError::OsError(format!(
+                    "eglSurfaceAttrib failed (eglGetError returned 0x{:x})",
+                    err
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Enables or disables `EGL_FRONT_BUFFER_AUTO_REFRESH_ANDROID` on the
+    /// surface, letting the compositor pick up front buffer rendering without
+    /// a swap.
+    #[cfg(target_os = "android")]
+    pub fn set_front_buffer_auto_refresh(
+        &self,
+        enabled: bool,
+    ) -> Result<(), ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        if !self
+            .extensions
+            .iter()
+            .any(|s| s == "EGL_ANDROID_front_buffer_auto_refresh")
+        {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        let guard = self.surface();
+        let surface = *guard;
+        if surface == ffi::egl::NO_SURFACE {
+            return Err(self.no_surface_error());
+        }
+
+        let ret = unsafe {
+            egl.SurfaceAttrib(
+                self.display,
+                surface,
+                ffi::egl::FRONT_BUFFER_AUTO_REFRESH_ANDROID
+                    as ffi::egl::types::EGLint,
+                enabled as ffi::egl::types::EGLint,
+            )
+        };
+
+        if ret == 0 {
+            Err(ContextError::OsError(format!(
+                "eglSurfaceAttrib failed (eglGetError returned 0x{:x})",
+                unsafe { egl.GetError() }
+            )))
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Replaces the pbuffer surface with a new one of the given dimensions,
+    /// or of the largest available ones if `largest` is set, and returns the
+    /// dimensions obtained.
+    ///
+    /// Pbuffers can't be resized, so we create a new one with the same config
+    /// and, if the context was current, make it current with the new surface
+    /// before destroying the old one.
+    #[cfg(any(
+        target_os = "android",
+        target_os = "windows",
+        target_os = "macos"
+    ))]
+    pub fn resize_pbuffer(
+        &self,
+        dimensions: (u32, u32),
+        largest: bool,
+    ) -> Result<(u32, u32), ContextError> {
+        let egl = EGL.as_ref().unwrap();
+        if self.surfaceless {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        let mut old_surface = self.surface();
+        unsafe {
+            let surface = create_pbuffer_surface(
+                self.display,
+                self.config_id,
+                dimensions,
+                self.pixel_format.srgb,
+                largest,
+                self.pbuffer_texture,
+            );
+            if surface.is_null() {
+                return Err(ContextError::OsError(format!(
+                    "eglCreatePbufferSurface failed (eglGetError returned 0x{:x})",
+                    egl.GetError()
+                )));
+            }
+
+            if self.is_current() {
+                if egl.MakeCurrent(self.display, surface, surface, self.context)
+                    == 0
+                {
+                    let err = egl.GetError();
+                    egl.DestroySurface(self.display, surface);
+                    return match err as u32 {
+                        ffi::egl::CONTEXT_LOST => {
+                            Err(ContextError::ContextLost)
+                        }
+                        err => Err(ContextError::OsError(format!(
+                            "eglMakeCurrent failed (eglGetError returned 0x{:x})",
+                            err
+                        ))),
+                    };
+                }
+            }
+
+            egl.DestroySurface(self.display, *old_surface);
+            *old_surface = surface;
+
+            let query = |attrib| {
+                let mut value = 0;
+                egl.QuerySurface(
+                    self.display,
+                    surface,
+                    attrib as ffi::egl::types::EGLint,
+                    &mut value,
+                );
+                value as u32
+            };
+            Ok((query(ffi::egl::WIDTH), query(ffi::egl::HEIGHT)))
+        }
+    }
+
     // Handle Android Life Cycle.
     // Android has started the activity or sent it to foreground.
     // Create a new surface and attach it to the recreated ANativeWindow.
@@ -506,27 +1947,33 @@ impl Context {
         native_window: ffi::EGLNativeWindowType,
     ) {
         let egl = EGL.as_ref().unwrap();
-        if self.surface.get() != ffi::egl::NO_SURFACE {
+        let mut surface = self.surface();
+        if *surface != ffi::egl::NO_SURFACE {
             return;
         }
-        self.surface.set(egl.CreateWindowSurface(
+        // the displays of Android are always obtained with `eglGetDisplay`
+        *surface = create_window_surface(
             self.display,
+            PlatformDisplay::Native,
             self.config_id,
+            &self.extensions,
             native_window,
-            ptr::null(),
-        ));
-        if self.surface.get().is_null() {
+            &WindowSurfaceAttribs {
+                double_buffer: self.pixel_format.double_buffer,
+                srgb: self.pixel_format.srgb,
+                extra: &self.surface_attribs,
+            },
+        );
+        if surface.is_null() {
             panic!("on_surface_created: eglCreateWindowSurface failed")
         }
-        let ret = egl.MakeCurrent(
-            self.display,
-            self.surface.get(),
-            self.surface.get(),
-            self.context,
-        );
+        self.bind_api();
+        let ret =
+            egl.MakeCurrent(self.display, *surface, *surface, self.context);
         if ret == 0 {
             panic!("on_surface_created: eglMakeCurrent failed");
         }
+        let _ = self.apply_swap_interval(*surface);
     }
 
     // Handle Android Life Cycle.
@@ -536,9 +1983,11 @@ impl Context {
     #[cfg(target_os = "android")]
     pub unsafe fn on_surface_destroyed(&self) {
         let egl = EGL.as_ref().unwrap();
-        if self.surface.get() == ffi::egl::NO_SURFACE {
+        let mut surface = self.surface();
+        if *surface == ffi::egl::NO_SURFACE {
             return;
         }
+        self.bind_api();
         let ret = egl.MakeCurrent(
             self.display,
             ffi::egl::NO_SURFACE,
@@ -549,23 +1998,89 @@ impl Context {
             panic!("on_surface_destroyed: eglMakeCurrent failed");
         }
 
-        egl.DestroySurface(self.display, self.surface.get());
-        self.surface.set(ffi::egl::NO_SURFACE);
+        egl.DestroySurface(self.display, *surface);
+        *surface = ffi::egl::NO_SURFACE;
+        self.swap_interval
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .forget_drawable();
     }
 }
 
+// EGL objects can be used from any thread, as long as the context is only
+// current on one thread at a time, which EGL itself enforces. The surface is
+// behind a lock since it can be replaced through a shared reference.
 unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
 
-impl Drop for Context {
-    fn drop(&mut self) {
+impl Context {
+    /// Destroys the surface, then the context and, if it counts its
+    /// references, the display, returning the first failure.
+    ///
+    /// The context is released first if it is current on the calling
+    /// thread, otherwise EGL only destroys it once it is released from the
+    /// thread it is current on. Does nothing if it was already called.
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        if self.destroyed.swap(true, Ordering::SeqCst) {
+            return Ok(());
+        }
         let egl = EGL.as_ref().unwrap();
+        let failed = |function: &str| {
+            ContextError::OsError(format!(
+                "{} failed: 0x{:x}",
+                function,
+                unsafe { egl.GetError() }
+            ))
+        };
+        let mut res = Ok(());
         unsafe {
-            // we don't call MakeCurrent(0, 0) because we are not sure that the
-            // context is still the current one
-            egl.DestroyContext(self.display, self.context);
-            egl.DestroySurface(self.display, self.surface.get());
-            egl.Terminate(self.display);
+            if self.ownership == Ownership::Owned {
+                if self.is_current()
+                    && egl.MakeCurrent(
+                        self.display,
+                        ffi::egl::NO_SURFACE,
+                        ffi::egl::NO_SURFACE,
+                        ffi::egl::NO_CONTEXT,
+                    ) == 0
+                {
+                    res = Err(failed("eglMakeCurrent"));
+                }
+                let mut surface = self.surface();
+                if *surface != ffi::egl::NO_SURFACE
+                    && egl.DestroySurface(self.display, *surface) == 0
+                    && res.is_ok()
+                {
+                    res = Err(failed("eglDestroySurface"));
+                }
+                *surface = ffi::egl::NO_SURFACE;
+                if egl.DestroyContext(self.display, self.context) == 0
+                    && res.is_ok()
+                {
+                    res = Err(failed("eglDestroyContext"));
+                }
+            }
+            // `eglGetDisplay` returns the same display to every user of a
+            // native display, so terminating one that doesn't count its
+            // references would destroy the contexts of the others, and the
+            // initialization is leaked instead
+            if self.tracks_references
+                && egl.Terminate(self.display) == 0
+                && res.is_ok()
+            {
+                res = Err(failed("eglTerminate"));
+            }
+        }
+        res
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        // nothing can be done about a failure here, which `destroy` returns
+        // and the callback of `set_egl_debug_callback` reports
+        let _ = self.destroy();
+        for label in &*self.labels.lock().unwrap() {
+            debug::unregister_label(label);
         }
     }
 }
@@ -573,12 +2088,34 @@ impl Drop for Context {
 pub struct ContextPrototype<'a> {
     opengl: &'a GlAttributes<&'a Context>,
     display: ffi::egl::types::EGLDisplay,
+    platform: PlatformDisplay,
+    /// Whether the display is an X11 one.
+    x11: bool,
     egl_version: (ffi::egl::types::EGLint, ffi::egl::types::EGLint),
     extensions: Vec<String>,
     api: Api,
     version: Option<(u8, u8)>,
     config_id: ffi::egl::types::EGLConfig,
     pixel_format: PixelFormat,
+    // The surface properties requested, the pixel format holds the ones
+    // obtained once the surface is created.
+    double_buffer: bool,
+    srgb: bool,
+    release_behavior: ReleaseBehavior,
+    driver_workarounds: DriverWorkarounds,
+    tracks_references: bool,
+    /// Whether the surface may be created with a lesser config or size when
+    /// the driver has no memory for it.
+    downgrade_on_alloc_failure: bool,
+    /// What was given up to create the surface so far.
+    downgrades: Vec<SurfaceDowngrade>,
+    /// How the pbuffer must be bindable to a texture, if it must be.
+    pbuffer_texture: Option<PbufferTexture>,
+    /// The attributes added to the ones the config was chosen with, which a
+    /// downgraded config must keep.
+    config_attribs: Vec<(u32, c_int)>,
+    /// The attributes added to the ones of the window surface.
+    surface_attribs: Vec<(u32, c_int)>,
 }
 
 impl<'a> ContextPrototype<'a> {
@@ -600,69 +2137,325 @@ impl<'a> ContextPrototype<'a> {
     }
 
     pub fn finish(
-        self,
+        mut self,
         native_window: ffi::EGLNativeWindowType,
     ) -> Result<Context, CreationError> {
         let egl = EGL.as_ref().unwrap();
         let surface = unsafe {
-            let surface = egl.CreateWindowSurface(
+            let srgb = self.srgb
+                && colorspace_supported(&self.egl_version, &self.extensions);
+            loop {
+                let surface = create_window_surface(
+                    self.display,
+                    self.platform,
+                    self.config_id,
+                    &self.extensions,
+                    native_window,
+                    &WindowSurfaceAttribs {
+                        double_buffer: self.double_buffer,
+                        srgb,
+                        extra: &self.surface_attribs,
+                    },
+                );
+                if !surface.is_null() {
+                    break surface;
+                }
+                let error = egl.GetError() as u32;
+                if error != ffi::egl::BAD_ALLOC || !self.downgrade_config()? {
+                    return Err(CreationError::OsError(format!(
+                        "eglCreateWindowSurface failed (eglGetError returned \
+                         0x{:x})",
+                        error
+                    )));
+                }
+            }
+        };
+
+        self.finish_impl(surface)
+    }
+
+    /// Finishes the context with a surface rendering to `native_pixmap`,
+    /// which must outlive it. The config must have been chosen for pixmaps.
+    pub fn finish_pixmap(
+        self,
+        native_pixmap: ffi::EGLNativePixmapType,
+    ) -> Result<Context, CreationError> {
+        let surface = unsafe {
+            let srgb = self.srgb
+                && colorspace_supported(&self.egl_version, &self.extensions);
+            create_pixmap_surface(
                 self.display,
+                self.platform,
                 self.config_id,
-                native_window,
-                ptr::null(),
-            );
-            if surface.is_null() {
-                return Err(CreationError::OsError(format!(
-                    "eglCreateWindowSurface failed"
-                )));
-            }
-            surface
+                native_pixmap,
+                srgb,
+            )
         };
+        if surface.is_null() {
+            let error = unsafe { EGL.as_ref().unwrap().GetError() };
+            return Err(CreationError::OsError(format!(
+                "eglCreatePixmapSurface failed (eglGetError returned 0x{:x})",
+                error
+            )));
+        }
 
         self.finish_impl(surface)
     }
 
-    #[cfg(any(target_os = "android", target_os = "windows"))]
     pub fn finish_pbuffer(
-        self,
+        mut self,
         dimensions: (u32, u32),
     ) -> Result<Context, CreationError> {
         let egl = EGL.as_ref().unwrap();
-        let attrs = &[
-            ffi::egl::WIDTH as c_int,
-            dimensions.0 as c_int,
-            ffi::egl::HEIGHT as c_int,
-            dimensions.1 as c_int,
-            ffi::egl::NONE as c_int,
-        ];
-
+        let mut obtained = dimensions;
         let surface = unsafe {
-            let surface = egl.CreatePbufferSurface(
-                self.display,
-                self.config_id,
-                attrs.as_ptr(),
-            );
-            if surface.is_null() {
+            let srgb = self.srgb
+                && colorspace_supported(&self.egl_version, &self.extensions);
+            loop {
+                let surface = create_pbuffer_surface(
+                    self.display,
+                    self.config_id,
+                    obtained,
+                    srgb,
+                    false,
+                    self.pbuffer_texture,
+                );
+                if !surface.is_null() {
+                    break surface;
+                }
+                let error = egl.GetError() as u32;
+                if error == ffi::egl::BAD_ALLOC && self.downgrade_config()? {
+                    continue;
+                }
+                // the size is only reduced once the config can't be anymore
+                if error == ffi::egl::BAD_ALLOC
+                    && self.downgrade_on_alloc_failure
+                    && obtained != (1, 1)
+                {
+                    // halved, rounding up
+                    obtained.0 -= obtained.0 / 2;
+                    obtained.1 -= obtained.1 / 2;
+                    continue;
+                }
                 return Err(CreationError::OsError(format!(
-                    "eglCreatePbufferSurface failed"
+                    "eglCreatePbufferSurface failed (eglGetError returned \
+                     0x{:x})",
+                    error
                 )));
             }
-            surface
+        };
+        if obtained != dimensions {
+            self.downgrades.push(SurfaceDowngrade::PbufferSize {
+                requested: dimensions,
+                obtained,
+            });
+        }
+
+        let pbuffer_texture = self.pbuffer_texture;
+        let mut context = self.finish_impl(surface)?;
+        context.pbuffer_texture = pbuffer_texture;
+        Ok(context)
+    }
+
+    /// Replaces the config with the next one down after the driver ran out
+    /// of memory for a surface, if allowed: the same config without
+    /// multisampling, then with smaller depth and stencil buffers. Returns
+    /// whether there was one.
+    unsafe fn downgrade_config(&mut self) -> Result<bool, CreationError> {
+        if !self.downgrade_on_alloc_failure {
+            return Ok(false);
+        }
+        let egl = EGL.as_ref().unwrap();
+        let mut num_configs = 0;
+        if egl.GetConfigs(self.display, ptr::null_mut(), 0, &mut num_configs)
+            == 0
+        {
+            let msg = "eglGetConfigs failed".to_string();
+            return Err(CreationError::OsError(msg));
+        }
+        let mut configs = vec![ptr::null(); num_configs as usize];
+        if egl.GetConfigs(
+            self.display,
+            configs.as_mut_ptr(),
+            num_configs,
+            &mut num_configs,
+        ) == 0
+        {
+            let msg = "eglGetConfigs failed".to_string();
+            return Err(CreationError::OsError(msg));
+        }
+        configs.truncate(num_configs as usize);
+
+        let display = self.display;
+        let attrib = |config, attrib: u32| {
+            let mut value = 0;
+            egl.GetConfigAttrib(
+                display,
+                config,
+                attrib as ffi::egl::types::EGLint,
+                &mut value,
+            );
+            value
+        };
+        // everything but the samples, depth and stencil must stay the same
+        let current = self.config_id;
+        let compatible = |config| {
+            let superset = |key| {
+                let value = attrib(current, key);
+                attrib(config, key) & value == value
+            };
+            let same = |key| attrib(config, key) == attrib(current, key);
+            superset(ffi::egl::SURFACE_TYPE)
+                && superset(ffi::egl::RENDERABLE_TYPE)
+                && superset(ffi::egl::CONFORMANT)
+                && same(ffi::egl::COLOR_BUFFER_TYPE)
+                && same(ffi::egl::RED_SIZE)
+                && same(ffi::egl::GREEN_SIZE)
+                && same(ffi::egl::BLUE_SIZE)
+                && same(ffi::egl::ALPHA_SIZE)
+                && same(ffi::egl::CONFIG_CAVEAT)
+                && same(ffi::egl::NATIVE_VISUAL_ID)
+                && same(ffi::egl::TRANSPARENT_TYPE)
+                && same(ffi::egl::LEVEL)
+                && same(ffi::egl::BIND_TO_TEXTURE_RGB)
+                && same(ffi::egl::BIND_TO_TEXTURE_RGBA)
+                && self.config_attribs.iter().all(|&(key, _)| same(key))
+        };
+        let depth_stencil = |config| {
+            (
+                attrib(config, ffi::egl::DEPTH_SIZE),
+                attrib(config, ffi::egl::STENCIL_SIZE),
+            )
+        };
+        let (depth, stencil) = depth_stencil(current);
+        let samples = attrib(current, ffi::egl::SAMPLES);
+
+        // multisampling goes first, keeping the largest buffers possible,
+        // then the buffers are reduced one step at a time
+        let next = configs
+            .into_iter()
+            .filter(|&c| {
+                let (d, s) = depth_stencil(c);
+                compatible(c)
+                    && attrib(c, ffi::egl::SAMPLES) == 0
+                    && d <= depth
+                    && s <= stencil
+                    && (samples > 0 || (d, s) != (depth, stencil))
+            })
+            .max_by_key(|&c| depth_stencil(c));
+        let next = match next {
+            Some(next) => next,
+            None => return Ok(false),
+        };
+
+        let pixel_format = describe_config(
+            egl,
+            self.display,
+            &self.extensions,
+            next,
+            self.x11,
+        )?;
+        if samples > 0 {
+            self.downgrades.push(SurfaceDowngrade::Multisampling {
+                samples: samples as u16,
+            });
+        }
+        if depth_stencil(next) != (depth, stencil) {
+            self.downgrades.push(SurfaceDowngrade::DepthStencil {
+                requested: (depth as u8, stencil as u8),
+                obtained: (pixel_format.depth_bits, pixel_format.stencil_bits),
+            });
+        }
+        self.config_id = next;
+        self.pixel_format = pixel_format;
+        Ok(true)
+    }
+
+    /// Finishes building a context without a surface if
+    /// `EGL_KHR_surfaceless_context` is supported, or with a 1x1 pbuffer
+    /// otherwise.
+    ///
+    /// OpenGL ES contexts also need `GL_OES_surfaceless_context`, which is
+    /// only known once the context exists, so they are given the pbuffer
+    /// afterwards if it's missing.
+    pub fn finish_surfaceless(self) -> Result<Context, CreationError> {
+        // a pbuffer that must be bound to a texture can't be left out
+        if self.pbuffer_texture.is_some()
+            || !self
+                .extensions
+                .iter()
+                .any(|s| s == "EGL_KHR_surfaceless_context")
+        {
+            return self.finish_pbuffer((1, 1));
+        }
+
+        let egl_version = self.egl_version;
+        let srgb =
+            self.srgb && colorspace_supported(&egl_version, &self.extensions);
+        let mut context = self.finish_impl(ffi::egl::NO_SURFACE)?;
+        unsafe {
+            if context.binds_without_surface() {
+                return Ok(context);
+            }
+
+            let surface = create_pbuffer_surface(
+                context.display,
+                context.config_id,
+                (1, 1),
+                srgb,
+                false,
+                None,
+            );
+            if surface.is_null() {
+                let msg = "eglCreatePbufferSurface failed".to_string();
+                return Err(CreationError::OsError(msg));
+            }
+            context.pixel_format = query_surface_format(
+                context.display,
+                &egl_version,
+                &context.extensions,
+                surface,
+                context.pixel_format.clone(),
+            );
+            *context.surface() = surface;
+            context.surfaceless = false;
+        }
+        Ok(context)
+    }
+
+    fn finish_impl(
+        self,
+        surface: ffi::egl::types::EGLSurface,
+    ) -> Result<Context, CreationError> {
+        let surfaceless = surface == ffi::egl::NO_SURFACE;
+        // without a surface, there is no framebuffer to describe beyond the
+        // config
+        let pixel_format = if surfaceless {
+            self.pixel_format.clone()
+        } else {
+            unsafe {
+                query_surface_format(
+                    self.display,
+                    &self.egl_version,
+                    &self.extensions,
+                    surface,
+                    self.pixel_format.clone(),
+                )
+            }
         };
 
-        self.finish_impl(surface)
-    }
-
-    fn finish_impl(
-        self,
-        surface: ffi::egl::types::EGLSurface,
-    ) -> Result<Context, CreationError> {
         let share = match self.opengl.sharing {
             Some(ctx) => ctx.context,
             None => ptr::null(),
         };
 
-        let context = unsafe {
+        // another context may have been created on this thread since the
+        // API was chosen
+        if let Some(client_api) = client_api(&self.egl_version, self.api) {
+            unsafe { EGL.as_ref().unwrap().BindAPI(client_api) };
+        }
+
+        let (context, driver_workarounds) = unsafe {
             if let Some(version) = self.version {
                 create_context(
                     self.display,
@@ -671,8 +2464,12 @@ impl<'a> ContextPrototype<'a> {
                     self.api,
                     version,
                     self.config_id,
+                    self.opengl.profile,
+                    self.opengl.forward_compatible,
                     self.opengl.debug,
                     self.opengl.robustness,
+                    self.release_behavior,
+                    self.driver_workarounds,
                     share,
                 )?
             } else if self.api == Api::OpenGlEs {
@@ -683,8 +2480,12 @@ impl<'a> ContextPrototype<'a> {
                     self.api,
                     (2, 0),
                     self.config_id,
+                    self.opengl.profile,
+                    self.opengl.forward_compatible,
                     self.opengl.debug,
                     self.opengl.robustness,
+                    self.release_behavior,
+                    self.driver_workarounds,
                     share,
                 ) {
                     ctx
@@ -695,8 +2496,12 @@ impl<'a> ContextPrototype<'a> {
                     self.api,
                     (1, 0),
                     self.config_id,
+                    self.opengl.profile,
+                    self.opengl.forward_compatible,
                     self.opengl.debug,
                     self.opengl.robustness,
+                    self.release_behavior,
+                    self.driver_workarounds,
                     share,
                 ) {
                     ctx
@@ -711,8 +2516,12 @@ impl<'a> ContextPrototype<'a> {
                     self.api,
                     (3, 2),
                     self.config_id,
+                    self.opengl.profile,
+                    self.opengl.forward_compatible,
                     self.opengl.debug,
                     self.opengl.robustness,
+                    self.release_behavior,
+                    self.driver_workarounds,
                     share,
                 ) {
                     ctx
@@ -723,8 +2532,12 @@ impl<'a> ContextPrototype<'a> {
                     self.api,
                     (3, 1),
                     self.config_id,
+                    self.opengl.profile,
+                    self.opengl.forward_compatible,
                     self.opengl.debug,
                     self.opengl.robustness,
+                    self.release_behavior,
+                    self.driver_workarounds,
                     share,
                 ) {
                     ctx
@@ -735,8 +2548,12 @@ impl<'a> ContextPrototype<'a> {
                     self.api,
                     (1, 0),
                     self.config_id,
+                    self.opengl.profile,
+                    self.opengl.forward_compatible,
                     self.opengl.debug,
                     self.opengl.robustness,
+                    self.release_behavior,
+                    self.driver_workarounds,
                     share,
                 ) {
                     ctx
@@ -746,119 +2563,492 @@ impl<'a> ContextPrototype<'a> {
             }
         };
 
+        let get_all_proc_addresses =
+            get_all_proc_addresses(&self.egl_version, &self.extensions);
+        let swap_buffers_with_damage =
+            load_swap_buffers_with_damage(&self.extensions);
+
+        // the interval of the driver is left alone without vsync
+        let swap_interval = if self.opengl.vsync { Some(1) } else { None };
         Ok(Context {
             display: self.display,
             context: context,
-            surface: Cell::new(surface),
+            surface: Mutex::new(surface),
+            swap_interval: Mutex::new(SwapIntervalTracker::new(swap_interval)),
             api: self.api,
-            pixel_format: self.pixel_format,
-            #[cfg(target_os = "android")]
+            client_api: client_api(&self.egl_version, self.api),
+            pixel_format,
+            release_behavior: self.release_behavior,
+            driver_workarounds,
+            extensions: self.extensions,
+            get_all_proc_addresses,
+            swap_buffers_with_damage,
             config_id: self.config_id,
+            surfaceless,
+            ownership: Ownership::Owned,
+            tracks_references: self.tracks_references,
+            labels: Mutex::new(Vec::new()),
+            downgrades: self.downgrades,
+            pbuffer_texture: None,
+            #[cfg(target_os = "android")]
+            surface_attribs: self.surface_attribs,
+            destroyed: AtomicBool::new(false),
+            _user: EGL.user(),
+        })
+    }
+}
+
+/// Returns the extensions of an initialized display, which differ from the
+/// client extensions obtained before.
+fn get_display_extensions(
+    egl: &Egl,
+    display: ffi::egl::types::EGLDisplay,
+    egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+) -> Vec<String> {
+    if *egl_version < (1, 2) {
+        return vec![];
+    }
+    let p = unsafe {
+        CStr::from_ptr(egl.QueryString(display, ffi::egl::EXTENSIONS as i32))
+    };
+    let list = String::from_utf8(p.to_bytes().to_vec())
+        .unwrap_or_else(|_| String::new());
+    list.split(' ').map(|e| e.to_string()).collect::<Vec<_>>()
+}
+
+/// Whether `eglGetProcAddress` also returns the core functions.
+fn get_all_proc_addresses(
+    egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+    extensions: &[String],
+) -> bool {
+    *egl_version >= (1, 5)
+        || extensions
+            .iter()
+            .any(|s| s == "EGL_KHR_get_all_proc_addresses")
+}
+
+/// Whether `display` was obtained with `EGL_TRACK_REFERENCES_KHR`, in which
+/// case each `eglInitialize` of it must be matched by an `eglTerminate`.
+#[cfg(not(target_os = "android"))]
+unsafe fn tracks_references(
+    egl: &Egl,
+    display: ffi::egl::types::EGLDisplay,
+) -> bool {
+    let has_extension = get_client_extensions(egl)
+        .iter()
+        .any(|s| s == "EGL_KHR_display_reference");
+    if !has_extension || !egl.QueryDisplayAttribKHR.is_loaded() {
+        return false;
+    }
+    let mut value = 0;
+    egl.QueryDisplayAttribKHR(
+        display,
+        ffi::egl::TRACK_REFERENCES_KHR as ffi::egl::types::EGLint,
+        &mut value,
+    ) != 0
+        && value == ffi::egl::TRUE as ffi::egl::types::EGLAttrib
+}
+
+/// The displays of Android come from `eglGetDisplay`, which can't track
+/// references.
+#[cfg(target_os = "android")]
+unsafe fn tracks_references(
+    _: &Egl,
+    _: ffi::egl::types::EGLDisplay,
+) -> bool {
+    false
+}
+
+/// Loads `eglSwapBuffersWithDamage{KHR,EXT}`, at runtime since the Android
+/// bindings are linked statically and the extension may be missing there.
+fn load_swap_buffers_with_damage(
+    extensions: &[String],
+) -> Option<SwapBuffersWithDamageFn> {
+    ["KHR", "EXT"]
+        .iter()
+        .filter(|vendor| {
+            let ext = format!("EGL_{}_swap_buffers_with_damage", vendor);
+            extensions.iter().any(|s| s == &ext)
         })
+        .filter_map(|vendor| unsafe {
+            let name = format!("eglSwapBuffersWithDamage{}\0", vendor);
+            let egl = EGL.as_ref().unwrap();
+            let p =
+                egl.GetProcAddress(name.as_ptr() as *const _) as *const c_void;
+            if p.is_null() {
+                None
+            } else {
+                Some(mem::transmute::<*const c_void, SwapBuffersWithDamageFn>(
+                    p,
+                ))
+            }
+        })
+        .next()
+}
+
+/// The attributes a window surface is created with.
+struct WindowSurfaceAttribs<'a> {
+    /// Whether to render to the back buffer rather than the front buffer.
+    double_buffer: bool,
+    /// Whether to request the sRGB color space. The caller checks that the
+    /// colorspace attribute is supported.
+    srgb: bool,
+    /// Attributes set by the user, overriding those above.
+    extra: &'a [(u32, c_int)],
+}
+
+unsafe fn create_window_surface(
+    display: ffi::egl::types::EGLDisplay,
+    platform: PlatformDisplay,
+    config_id: ffi::egl::types::EGLConfig,
+    extensions: &[String],
+    native_window: ffi::EGLNativeWindowType,
+    attribs: &WindowSurfaceAttribs,
+) -> ffi::egl::types::EGLSurface {
+    let egl = EGL.as_ref().unwrap();
+    let render_buffer = if attribs.double_buffer {
+        ffi::egl::BACK_BUFFER
+    } else {
+        ffi::egl::SINGLE_BUFFER
+    };
+    let mut attrs = AttribList::new(ffi::egl::NONE);
+    attrs.push(ffi::egl::RENDER_BUFFER, render_buffer);
+    if attribs.srgb {
+        attrs.push(ffi::egl::GL_COLORSPACE, ffi::egl::GL_COLORSPACE_SRGB);
+    }
+    for &(key, value) in attribs.extra {
+        attrs.remove(key);
+        attrs.push(key, value);
+    }
+
+    // the platform functions take a pointer to the `Window` on X11, and the
+    // `wl_egl_window` or `gbm_surface` itself elsewhere
+    #[cfg(not(target_os = "android"))]
+    let x11_window = native_window as c_ulong;
+    #[cfg(not(target_os = "android"))]
+    let platform_window = |platform| {
+        // `EGL_PLATFORM_X11_EXT` has the same value
+        if platform == ffi::egl::PLATFORM_X11_KHR {
+            &x11_window as *const c_ulong as *mut c_void
+        } else {
+            native_window as *mut c_void
+        }
+    };
+
+    let surface = match platform {
+        #[cfg(not(target_os = "android"))]
+        PlatformDisplay::Khr(platform)
+            if egl.CreatePlatformWindowSurface.is_loaded() =>
+        {
+            egl.CreatePlatformWindowSurface(
+                display,
+                config_id,
+                platform_window(platform),
+                attrs.to_egl_attribs().as_ptr(),
+            )
+        }
+        #[cfg(not(target_os = "android"))]
+        PlatformDisplay::Ext(platform)
+            if egl.CreatePlatformWindowSurfaceEXT.is_loaded() =>
+        {
+            egl.CreatePlatformWindowSurfaceEXT(
+                display,
+                config_id,
+                platform_window(platform),
+                attrs.as_ptr(),
+            )
+        }
+        _ => egl.CreateWindowSurface(
+            display,
+            config_id,
+            native_window,
+            attrs.as_ptr(),
+        ),
+    };
+    if !surface.is_null()
+        && !attribs.double_buffer
+        && extensions.iter().any(|s| s == "EGL_KHR_mutable_render_buffer")
+    {
+        // Some platforms ignore `EGL_RENDER_BUFFER` at creation and only
+        // switch to the front buffer through the mutable render buffer
+        // extension. This takes effect after the next swap.
+        egl.SurfaceAttrib(
+            display,
+            surface,
+            ffi::egl::RENDER_BUFFER as ffi::egl::types::EGLint,
+            ffi::egl::SINGLE_BUFFER as ffi::egl::types::EGLint,
+        );
+    }
+
+    surface
+}
+
+unsafe fn create_pixmap_surface(
+    display: ffi::egl::types::EGLDisplay,
+    platform: PlatformDisplay,
+    config_id: ffi::egl::types::EGLConfig,
+    native_pixmap: ffi::EGLNativePixmapType,
+    srgb: bool,
+) -> ffi::egl::types::EGLSurface {
+    let egl = EGL.as_ref().unwrap();
+    let mut attrs = AttribList::new(ffi::egl::NONE);
+    // The caller checks that the colorspace attribute is supported.
+    if srgb {
+        attrs.push(ffi::egl::GL_COLORSPACE, ffi::egl::GL_COLORSPACE_SRGB);
+    }
+
+    // like windows, the platform functions take a pointer to the `Pixmap` on
+    // X11
+    #[cfg(not(target_os = "android"))]
+    let x11_pixmap = native_pixmap as c_ulong;
+    #[cfg(not(target_os = "android"))]
+    let platform_pixmap = |platform| {
+        if platform == ffi::egl::PLATFORM_X11_KHR {
+            &x11_pixmap as *const c_ulong as *mut c_void
+        } else {
+            native_pixmap as *mut c_void
+        }
+    };
+
+    match platform {
+        #[cfg(not(target_os = "android"))]
+        PlatformDisplay::Khr(platform)
+            if egl.CreatePlatformPixmapSurface.is_loaded() =>
+        {
+            egl.CreatePlatformPixmapSurface(
+                display,
+                config_id,
+                platform_pixmap(platform),
+                attrs.to_egl_attribs().as_ptr(),
+            )
+        }
+        #[cfg(not(target_os = "android"))]
+        PlatformDisplay::Ext(platform)
+            if egl.CreatePlatformPixmapSurfaceEXT.is_loaded() =>
+        {
+            egl.CreatePlatformPixmapSurfaceEXT(
+                display,
+                config_id,
+                platform_pixmap(platform),
+                attrs.as_ptr(),
+            )
+        }
+        _ => egl.CreatePixmapSurface(
+            display,
+            config_id,
+            native_pixmap,
+            attrs.as_ptr(),
+        ),
+    }
+}
+
+unsafe fn create_pbuffer_surface(
+    display: ffi::egl::types::EGLDisplay,
+    config_id: ffi::egl::types::EGLConfig,
+    dimensions: (u32, u32),
+    srgb: bool,
+    largest: bool,
+    texture: Option<PbufferTexture>,
+) -> ffi::egl::types::EGLSurface {
+    let egl = EGL.as_ref().unwrap();
+    let mut attrs = AttribList::new(ffi::egl::NONE);
+    attrs.push(ffi::egl::WIDTH, dimensions.0 as c_int);
+    attrs.push(ffi::egl::HEIGHT, dimensions.1 as c_int);
+    if largest {
+        attrs.push(ffi::egl::LARGEST_PBUFFER, true);
+    }
+    if let Some(texture) = texture {
+        let format = if texture.alpha {
+            ffi::egl::TEXTURE_RGBA
+        } else {
+            ffi::egl::TEXTURE_RGB
+        };
+        attrs.push(ffi::egl::TEXTURE_FORMAT, format);
+        attrs.push(ffi::egl::TEXTURE_TARGET, ffi::egl::TEXTURE_2D);
+        attrs.push(ffi::egl::MIPMAP_TEXTURE, texture.mipmap);
     }
+    // The caller checks that the colorspace attribute is supported.
+    if srgb {
+        attrs.push(ffi::egl::GL_COLORSPACE, ffi::egl::GL_COLORSPACE_SRGB);
+    }
+
+    egl.CreatePbufferSurface(display, config_id, attrs.as_ptr())
+}
+
+/// Builds the error of an EGL function binding a pbuffer to a texture that
+/// failed.
+fn texture_error(function: &str) -> ContextError {
+    let egl = EGL.as_ref().unwrap();
+    match unsafe { egl.GetError() } as u32 {
+        ffi::egl::CONTEXT_LOST => ContextError::ContextLost,
+        err => ContextError::OsError(format!(
+            "{} failed (eglGetError returned 0x{:x})",
+            function, err
+        )),
+    }
+}
+
+/// Whether surfaces can be created with `EGL_GL_COLORSPACE`.
+fn colorspace_supported(
+    egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+    extensions: &[String],
+) -> bool {
+    egl_version >= &(1, 5)
+        || extensions.iter().any(|s| s == "EGL_KHR_gl_colorspace")
+}
+
+/// Fills in the parts of `pixel_format` that EGL ties to the surface rather
+/// than to the config.
+unsafe fn query_surface_format(
+    display: ffi::egl::types::EGLDisplay,
+    egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+    extensions: &[String],
+    surface: ffi::egl::types::EGLSurface,
+    mut pixel_format: PixelFormat,
+) -> PixelFormat {
+    let egl = EGL.as_ref().unwrap();
+    let query = |attrib| {
+        let mut value = 0;
+        let ret = egl.QuerySurface(
+            display,
+            surface,
+            attrib as ffi::egl::types::EGLint,
+            &mut value,
+        );
+        if ret == 0 {
+            None
+        } else {
+            Some(value as u32)
+        }
+    };
+
+    // pbuffers always render to their back buffer
+    pixel_format.double_buffer =
+        query(ffi::egl::RENDER_BUFFER) != Some(ffi::egl::SINGLE_BUFFER);
+    pixel_format.srgb = colorspace_supported(egl_version, extensions)
+        && query(ffi::egl::GL_COLORSPACE) == Some(ffi::egl::GL_COLORSPACE_SRGB);
+    pixel_format
 }
 
 unsafe fn choose_fbconfig(
     egl: &Egl,
     display: ffi::egl::types::EGLDisplay,
     egl_version: &(ffi::egl::types::EGLint, ffi::egl::types::EGLint),
+    extensions: &[String],
     api: Api,
     version: Option<(u8, u8)>,
     reqs: &PixelFormatRequirements,
+    pbuffer_only: bool,
+    x11: bool,
 ) -> Result<(ffi::egl::types::EGLConfig, PixelFormat), CreationError> {
     let descriptor = {
-        let mut out: Vec<c_int> = Vec::with_capacity(37);
+        let mut out = AttribList::new(ffi::egl::NONE);
 
         if egl_version >= &(1, 2) {
-            out.push(ffi::egl::COLOR_BUFFER_TYPE as c_int);
-            out.push(ffi::egl::RGB_BUFFER as c_int);
+            out.push(ffi::egl::COLOR_BUFFER_TYPE, ffi::egl::RGB_BUFFER);
         }
 
-        out.push(ffi::egl::SURFACE_TYPE as c_int);
         // TODO: Some versions of Mesa report a BAD_ATTRIBUTE error
         // if we ask for PBUFFER_BIT as well as WINDOW_BIT
-        out.push((ffi::egl::WINDOW_BIT) as c_int);
-
-        match (api, version) {
-            (Api::OpenGlEs, Some((3, _))) => {
-                if egl_version < &(1, 3) {
-                    return Err(CreationError::NoAvailablePixelFormat);
-                }
-                out.push(ffi::egl::RENDERABLE_TYPE as c_int);
-                out.push(ffi::egl::OPENGL_ES3_BIT as c_int);
-                out.push(ffi::egl::CONFORMANT as c_int);
-                out.push(ffi::egl::OPENGL_ES3_BIT as c_int);
-            }
-            (Api::OpenGlEs, Some((2, _))) => {
-                if egl_version < &(1, 3) {
-                    return Err(CreationError::NoAvailablePixelFormat);
-                }
-                out.push(ffi::egl::RENDERABLE_TYPE as c_int);
-                out.push(ffi::egl::OPENGL_ES2_BIT as c_int);
-                out.push(ffi::egl::CONFORMANT as c_int);
-                out.push(ffi::egl::OPENGL_ES2_BIT as c_int);
+        let mut surface_type = if pbuffer_only {
+            // in case the context can't be surfaceless
+            ffi::egl::PBUFFER_BIT
+        } else if reqs.x11_pixmap {
+            ffi::egl::PIXMAP_BIT
+        } else {
+            ffi::egl::WINDOW_BIT
+        };
+        let window = !pbuffer_only && !reqs.x11_pixmap;
+        if reqs.swap_behavior_preserved {
+            if egl_version < &(1, 4) {
+                return Err(CreationError::NoAvailablePixelFormat);
             }
-            (Api::OpenGlEs, Some((1, _))) => {
-                if egl_version >= &(1, 3) {
-                    out.push(ffi::egl::RENDERABLE_TYPE as c_int);
-                    out.push(ffi::egl::OPENGL_ES_BIT as c_int);
-                    out.push(ffi::egl::CONFORMANT as c_int);
-                    out.push(ffi::egl::OPENGL_ES_BIT as c_int);
-                }
+            surface_type |= ffi::egl::SWAP_BEHAVIOR_PRESERVED_BIT;
+        }
+        // Window surfaces can't be created single-buffered everywhere, the
+        // mutable render buffer extension lets us switch them afterwards.
+        let mutable_render_buffer =
+            extensions.iter().any(|s| s == "EGL_KHR_mutable_render_buffer");
+        if reqs.mutable_render_buffer && window {
+            if !mutable_render_buffer {
+                return Err(CreationError::NotSupported(
+                    "EGL_KHR_mutable_render_buffer is unavailable",
+                ));
             }
-            (Api::OpenGlEs, _) => unimplemented!(),
-            (Api::OpenGl, _) => {
-                if egl_version < &(1, 3) {
-                    return Err(CreationError::NoAvailablePixelFormat);
-                }
-                out.push(ffi::egl::RENDERABLE_TYPE as c_int);
-                out.push(ffi::egl::OPENGL_BIT as c_int);
-                out.push(ffi::egl::CONFORMANT as c_int);
-                out.push(ffi::egl::OPENGL_BIT as c_int);
+            surface_type |= ffi::egl::MUTABLE_RENDER_BUFFER_BIT_KHR;
+        } else if reqs.double_buffer == Some(false)
+            && mutable_render_buffer
+            && window
+        {
+            surface_type |= ffi::egl::MUTABLE_RENDER_BUFFER_BIT_KHR;
+        }
+        if let Some(texture) = reqs.pbuffer_texture {
+            surface_type |= ffi::egl::PBUFFER_BIT;
+            if texture.alpha {
+                out.push(ffi::egl::BIND_TO_TEXTURE_RGBA, true);
+            } else {
+                out.push(ffi::egl::BIND_TO_TEXTURE_RGB, true);
             }
-            (_, _) => unimplemented!(),
-        };
+        }
+        out.push(ffi::egl::SURFACE_TYPE, surface_type);
+
+        let renderable =
+            renderable_type(egl_version, extensions, api, version)?;
+        if let Some(renderable) = renderable {
+            out.push(ffi::egl::RENDERABLE_TYPE, renderable);
+            out.push(ffi::egl::CONFORMANT, renderable);
+        }
 
         if let Some(hardware_accelerated) = reqs.hardware_accelerated {
-            out.push(ffi::egl::CONFIG_CAVEAT as c_int);
-            out.push(if hardware_accelerated {
-                ffi::egl::NONE as c_int
-            } else {
-                ffi::egl::SLOW_CONFIG as c_int
-            });
+            out.push(
+                ffi::egl::CONFIG_CAVEAT,
+                if hardware_accelerated {
+                    ffi::egl::NONE
+                } else {
+                    ffi::egl::SLOW_CONFIG
+                },
+            );
         }
 
         if let Some(color) = reqs.color_bits {
-            out.push(ffi::egl::RED_SIZE as c_int);
-            out.push((color / 3) as c_int);
-            out.push(ffi::egl::GREEN_SIZE as c_int);
-            out.push((color / 3 + if color % 3 != 0 { 1 } else { 0 }) as c_int);
-            out.push(ffi::egl::BLUE_SIZE as c_int);
-            out.push((color / 3 + if color % 3 == 2 { 1 } else { 0 }) as c_int);
+            out.push(ffi::egl::RED_SIZE, color / 3);
+            out.push(
+                ffi::egl::GREEN_SIZE,
+                color / 3 + if color % 3 != 0 { 1 } else { 0 },
+            );
+            out.push(
+                ffi::egl::BLUE_SIZE,
+                color / 3 + if color % 3 == 2 { 1 } else { 0 },
+            );
         }
 
         if let Some(alpha) = reqs.alpha_bits {
-            out.push(ffi::egl::ALPHA_SIZE as c_int);
-            out.push(alpha as c_int);
+            out.push(ffi::egl::ALPHA_SIZE, alpha);
         }
 
         if let Some(depth) = reqs.depth_bits {
-            out.push(ffi::egl::DEPTH_SIZE as c_int);
-            out.push(depth as c_int);
+            out.push(ffi::egl::DEPTH_SIZE, depth);
         }
 
         if let Some(stencil) = reqs.stencil_bits {
-            out.push(ffi::egl::STENCIL_SIZE as c_int);
-            out.push(stencil as c_int);
+            out.push(ffi::egl::STENCIL_SIZE, stencil);
         }
 
-        if let Some(true) = reqs.double_buffer {
-            return Err(CreationError::NoAvailablePixelFormat);
+        // several drivers ignore `EGL_SAMPLES` without `EGL_SAMPLE_BUFFERS`
+        match reqs.samples() {
+            Some(0) => out.push(ffi::egl::SAMPLE_BUFFERS, 0),
+            Some(samples) => {
+                out.push(ffi::egl::SAMPLE_BUFFERS, 1);
+                out.push(ffi::egl::SAMPLES, samples);
+            }
+            None => (),
         }
 
-        if let Some(multisampling) = reqs.multisampling {
-            out.push(ffi::egl::SAMPLES as c_int);
-            out.push(multisampling as c_int);
+        if let Some(coverage) = reqs.coverage_samples {
+            if extensions.iter().any(|s| s == "EGL_NV_coverage_sample") {
+                out.push(ffi::egl::COVERAGE_BUFFERS_NV, 1);
+                out.push(ffi::egl::COVERAGE_SAMPLES_NV, coverage);
+            }
         }
 
         if reqs.stereoscopy {
@@ -866,45 +3056,217 @@ unsafe fn choose_fbconfig(
         }
 
         if let Some(xid) = reqs.x11_visual_xid {
-            out.push(ffi::egl::NATIVE_VISUAL_ID as c_int);
-            out.push(xid as c_int);
+            out.push(ffi::egl::NATIVE_VISUAL_ID, xid as c_int);
         }
 
-        // FIXME: srgb is not taken into account
+        if reqs.plane != 0 {
+            out.push(ffi::egl::LEVEL, reqs.plane);
+        }
 
-        match reqs.release_behavior {
-            ReleaseBehavior::Flush => (),
-            ReleaseBehavior::None => {
-                // TODO: with EGL you need to manually set the behavior
-                unimplemented!()
+        // the default of `eglChooseConfig`, which strict selection needs
+        // explicitly
+        match reqs.transparent_type {
+            TransparentType::None => {
+                out.push(ffi::egl::TRANSPARENT_TYPE, ffi::egl::NONE)
             }
+            TransparentType::Rgb => out.push(
+                ffi::egl::TRANSPARENT_TYPE,
+                ffi::egl::TRANSPARENT_RGB,
+            ),
+            TransparentType::Index => {
+                return Err(CreationError::NoAvailablePixelFormat);
+            }
+        }
+
+        if let Some(renderable) = reqs.native_renderable {
+            out.push(ffi::egl::NATIVE_RENDERABLE, renderable);
+        }
+
+        if let (true, Some(visual_type)) = (x11, reqs.x11_visual_type) {
+            out.push(
+                ffi::egl::NATIVE_VISUAL_TYPE,
+                x11_visual_class(visual_type),
+            );
+        }
+
+        // sRGB is a property of the surface, requested when creating it
+
+        for &(key, value) in &reqs.egl_config_attribs {
+            out.remove(key);
+            out.push(key, value);
         }
 
-        out.push(ffi::egl::NONE as c_int);
         out
     };
 
-    // calling `eglChooseConfig`
-    let mut config_id = mem::uninitialized();
-    let mut num_configs = mem::uninitialized();
-    if egl.ChooseConfig(
+    let configs = if reqs.strict_selection {
+        choose_configs_strictly(egl, display, &descriptor)?
+    } else {
+        // calling `eglChooseConfig`, for every matching config only when
+        // the application picks one
+        let mut num_configs = 1;
+        if reqs.selection_hook.is_some()
+            && egl.ChooseConfig(
+                display,
+                descriptor.as_ptr(),
+                ptr::null_mut(),
+                0,
+                &mut num_configs,
+            ) == 0
+        {
+            let msg = "eglChooseConfig failed".to_string();
+            return Err(CreationError::OsError(msg));
+        }
+        let mut configs = vec![ptr::null(); num_configs.max(1) as usize];
+        if egl.ChooseConfig(
+            display,
+            descriptor.as_ptr(),
+            configs.as_mut_ptr(),
+            configs.len() as ffi::egl::types::EGLint,
+            &mut num_configs,
+        ) == 0
+        {
+            let msg = "eglChooseConfig failed".to_string();
+            return Err(CreationError::OsError(msg));
+        }
+        configs.truncate(num_configs as usize);
+        configs
+    };
+
+    let (config_id, desc) = match reqs.selection_hook {
+        Some(ref hook) if !configs.is_empty() => {
+            let mut candidates = Vec::with_capacity(configs.len());
+            for config in configs {
+                let desc =
+                    describe_config(egl, display, extensions, config, x11)?;
+                candidates.push((config, desc));
+            }
+            hook.select(candidates)
+                .ok_or(CreationError::NoAvailablePixelFormat)?
+        }
+        _ => {
+            let config_id = *configs
+                .first()
+                .ok_or(CreationError::NoAvailablePixelFormat)?;
+            let desc =
+                describe_config(egl, display, extensions, config_id, x11)?;
+            (config_id, desc)
+        }
+    };
+    desc.debug_assert_satisfies(reqs);
+
+    Ok((config_id, desc))
+}
+
+/// Chooses configs among all the ones of the display, instead of trusting
+/// the drivers whose `eglChooseConfig` omits or misorders some of them.
+///
+/// The configs are filtered with the matching rule that the EGL
+/// specification gives to each attribute of `descriptor`. The remaining
+/// ones are sorted with the configs without a caveat first, then the ones
+/// with the fewest samples and the fewest color, alpha, depth and stencil
+/// bits, and ties are broken by the smallest `EGL_CONFIG_ID`.
+unsafe fn choose_configs_strictly(
+    egl: &Egl,
+    display: ffi::egl::types::EGLDisplay,
+    descriptor: &AttribList,
+) -> Result<Vec<ffi::egl::types::EGLConfig>, CreationError> {
+    let mut num_configs = 0;
+    if egl.GetConfigs(display, ptr::null_mut(), 0, &mut num_configs) == 0 {
+        let msg = "eglGetConfigs failed".to_string();
+        return Err(CreationError::OsError(msg));
+    }
+    let mut configs = vec![ptr::null(); num_configs as usize];
+    if egl.GetConfigs(
         display,
-        descriptor.as_ptr(),
-        &mut config_id,
-        1,
+        configs.as_mut_ptr(),
+        num_configs,
         &mut num_configs,
     ) == 0
     {
-        return Err(CreationError::OsError(format!("eglChooseConfig failed")));
+        let msg = "eglGetConfigs failed".to_string();
+        return Err(CreationError::OsError(msg));
     }
-    if num_configs == 0 {
-        return Err(CreationError::NoAvailablePixelFormat);
+    configs.truncate(num_configs as usize);
+
+    let attrib = |config, attrib: u32| {
+        let mut value = 0;
+        egl.GetConfigAttrib(
+            display,
+            config,
+            attrib as ffi::egl::types::EGLint,
+            &mut value,
+        );
+        value
+    };
+    let matches = |config, (key, value)| {
+        let obtained = attrib(config, key);
+        match key {
+            ffi::egl::SURFACE_TYPE
+            | ffi::egl::RENDERABLE_TYPE
+            | ffi::egl::CONFORMANT => obtained & value == value,
+            ffi::egl::RED_SIZE
+            | ffi::egl::GREEN_SIZE
+            | ffi::egl::BLUE_SIZE
+            | ffi::egl::ALPHA_SIZE
+            | ffi::egl::DEPTH_SIZE
+            | ffi::egl::STENCIL_SIZE
+            | ffi::egl::SAMPLE_BUFFERS
+            | ffi::egl::SAMPLES
+            | ffi::egl::COVERAGE_BUFFERS_NV
+            | ffi::egl::COVERAGE_SAMPLES_NV => obtained >= value,
+            _ => obtained == value,
+        }
+    };
+
+    let mut configs = configs
+        .into_iter()
+        .filter(|&config| descriptor.pairs().all(|pair| matches(config, pair)))
+        .collect::<Vec<_>>();
+    configs.sort_by_key(|&config| {
+        (
+            attrib(config, ffi::egl::CONFIG_CAVEAT)
+                != ffi::egl::NONE as ffi::egl::types::EGLint,
+            attrib(config, ffi::egl::SAMPLES),
+            attrib(config, ffi::egl::RED_SIZE)
+                + attrib(config, ffi::egl::GREEN_SIZE)
+                + attrib(config, ffi::egl::BLUE_SIZE),
+            attrib(config, ffi::egl::ALPHA_SIZE),
+            attrib(config, ffi::egl::DEPTH_SIZE),
+            attrib(config, ffi::egl::STENCIL_SIZE),
+            attrib(config, ffi::egl::CONFIG_ID),
+        )
+    });
+    Ok(configs)
+}
+
+/// The classes of X11 visuals, as defined by `X.h`.
+const X11_TRUE_COLOR: c_int = 4;
+const X11_DIRECT_COLOR: c_int = 5;
+
+/// Returns the X11 visual class, which X11 displays give to
+/// `EGL_NATIVE_VISUAL_TYPE`.
+fn x11_visual_class(visual_type: X11VisualType) -> c_int {
+    match visual_type {
+        X11VisualType::TrueColor => X11_TRUE_COLOR,
+        X11VisualType::DirectColor => X11_DIRECT_COLOR,
     }
+}
 
-    // analyzing each config
+/// Describes the framebuffer of a config, before a surface is created.
+///
+/// The native visual type is only known to be an X11 visual class if `x11`
+/// is set.
+unsafe fn describe_config(
+    egl: &Egl,
+    display: ffi::egl::types::EGLDisplay,
+    extensions: &[String],
+    config_id: ffi::egl::types::EGLConfig,
+    x11: bool,
+) -> Result<PixelFormat, CreationError> {
     macro_rules! attrib {
         ($egl:expr, $display:expr, $config:expr, $attr:expr) => {{
-            let mut value = mem::uninitialized();
+            let mut value = 0;
             let res = $egl.GetConfigAttrib(
                 $display,
                 $config,
@@ -920,6 +3282,48 @@ unsafe fn choose_fbconfig(
         }};
     };
 
+    let coverage_samples = if extensions
+        .iter()
+        .any(|s| s == "EGL_NV_coverage_sample")
+        && attrib!(egl, display, config_id, ffi::egl::COVERAGE_BUFFERS_NV) != 0
+    {
+        let samples =
+            attrib!(egl, display, config_id, ffi::egl::COVERAGE_SAMPLES_NV);
+        Some(samples as u16)
+    } else {
+        None
+    };
+
+    let sample_buffers =
+        attrib!(egl, display, config_id, ffi::egl::SAMPLE_BUFFERS);
+
+    let transparent_type =
+        attrib!(egl, display, config_id, ffi::egl::TRANSPARENT_TYPE);
+    let transparent = if transparent_type == ffi::egl::TRANSPARENT_RGB as i32 {
+        Some(TransparentPixel::Rgb {
+            red: attrib!(
+                egl,
+                display,
+                config_id,
+                ffi::egl::TRANSPARENT_RED_VALUE
+            ) as u32,
+            green: attrib!(
+                egl,
+                display,
+                config_id,
+                ffi::egl::TRANSPARENT_GREEN_VALUE
+            ) as u32,
+            blue: attrib!(
+                egl,
+                display,
+                config_id,
+                ffi::egl::TRANSPARENT_BLUE_VALUE
+            ) as u32,
+        })
+    } else {
+        None
+    };
+
     let desc = PixelFormat {
         hardware_accelerated: attrib!(
             egl,
@@ -936,17 +3340,42 @@ unsafe fn choose_fbconfig(
             as u8,
         stencil_bits: attrib!(egl, display, config_id, ffi::egl::STENCIL_SIZE)
             as u8,
+        // EGL has no stereoscopic configs
         stereoscopy: false,
+        // queried from the surface once it is created
         double_buffer: true,
+        sample_buffers: sample_buffers as u8,
         multisampling: match attrib!(egl, display, config_id, ffi::egl::SAMPLES)
         {
             0 | 1 => None,
+            _ if sample_buffers == 0 => None,
             a => Some(a as u16),
         },
-        srgb: false, // TODO: use EGL_KHR_gl_colorspace to know that
+        coverage_samples,
+        srgb: false,
+        transparent,
+        native_renderable: attrib!(
+            egl,
+            display,
+            config_id,
+            ffi::egl::NATIVE_RENDERABLE
+        ) != 0,
+        x11_visual_type: match attrib!(
+            egl,
+            display,
+            config_id,
+            ffi::egl::NATIVE_VISUAL_TYPE
+        ) {
+            X11_TRUE_COLOR if x11 => Some(X11VisualType::TrueColor),
+            X11_DIRECT_COLOR if x11 => Some(X11VisualType::DirectColor),
+            _ => None,
+        },
+        native_id: Some(
+            attrib!(egl, display, config_id, ffi::egl::CONFIG_ID) as u32,
+        ),
     };
 
-    Ok((config_id, desc))
+    Ok(desc)
 }
 
 unsafe fn create_context(
@@ -956,14 +3385,21 @@ unsafe fn create_context(
     api: Api,
     version: (u8, u8),
     config_id: ffi::egl::types::EGLConfig,
+    gl_profile: Option<GlProfile>,
+    gl_forward_compatible: bool,
     gl_debug: bool,
     gl_robustness: Robustness,
+    release_behavior: ReleaseBehavior,
+    workarounds: DriverWorkarounds,
     share: ffi::EGLContext,
-) -> Result<ffi::egl::types::EGLContext, CreationError> {
+) -> Result<(ffi::egl::types::EGLContext, DriverWorkarounds), CreationError>
+{
     let egl = EGL.as_ref().unwrap();
 
-    let mut context_attributes = Vec::with_capacity(10);
+    let mut context_attributes = AttribList::new(ffi::egl::NONE);
     let mut flags = 0;
+    // whether the context is useless without the bits of `flags`
+    let mut flags_required = false;
 
     if egl_version >= &(1, 5)
         || extensions
@@ -971,10 +3407,37 @@ unsafe fn create_context(
             .find(|s| s == &"EGL_KHR_create_context")
             .is_some()
     {
-        context_attributes.push(ffi::egl::CONTEXT_MAJOR_VERSION as i32);
-        context_attributes.push(version.0 as i32);
-        context_attributes.push(ffi::egl::CONTEXT_MINOR_VERSION as i32);
-        context_attributes.push(version.1 as i32);
+        context_attributes.push(ffi::egl::CONTEXT_MAJOR_VERSION, version.0);
+        context_attributes.push(ffi::egl::CONTEXT_MINOR_VERSION, version.1);
+
+        // profiles and forward-compatibility only apply to desktop OpenGL
+        if api == Api::OpenGl {
+            if let Some(profile) = gl_profile {
+                let bit = match profile {
+                    GlProfile::Core => {
+                        ffi::egl::CONTEXT_OPENGL_CORE_PROFILE_BIT
+                    }
+                    GlProfile::Compatibility => {
+                        ffi::egl::CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT
+                    }
+                };
+                context_attributes
+                    .push(ffi::egl::CONTEXT_OPENGL_PROFILE_MASK, bit);
+            }
+
+            if gl_forward_compatible {
+                if egl_version >= &(1, 5) {
+                    context_attributes.push(
+                        ffi::egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE,
+                        true,
+                    );
+                } else {
+                    flags |=
+                        ffi::egl::CONTEXT_OPENGL_FORWARD_COMPATIBLE_BIT_KHR;
+                    flags_required = true;
+                }
+            }
+        }
 
         // handling robustness
         let supports_robustness = egl_version >= &(1, 5)
@@ -983,82 +3446,54 @@ unsafe fn create_context(
                 .find(|s| s == &"EGL_EXT_create_context_robustness")
                 .is_some();
 
-        match gl_robustness {
-            Robustness::NotRobust => (),
+        let (reset_strategy, required) = match gl_robustness {
+            Robustness::NotRobust => (None, false),
 
+            // debug contexts can't be created without errors
             Robustness::NoError => {
-                if extensions
-                    .iter()
-                    .find(|s| s == &"EGL_KHR_create_context_no_error")
-                    .is_some()
+                if !gl_debug
+                    && extensions
+                        .iter()
+                        .any(|s| s == "EGL_KHR_create_context_no_error")
                 {
                     context_attributes
-                        .push(ffi::egl::CONTEXT_OPENGL_NO_ERROR_KHR as c_int);
-                    context_attributes.push(1);
+                        .push(ffi::egl::CONTEXT_OPENGL_NO_ERROR_KHR, true);
                 }
+                (None, false)
             }
 
             Robustness::RobustNoResetNotification => {
-                if supports_robustness {
-                    context_attributes.push(
-                        ffi::egl::CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY
-                            as c_int,
-                    );
-                    context_attributes
-                        .push(ffi::egl::NO_RESET_NOTIFICATION as c_int);
-                    flags =
-                        flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
-                } else {
-                    return Err(CreationError::RobustnessNotSupported);
-                }
+                (Some(ffi::egl::NO_RESET_NOTIFICATION), true)
             }
 
             Robustness::TryRobustNoResetNotification => {
-                if supports_robustness {
-                    context_attributes.push(
-                        ffi::egl::CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY
-                            as c_int,
-                    );
-                    context_attributes
-                        .push(ffi::egl::NO_RESET_NOTIFICATION as c_int);
-                    flags =
-                        flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
-                }
+                (Some(ffi::egl::NO_RESET_NOTIFICATION), false)
             }
 
             Robustness::RobustLoseContextOnReset => {
-                if supports_robustness {
-                    context_attributes.push(
-                        ffi::egl::CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY
-                            as c_int,
-                    );
-                    context_attributes
-                        .push(ffi::egl::LOSE_CONTEXT_ON_RESET as c_int);
-                    flags =
-                        flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
-                } else {
-                    return Err(CreationError::RobustnessNotSupported);
-                }
+                (Some(ffi::egl::LOSE_CONTEXT_ON_RESET), true)
             }
 
             Robustness::TryRobustLoseContextOnReset => {
-                if supports_robustness {
-                    context_attributes.push(
-                        ffi::egl::CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY
-                            as c_int,
-                    );
-                    context_attributes
-                        .push(ffi::egl::LOSE_CONTEXT_ON_RESET as c_int);
-                    flags =
-                        flags | ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS as c_int;
-                }
+                (Some(ffi::egl::LOSE_CONTEXT_ON_RESET), false)
+            }
+        };
+        if let Some(reset_strategy) = reset_strategy {
+            if supports_robustness {
+                context_attributes.push(
+                    ffi::egl::CONTEXT_OPENGL_RESET_NOTIFICATION_STRATEGY,
+                    reset_strategy,
+                );
+                flags |= ffi::egl::CONTEXT_OPENGL_ROBUST_ACCESS;
+                flags_required |= required;
+            } else if required {
+                return Err(CreationError::RobustnessNotSupported);
             }
         }
 
         if gl_debug {
             if egl_version >= &(1, 5) {
-                context_attributes.push(ffi::egl::CONTEXT_OPENGL_DEBUG as i32);
-                context_attributes.push(ffi::egl::TRUE as i32);
+                context_attributes.push(ffi::egl::CONTEXT_OPENGL_DEBUG, true);
             }
 
             // TODO: using this flag sometimes generates an error
@@ -1072,11 +3507,16 @@ unsafe fn create_context(
         // In at least some configurations, the Android emulator’s GL
         // implementation advertises support for the
         // EGL_KHR_create_context extension but returns BAD_ATTRIBUTE
-        // when CONTEXT_FLAGS_KHR is used.
+        // when CONTEXT_FLAGS_KHR is used. See the retry below.
         if flags != 0 {
-            context_attributes.push(ffi::egl::CONTEXT_FLAGS_KHR as i32);
-            context_attributes.push(flags);
+            context_attributes.push(ffi::egl::CONTEXT_FLAGS_KHR, flags);
         }
+    } else if api == Api::OpenGl
+        && (gl_profile == Some(GlProfile::Core) || gl_forward_compatible)
+    {
+        // without `EGL_KHR_create_context` we can't ask for anything but a
+        // compatibility context
+        return Err(CreationError::ProfileNotSupported);
     } else if egl_version >= &(1, 3) && api == Api::OpenGlEs {
         // robustness is not supported
         match gl_robustness {
@@ -1087,11 +3527,15 @@ unsafe fn create_context(
             _ => (),
         }
 
-        context_attributes.push(ffi::egl::CONTEXT_CLIENT_VERSION as i32);
-        context_attributes.push(version.0 as i32);
+        context_attributes.push(ffi::egl::CONTEXT_CLIENT_VERSION, version.0);
     }
 
-    context_attributes.push(ffi::egl::NONE as i32);
+    if release_behavior == ReleaseBehavior::None {
+        context_attributes.push(
+            ffi::egl::CONTEXT_RELEASE_BEHAVIOR_KHR,
+            ffi::egl::CONTEXT_RELEASE_BEHAVIOR_NONE_KHR,
+        );
+    }
 
     let context = egl.CreateContext(
         display,
@@ -1100,14 +3544,39 @@ unsafe fn create_context(
         context_attributes.as_ptr(),
     );
 
-    if context.is_null() {
-        match egl.GetError() as u32 {
-            ffi::egl::BAD_MATCH | ffi::egl::BAD_ATTRIBUTE => {
+    if !context.is_null() {
+        return Ok((context, DriverWorkarounds::none()));
+    }
+
+    match egl.GetError() as u32 {
+        ffi::egl::BAD_ATTRIBUTE
+            if workarounds.egl_retry_without_context_flags
+                && !flags_required =>
+        {
+            if !context_attributes.remove(ffi::egl::CONTEXT_FLAGS_KHR) {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
+
+            let context = egl.CreateContext(
+                display,
+                config_id,
+                share,
+                context_attributes.as_ptr(),
+            );
+            if context.is_null() {
                 return Err(CreationError::OpenGlVersionNotSupported);
             }
-            e => panic!("eglCreateContext failed: 0x{:x}", e),
+
+            let mut applied = DriverWorkarounds::none();
+            applied.egl_retry_without_context_flags = true;
+            Ok((context, applied))
         }
+        ffi::egl::BAD_MATCH | ffi::egl::BAD_ATTRIBUTE => {
+            Err(CreationError::OpenGlVersionNotSupported)
+        }
+        e => Err(CreationError::OsError(format!(
+            "eglCreateContext failed (eglGetError returned 0x{:x})",
+            e
+        ))),
     }
-
-    Ok(context)
 }
@@ -7,18 +7,29 @@
 ))]
 
 use {
-    Api, ContextError, CreationError, GlAttributes, GlProfile, GlRequest,
-    PixelFormat, PixelFormatRequirements, ReleaseBehavior, Robustness,
+    Api, Backend, BackendInfo, ContextError, CreationError, DisplayToken,
+    DriverWorkarounds, GlAttributes, GlProfile, GlRequest, PbufferLimits,
+    PixelFormat,
+    PixelFormatRequirements, ReleaseBehavior, RenderBuffer, Robustness,
+    SurfaceInfo, SwapIntervalRange, TransparentPixel, TransparentType,
+    X11VisualType,
 };
 
+use std::collections::hash_map::DefaultHasher;
 use std::ffi::{CStr, CString};
-use std::sync::Arc;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
 use std::{mem, ptr, slice};
 
 use libc::{self, c_int};
 
 use winit::os::unix::x11::XConnection;
 
+use api::attribs::AttribList;
+use api::proc_cache::ProcCache;
+use api::reloadable::{LibraryUser, Reloadable};
+use api::swap_interval::SwapIntervalTracker;
+
 pub mod ffi {
     pub use self::glx::types::GLXContext;
     pub use x11_dl::xlib::*;
@@ -79,14 +90,71 @@ mod glx {
 pub use self::glx::Glx;
 
 lazy_static! {
-    pub static ref GLX: Option<Glx> = Glx::new().ok();
+    pub static ref GLX: Reloadable<Glx> = Reloadable::new(Glx::new().ok());
+    /// `glXGetProcAddress` returns the same functions for every context.
+    static ref PROC_ADDRESSES: ProcCache = ProcCache::default();
+}
+
+/// Loads GLX, unless it already is.
+pub fn preload() {
+    lazy_static::initialize(&GLX);
+}
+
+/// Resolves `addr` with `glXGetProcAddress`, once per process.
+fn get_proc_address(glx: &Glx, addr: &str) -> *const () {
+    PROC_ADDRESSES.get(addr, |addr| {
+        let addr = CString::new(addr.as_bytes()).unwrap();
+        unsafe { glx.GetProcAddress(addr.as_ptr() as *const _) as *const _ }
+    })
+}
+
+/// The number of GLX contexts alive, which keep the library loaded.
+pub fn live_contexts() -> usize {
+    GLX.users()
+}
+
+/// Unloads and loads again GLX.
+///
+/// # Safety
+///
+/// No GLX context may be alive or being built.
+pub unsafe fn reload_symbols() {
+    GLX.replace(None);
+    GLX.replace(Glx::new().ok());
+    PROC_ADDRESSES.clear();
+}
+
+/// The display, drawables and context current on a thread, to restore them
+/// afterwards.
+pub struct CurrentState {
+    display: *mut ffi::glx::types::Display,
+    draw: ffi::glx::types::GLXDrawable,
+    read: ffi::glx::types::GLXDrawable,
+    context: ffi::glx::types::GLXContext,
 }
 
 pub struct Context {
     xconn: Arc<XConnection>,
     window: ffi::Window,
     context: ffi::GLXContext,
+    fb_config: ffi::glx::types::GLXFBConfig,
     pixel_format: PixelFormat,
+    extensions: String,
+    extra_functions: Box<ffi::glx_extra::Glx>,
+    screen_id: libc::c_int,
+    release_behavior: ReleaseBehavior,
+    /// The workarounds that were needed to create the context.
+    driver_workarounds: DriverWorkarounds,
+    swap_interval: Mutex<SwapIntervalTracker<ffi::Window>>,
+    /// The divisor and remainder of `glXSwapBuffersMscOML`, if the swaps are
+    /// scheduled with it.
+    swap_msc: Mutex<Option<(i64, i64)>>,
+    api: Api,
+    /// Set when `window` is a GLX pixmap created with the context, which is
+    /// destroyed along with it.
+    pixmap: bool,
+    /// Keeps `GLX` from being reloaded while the context exists.
+    _user: LibraryUser,
 }
 
 impl Context {
@@ -98,6 +166,12 @@ impl Context {
         transparent: bool,
     ) -> Result<ContextPrototype<'a>, CreationError> {
         let glx = GLX.as_ref().unwrap();
+        if let Some(share) = opengl.sharing {
+            if !share.is_on_screen(&xconn, screen_id) {
+                return Err(CreationError::IncompatibleSharing);
+            }
+        }
+
         // This is completely ridiculous, but VirtualBox's OpenGL driver needs
         // some call handled by *it* (i.e. not Mesa) to occur before
         // anything else can happen. That is because VirtualBox's OpenGL
@@ -152,13 +226,28 @@ impl Context {
             vi_copy
         };
 
+        // The release behavior can only be set through
+        // `glXCreateContextAttribsARB`.
+        let release_behavior = if pf_reqs.release_behavior
+            == ReleaseBehavior::None
+            && check_ext(&extensions, "GLX_ARB_create_context")
+            && check_ext(&extensions, "GLX_ARB_context_flush_control")
+        {
+            ReleaseBehavior::None
+        } else {
+            ReleaseBehavior::Flush
+        };
+
         Ok(ContextPrototype {
             extensions,
+            screen_id,
             xconn,
             opengl,
             fb_config,
             visual_infos: unsafe { mem::transmute(visual_infos) },
             pixel_format,
+            release_behavior,
+            driver_workarounds: pf_reqs.driver_workarounds,
         })
     }
 
@@ -169,6 +258,128 @@ impl Context {
             self.window,
             self.context,
         );
+        if res == 0 {
+            let err = self.xconn.check_errors();
+            Err(ContextError::OsError(format!(
+                "`glXMakeCurrent` failed: {:?}",
+                err
+            )))
+        } else {
+            // a failure is reported by `set_swap_interval`
+            let _ = self.apply_swap_interval();
+            Ok(())
+        }
+    }
+
+    /// Returns what is current on the calling thread.
+    pub unsafe fn current_state() -> CurrentState {
+        let glx = GLX.as_ref().unwrap();
+        CurrentState {
+            display: glx.GetCurrentDisplay(),
+            draw: glx.GetCurrentDrawable(),
+            read: glx.GetCurrentReadDrawable(),
+            context: glx.GetCurrentContext(),
+        }
+    }
+
+    /// Makes `state` current again. Releasing the current context needs a
+    /// display, so that of this context is used if none was current.
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        let glx = GLX.as_ref().unwrap();
+        if state.context.is_null() {
+            glx.MakeCurrent(self.xconn.display as *mut _, 0, ptr::null());
+        } else {
+            glx.MakeContextCurrent(
+                state.display,
+                state.draw,
+                state.read,
+                state.context,
+            );
+        }
+    }
+
+    /// Whether the context is on `screen_id` of the X server `xconn` is
+    /// connected to, possibly through another connection.
+    fn is_on_screen(&self, xconn: &XConnection, screen_id: c_int) -> bool {
+        if self.screen_id != screen_id {
+            return false;
+        }
+        if self.xconn.display == xconn.display {
+            return true;
+        }
+        let name = |xconn: &XConnection| unsafe {
+            CStr::from_ptr((xconn.xlib.XDisplayString)(xconn.display))
+                .to_owned()
+        };
+        name(&self.xconn) == name(xconn)
+    }
+
+    /// Applies the wanted swap interval to the window unless it was applied
+    /// already. `GLX_SGI_swap_control` sets the interval of the current
+    /// drawable, so the context must be current when only it is supported.
+    unsafe fn apply_swap_interval(&self) -> Result<(), ContextError> {
+        let mut tracker =
+            self.swap_interval.lock().unwrap_or_else(|err| err.into_inner());
+        let interval = match tracker.pending(self.window) {
+            Some(interval) => interval,
+            None => return Ok(()),
+        };
+
+        if check_ext(&self.extensions, "GLX_EXT_swap_control")
+            && self.extra_functions.SwapIntervalEXT.is_loaded()
+        {
+            self.extra_functions.SwapIntervalEXT(
+                self.xconn.display as *mut _,
+                self.window,
+                interval,
+            );
+            if let Err(err) = self.xconn.check_errors() {
+                return Err(ContextError::OsError(format!(
+                    "`glXSwapIntervalEXT` failed: {:?}",
+                    err
+                )));
+            }
+        } else if self.extra_functions.SwapIntervalSGI(interval) != 0 {
+            return Err(ContextError::OsError(
+                "`glXSwapIntervalSGI` failed".to_string(),
+            ));
+        }
+        tracker.applied(self.window, interval);
+        Ok(())
+    }
+
+    /// `GLX_EXT_swap_control` sets the interval of the window right away.
+    /// `GLX_SGI_swap_control` can't disable vsync, and sets the interval of
+    /// the current drawable, so the interval is applied when the context is
+    /// next made current if it isn't current on the calling thread.
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        let ext = check_ext(&self.extensions, "GLX_EXT_swap_control")
+            && self.extra_functions.SwapIntervalEXT.is_loaded();
+        let sgi = check_ext(&self.extensions, "GLX_SGI_swap_control")
+            && self.extra_functions.SwapIntervalSGI.is_loaded();
+        if !(ext || sgi && interval > 0) {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        self.swap_interval
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .set(interval as c_int);
+        if ext || self.is_current() {
+            unsafe { self.apply_swap_interval() }
+        } else {
+            Ok(())
+        }
+    }
+
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if !self.is_current() {
+            return Ok(());
+        }
+
+        let glx = GLX.as_ref().unwrap();
+        let res =
+            glx.MakeCurrent(self.xconn.display as *mut _, 0, ptr::null());
         if res == 0 {
             let err = self.xconn.check_errors();
             Err(ContextError::OsError(format!(
@@ -187,31 +398,112 @@ impl Context {
     }
 
     pub fn get_proc_address(&self, addr: &str) -> *const () {
-        let glx = GLX.as_ref().unwrap();
-        let addr = CString::new(addr.as_bytes()).unwrap();
-        let addr = addr.as_ptr();
-        unsafe { glx.GetProcAddress(addr as *const _) as *const _ }
+        get_proc_address(GLX.as_ref().unwrap(), addr)
     }
 
     #[inline]
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
         let glx = GLX.as_ref().unwrap();
-        unsafe {
-            glx.SwapBuffers(self.xconn.display as *mut _, self.window);
-        }
+        let swap_msc =
+            *self.swap_msc.lock().unwrap_or_else(|err| err.into_inner());
+        let name = match swap_msc {
+            Some((divisor, remainder)) => unsafe {
+                self.extra_functions.SwapBuffersMscOML(
+                    self.xconn.display as *mut _,
+                    self.window,
+                    0,
+                    divisor,
+                    remainder,
+                );
+                "glXSwapBuffersMscOML"
+            },
+            None => unsafe {
+                glx.SwapBuffers(self.xconn.display as *mut _, self.window);
+                "glXSwapBuffers"
+            },
+        };
         if let Err(err) = self.xconn.check_errors() {
             Err(ContextError::OsError(format!(
-                "`glXSwapBuffers` failed: {:?}",
-                err
+                "`{}` failed: {:?}",
+                name, err
             )))
         } else {
             Ok(())
         }
     }
 
+    pub fn set_swap_msc(
+        &self,
+        divisor: u32,
+        remainder: u32,
+    ) -> Result<(), ContextError> {
+        if !check_ext(&self.extensions, "GLX_OML_sync_control")
+            || !self.extra_functions.SwapBuffersMscOML.is_loaded()
+        {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        if divisor != 0 && remainder >= divisor {
+            return Err(ContextError::OsError(format!(
+                "the remainder {} isn't smaller than the divisor {}",
+                remainder, divisor
+            )));
+        }
+
+        *self.swap_msc.lock().unwrap_or_else(|err| err.into_inner()) =
+            if divisor == 0 {
+                None
+            } else {
+                Some((i64::from(divisor), i64::from(remainder)))
+            };
+        Ok(())
+    }
+
+    /// Waits for the vertical blank after the current media stream counter
+    /// of the window.
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        if !check_ext(&self.extensions, "GLX_OML_sync_control")
+            || !self.extra_functions.GetSyncValuesOML.is_loaded()
+            || !self.extra_functions.WaitForMscOML.is_loaded()
+        {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        let display = self.xconn.display as *mut _;
+        let (mut ust, mut msc, mut sbc) = (0, 0, 0);
+        unsafe {
+            if self.extra_functions.GetSyncValuesOML(
+                display,
+                self.window,
+                &mut ust,
+                &mut msc,
+                &mut sbc,
+            ) == 0
+            {
+                return Err(ContextError::OsError(
+                    "`glXGetSyncValuesOML` failed".to_string(),
+                ));
+            }
+            if self.extra_functions.WaitForMscOML(
+                display,
+                self.window,
+                msc + 1,
+                0,
+                0,
+                &mut ust,
+                &mut msc,
+                &mut sbc,
+            ) == 0
+            {
+                return Err(ContextError::OsError(
+                    "`glXWaitForMscOML` failed".to_string(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn get_api(&self) -> ::Api {
-        ::Api::OpenGl
+        self.api
     }
 
     #[inline]
@@ -219,6 +511,221 @@ impl Context {
         self.pixel_format.clone()
     }
 
+    /// Contexts of different connections to the same screen can share, so
+    /// the screen is identified by the name of the display.
+    pub fn display_token(&self) -> DisplayToken {
+        let name = unsafe {
+            CStr::from_ptr((self.xconn.xlib.XDisplayString)(self.xconn.display))
+        };
+        let mut hasher = DefaultHasher::new();
+        (name, self.screen_id).hash(&mut hasher);
+        DisplayToken::new("glx", hasher.finish())
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        self.release_behavior
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        self.driver_workarounds
+    }
+
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        let glx = GLX.as_ref().unwrap();
+        let query = |attrib| {
+            let mut value = 0;
+            let ret = unsafe {
+                glx.GetFBConfigAttrib(
+                    self.xconn.display as *mut _,
+                    self.fb_config,
+                    attrib as c_int,
+                    &mut value,
+                )
+            };
+            // `Success` is 0, anything else is a GLX error code.
+            if ret != 0 {
+                return Err(ContextError::OsError(format!(
+                    "`glXGetFBConfigAttrib` failed with error {}",
+                    ret
+                )));
+            }
+            Ok(value as u32)
+        };
+
+        Ok(PbufferLimits {
+            max_width: query(ffi::glx::MAX_PBUFFER_WIDTH)?,
+            max_height: query(ffi::glx::MAX_PBUFFER_HEIGHT)?,
+            max_pixels: query(ffi::glx::MAX_PBUFFER_PIXELS)?,
+        })
+    }
+
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        let glx = GLX.as_ref().unwrap();
+        let query = |attrib| {
+            let mut value = 0;
+            unsafe {
+                glx.QueryDrawable(
+                    self.xconn.display as *mut _,
+                    self.window,
+                    attrib as c_int,
+                    &mut value,
+                );
+            }
+            value
+        };
+
+        let width = query(ffi::glx::WIDTH);
+        let height = query(ffi::glx::HEIGHT);
+        // The buffer age is only defined for the drawable of the current
+        // context.
+        let buffer_age = if self.is_current()
+            && check_ext(&self.extensions, "GLX_EXT_buffer_age")
+        {
+            Some(query(ffi::glx_extra::BACK_BUFFER_AGE_EXT))
+        } else {
+            None
+        };
+        if let Err(err) = self.xconn.check_errors() {
+            return Err(ContextError::OsError(format!(
+                "`glXQueryDrawable` failed: {:?}",
+                err
+            )));
+        }
+
+        let render_buffer = if self.pixel_format.double_buffer {
+            RenderBuffer::Back
+        } else {
+            RenderBuffer::Single
+        };
+
+        Ok(SurfaceInfo {
+            width,
+            height,
+            swap_behavior: None,
+            render_buffer: Some(render_buffer),
+            multisample_resolve: None,
+            buffer_age,
+            largest_pbuffer: false,
+        })
+    }
+
+    pub fn join_swap_group(
+        &self,
+        group: u32,
+        barrier: u32,
+    ) -> Result<(), ContextError> {
+        if !check_ext(&self.extensions, "GLX_NV_swap_group") {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        let display = self.xconn.display as *mut _;
+        unsafe {
+            if self.extra_functions.JoinSwapGroupNV(display, self.window, group)
+                == 0
+            {
+                return Err(ContextError::OsError(
+                    "`glXJoinSwapGroupNV` failed".to_string(),
+                ));
+            }
+            // the barrier is bound to the group, which doesn't exist anymore
+            // when leaving it
+            if group != 0
+                && self.extra_functions.BindSwapBarrierNV(
+                    display, group, barrier,
+                ) == 0
+            {
+                return Err(ContextError::OsError(
+                    "`glXBindSwapBarrierNV` failed".to_string(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        if !check_ext(&self.extensions, "GLX_NV_swap_group") {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        let mut max_groups = 0;
+        let mut max_barriers = 0;
+        let ret = unsafe {
+            self.extra_functions.QueryMaxSwapGroupsNV(
+                self.xconn.display as *mut _,
+                self.screen_id,
+                &mut max_groups,
+                &mut max_barriers,
+            )
+        };
+        if ret == 0 {
+            return Err(ContextError::OsError(
+                "`glXQueryMaxSwapGroupsNV` failed".to_string(),
+            ));
+        }
+
+        Ok((max_groups, max_barriers))
+    }
+
+    /// Only `GLX_EXT_swap_control` can report the maximum interval, which
+    /// depends on the drawable.
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        if !check_ext(&self.extensions, "GLX_EXT_swap_control") {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        let glx = GLX.as_ref().unwrap();
+        let mut max = 0;
+        unsafe {
+            glx.QueryDrawable(
+                self.xconn.display as *mut _,
+                self.window,
+                ffi::glx_extra::MAX_SWAP_INTERVAL_EXT as c_int,
+                &mut max,
+            );
+        }
+        if let Err(err) = self.xconn.check_errors() {
+            return Err(ContextError::OsError(format!(
+                "`glXQueryDrawable` failed: {:?}",
+                err
+            )));
+        }
+
+        Ok(SwapIntervalRange {
+            min: 0,
+            max,
+            adaptive: check_ext(&self.extensions, "GLX_EXT_swap_control_tear"),
+        })
+    }
+
+    pub fn copy_state_from(
+        &self,
+        other: &Context,
+        mask: u32,
+    ) -> Result<(), ContextError> {
+        let glx = GLX.as_ref().unwrap();
+        unsafe {
+            glx.CopyContext(
+                self.xconn.display as *mut _,
+                other.context,
+                self.context,
+                mask as libc::c_ulong,
+            );
+        }
+        if let Err(err) = self.xconn.check_errors() {
+            return Err(ContextError::OsError(format!(
+                "`glXCopyContext` failed: {:?}",
+                err
+            )));
+        }
+
+        Ok(())
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::GLXContext {
         self.context
@@ -241,17 +748,23 @@ impl Drop for Context {
             }
 
             glx.DestroyContext(self.xconn.display as *mut _, self.context);
+            if self.pixmap {
+                glx.DestroyPixmap(self.xconn.display as *mut _, self.window);
+            }
         }
     }
 }
 
 pub struct ContextPrototype<'a> {
     extensions: String,
+    screen_id: libc::c_int,
     xconn: Arc<XConnection>,
     opengl: &'a GlAttributes<&'a Context>,
     fb_config: ffi::glx::types::GLXFBConfig,
     visual_infos: ffi::XVisualInfo,
     pixel_format: PixelFormat,
+    release_behavior: ReleaseBehavior,
+    driver_workarounds: DriverWorkarounds,
 }
 
 impl<'a> ContextPrototype<'a> {
@@ -269,12 +782,14 @@ impl<'a> ContextPrototype<'a> {
 
         // loading the extra GLX functions
         let extra_functions = ffi::glx_extra::Glx::load_with(|proc_name| {
-            let c_str = CString::new(proc_name).unwrap();
-            unsafe {
-                glx.GetProcAddress(c_str.as_ptr() as *const u8) as *const _
-            }
+            get_proc_address(glx, proc_name) as *const _
         });
 
+        let api = match self.opengl.version {
+            GlRequest::Specific(Api::OpenGlEs, _) => Api::OpenGlEs,
+            _ => Api::OpenGl,
+        };
+
         // creating GL context
         let context = match self.opengl.version {
             GlRequest::Latest => {
@@ -300,14 +815,18 @@ impl<'a> ContextPrototype<'a> {
                             &extra_functions,
                             &self.extensions,
                             &self.xconn.xlib,
+                            Api::OpenGl,
                             *opengl_version,
                             self.opengl.profile,
+                            self.opengl.forward_compatible,
                             self.opengl.debug,
                             self.opengl.robustness,
+                            self.release_behavior,
                             share,
                             self.xconn.display,
                             self.fb_config,
-                            &self.visual_infos,
+                            self.driver_workarounds
+                                .glx_legacy_create_context,
                         ) {
                             Ok(x) => {
                                 ctx = x;
@@ -320,14 +839,17 @@ impl<'a> ContextPrototype<'a> {
                         &extra_functions,
                         &self.extensions,
                         &self.xconn.xlib,
+                        Api::OpenGl,
                         (1, 0),
                         self.opengl.profile,
+                        self.opengl.forward_compatible,
                         self.opengl.debug,
                         self.opengl.robustness,
+                        self.release_behavior,
                         share,
                         self.xconn.display,
                         self.fb_config,
-                        &self.visual_infos,
+                        self.driver_workarounds.glx_legacy_create_context,
                     )?;
                     break;
                 }
@@ -337,16 +859,39 @@ impl<'a> ContextPrototype<'a> {
                 &extra_functions,
                 &self.extensions,
                 &self.xconn.xlib,
+                Api::OpenGl,
                 (major, minor),
                 self.opengl.profile,
+                self.opengl.forward_compatible,
                 self.opengl.debug,
                 self.opengl.robustness,
+                self.release_behavior,
                 share,
                 self.xconn.display,
                 self.fb_config,
-                &self.visual_infos,
+                self.driver_workarounds.glx_legacy_create_context,
             )?,
-            GlRequest::Specific(_, _) => panic!("Only OpenGL is supported"),
+            GlRequest::Specific(Api::OpenGlEs, (major, minor)) => {
+                create_context(
+                    &extra_functions,
+                    &self.extensions,
+                    &self.xconn.xlib,
+                    Api::OpenGlEs,
+                    (major, minor),
+                    self.opengl.profile,
+                    self.opengl.forward_compatible,
+                    self.opengl.debug,
+                    self.opengl.robustness,
+                    self.release_behavior,
+                    share,
+                    self.xconn.display,
+                    self.fb_config,
+                    self.driver_workarounds.glx_legacy_create_context,
+                )?
+            }
+            GlRequest::Specific(_, _) => {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
             GlRequest::GlThenGles {
                 opengl_version: (major, minor),
                 ..
@@ -354,18 +899,22 @@ impl<'a> ContextPrototype<'a> {
                 &extra_functions,
                 &self.extensions,
                 &self.xconn.xlib,
+                Api::OpenGl,
                 (major, minor),
                 self.opengl.profile,
+                self.opengl.forward_compatible,
                 self.opengl.debug,
                 self.opengl.robustness,
+                self.release_behavior,
                 share,
                 self.xconn.display,
                 self.fb_config,
-                &self.visual_infos,
+                self.driver_workarounds.glx_legacy_create_context,
             )?,
         };
 
         // vsync
+        let mut swap_interval = SwapIntervalTracker::new(None);
         if self.opengl.vsync {
             unsafe {
                 glx
@@ -379,6 +928,8 @@ impl<'a> ContextPrototype<'a> {
                 unsafe {
                     extra_functions.SwapIntervalEXT(self.xconn.display as *mut _, window, 1);
                 }
+                swap_interval.set(1);
+                swap_interval.applied(window, 1);
 
             // checking that it worked
             // TODO: handle this
@@ -407,6 +958,8 @@ impl<'a> ContextPrototype<'a> {
                 unsafe {
                     extra_functions.SwapIntervalSGI(1);
                 }
+                swap_interval.set(1);
+                swap_interval.applied(window, 1);
             } /* else if self.builder.strict {
                   // TODO: handle this
                   return Err(CreationError::OsError(format!("Couldn't find any available vsync extension")));
@@ -418,13 +971,119 @@ impl<'a> ContextPrototype<'a> {
             };
         }
 
+        let mut driver_workarounds = DriverWorkarounds::none();
+        driver_workarounds.glx_legacy_create_context =
+            !check_ext(&self.extensions, "GLX_ARB_create_context");
+
         Ok(Context {
             xconn: self.xconn,
             window,
             context,
+            fb_config: self.fb_config,
             pixel_format: self.pixel_format,
+            extensions: self.extensions,
+            extra_functions: Box::new(extra_functions),
+            screen_id: self.screen_id,
+            release_behavior: self.release_behavior,
+            driver_workarounds,
+            swap_interval: Mutex::new(swap_interval),
+            swap_msc: Mutex::new(None),
+            api,
+            pixmap: false,
+            _user: GLX.user(),
         })
     }
+
+    /// Finishes the context with a GLX pixmap wrapping `pixmap`, which must
+    /// outlive it. The config must have been chosen for pixmaps.
+    pub fn finish_pixmap(
+        self,
+        pixmap: ffi::Pixmap,
+    ) -> Result<Context, CreationError> {
+        let glx = GLX.as_ref().unwrap();
+        let display = self.xconn.display as *mut _;
+        let glx_pixmap = unsafe {
+            glx.CreatePixmap(display, self.fb_config, pixmap, ptr::null())
+        };
+        if glx_pixmap == 0 {
+            return Err(CreationError::OsError(
+                "glXCreatePixmap failed".to_string(),
+            ));
+        }
+        match self.finish(glx_pixmap) {
+            Ok(mut context) => {
+                context.pixmap = true;
+                Ok(context)
+            }
+            Err(err) => {
+                unsafe { glx.DestroyPixmap(display, glx_pixmap) };
+                Err(err)
+            }
+        }
+    }
+}
+
+/// Queries the default screen of the default X display for
+/// `glutin::probe`.
+///
+/// A separate connection is opened rather than going through
+/// `XConnection::new`, which would replace the error handler of the
+/// application.
+pub fn probe() -> Option<BackendInfo> {
+    let glx = GLX.as_ref()?;
+    let xlib = ffi::Xlib::open().ok()?;
+
+    unsafe {
+        let display = (xlib.XOpenDisplay)(ptr::null());
+        if display.is_null() {
+            return None;
+        }
+        let screen = (xlib.XDefaultScreen)(display);
+
+        let split = |p: *const libc::c_char| -> Vec<String> {
+            if p.is_null() {
+                return vec![];
+            }
+            CStr::from_ptr(p)
+                .to_string_lossy()
+                .split(' ')
+                .filter(|e| !e.is_empty())
+                .map(|e| e.to_string())
+                .collect()
+        };
+
+        let (mut major, mut minor) = (0, 0);
+        let ret =
+            glx.QueryVersion(display as *mut _, &mut major, &mut minor);
+        let info = if ret != 0 {
+            let client_extensions = split(glx.GetClientString(
+                display as *mut _,
+                ffi::glx::EXTENSIONS as c_int,
+            ));
+            let display_extensions =
+                split(glx.QueryExtensionsString(display as *mut _, screen));
+
+            let mut num_configs = 0;
+            let configs =
+                glx.GetFBConfigs(display as *mut _, screen, &mut num_configs);
+            if !configs.is_null() {
+                (xlib.XFree)(configs as *mut _);
+            }
+
+            Some(BackendInfo {
+                backend: Backend::Glx,
+                version: (major as u8, minor as u8),
+                client_extensions,
+                display_extensions,
+                num_configs: num_configs as usize,
+            })
+        } else {
+            None
+        };
+
+        (xlib.XCloseDisplay)(display);
+        info
+    }
 }
 
 extern "C" fn x_error_callback(
@@ -438,27 +1097,76 @@ fn create_context(
     extra_functions: &ffi::glx_extra::Glx,
     extensions: &str,
     xlib: &ffi::Xlib,
+    api: Api,
     version: (u8, u8),
     profile: Option<GlProfile>,
+    forward_compatible: bool,
     debug: bool,
     robustness: Robustness,
+    release_behavior: ReleaseBehavior,
     share: ffi::GLXContext,
     display: *mut ffi::Display,
     fb_config: ffi::glx::types::GLXFBConfig,
-    visual_infos: &ffi::XVisualInfo,
+    allow_legacy: bool,
 ) -> Result<ffi::GLXContext, CreationError> {
     let glx = GLX.as_ref().unwrap();
+    // ES contexts can only be created through `glXCreateContextAttribsARB`.
+    if api == Api::OpenGlEs
+        && !(check_ext(extensions, "GLX_ARB_create_context")
+            && check_ext(extensions, "GLX_EXT_create_context_es2_profile"))
+    {
+        return Err(CreationError::OpenGlVersionNotSupported);
+    }
+
+    // Profiles need `GLX_ARB_create_context_profile`, and the legacy entry
+    // point only gives compatibility contexts.
+    if profile.is_some() || forward_compatible {
+        let supported = if check_ext(extensions, "GLX_ARB_create_context") {
+            profile.is_none()
+                || check_ext(extensions, "GLX_ARB_create_context_profile")
+        } else {
+            profile != Some(GlProfile::Core) && !forward_compatible
+        };
+        if !supported {
+            return Err(CreationError::ProfileNotSupported);
+        }
+    }
+
+    // Without `GLX_ARB_create_context`, the legacy entry point ignores the
+    // version, the debug flag and the robustness.
+    if !check_ext(extensions, "GLX_ARB_create_context") {
+        if !allow_legacy {
+            return Err(CreationError::OpenGlVersionNotSupported);
+        }
+        match robustness {
+            Robustness::RobustNoResetNotification
+            | Robustness::RobustLoseContextOnReset => {
+                return Err(CreationError::RobustnessNotSupported);
+            }
+            _ => (),
+        }
+    }
+
     unsafe {
         let old_callback = (xlib.XSetErrorHandler)(Some(x_error_callback));
         let context = if check_ext(extensions, "GLX_ARB_create_context") {
-            let mut attributes = Vec::with_capacity(9);
-
-            attributes.push(ffi::glx_extra::CONTEXT_MAJOR_VERSION_ARB as c_int);
-            attributes.push(version.0 as c_int);
-            attributes.push(ffi::glx_extra::CONTEXT_MINOR_VERSION_ARB as c_int);
-            attributes.push(version.1 as c_int);
-
-            if let Some(profile) = profile {
+            let mut attributes = AttribList::new(0);
+
+            attributes.push(
+                ffi::glx_extra::CONTEXT_MAJOR_VERSION_ARB,
+                version.0,
+            );
+            attributes.push(
+                ffi::glx_extra::CONTEXT_MINOR_VERSION_ARB,
+                version.1,
+            );
+
+            if api == Api::OpenGlEs {
+                attributes.push(
+                    ffi::glx_extra::CONTEXT_PROFILE_MASK_ARB,
+                    ffi::glx_extra::CONTEXT_ES2_PROFILE_BIT_EXT,
+                );
+            } else if let Some(profile) = profile {
                 let flag = match profile {
                     GlProfile::Compatibility => {
                         ffi::glx_extra::CONTEXT_COMPATIBILITY_PROFILE_BIT_ARB
@@ -469,8 +1177,7 @@ fn create_context(
                 };
 
                 attributes
-                    .push(ffi::glx_extra::CONTEXT_PROFILE_MASK_ARB as c_int);
-                attributes.push(flag as c_int);
+                    .push(ffi::glx_extra::CONTEXT_PROFILE_MASK_ARB, flag);
             }
 
             let flags = {
@@ -478,35 +1185,24 @@ fn create_context(
 
                 // robustness
                 if check_ext(extensions, "GLX_ARB_create_context_robustness") {
-                    match robustness {
+                    let reset_strategy = match robustness {
                         Robustness::RobustNoResetNotification
                         | Robustness::TryRobustNoResetNotification => {
-                            attributes.push(
-                                ffi::glx_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int,
-                            );
-                            attributes.push(
-                                ffi::glx_extra::NO_RESET_NOTIFICATION_ARB
-                                    as c_int,
-                            );
-                            flags = flags
-                                | ffi::glx_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB
-                                    as c_int;
+                            Some(ffi::glx_extra::NO_RESET_NOTIFICATION_ARB)
                         }
                         Robustness::RobustLoseContextOnReset
                         | Robustness::TryRobustLoseContextOnReset => {
-                            attributes.push(
-                                ffi::glx_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int,
-                            );
-                            attributes.push(
-                                ffi::glx_extra::LOSE_CONTEXT_ON_RESET_ARB
-                                    as c_int,
-                            );
-                            flags = flags
-                                | ffi::glx_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB
-                                    as c_int;
+                            Some(ffi::glx_extra::LOSE_CONTEXT_ON_RESET_ARB)
                         }
-                        Robustness::NotRobust => (),
-                        Robustness::NoError => (),
+                        Robustness::NotRobust => None,
+                        Robustness::NoError => None,
+                    };
+                    if let Some(reset_strategy) = reset_strategy {
+                        attributes.push(
+                            ffi::glx_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB,
+                            reset_strategy,
+                        );
+                        flags |= ffi::glx_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB;
                     }
                 } else {
                     match robustness {
@@ -519,17 +1215,24 @@ fn create_context(
                 }
 
                 if debug {
-                    flags =
-                        flags | ffi::glx_extra::CONTEXT_DEBUG_BIT_ARB as c_int;
+                    flags |= ffi::glx_extra::CONTEXT_DEBUG_BIT_ARB;
+                }
+
+                if forward_compatible && api == Api::OpenGl {
+                    flags |= ffi::glx_extra::CONTEXT_FORWARD_COMPATIBLE_BIT_ARB;
                 }
 
                 flags
             };
 
-            attributes.push(ffi::glx_extra::CONTEXT_FLAGS_ARB as c_int);
-            attributes.push(flags);
+            attributes.push(ffi::glx_extra::CONTEXT_FLAGS_ARB, flags);
 
-            attributes.push(0);
+            if release_behavior == ReleaseBehavior::None {
+                attributes.push(
+                    ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_ARB,
+                    ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_NONE_ARB,
+                );
+            }
 
             extra_functions.CreateContextAttribsARB(
                 display as *mut _,
@@ -539,13 +1242,23 @@ fn create_context(
                 attributes.as_ptr(),
             )
         } else {
-            let visual_infos: *const ffi::XVisualInfo = visual_infos;
-            glx.CreateContext(
-                display as *mut _,
-                visual_infos as *mut _,
-                share,
-                1,
-            )
+            let create = |direct| {
+                glx.CreateNewContext(
+                    display as *mut _,
+                    fb_config,
+                    ffi::glx::RGBA_TYPE as c_int,
+                    share,
+                    direct,
+                )
+            };
+            // remote servers, as used by thin clients, only support indirect
+            // rendering
+            let context = create(1);
+            if context.is_null() {
+                create(0)
+            } else {
+                context
+            }
         };
 
         (xlib.XSetErrorHandler)(old_callback);
@@ -572,113 +1285,122 @@ unsafe fn choose_fbconfig(
     transparent: bool,
 ) -> Result<(ffi::glx::types::GLXFBConfig, PixelFormat), ()> {
     let descriptor = {
-        let mut out: Vec<c_int> = Vec::with_capacity(37);
+        let mut out = AttribList::new(0);
 
-        out.push(ffi::glx::X_RENDERABLE as c_int);
-        out.push(1);
+        out.push(ffi::glx::X_RENDERABLE, true);
 
         // TODO: If passed an visual xid, maybe we should stop assuming
         // TRUE_COLOR.
-        out.push(ffi::glx::X_VISUAL_TYPE as c_int);
-        out.push(ffi::glx::TRUE_COLOR as c_int);
+        let visual_type = match reqs.x11_visual_type {
+            None | Some(X11VisualType::TrueColor) => ffi::glx::TRUE_COLOR,
+            Some(X11VisualType::DirectColor) => ffi::glx::DIRECT_COLOR,
+        };
+        out.push(ffi::glx::X_VISUAL_TYPE, visual_type);
 
         if let Some(xid) = reqs.x11_visual_xid {
-            out.push(ffi::glx::VISUAL_ID as c_int);
-            out.push(xid as c_int);
+            out.push(ffi::glx::VISUAL_ID, xid as c_int);
+        }
+
+        let drawable_type = if reqs.x11_pixmap {
+            ffi::glx::PIXMAP_BIT
+        } else {
+            ffi::glx::WINDOW_BIT
+        };
+        out.push(ffi::glx::DRAWABLE_TYPE, drawable_type);
+
+        if reqs.plane != 0 {
+            out.push(ffi::glx::LEVEL, reqs.plane);
         }
 
-        out.push(ffi::glx::DRAWABLE_TYPE as c_int);
-        out.push(ffi::glx::WINDOW_BIT as c_int);
+        out.push(
+            ffi::glx::TRANSPARENT_TYPE,
+            match reqs.transparent_type {
+                TransparentType::None => ffi::glx::NONE,
+                TransparentType::Rgb => ffi::glx::TRANSPARENT_RGB,
+                TransparentType::Index => ffi::glx::TRANSPARENT_INDEX,
+            },
+        );
 
-        out.push(ffi::glx::RENDER_TYPE as c_int);
         if reqs.float_color_buffer {
             if check_ext(extensions, "GLX_ARB_fbconfig_float") {
-                out.push(ffi::glx_extra::RGBA_FLOAT_BIT_ARB as c_int);
+                out.push(
+                    ffi::glx::RENDER_TYPE,
+                    ffi::glx_extra::RGBA_FLOAT_BIT_ARB,
+                );
             } else {
                 return Err(());
             }
         } else {
-            out.push(ffi::glx::RGBA_BIT as c_int);
+            out.push(ffi::glx::RENDER_TYPE, ffi::glx::RGBA_BIT);
         }
 
         if let Some(color) = reqs.color_bits {
-            out.push(ffi::glx::RED_SIZE as c_int);
-            out.push((color / 3) as c_int);
-            out.push(ffi::glx::GREEN_SIZE as c_int);
-            out.push((color / 3 + if color % 3 != 0 { 1 } else { 0 }) as c_int);
-            out.push(ffi::glx::BLUE_SIZE as c_int);
-            out.push((color / 3 + if color % 3 == 2 { 1 } else { 0 }) as c_int);
+            out.push(ffi::glx::RED_SIZE, color / 3);
+            out.push(
+                ffi::glx::GREEN_SIZE,
+                color / 3 + if color % 3 != 0 { 1 } else { 0 },
+            );
+            out.push(
+                ffi::glx::BLUE_SIZE,
+                color / 3 + if color % 3 == 2 { 1 } else { 0 },
+            );
         }
 
         if let Some(alpha) = reqs.alpha_bits {
-            out.push(ffi::glx::ALPHA_SIZE as c_int);
-            out.push(alpha as c_int);
+            out.push(ffi::glx::ALPHA_SIZE, alpha);
         }
 
         if let Some(depth) = reqs.depth_bits {
-            out.push(ffi::glx::DEPTH_SIZE as c_int);
-            out.push(depth as c_int);
+            out.push(ffi::glx::DEPTH_SIZE, depth);
         }
 
         if let Some(stencil) = reqs.stencil_bits {
-            out.push(ffi::glx::STENCIL_SIZE as c_int);
-            out.push(stencil as c_int);
+            out.push(ffi::glx::STENCIL_SIZE, stencil);
         }
 
         let double_buffer = reqs.double_buffer.unwrap_or(true);
-        out.push(ffi::glx::DOUBLEBUFFER as c_int);
-        out.push(if double_buffer { 1 } else { 0 });
+        out.push(ffi::glx::DOUBLEBUFFER, double_buffer);
 
-        if let Some(multisampling) = reqs.multisampling {
+        if let Some(multisampling) = reqs.samples() {
             if check_ext(extensions, "GLX_ARB_multisample") {
-                out.push(ffi::glx_extra::SAMPLE_BUFFERS_ARB as c_int);
-                out.push(if multisampling == 0 { 0 } else { 1 });
-                out.push(ffi::glx_extra::SAMPLES_ARB as c_int);
-                out.push(multisampling as c_int);
-            } else {
+                out.push(
+                    ffi::glx_extra::SAMPLE_BUFFERS_ARB,
+                    multisampling != 0,
+                );
+                if check_ext(extensions, "GLX_NV_multisample_coverage") {
+                    // `GLX_SAMPLES_ARB` counts the coverage samples then
+                    out.push(
+                        ffi::glx_extra::COVERAGE_SAMPLES_NV,
+                        reqs.coverage_samples.unwrap_or(multisampling),
+                    );
+                    out.push(ffi::glx_extra::COLOR_SAMPLES_NV, multisampling);
+                } else {
+                    out.push(ffi::glx_extra::SAMPLES_ARB, multisampling);
+                }
+            } else if multisampling != 0 {
                 return Err(());
             }
         }
 
-        out.push(ffi::glx::STEREO as c_int);
-        out.push(if reqs.stereoscopy { 1 } else { 0 });
+        out.push(ffi::glx::STEREO, reqs.stereoscopy);
 
         if reqs.srgb {
             if check_ext(extensions, "GLX_ARB_framebuffer_sRGB") {
-                out.push(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int);
-                out.push(1);
+                out.push(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB, true);
             } else if check_ext(extensions, "GLX_EXT_framebuffer_sRGB") {
-                out.push(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int);
-                out.push(1);
+                out.push(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT, true);
             } else {
                 return Err(());
             }
         }
 
-        match reqs.release_behavior {
-            ReleaseBehavior::Flush => (),
-            ReleaseBehavior::None => {
-                if check_ext(extensions, "GLX_ARB_context_flush_control") {
-                    out.push(
-                        ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_ARB as c_int,
-                    );
-                    out.push(
-                        ffi::glx_extra::CONTEXT_RELEASE_BEHAVIOR_NONE_ARB
-                            as c_int,
-                    );
-                }
-            }
-        }
-
-        out.push(ffi::glx::CONFIG_CAVEAT as c_int);
-        out.push(ffi::glx::DONT_CARE as c_int);
+        out.push(ffi::glx::CONFIG_CAVEAT, ffi::glx::DONT_CARE);
 
-        out.push(0);
         out
     };
 
     // calling glXChooseFBConfig
-    let fb_config = {
+    let configs = {
         let mut num_configs = 1;
         let configs = glx.ChooseFBConfig(
             display as *mut _,
@@ -689,14 +1411,15 @@ unsafe fn choose_fbconfig(
         if configs.is_null() {
             return Err(());
         }
-        if num_configs == 0 {
-            return Err(());
-        }
 
-        let config = if transparent {
-            let configs = slice::from_raw_parts(configs, num_configs as usize);
-            configs.iter().find(|&config| {
-                let vi = glx.GetVisualFromFBConfig(display as *mut _, *config);
+        let matching = slice::from_raw_parts(configs, num_configs as usize)
+            .iter()
+            .cloned()
+            .filter(|&config| {
+                if !transparent {
+                    return true;
+                }
+                let vi = glx.GetVisualFromFBConfig(display as *mut _, config);
                 // Transparency was requested, so only choose configs with 32
                 // bits for RGBA.
                 let found = !vi.is_null() && (*vi).depth == 32;
@@ -704,20 +1427,41 @@ unsafe fn choose_fbconfig(
 
                 found
             })
-        } else {
-            Some(&*configs)
-        };
-
-        let res = if let Some(&conf) = config {
-            Ok(conf)
-        } else {
-            Err(())
-        };
+            .collect::<Vec<_>>();
 
         (xlib.XFree)(configs as *mut _);
-        res?
+        matching
+    };
+
+    let (fb_config, pf_desc) = match reqs.selection_hook {
+        Some(ref hook) if !configs.is_empty() => {
+            let candidates = configs
+                .into_iter()
+                .map(|config| {
+                    let desc =
+                        describe_fbconfig(glx, extensions, display, config);
+                    (config, desc)
+                })
+                .collect();
+            hook.select(candidates).ok_or(())?
+        }
+        _ => {
+            let config = *configs.first().ok_or(())?;
+            (config, describe_fbconfig(glx, extensions, display, config))
+        }
     };
+    pf_desc.debug_assert_satisfies(reqs);
 
+    Ok((fb_config, pf_desc))
+}
+
+/// Describes the framebuffer of a config.
+unsafe fn describe_fbconfig(
+    glx: &Glx,
+    extensions: &str,
+    display: *mut ffi::Display,
+    fb_config: ffi::glx::types::GLXFBConfig,
+) -> PixelFormat {
     let get_attrib = |attrib: c_int| -> i32 {
         let mut value = 0;
         glx.GetFBConfigAttrib(display as *mut _, fb_config, attrib, &mut value);
@@ -725,7 +1469,44 @@ unsafe fn choose_fbconfig(
         value
     };
 
-    let pf_desc = PixelFormat {
+    let sample_buffers = get_attrib(ffi::glx::SAMPLE_BUFFERS as c_int);
+    let samples = match get_attrib(ffi::glx::SAMPLES as c_int) {
+        0 | 1 => None,
+        _ if sample_buffers == 0 => None,
+        samples => Some(samples as u16),
+    };
+    let (multisampling, coverage_samples) = match samples {
+        Some(samples)
+            if check_ext(extensions, "GLX_NV_multisample_coverage") =>
+        {
+            let color =
+                get_attrib(ffi::glx_extra::COLOR_SAMPLES_NV as c_int) as u16;
+            if color != 0 && color < samples {
+                (Some(color), Some(samples))
+            } else {
+                (Some(samples), None)
+            }
+        }
+        samples => (samples, None),
+    };
+
+    let transparent_type = get_attrib(ffi::glx::TRANSPARENT_TYPE as c_int);
+    let transparent = if transparent_type == ffi::glx::TRANSPARENT_RGB as c_int
+    {
+        Some(TransparentPixel::Rgb {
+            red: get_attrib(ffi::glx::TRANSPARENT_RED_VALUE as c_int) as u32,
+            green: get_attrib(ffi::glx::TRANSPARENT_GREEN_VALUE as c_int)
+                as u32,
+            blue: get_attrib(ffi::glx::TRANSPARENT_BLUE_VALUE as c_int) as u32,
+        })
+    } else if transparent_type == ffi::glx::TRANSPARENT_INDEX as c_int {
+        let index = get_attrib(ffi::glx::TRANSPARENT_INDEX_VALUE as c_int);
+        Some(TransparentPixel::Index(index as u32))
+    } else {
+        None
+    };
+
+    PixelFormat {
         hardware_accelerated: get_attrib(ffi::glx::CONFIG_CAVEAT as c_int)
             != ffi::glx::SLOW_CONFIG as c_int,
         color_bits: get_attrib(ffi::glx::RED_SIZE as c_int) as u8
@@ -736,19 +1517,27 @@ unsafe fn choose_fbconfig(
         stencil_bits: get_attrib(ffi::glx::STENCIL_SIZE as c_int) as u8,
         stereoscopy: get_attrib(ffi::glx::STEREO as c_int) != 0,
         double_buffer: get_attrib(ffi::glx::DOUBLEBUFFER as c_int) != 0,
-        multisampling: if get_attrib(ffi::glx::SAMPLE_BUFFERS as c_int) != 0 {
-            Some(get_attrib(ffi::glx::SAMPLES as c_int) as u16)
-        } else {
-            None
-        },
+        sample_buffers: sample_buffers as u8,
+        multisampling,
+        coverage_samples,
         srgb: get_attrib(ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int)
             != 0
             || get_attrib(
                 ffi::glx_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int,
             ) != 0,
-    };
-
-    Ok((fb_config, pf_desc))
+        transparent,
+        native_renderable: get_attrib(ffi::glx::X_RENDERABLE as c_int) != 0,
+        x11_visual_type: match get_attrib(ffi::glx::X_VISUAL_TYPE as c_int) {
+            v if v == ffi::glx::TRUE_COLOR as c_int => {
+                Some(X11VisualType::TrueColor)
+            }
+            v if v == ffi::glx::DIRECT_COLOR as c_int => {
+                Some(X11VisualType::DirectColor)
+            }
+            _ => None,
+        },
+        native_id: Some(get_attrib(ffi::glx::FBCONFIG_ID as c_int) as u32),
+    }
 }
 
 /// Checks if `ext` is available.
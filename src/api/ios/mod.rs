@@ -64,6 +64,8 @@ use std::ffi::CString;
 use std::io;
 use std::mem;
 use std::os::raw::*;
+use std::thread;
+use std::time::Duration;
 
 use objc::declare::ClassDecl;
 use objc::runtime::{Class, Object, Sel, BOOL, NO, YES};
@@ -71,8 +73,11 @@ use objc::runtime::{Class, Object, Sel, BOOL, NO, YES};
 use os::ios::{WindowBuilderExt, WindowExt};
 use os::ContextTraitExt;
 use {
-    Api, ContextError, CreationError, EventsLoop, GlAttributes, GlRequest,
-    PixelFormat, PixelFormatRequirements, Window, WindowBuilder,
+    Api, BackendInfo, ContextError, CreationError, DisplayToken,
+    DriverWorkarounds, EventsLoop, GlAttributes, GlRequest, PbufferLimits,
+    PixelFormat, PixelFormatRequirements, PresentStats, Rect, ReleaseBehavior,
+    RenderBuffer, SurfaceDowngrade, SurfaceInfo, SurfaceVisibility,
+    SwapBehavior, SwapIntervalRange, TransparentType, Window, WindowBuilder,
 };
 
 mod ffi;
@@ -153,6 +158,10 @@ fn multisampling_for_view(view: id) -> Option<u16> {
     }
 }
 
+/// The `EAGLContext` current on a thread before a context was made current
+/// by `Context::make_current_scoped`, which may be `nil`.
+pub struct CurrentState(id);
+
 pub struct Context {
     eagl_context: id,
     view: id, // this will be invalid after the `EventsLoop` is dropped
@@ -172,14 +181,38 @@ fn validate_version(version: u8) -> Result<NSUInteger, CreationError> {
     }
 }
 
+/// See the docs in the crate root file.
+pub fn probe() -> Vec<BackendInfo> {
+    Vec::new()
+}
+
+/// Nothing is loaded at runtime.
+pub unsafe fn reload_symbols() -> Result<(), usize> {
+    Ok(())
+}
+
+/// Nothing is loaded at runtime.
+pub fn preload() {}
+
 impl Context {
     #[inline]
     pub fn new(
         builder: WindowBuilder,
         event_loop: &EventsLoop,
-        _: &PixelFormatRequirements,
+        pf_reqs: &PixelFormatRequirements,
         gl_attrs: &GlAttributes<&Context>,
     ) -> Result<(Window, Self), CreationError> {
+        if pf_reqs.plane != 0 {
+            return Err(CreationError::NotSupported(
+                "layer planes are not supported on iOS",
+            ));
+        }
+
+        if pf_reqs.transparent_type != TransparentType::None {
+            return Err(CreationError::NotSupported(
+                "transparent configs are not supported on iOS",
+            ));
+        }
         create_view_class();
         let view_class =
             Class::get("MainGLView").expect("Failed to get class `MainGLView`");
@@ -226,6 +259,17 @@ impl Context {
         Self::new(wb, el, pf_reqs, gl_attr).map(|(_window, context)| context)
     }
 
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn new_compute(
+        _pf_reqs: &PixelFormatRequirements,
+        _gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        Err(CreationError::NotSupported(
+            "contexts can't be built without an events loop on iOS",
+        ))
+    }
+
     /// See the docs in the crate root file.
     #[inline]
     pub fn new_separated(
@@ -334,9 +378,25 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        _rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        self.swap_buffers()
+    }
+
+    /// Waits for about `timeout`, in place of a swap while rendering is
+    /// paused.
+    #[inline]
+    pub fn wait_paused_frame(&self, timeout: Duration) {
+        thread::sleep(timeout)
+    }
+
     #[inline]
     pub fn get_pixel_format(&self) -> PixelFormat {
         let color_format = ColorFormat::for_view(self.view);
+        let multisampling = multisampling_for_view(self.view);
         PixelFormat {
             hardware_accelerated: true,
             color_bits: color_format.color_bits(),
@@ -345,8 +405,14 @@ impl Context {
             stencil_bits: stencil_for_view(self.view),
             stereoscopy: false,
             double_buffer: true,
-            multisampling: multisampling_for_view(self.view),
+            sample_buffers: multisampling.is_some() as u8,
+            multisampling,
+            coverage_samples: None,
             srgb: color_format.srgb(),
+            transparent: None,
+            native_renderable: false,
+            x11_visual_type: None,
+            native_id: None,
         }
     }
 
@@ -355,6 +421,187 @@ impl Context {
         // N/A
     }
 
+    #[inline]
+    pub fn needs_resize(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn resize_pbuffer(
+        &self,
+        _width: u32,
+        _height: u32,
+        _largest: bool,
+    ) -> Result<(u32, u32), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_pbuffer_size(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_surface_visibility(
+        &self,
+        _window: &Window,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn is_vsync_forced(
+        &self,
+        _window: &Window,
+    ) -> Result<bool, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        DisplayToken::new("eagl", 0)
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        ReleaseBehavior::Flush
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        DriverWorkarounds::none()
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        _behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        _render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        _metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn join_swap_group(
+        &self,
+        _group: u32,
+        _barrier: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn copy_state_from(
+        &self,
+        _other: &Context,
+        _mask: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_swap_msc(
+        &self,
+        _divisor: u32,
+        _remainder: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        _: i32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn bind_pbuffer_tex_image(&self, _: bool) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, _: u32) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub unsafe fn current_state(&self) -> CurrentState {
+        let context_class = Class::get("EAGLContext")
+            .expect("Failed to get class `EAGLContext`");
+        CurrentState(msg_send![context_class, currentContext])
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        let context_class = Class::get("EAGLContext")
+            .expect("Failed to get class `EAGLContext`");
+        let _: BOOL = msg_send![context_class, setCurrentContext: state.0];
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         let context_class = Class::get("EAGLContext")
@@ -371,6 +618,21 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        let context_class = Class::get("EAGLContext")
+            .expect("Failed to get class `EAGLContext`");
+        let res: BOOL = msg_send![context_class, setCurrentContext: nil];
+        if res == YES {
+            Ok(())
+        } else {
+            Err(ContextError::IoError(io::Error::new(
+                io::ErrorKind::Other,
+                "`EAGLContext setCurrentContext` failed",
+            )))
+        }
+    }
+
     #[inline]
     pub fn is_current(&self) -> bool {
         // TODO: This can likely be implemented using
@@ -459,4 +721,14 @@ impl ContextTraitExt for Context {
     unsafe fn get_egl_display(&self) -> Option<*const c_void> {
         None
     }
+
+    #[inline]
+    fn egl_display_tracks_references(&self) -> Option<bool> {
+        None
+    }
+
+    #[inline]
+    fn set_egl_label(&self, _label: &str) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
 }
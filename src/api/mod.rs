@@ -1,8 +1,12 @@
 pub mod android;
+pub mod attribs;
 pub mod caca;
 pub mod dlloader;
 pub mod egl;
 pub mod glx;
 pub mod ios;
 pub mod osmesa;
+pub mod proc_cache;
+pub mod reloadable;
+pub mod swap_interval;
 pub mod wgl;
@@ -12,12 +12,17 @@ use libc;
 use Api;
 use ContextError;
 use CreationError;
+use DisplayToken;
 use GlAttributes;
 use GlProfile;
 use GlRequest;
 use PixelFormat;
 use PixelFormatRequirements;
+use ReleaseBehavior;
+use RenderBuffer;
 use Robustness;
+use SurfaceInfo;
+use TransparentType;
 
 use std::error::Error;
 use std::ffi::CString;
@@ -36,6 +41,9 @@ pub struct OsMesaContext {
     height: u32,
 }
 
+/// The context current on a thread, to restore it afterwards.
+pub struct CurrentState(osmesa_sys::OSMesaContext);
+
 #[derive(Debug)]
 struct NoEsOrWebGlSupported;
 
@@ -78,7 +86,7 @@ impl Error for LoadingError {
 impl OsMesaContext {
     pub fn new(
         dimensions: (u32, u32),
-        _pf_reqs: &PixelFormatRequirements,
+        pf_reqs: &PixelFormatRequirements,
         opengl: &GlAttributes<&OsMesaContext>,
     ) -> Result<OsMesaContext, CreationError> {
         osmesa_sys::OsMesa::try_loading()
@@ -97,6 +105,18 @@ impl OsMesaContext {
             _ => (),
         }
 
+        if pf_reqs.plane != 0 {
+            return Err(CreationError::NotSupported(
+                "layer planes are not supported with OsMesa",
+            ));
+        }
+
+        if pf_reqs.transparent_type != TransparentType::None {
+            return Err(CreationError::NotSupported(
+                "transparent configs are not supported with OsMesa",
+            ));
+        }
+
         // TODO: use `pf_reqs` for the format
 
         let mut attribs = Vec::new();
@@ -192,6 +212,56 @@ impl OsMesaContext {
         Ok(())
     }
 
+    /// Returns the context current on the calling thread.
+    #[inline]
+    pub unsafe fn current_state() -> CurrentState {
+        CurrentState(osmesa_sys::OSMesaGetCurrentContext())
+    }
+
+    /// Makes `state` current again, rendering to the buffer it was last
+    /// bound to. OSMesa can't tell the type of that buffer, so it is
+    /// assumed to be `GL_UNSIGNED_BYTE`, like the buffers of glutin.
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        let (mut width, mut height, mut format) = (0, 0, 0);
+        let mut buffer = ptr::null_mut();
+        if state.0.is_null()
+            || osmesa_sys::OSMesaGetColorBuffer(
+                state.0,
+                &mut width,
+                &mut height,
+                &mut format,
+                &mut buffer,
+            ) == 0
+        {
+            osmesa_sys::OSMesaMakeCurrent(
+                ptr::null_mut(),
+                ptr::null_mut(),
+                0,
+                0,
+                0,
+            );
+            return;
+        }
+        osmesa_sys::OSMesaMakeCurrent(state.0, buffer, 0x1401, width, height);
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if !self.is_current() {
+            return Ok(());
+        }
+
+        // passing a null context releases the current one
+        osmesa_sys::OSMesaMakeCurrent(
+            ptr::null_mut(),
+            ptr::null_mut(),
+            0,
+            0,
+            0,
+        );
+        Ok(())
+    }
+
     #[inline]
     pub fn is_current(&self) -> bool {
         unsafe { osmesa_sys::OSMesaGetCurrentContext() == self.context }
@@ -217,6 +287,30 @@ impl OsMesaContext {
         unimplemented!();
     }
 
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        DisplayToken::new("osmesa", 0)
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        ReleaseBehavior::Flush
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        // OSMesa renders straight into our buffer.
+        Ok(SurfaceInfo {
+            width: self.width,
+            height: self.height,
+            swap_behavior: None,
+            render_buffer: Some(RenderBuffer::Single),
+            multisample_resolve: None,
+            buffer_age: None,
+            largest_pbuffer: false,
+        })
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> *mut c_void {
         self.context as *mut _
@@ -0,0 +1,50 @@
+#![cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// The addresses of the functions resolved from a library, shared by all
+/// the contexts using it, so that each function is only resolved once per
+/// process rather than once per context.
+///
+/// Only suits the libraries whose functions don't depend on the context or
+/// the display they're resolved for.
+#[derive(Default)]
+pub struct ProcCache {
+    addresses: Mutex<HashMap<String, usize>>,
+}
+
+impl ProcCache {
+    /// Returns the address of `name`, resolving it with `resolve` unless it
+    /// already was. Functions that couldn't be resolved are remembered too.
+    pub fn get<F>(&self, name: &str, resolve: F) -> *const ()
+    where
+        F: FnOnce(&str) -> *const (),
+    {
+        let mut addresses =
+            self.addresses.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(&address) = addresses.get(name) {
+            return address as *const ();
+        }
+        let address = resolve(name);
+        addresses.insert(name.to_string(), address as usize);
+        address
+    }
+
+    /// Forgets the addresses, once the library is unloaded.
+    pub fn clear(&self) {
+        self.addresses
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .clear();
+    }
+}
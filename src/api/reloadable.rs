@@ -0,0 +1,72 @@
+use std::marker::PhantomData;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+
+/// A library, or the functions loaded from it, that `reload_symbols` can
+/// unload and load again while no context uses it.
+///
+/// It derefs to the loaded value, `None` if the library couldn't be loaded.
+pub struct Reloadable<T> {
+    current: AtomicPtr<Option<T>>,
+    /// The number of `LibraryUser`s alive.
+    users: AtomicUsize,
+    _marker: PhantomData<Option<T>>,
+}
+
+impl<T> Reloadable<T> {
+    pub fn new(value: Option<T>) -> Self {
+        Reloadable {
+            current: AtomicPtr::new(Box::into_raw(Box::new(value))),
+            users: AtomicUsize::new(0),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Registers a context using the library until the returned value is
+    /// dropped.
+    pub fn user(&'static self) -> LibraryUser {
+        self.users.fetch_add(1, Ordering::SeqCst);
+        LibraryUser(&self.users)
+    }
+
+    /// The number of contexts using the library.
+    pub fn users(&self) -> usize {
+        self.users.load(Ordering::SeqCst)
+    }
+
+    /// Replaces the loaded value, dropping the previous one.
+    ///
+    /// # Safety
+    ///
+    /// The previous value must not be borrowed anymore, which means that no
+    /// context uses it and that no other thread is building one.
+    pub unsafe fn replace(&self, value: Option<T>) {
+        let previous = self
+            .current
+            .swap(Box::into_raw(Box::new(value)), Ordering::SeqCst);
+        drop(Box::from_raw(previous));
+    }
+}
+
+impl<T> Deref for Reloadable<T> {
+    type Target = Option<T>;
+
+    fn deref(&self) -> &Option<T> {
+        unsafe { &*self.current.load(Ordering::SeqCst) }
+    }
+}
+
+impl<T> Drop for Reloadable<T> {
+    fn drop(&mut self) {
+        unsafe { drop(Box::from_raw(*self.current.get_mut())) }
+    }
+}
+
+/// Keeps a `Reloadable` from being reloaded while it exists.
+pub struct LibraryUser(&'static AtomicUsize);
+
+impl Drop for LibraryUser {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
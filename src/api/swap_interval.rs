@@ -0,0 +1,64 @@
+#![cfg(any(
+    target_os = "windows",
+    target_os = "macos",
+    target_os = "linux",
+    target_os = "android",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+
+use std::os::raw::c_int;
+
+/// The swap interval wanted for a context, and the drawable it was last
+/// applied to.
+///
+/// `eglSwapInterval`, `glXSwapIntervalSGI` and `wglSwapIntervalEXT` set the
+/// interval of whatever drawable is bound to the current context, so the
+/// interval belongs to the pair and must be applied again whenever the
+/// context is made current with another drawable or a new interval is
+/// wanted.
+#[derive(Debug)]
+pub struct SwapIntervalTracker<D> {
+    desired: Option<c_int>,
+    applied: Option<(D, c_int)>,
+}
+
+impl<D: Copy + PartialEq> SwapIntervalTracker<D> {
+    /// `None` leaves the interval of the driver alone until `set` is called.
+    pub fn new(desired: Option<c_int>) -> Self {
+        SwapIntervalTracker {
+            desired,
+            applied: None,
+        }
+    }
+
+    /// Records the interval wanted from now on.
+    pub fn set(&mut self, interval: c_int) {
+        self.desired = Some(interval);
+    }
+
+    /// Returns the interval to apply to `drawable`, unless it is already
+    /// the one applied.
+    pub fn pending(&self, drawable: D) -> Option<c_int> {
+        match self.desired {
+            Some(interval) if self.applied != Some((drawable, interval)) => {
+                Some(interval)
+            }
+            _ => None,
+        }
+    }
+
+    /// Records that `interval` was applied to `drawable`.
+    pub fn applied(&mut self, drawable: D, interval: c_int) {
+        self.applied = Some((drawable, interval));
+    }
+
+    /// Forgets the drawable the interval was applied to, once it is
+    /// destroyed, since a new one may be given the same handle.
+    #[cfg(target_os = "android")]
+    pub fn forget_drawable(&mut self) {
+        self.applied = None;
+    }
+}
@@ -0,0 +1,167 @@
+use std::io;
+use std::os::raw::c_void;
+
+use ContextError;
+
+use super::gl;
+
+/// How OpenGL is going to access a registered Direct3D object.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DxInteropAccess {
+    /// OpenGL only reads from the object.
+    ReadOnly,
+
+    /// OpenGL reads from and writes to the object.
+    ReadWrite,
+
+    /// OpenGL overwrites the whole object without reading its contents.
+    WriteDiscard,
+}
+
+impl DxInteropAccess {
+    fn to_wgl(self) -> gl::wgl_extra::types::GLenum {
+        match self {
+            DxInteropAccess::ReadOnly => gl::wgl_extra::ACCESS_READ_ONLY_NV,
+            DxInteropAccess::ReadWrite => gl::wgl_extra::ACCESS_READ_WRITE_NV,
+            DxInteropAccess::WriteDiscard => {
+                gl::wgl_extra::ACCESS_WRITE_DISCARD_NV
+            }
+        }
+    }
+}
+
+/// A Direct3D device opened for interoperation with a WGL context through
+/// `WGL_NV_DX_interop`.
+///
+/// The device is closed when this is dropped.
+pub struct DxInteropDevice {
+    handle: gl::wgl_extra::types::HANDLE,
+    functions: Box<gl::wgl_extra::Wgl>,
+}
+
+/// A Direct3D resource registered as an OpenGL object.
+///
+/// The object is unregistered when this is dropped.
+pub struct DxInteropObject<'a> {
+    device: &'a DxInteropDevice,
+    handle: gl::wgl_extra::types::HANDLE,
+}
+
+impl DxInteropDevice {
+    pub(super) unsafe fn open(
+        functions: Box<gl::wgl_extra::Wgl>,
+        dx_device: *mut c_void,
+    ) -> Result<DxInteropDevice, ContextError> {
+        let handle = functions.DXOpenDeviceNV(dx_device as *mut _);
+        if handle.is_null() {
+            return Err(ContextError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(DxInteropDevice { handle, functions })
+    }
+
+    /// Registers a Direct3D resource, such as an `ID3D11Texture2D`, as the
+    /// OpenGL object `name` of type `ty` (for example `GL_TEXTURE_2D` or
+    /// `GL_RENDERBUFFER`).
+    ///
+    /// The OpenGL object must already exist and the context it belongs to
+    /// must be current.
+    pub unsafe fn register_object(
+        &self,
+        dx_object: *mut c_void,
+        name: u32,
+        ty: u32,
+        access: DxInteropAccess,
+    ) -> Result<DxInteropObject, ContextError> {
+        let handle = self.functions.DXRegisterObjectNV(
+            self.handle,
+            dx_object as *mut _,
+            name,
+            ty,
+            access.to_wgl(),
+        );
+        if handle.is_null() {
+            return Err(ContextError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(DxInteropObject {
+            device: self,
+            handle,
+        })
+    }
+
+    /// Locks the objects so that OpenGL can use them. Direct3D must not
+    /// access them until they are unlocked.
+    pub fn lock_objects(
+        &self,
+        objects: &[&DxInteropObject],
+    ) -> Result<(), ContextError> {
+        let mut handles = self.handles(objects);
+        let ret = unsafe {
+            self.functions.DXLockObjectsNV(
+                self.handle,
+                handles.len() as _,
+                handles.as_mut_ptr(),
+            )
+        };
+        if ret == 0 {
+            return Err(ContextError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    /// Unlocks the objects, giving them back to Direct3D.
+    pub fn unlock_objects(
+        &self,
+        objects: &[&DxInteropObject],
+    ) -> Result<(), ContextError> {
+        let mut handles = self.handles(objects);
+        let ret = unsafe {
+            self.functions.DXUnlockObjectsNV(
+                self.handle,
+                handles.len() as _,
+                handles.as_mut_ptr(),
+            )
+        };
+        if ret == 0 {
+            return Err(ContextError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    fn handles(
+        &self,
+        objects: &[&DxInteropObject],
+    ) -> Vec<gl::wgl_extra::types::HANDLE> {
+        objects
+            .iter()
+            .map(|object| {
+                assert!(
+                    object.device.handle == self.handle,
+                    "object registered with another device"
+                );
+                object.handle
+            })
+            .collect()
+    }
+}
+
+impl Drop for DxInteropDevice {
+    fn drop(&mut self) {
+        unsafe {
+            self.functions.DXCloseDeviceNV(self.handle);
+        }
+    }
+}
+
+impl<'a> Drop for DxInteropObject<'a> {
+    fn drop(&mut self) {
+        unsafe {
+            self.device
+                .functions
+                .DXUnregisterObjectNV(self.device.handle, self.handle);
+        }
+    }
+}
@@ -0,0 +1,252 @@
+use CreationError;
+
+use super::make_current_guard::CurrentContextGuard;
+use super::{create_context, gl, set_pixel_format};
+
+use std::ffi::{CStr, CString, OsStr};
+use std::os::raw::{c_int, c_void};
+use std::os::windows::ffi::OsStrExt;
+use std::sync::{Arc, Mutex};
+use std::{io, mem, ptr};
+
+use winapi::shared::minwindef::*;
+use winapi::shared::ntdef::LPCWSTR;
+use winapi::shared::windef::{HDC, HWND};
+use winapi::um::libloaderapi::*;
+use winapi::um::wingdi::*;
+use winapi::um::winuser::*;
+
+lazy_static! {
+    static ref WGL_INIT: Mutex<Option<Arc<WglInit>>> = Mutex::new(None);
+}
+
+/// A simple wrapper that destroys the window when it is destroyed.
+struct WindowWrapper(HWND, HDC);
+
+impl Drop for WindowWrapper {
+    #[inline]
+    fn drop(&mut self) {
+        unsafe {
+            DestroyWindow(self.0);
+        }
+    }
+}
+
+/// The WGL functions that are only available through a context, such as
+/// `wglChoosePixelFormatARB` and `wglCreateContextAttribsARB`.
+///
+/// Loading them requires creating a hidden dummy window along with a legacy
+/// context. This is only done once per process: the functions are then
+/// shared by every context created afterwards.
+pub struct WglInit {
+    pub(super) extra_functions: gl::wgl_extra::Wgl,
+}
+
+// the function pointers are valid on every thread
+unsafe impl Send for WglInit {}
+unsafe impl Sync for WglInit {}
+
+impl WglInit {
+    /// Returns the functions of the process, loading them on the first call.
+    ///
+    /// The dummy window is created with the class and size of `window`, so
+    /// that the same driver gets picked. Later calls reuse the functions
+    /// loaded for the first window, whatever their `window`.
+    ///
+    /// # Safety
+    ///
+    /// `window` must be a valid window.
+    pub unsafe fn get(window: HWND) -> Result<Arc<WglInit>, CreationError> {
+        let mut init = WGL_INIT.lock().unwrap();
+        if let Some(ref init) = *init {
+            return Ok(Arc::clone(init));
+        }
+
+        let extra_functions = load_extra_functions(window)?;
+        let new = Arc::new(WglInit { extra_functions });
+        *init = Some(Arc::clone(&new));
+        Ok(new)
+    }
+
+    /// Returns the space-separated list of the WGL extensions supported on
+    /// `hdc`, or an empty string if it can't be queried.
+    ///
+    /// # Safety
+    ///
+    /// `hdc` must be a valid device context.
+    pub unsafe fn get_extensions(&self, hdc: HDC) -> String {
+        let data = if self.extra_functions.GetExtensionsStringARB.is_loaded() {
+            self.extra_functions.GetExtensionsStringARB(hdc as *const _)
+        } else if self.extra_functions.GetExtensionsStringEXT.is_loaded() {
+            self.extra_functions.GetExtensionsStringEXT()
+        } else {
+            return String::new();
+        };
+        let data = CStr::from_ptr(data).to_bytes().to_vec();
+        String::from_utf8(data).unwrap()
+    }
+}
+
+/// Loads the WGL functions that are not guaranteed to be supported.
+///
+/// The `window` must be passed because the driver can vary depending on the
+/// window's characteristics.
+unsafe fn load_extra_functions(
+    window: HWND,
+) -> Result<gl::wgl_extra::Wgl, CreationError> {
+    let (ex_style, style) = (
+        WS_EX_APPWINDOW,
+        WS_POPUP | WS_CLIPSIBLINGS | WS_CLIPCHILDREN,
+    );
+
+    // creating a dummy invisible window
+    let dummy_window = {
+        // getting the rect of the real window
+        let rect = {
+            let mut placement: WINDOWPLACEMENT = mem::zeroed();
+            placement.length = mem::size_of::<WINDOWPLACEMENT>() as UINT;
+            if GetWindowPlacement(window, &mut placement) == 0 {
+                panic!();
+            }
+            placement.rcNormalPosition
+        };
+
+        // getting the class name of the real window
+        let mut class_name = [0u16; 128];
+        if GetClassNameW(window, class_name.as_mut_ptr(), 128) == 0 {
+            return Err(CreationError::OsError(format!(
+                "GetClassNameW function failed: {}",
+                format!("{}", io::Error::last_os_error())
+            )));
+        }
+
+        // access to class information of the real window
+        let instance = GetModuleHandleW(ptr::null());
+        let mut class: WNDCLASSEXW = mem::zeroed();
+
+        if GetClassInfoExW(instance, class_name.as_ptr(), &mut class) == 0 {
+            return Err(CreationError::OsError(format!(
+                "GetClassInfoExW function failed: {}",
+                format!("{}", io::Error::last_os_error())
+            )));
+        }
+
+        // register a new class for the dummy window,
+        // similar to the class of the real window but with a different callback
+        let class_name = OsStr::new("WglDummy Class")
+            .encode_wide()
+            .chain(Some(0).into_iter())
+            .collect::<Vec<_>>();
+
+        class.cbSize = mem::size_of::<WNDCLASSEXW>() as UINT;
+        class.lpszClassName = class_name.as_ptr();
+        class.lpfnWndProc = Some(DefWindowProcW);
+
+        // this shouldn't fail if the registration of the real window class
+        // worked. multiple registrations of the window class trigger an
+        // error which we want to ignore silently (e.g for multi-window
+        // setups)
+        RegisterClassExW(&class);
+
+        // this dummy window should match the real one enough to get the same
+        // OpenGL driver
+        let title = OsStr::new("dummy window")
+            .encode_wide()
+            .chain(Some(0).into_iter())
+            .collect::<Vec<_>>();
+        let win = CreateWindowExW(
+            ex_style,
+            class_name.as_ptr(),
+            title.as_ptr() as LPCWSTR,
+            style,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            rect.right - rect.left,
+            rect.bottom - rect.top,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            GetModuleHandleW(ptr::null()),
+            ptr::null_mut(),
+        );
+
+        if win.is_null() {
+            return Err(CreationError::OsError(format!(
+                "CreateWindowEx function failed: {}",
+                format!("{}", io::Error::last_os_error())
+            )));
+        }
+
+        let hdc = GetDC(win);
+        if hdc.is_null() {
+            let err = Err(CreationError::OsError(format!(
+                "GetDC function failed: {}",
+                format!("{}", io::Error::last_os_error())
+            )));
+            return err;
+        }
+
+        WindowWrapper(win, hdc)
+    };
+
+    // getting the pixel format that we will use and setting it
+    {
+        let id = choose_dummy_pixel_format(dummy_window.1)?;
+        set_pixel_format(dummy_window.1, id)?;
+    }
+
+    // creating the dummy OpenGL context and making it current
+    let dummy_context = create_context(None, dummy_window.0, dummy_window.1)?;
+    let _current_context =
+        CurrentContextGuard::make_current(dummy_window.1, dummy_context.0)?;
+
+    // loading the extra WGL functions
+    Ok(gl::wgl_extra::Wgl::load_with(|addr| {
+        let addr = CString::new(addr.as_bytes()).unwrap();
+        let addr = addr.as_ptr();
+        gl::wgl::GetProcAddress(addr) as *const c_void
+    }))
+}
+
+/// This function chooses a pixel format that is likely to be provided by
+/// the main video driver of the system.
+fn choose_dummy_pixel_format(hdc: HDC) -> Result<c_int, CreationError> {
+    // building the descriptor to pass to ChoosePixelFormat
+    let descriptor = PIXELFORMATDESCRIPTOR {
+        nSize: mem::size_of::<PIXELFORMATDESCRIPTOR>() as u16,
+        nVersion: 1,
+        dwFlags: PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER,
+        iPixelType: PFD_TYPE_RGBA,
+        cColorBits: 24,
+        cRedBits: 0,
+        cRedShift: 0,
+        cGreenBits: 0,
+        cGreenShift: 0,
+        cBlueBits: 0,
+        cBlueShift: 0,
+        cAlphaBits: 8,
+        cAlphaShift: 0,
+        cAccumBits: 0,
+        cAccumRedBits: 0,
+        cAccumGreenBits: 0,
+        cAccumBlueBits: 0,
+        cAccumAlphaBits: 0,
+        cDepthBits: 24,
+        cStencilBits: 8,
+        cAuxBuffers: 0,
+        iLayerType: PFD_MAIN_PLANE,
+        bReserved: 0,
+        dwLayerMask: 0,
+        dwVisibleMask: 0,
+        dwDamageMask: 0,
+    };
+
+    // now querying
+    let pf_id = unsafe { ChoosePixelFormat(hdc, &descriptor) };
+    if pf_id == 0 {
+        return Err(CreationError::OsError(
+            "No available pixel format".to_owned(),
+        ));
+    }
+
+    Ok(pf_id)
+}
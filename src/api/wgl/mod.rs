@@ -3,32 +3,52 @@
 use Api;
 use ContextError;
 use CreationError;
+use DisplayToken;
 use GlAttributes;
 use GlProfile;
 use GlRequest;
+use Ownership;
 use PixelFormat;
 use PixelFormatRequirements;
 use ReleaseBehavior;
+use RenderBuffer;
 use Robustness;
+use SurfaceInfo;
+use TransparentType;
 
 use self::make_current_guard::CurrentContextGuard;
 
-use std::ffi::{CStr, CString, OsStr};
+use api::attribs::AttribList;
+use api::swap_interval::SwapIntervalTracker;
+
+use std::ffi::{CString, OsStr};
 use std::os::raw::{c_int, c_void};
 use std::os::windows::ffi::OsStrExt;
+use std::sync::Mutex;
 use std::{io, mem, ptr};
 
 use winapi::shared::minwindef::HMODULE;
 use winapi::shared::minwindef::*;
-use winapi::shared::ntdef::LPCWSTR;
-use winapi::shared::windef::{HDC, HGLRC, HWND};
+use winapi::shared::windef::{HDC, HGLRC, HWND, RECT};
 use winapi::um::libloaderapi::*;
 use winapi::um::wingdi::*;
 use winapi::um::winuser::*;
 
+pub use self::dx_interop::{DxInteropAccess, DxInteropDevice, DxInteropObject};
+pub use self::init::WglInit;
+
+mod dx_interop;
 mod gl;
+mod init;
 mod make_current_guard;
 
+/// The device context and context current on a thread, to restore them
+/// afterwards.
+pub struct CurrentState {
+    hdc: HDC,
+    hglrc: HGLRC,
+}
+
 /// A WGL context.
 ///
 /// Note: should be destroyed before its window.
@@ -45,26 +65,37 @@ pub struct Context {
 
     /// The pixel format that has been used to create this context.
     pixel_format: PixelFormat,
-}
 
-/// A simple wrapper that destroys the window when it is destroyed.
-struct WindowWrapper(HWND, HDC);
+    /// The release behavior the context was actually created with.
+    release_behavior: ReleaseBehavior,
 
-impl Drop for WindowWrapper {
-    #[inline]
-    fn drop(&mut self) {
-        unsafe {
-            DestroyWindow(self.0);
-        }
-    }
+    /// The layer plane the context renders to, `0` being the main plane.
+    plane: i32,
+
+    /// The API the context was created with.
+    api: Api,
+
+    /// Functions that are not guaranteed to be supported.
+    extra_functions: Box<gl::wgl_extra::Wgl>,
+
+    /// The list of the supported WGL extensions.
+    extensions: String,
+
+    /// The swap interval, which `wglSwapIntervalEXT` sets for the window
+    /// of the current context.
+    swap_interval: Mutex<SwapIntervalTracker<HDC>>,
 }
 
-/// Wraps around a context so that it is destroyed when necessary.
-struct ContextWrapper(HGLRC);
+/// Wraps around a context so that it is destroyed when necessary, unless it
+/// is borrowed from another library.
+struct ContextWrapper(HGLRC, Ownership);
 
 impl Drop for ContextWrapper {
     #[inline]
     fn drop(&mut self) {
+        if self.1 == Ownership::Borrowed {
+            return;
+        }
         unsafe {
             gl::wgl::DeleteContext(self.0 as *const _);
         }
@@ -95,21 +126,31 @@ impl Context {
             return err;
         }
 
-        // loading the functions that are not guaranteed to be supported
-        let extra_functions = load_extra_functions(window)?;
+        // loading the functions that are not guaranteed to be supported, the
+        // dummy window is only created by the first context of the process
+        let init = WglInit::get(window)?;
+        let extra_functions = &init.extra_functions;
 
         // getting the list of the supported extensions
-        let extensions = if extra_functions.GetExtensionsStringARB.is_loaded() {
-            let data = extra_functions.GetExtensionsStringARB(hdc as *const _);
-            let data = CStr::from_ptr(data).to_bytes().to_vec();
-            String::from_utf8(data).unwrap()
-        } else if extra_functions.GetExtensionsStringEXT.is_loaded() {
-            let data = extra_functions.GetExtensionsStringEXT();
-            let data = CStr::from_ptr(data).to_bytes().to_vec();
-            String::from_utf8(data).unwrap()
-        } else {
-            format!("")
-        };
+        let extensions = init.get_extensions(hdc);
+
+        // layer planes can only be chosen through the ARB extensions
+        if pf_reqs.plane != 0
+            && ["WGL_ARB_pixel_format", "WGL_ARB_create_context"]
+                .iter()
+                .any(|&ext| extensions.split(' ').find(|&i| i == ext).is_none())
+        {
+            return Err(CreationError::NotSupported(
+                "layer planes require WGL_ARB_pixel_format and \
+                 WGL_ARB_create_context",
+            ));
+        }
+
+        if pf_reqs.transparent_type != TransparentType::None {
+            return Err(CreationError::NotSupported(
+                "transparent configs are not supported with WGL",
+            ));
+        }
 
         // calling SetPixelFormat
         let pixel_format = {
@@ -119,7 +160,7 @@ impl Context {
                 .is_some()
             {
                 choose_arb_pixel_format(
-                    &extra_functions,
+                    extra_functions,
                     &extensions,
                     hdc,
                     pf_reqs,
@@ -134,9 +175,37 @@ impl Context {
             f
         };
 
+        // ES contexts can only be created through `wglCreateContextAttribsARB`,
+        // so make sure we don't silently end up with desktop GL
+        let api = match opengl.version {
+            GlRequest::Specific(Api::OpenGlEs, _) => {
+                if extensions
+                    .split(' ')
+                    .find(|&i| i == "WGL_ARB_create_context")
+                    .is_none()
+                {
+                    return Err(CreationError::OpenGlVersionNotSupported);
+                }
+                Api::OpenGlEs
+            }
+            _ => Api::OpenGl,
+        };
+
+        // the legacy entry point only gives compatibility contexts
+        let needs_arb = opengl.profile == Some(GlProfile::Core)
+            || opengl.forward_compatible;
+        if needs_arb
+            && extensions
+                .split(' ')
+                .find(|&i| i == "WGL_ARB_create_context")
+                .is_none()
+        {
+            return Err(CreationError::ProfileNotSupported);
+        }
+
         // creating the OpenGL context
         let context = try!(create_context(
-            Some((&extra_functions, pf_reqs, opengl, &extensions)),
+            Some((extra_functions, pf_reqs, opengl, &extensions)),
             window,
             hdc
         ));
@@ -144,28 +213,96 @@ impl Context {
         // loading the opengl32 module
         let gl_library = load_opengl32_dll()?;
 
-        // handling vsync
-        if extensions
-            .split(' ')
-            .find(|&i| i == "WGL_EXT_swap_control")
-            .is_some()
+        // handling vsync, which compute contexts never present with
+        let mut swap_interval = SwapIntervalTracker::new(None);
+        if !pf_reqs.compute_only
+            && extensions
+                .split(' ')
+                .find(|&i| i == "WGL_EXT_swap_control")
+                .is_some()
         {
             let _guard = CurrentContextGuard::make_current(hdc, context.0)?;
 
-            if extra_functions.SwapIntervalEXT(if opengl.vsync { 1 } else { 0 })
-                == 0
-            {
+            let interval = if opengl.vsync { 1 } else { 0 };
+            if extra_functions.SwapIntervalEXT(interval) == 0 {
                 return Err(CreationError::OsError(format!(
                     "wglSwapIntervalEXT failed"
                 )));
             }
+            swap_interval.set(interval);
+            swap_interval.applied(hdc, interval);
         }
 
+        let release_behavior =
+            if release_behavior_none_supported(pf_reqs, &extensions) {
+                ReleaseBehavior::None
+            } else {
+                ReleaseBehavior::Flush
+            };
+
         Ok(Context {
             context: context,
             hdc: hdc,
             gl_library: gl_library,
             pixel_format: pixel_format,
+            release_behavior,
+            plane: pf_reqs.plane,
+            api,
+            extra_functions: Box::new(extra_functions.clone()),
+            extensions,
+            swap_interval: Mutex::new(swap_interval),
+        })
+    }
+
+    /// Wraps a context created by another library for the window of `hdc`.
+    ///
+    /// The pixel format is the one of the window, described without the
+    /// ARB extensions, so its multisampling and sRGB capability are unknown.
+    ///
+    /// # Unsafety
+    ///
+    /// `hdc` and `hglrc` must be valid, and outlive the resulting `Context`
+    /// unless it owns them.
+    pub unsafe fn from_raw(
+        hdc: HDC,
+        hglrc: HGLRC,
+        ownership: Ownership,
+    ) -> Result<Context, CreationError> {
+        let context = ContextWrapper(hglrc, ownership);
+
+        let id = GetPixelFormat(hdc);
+        let mut output: PIXELFORMATDESCRIPTOR = mem::zeroed();
+        if id == 0
+            || DescribePixelFormat(
+                hdc,
+                id,
+                mem::size_of::<PIXELFORMATDESCRIPTOR>() as UINT,
+                &mut output,
+            ) == 0
+        {
+            return Err(CreationError::OsError(format!(
+                "DescribePixelFormat function failed: {}",
+                format!("{}", io::Error::last_os_error())
+            )));
+        }
+
+        let init = WglInit::get(WindowFromDC(hdc))?;
+        let extensions = init.get_extensions(hdc);
+        let gl_library = load_opengl32_dll()?;
+
+        Ok(Context {
+            context,
+            hdc,
+            gl_library,
+            pixel_format: describe_native_pixel_format(id, &output),
+            // the flush control can't be queried
+            release_behavior: ReleaseBehavior::Flush,
+            plane: 0,
+            // WGL_EXT_create_context_es2_profile contexts can't be told apart
+            api: Api::OpenGl,
+            extra_functions: Box::new(init.extra_functions.clone()),
+            extensions,
+            swap_interval: Mutex::new(SwapIntervalTracker::new(None)),
         })
     }
 
@@ -175,6 +312,26 @@ impl Context {
         self.context.0
     }
 
+    /// Returns the device context and context current on the calling
+    /// thread.
+    #[inline]
+    pub unsafe fn current_state() -> CurrentState {
+        CurrentState {
+            hdc: gl::wgl::GetCurrentDC() as HDC,
+            hglrc: gl::wgl::GetCurrentContext() as HGLRC,
+        }
+    }
+
+    /// Makes `state` current again, which releases the current context if
+    /// none was current.
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        gl::wgl::MakeCurrent(
+            state.hdc as *const c_void,
+            state.hglrc as *const c_void,
+        );
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         if gl::wgl::MakeCurrent(
@@ -182,6 +339,52 @@ impl Context {
             self.context.0 as *const _,
         ) != 0
         {
+            // a failure is reported by `set_swap_interval`
+            let _ = self.apply_swap_interval();
+            Ok(())
+        } else {
+            Err(ContextError::IoError(io::Error::last_os_error()))
+        }
+    }
+
+    /// Applies the wanted swap interval to the window, which must be the one
+    /// of the current context, unless it was applied already.
+    unsafe fn apply_swap_interval(&self) -> Result<(), ContextError> {
+        let mut tracker =
+            self.swap_interval.lock().unwrap_or_else(|err| err.into_inner());
+        if let Some(interval) = tracker.pending(self.hdc) {
+            if self.extra_functions.SwapIntervalEXT(interval) == 0 {
+                return Err(ContextError::IoError(io::Error::last_os_error()));
+            }
+            tracker.applied(self.hdc, interval);
+        }
+        Ok(())
+    }
+
+    /// The interval is applied right away if the context is current on the
+    /// calling thread, and when it is next made current otherwise.
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        if !self.extensions.split(' ').any(|e| e == "WGL_EXT_swap_control") {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        self.swap_interval
+            .lock()
+            .unwrap_or_else(|err| err.into_inner())
+            .set(interval as c_int);
+        if self.is_current() {
+            unsafe { self.apply_swap_interval() }
+        } else {
+            Ok(())
+        }
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if !self.is_current() {
+            return Ok(());
+        }
+
+        if gl::wgl::MakeCurrent(ptr::null(), ptr::null()) != 0 {
             Ok(())
         } else {
             Err(ContextError::IoError(io::Error::last_os_error()))
@@ -200,9 +403,13 @@ impl Context {
         let addr = addr.as_ptr();
 
         unsafe {
+            // `wglGetProcAddress` doesn't return the functions exported by
+            // `opengl32.dll`, and some drivers return small invalid values
+            // instead of null on failure
             let p = gl::wgl::GetProcAddress(addr) as *const ();
-            if !p.is_null() {
-                return p;
+            match p as isize {
+                0 | 1 | 2 | 3 | -1 => (),
+                _ => return p,
             }
             GetProcAddress(self.gl_library, addr) as *const _
         }
@@ -216,25 +423,188 @@ impl Context {
         // } else {
         // Err(ContextError::IoError(io::Error::last_os_error()))
         // }
-        unsafe { SwapBuffers(self.hdc) };
+        if self.plane == 0 {
+            unsafe { SwapBuffers(self.hdc) };
+        } else {
+            // `WGL_SWAP_OVERLAYn` and `WGL_SWAP_UNDERLAYn` are one bit per
+            // plane, starting from the first one
+            let flag = if self.plane > 0 {
+                gl::wgl::SWAP_OVERLAY1 << (self.plane - 1)
+            } else {
+                gl::wgl::SWAP_UNDERLAY1 << (-self.plane - 1)
+            };
+            unsafe { gl::wgl::SwapLayerBuffers(self.hdc as *const _, flag) };
+        }
         Ok(())
     }
 
     #[inline]
     pub fn get_api(&self) -> Api {
-        // FIXME: can be opengl es
-        Api::OpenGl
+        self.api
     }
 
     #[inline]
     pub fn get_pixel_format(&self) -> PixelFormat {
         self.pixel_format.clone()
     }
+
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        DisplayToken::new("wgl", 0)
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        self.release_behavior
+    }
+
+    /// Opens a Direct3D device for sharing resources with this context
+    /// through `WGL_NV_DX_interop`.
+    pub unsafe fn dx_open_device(
+        &self,
+        dx_device: *mut c_void,
+    ) -> Result<DxInteropDevice, ContextError> {
+        if self
+            .extensions
+            .split(' ')
+            .find(|&i| i == "WGL_NV_DX_interop")
+            .is_none()
+        {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        DxInteropDevice::open(self.extra_functions.clone(), dx_device)
+    }
+
+    pub fn copy_state_from(
+        &self,
+        other: &Context,
+        mask: u32,
+    ) -> Result<(), ContextError> {
+        let ret = unsafe {
+            gl::wgl::CopyContext(
+                other.context.0 as *const _,
+                self.context.0 as *const _,
+                mask,
+            )
+        };
+        if ret == 0 {
+            return Err(ContextError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok(())
+    }
+
+    pub fn join_swap_group(
+        &self,
+        group: u32,
+        barrier: u32,
+    ) -> Result<(), ContextError> {
+        if self
+            .extensions
+            .split(' ')
+            .find(|&i| i == "WGL_NV_swap_group")
+            .is_none()
+        {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        unsafe {
+            if self
+                .extra_functions
+                .JoinSwapGroupNV(self.hdc as *const _, group)
+                == 0
+            {
+                return Err(ContextError::IoError(io::Error::last_os_error()));
+            }
+            // the barrier is bound to the group, which doesn't exist anymore
+            // when leaving it
+            if group != 0
+                && self.extra_functions.BindSwapBarrierNV(group, barrier) == 0
+            {
+                return Err(ContextError::IoError(io::Error::last_os_error()));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        if self
+            .extensions
+            .split(' ')
+            .find(|&i| i == "WGL_NV_swap_group")
+            .is_none()
+        {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        let mut max_groups = 0;
+        let mut max_barriers = 0;
+        let ret = unsafe {
+            self.extra_functions.QueryMaxSwapGroupsNV(
+                self.hdc as *const _,
+                &mut max_groups,
+                &mut max_barriers,
+            )
+        };
+        if ret == 0 {
+            return Err(ContextError::IoError(io::Error::last_os_error()));
+        }
+
+        Ok((max_groups, max_barriers))
+    }
+
+    /// WGL has no way to query the drawable, so the size is taken from the
+    /// client area of the window owning the device context.
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        let mut rect: RECT = unsafe { mem::zeroed() };
+        unsafe {
+            let hwnd = WindowFromDC(self.hdc);
+            if hwnd.is_null() || GetClientRect(hwnd, &mut rect) == 0 {
+                return Err(ContextError::IoError(io::Error::last_os_error()));
+            }
+        }
+
+        let render_buffer = if self.pixel_format.double_buffer {
+            RenderBuffer::Back
+        } else {
+            RenderBuffer::Single
+        };
+
+        Ok(SurfaceInfo {
+            width: (rect.right - rect.left) as u32,
+            height: (rect.bottom - rect.top) as u32,
+            swap_behavior: None,
+            render_buffer: Some(render_buffer),
+            multisample_resolve: None,
+            buffer_age: None,
+            largest_pbuffer: false,
+        })
+    }
 }
 
 unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
 
+/// Returns whether the context can be created without flushing on release.
+///
+/// The release behavior can only be set through `wglCreateContextAttribsARB`.
+fn release_behavior_none_supported(
+    pf_reqs: &PixelFormatRequirements,
+    extensions: &str,
+) -> bool {
+    pf_reqs.release_behavior == ReleaseBehavior::None
+        && extensions
+            .split(' ')
+            .find(|&i| i == "WGL_ARB_create_context")
+            .is_some()
+        && extensions
+            .split(' ')
+            .find(|&i| i == "WGL_ARB_context_flush_control")
+            .is_some()
+}
+
 /// Creates an OpenGL context.
 ///
 /// If `extra` is `Some`, this function will attempt to use the latest WGL
@@ -254,7 +624,7 @@ unsafe fn create_context(
 ) -> Result<ContextWrapper, CreationError> {
     let share;
 
-    if let Some((extra_functions, _pf_reqs, opengl, extensions)) = extra {
+    if let Some((extra_functions, pf_reqs, opengl, extensions)) = extra {
         share = opengl.sharing.unwrap_or(ptr::null_mut());
 
         if extensions
@@ -262,19 +632,15 @@ unsafe fn create_context(
             .find(|&i| i == "WGL_ARB_create_context")
             .is_some()
         {
-            let mut attributes = Vec::new();
+            let mut attributes = AttribList::new(0);
 
             match opengl.version {
                 GlRequest::Latest => {}
                 GlRequest::Specific(Api::OpenGl, (major, minor)) => {
-                    attributes.push(
-                        gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB as c_int,
-                    );
-                    attributes.push(major as c_int);
-                    attributes.push(
-                        gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB as c_int,
-                    );
-                    attributes.push(minor as c_int);
+                    attributes
+                        .push(gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB, major);
+                    attributes
+                        .push(gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB, minor);
                 }
                 GlRequest::Specific(Api::OpenGlEs, (major, minor)) => {
                     if extensions
@@ -283,23 +649,17 @@ unsafe fn create_context(
                         .is_some()
                     {
                         attributes.push(
-                            gl::wgl_extra::CONTEXT_PROFILE_MASK_ARB as c_int,
-                        );
-                        attributes.push(
-                            gl::wgl_extra::CONTEXT_ES2_PROFILE_BIT_EXT as c_int,
+                            gl::wgl_extra::CONTEXT_PROFILE_MASK_ARB,
+                            gl::wgl_extra::CONTEXT_ES2_PROFILE_BIT_EXT,
                         );
                     } else {
                         return Err(CreationError::OpenGlVersionNotSupported);
                     }
 
-                    attributes.push(
-                        gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB as c_int,
-                    );
-                    attributes.push(major as c_int);
-                    attributes.push(
-                        gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB as c_int,
-                    );
-                    attributes.push(minor as c_int);
+                    attributes
+                        .push(gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB, major);
+                    attributes
+                        .push(gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB, minor);
                 }
                 GlRequest::Specific(_, _) => {
                     return Err(CreationError::OpenGlVersionNotSupported);
@@ -308,18 +668,19 @@ unsafe fn create_context(
                     opengl_version: (major, minor),
                     ..
                 } => {
-                    attributes.push(
-                        gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB as c_int,
-                    );
-                    attributes.push(major as c_int);
-                    attributes.push(
-                        gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB as c_int,
-                    );
-                    attributes.push(minor as c_int);
+                    attributes
+                        .push(gl::wgl_extra::CONTEXT_MAJOR_VERSION_ARB, major);
+                    attributes
+                        .push(gl::wgl_extra::CONTEXT_MINOR_VERSION_ARB, minor);
                 }
             }
 
-            if let Some(profile) = opengl.profile {
+            // ES contexts already have their profile set above
+            let is_es = match opengl.version {
+                GlRequest::Specific(Api::OpenGlEs, _) => true,
+                _ => false,
+            };
+            if let (Some(profile), false) = (opengl.profile, is_es) {
                 if extensions
                     .split(' ')
                     .find(|&i| i == "WGL_ARB_create_context_profile")
@@ -334,12 +695,9 @@ unsafe fn create_context(
                         }
                     };
                     attributes
-                        .push(gl::wgl_extra::CONTEXT_PROFILE_MASK_ARB as c_int);
-                    attributes.push(flag as c_int);
+                        .push(gl::wgl_extra::CONTEXT_PROFILE_MASK_ARB, flag);
                 } else {
-                    return Err(CreationError::NotSupported(
-                        "required extension \"WGL_ARB_create_context_profile\" not found",
-                    ));
+                    return Err(CreationError::ProfileNotSupported);
                 }
             }
 
@@ -352,35 +710,23 @@ unsafe fn create_context(
                     .find(|&i| i == "WGL_ARB_create_context_robustness")
                     .is_some()
                 {
-                    match opengl.robustness {
+                    let reset_strategy = match opengl.robustness {
                         Robustness::RobustNoResetNotification
                         | Robustness::TryRobustNoResetNotification => {
-                            attributes.push(
-                                gl::wgl_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int,
-                            );
-                            attributes.push(
-                                gl::wgl_extra::NO_RESET_NOTIFICATION_ARB
-                                    as c_int,
-                            );
-                            flags = flags
-                                | gl::wgl_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB
-                                    as c_int;
+                            Some(gl::wgl_extra::NO_RESET_NOTIFICATION_ARB)
                         }
                         Robustness::RobustLoseContextOnReset
                         | Robustness::TryRobustLoseContextOnReset => {
-                            attributes.push(
-                                gl::wgl_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB as c_int,
-                            );
-                            attributes.push(
-                                gl::wgl_extra::LOSE_CONTEXT_ON_RESET_ARB
-                                    as c_int,
-                            );
-                            flags = flags
-                                | gl::wgl_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB
-                                    as c_int;
+                            Some(gl::wgl_extra::LOSE_CONTEXT_ON_RESET_ARB)
                         }
-                        Robustness::NotRobust => (),
-                        Robustness::NoError => (),
+                        Robustness::NotRobust => None,
+                        Robustness::NoError => None,
+                    };
+                    if let Some(reset_strategy) = reset_strategy {
+                        let key =
+                            gl::wgl_extra::CONTEXT_RESET_NOTIFICATION_STRATEGY_ARB;
+                        attributes.push(key, reset_strategy);
+                        flags |= gl::wgl_extra::CONTEXT_ROBUST_ACCESS_BIT_ARB;
                     }
                 } else {
                     match opengl.robustness {
@@ -393,17 +739,31 @@ unsafe fn create_context(
                 }
 
                 if opengl.debug {
-                    flags =
-                        flags | gl::wgl_extra::CONTEXT_DEBUG_BIT_ARB as c_int;
+                    flags |= gl::wgl_extra::CONTEXT_DEBUG_BIT_ARB;
+                }
+
+                if opengl.forward_compatible && !is_es {
+                    flags |= gl::wgl_extra::CONTEXT_FORWARD_COMPATIBLE_BIT_ARB;
                 }
 
                 flags
             };
 
-            attributes.push(gl::wgl_extra::CONTEXT_FLAGS_ARB as c_int);
-            attributes.push(flags);
+            attributes.push(gl::wgl_extra::CONTEXT_FLAGS_ARB, flags);
 
-            attributes.push(0);
+            if release_behavior_none_supported(pf_reqs, extensions) {
+                attributes.push(
+                    gl::wgl_extra::CONTEXT_RELEASE_BEHAVIOR_ARB,
+                    gl::wgl_extra::CONTEXT_RELEASE_BEHAVIOR_NONE_ARB,
+                );
+            }
+
+            if pf_reqs.plane != 0 {
+                attributes.push(
+                    gl::wgl_extra::CONTEXT_LAYER_PLANE_ARB,
+                    pf_reqs.plane,
+                );
+            }
 
             let ctx = extra_functions.CreateContextAttribsARB(
                 hdc as *const c_void,
@@ -417,7 +777,7 @@ unsafe fn create_context(
                     format!("{}", io::Error::last_os_error())
                 )));
             } else {
-                return Ok(ContextWrapper(ctx as HGLRC));
+                return Ok(ContextWrapper(ctx as HGLRC, Ownership::Owned));
             }
         }
     } else {
@@ -441,7 +801,32 @@ unsafe fn create_context(
         }
     };
 
-    Ok(ContextWrapper(ctx as HGLRC))
+    Ok(ContextWrapper(ctx as HGLRC, Ownership::Owned))
+}
+
+/// Converts the descriptor of the pixel format `id`, which doesn't know
+/// about multisampling and sRGB.
+fn describe_native_pixel_format(
+    id: c_int,
+    output: &PIXELFORMATDESCRIPTOR,
+) -> PixelFormat {
+    PixelFormat {
+        hardware_accelerated: (output.dwFlags & PFD_GENERIC_FORMAT) == 0,
+        color_bits: output.cRedBits + output.cGreenBits + output.cBlueBits,
+        alpha_bits: output.cAlphaBits,
+        depth_bits: output.cDepthBits,
+        stencil_bits: output.cStencilBits,
+        stereoscopy: (output.dwFlags & PFD_STEREO) != 0,
+        double_buffer: (output.dwFlags & PFD_DOUBLEBUFFER) != 0,
+        sample_buffers: 0,
+        multisampling: None,
+        coverage_samples: None,
+        srgb: false,
+        transparent: None,
+        native_renderable: false,
+        x11_visual_type: None,
+        native_id: Some(id as u32),
+    }
 }
 
 /// Chooses a pixel formats without using WGL.
@@ -458,7 +843,7 @@ unsafe fn choose_native_pixel_format(
         return Err(());
     }
 
-    match reqs.multisampling {
+    match reqs.samples() {
         Some(0) => (),
         None => (),
         Some(_) => return Err(()),
@@ -472,10 +857,6 @@ unsafe fn choose_native_pixel_format(
         return Err(());
     }
 
-    if reqs.release_behavior != ReleaseBehavior::Flush {
-        return Err(());
-    }
-
     // building the descriptor to pass to ChoosePixelFormat
     let descriptor = PIXELFORMATDESCRIPTOR {
         nSize: mem::size_of::<PIXELFORMATDESCRIPTOR>() as u16,
@@ -546,17 +927,7 @@ unsafe fn choose_native_pixel_format(
         return Err(());
     }
 
-    let pf_desc = PixelFormat {
-        hardware_accelerated: (output.dwFlags & PFD_GENERIC_FORMAT) == 0,
-        color_bits: output.cRedBits + output.cGreenBits + output.cBlueBits,
-        alpha_bits: output.cAlphaBits,
-        depth_bits: output.cDepthBits,
-        stencil_bits: output.cStencilBits,
-        stereoscopy: (output.dwFlags & PFD_STEREO) != 0,
-        double_buffer: (output.dwFlags & PFD_DOUBLEBUFFER) != 0,
-        multisampling: None,
-        srgb: false,
-    };
+    let pf_desc = describe_native_pixel_format(pf_id, &output);
 
     if pf_desc.alpha_bits < reqs.alpha_bits.unwrap_or(0) {
         return Err(());
@@ -581,7 +952,10 @@ unsafe fn choose_native_pixel_format(
         }
     }
 
-    Ok((pf_id, pf_desc))
+    match reqs.selection_hook {
+        Some(ref hook) => hook.select(vec![(pf_id, pf_desc)]).ok_or(()),
+        None => Ok((pf_id, pf_desc)),
+    }
 }
 
 /// Enumerates the list of pixel formats by using extra WGL functions.
@@ -594,81 +968,101 @@ unsafe fn choose_arb_pixel_format(
     reqs: &PixelFormatRequirements,
 ) -> Result<(c_int, PixelFormat), ()> {
     let descriptor = {
-        let mut out: Vec<c_int> = Vec::with_capacity(37);
+        let mut out = AttribList::new(0);
 
-        out.push(gl::wgl_extra::DRAW_TO_WINDOW_ARB as c_int);
-        out.push(1);
+        out.push(gl::wgl_extra::DRAW_TO_WINDOW_ARB, true);
 
-        out.push(gl::wgl_extra::SUPPORT_OPENGL_ARB as c_int);
-        out.push(1);
+        out.push(gl::wgl_extra::SUPPORT_OPENGL_ARB, true);
 
-        out.push(gl::wgl_extra::PIXEL_TYPE_ARB as c_int);
         if reqs.float_color_buffer {
             if extensions
                 .split(' ')
                 .find(|&i| i == "WGL_ARB_pixel_format_float")
                 .is_some()
             {
-                out.push(gl::wgl_extra::TYPE_RGBA_FLOAT_ARB as c_int);
+                out.push(
+                    gl::wgl_extra::PIXEL_TYPE_ARB,
+                    gl::wgl_extra::TYPE_RGBA_FLOAT_ARB,
+                );
             } else {
                 return Err(());
             }
         } else {
-            out.push(gl::wgl_extra::TYPE_RGBA_ARB as c_int);
+            out.push(
+                gl::wgl_extra::PIXEL_TYPE_ARB,
+                gl::wgl_extra::TYPE_RGBA_ARB,
+            );
         }
 
         if let Some(hardware_accelerated) = reqs.hardware_accelerated {
-            out.push(gl::wgl_extra::ACCELERATION_ARB as c_int);
-            out.push(if hardware_accelerated {
-                gl::wgl_extra::FULL_ACCELERATION_ARB as c_int
-            } else {
-                gl::wgl_extra::NO_ACCELERATION_ARB as c_int
-            });
+            out.push(
+                gl::wgl_extra::ACCELERATION_ARB,
+                if hardware_accelerated {
+                    gl::wgl_extra::FULL_ACCELERATION_ARB
+                } else {
+                    gl::wgl_extra::NO_ACCELERATION_ARB
+                },
+            );
         }
 
         if let Some(color) = reqs.color_bits {
-            out.push(gl::wgl_extra::COLOR_BITS_ARB as c_int);
-            out.push(color as c_int);
+            out.push(gl::wgl_extra::COLOR_BITS_ARB, color);
         }
 
         if let Some(alpha) = reqs.alpha_bits {
-            out.push(gl::wgl_extra::ALPHA_BITS_ARB as c_int);
-            out.push(alpha as c_int);
+            out.push(gl::wgl_extra::ALPHA_BITS_ARB, alpha);
         }
 
         if let Some(depth) = reqs.depth_bits {
-            out.push(gl::wgl_extra::DEPTH_BITS_ARB as c_int);
-            out.push(depth as c_int);
+            out.push(gl::wgl_extra::DEPTH_BITS_ARB, depth);
         }
 
         if let Some(stencil) = reqs.stencil_bits {
-            out.push(gl::wgl_extra::STENCIL_BITS_ARB as c_int);
-            out.push(stencil as c_int);
+            out.push(gl::wgl_extra::STENCIL_BITS_ARB, stencil);
         }
 
         // Prefer double buffering if unspecified (probably shouldn't once you
         // can choose)
         let double_buffer = reqs.double_buffer.unwrap_or(true);
-        out.push(gl::wgl_extra::DOUBLE_BUFFER_ARB as c_int);
-        out.push(if double_buffer { 1 } else { 0 });
+        out.push(gl::wgl_extra::DOUBLE_BUFFER_ARB, double_buffer);
 
-        if let Some(multisampling) = reqs.multisampling {
+        if let Some(multisampling) = reqs.samples() {
             if extensions
                 .split(' ')
                 .find(|&i| i == "WGL_ARB_multisample")
                 .is_some()
             {
-                out.push(gl::wgl_extra::SAMPLE_BUFFERS_ARB as c_int);
-                out.push(if multisampling == 0 { 0 } else { 1 });
-                out.push(gl::wgl_extra::SAMPLES_ARB as c_int);
-                out.push(multisampling as c_int);
-            } else {
+                out.push(
+                    gl::wgl_extra::SAMPLE_BUFFERS_ARB,
+                    multisampling != 0,
+                );
+                if extensions
+                    .split(' ')
+                    .find(|&i| i == "WGL_NV_multisample_coverage")
+                    .is_some()
+                {
+                    // `WGL_SAMPLES_ARB` counts the coverage samples then
+                    out.push(
+                        gl::wgl_extra::COVERAGE_SAMPLES_NV,
+                        reqs.coverage_samples.unwrap_or(multisampling),
+                    );
+                    out.push(gl::wgl_extra::COLOR_SAMPLES_NV, multisampling);
+                } else {
+                    out.push(gl::wgl_extra::SAMPLES_ARB, multisampling);
+                }
+            } else if multisampling != 0 {
                 return Err(());
             }
         }
 
-        out.push(gl::wgl_extra::STEREO_ARB as c_int);
-        out.push(if reqs.stereoscopy { 1 } else { 0 });
+        out.push(gl::wgl_extra::STEREO_ARB, reqs.stereoscopy);
+
+        // the planes are numbered from 1 above and below the main plane
+        if reqs.plane > 0 {
+            out.push(gl::wgl_extra::NUMBER_OVERLAYS_ARB, reqs.plane);
+        } else if reqs.plane < 0 {
+            out.push(gl::wgl_extra::NUMBER_UNDERLAYS_ARB, -reqs.plane);
+        }
 
         if reqs.srgb {
             if extensions
@@ -676,66 +1070,78 @@ unsafe fn choose_arb_pixel_format(
                 .find(|&i| i == "WGL_ARB_framebuffer_sRGB")
                 .is_some()
             {
-                out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB as c_int);
-                out.push(1);
+                out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_ARB, true);
             } else if extensions
                 .split(' ')
                 .find(|&i| i == "WGL_EXT_framebuffer_sRGB")
                 .is_some()
             {
-                out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT as c_int);
-                out.push(1);
+                out.push(gl::wgl_extra::FRAMEBUFFER_SRGB_CAPABLE_EXT, true);
             } else {
                 return Err(());
             }
         }
 
-        match reqs.release_behavior {
-            ReleaseBehavior::Flush => (),
-            ReleaseBehavior::None => {
-                if extensions
-                    .split(' ')
-                    .find(|&i| i == "WGL_ARB_context_flush_control")
-                    .is_some()
-                {
-                    out.push(
-                        gl::wgl_extra::CONTEXT_RELEASE_BEHAVIOR_ARB as c_int,
-                    );
-                    out.push(
-                        gl::wgl_extra::CONTEXT_RELEASE_BEHAVIOR_NONE_ARB
-                            as c_int,
-                    );
-                }
-            }
-        }
-
-        out.push(0);
         out
     };
 
-    let mut format_id = mem::uninitialized();
-    let mut num_formats = mem::uninitialized();
+    // every matching format is only needed when the application picks one
+    let max_formats = if reqs.selection_hook.is_some() {
+        MAX_SELECTABLE_FORMATS
+    } else {
+        1
+    };
+    let mut format_ids = vec![0; max_formats];
+    let mut num_formats = 0;
     if extra.ChoosePixelFormatARB(
         hdc as *const _,
         descriptor.as_ptr(),
         ptr::null(),
-        1,
-        &mut format_id,
+        max_formats as UINT,
+        format_ids.as_mut_ptr(),
         &mut num_formats,
     ) == 0
     {
         return Err(());
     }
+    format_ids.truncate((num_formats as usize).min(max_formats));
+
+    let (format_id, pf_desc) = match reqs.selection_hook {
+        Some(ref hook) if !format_ids.is_empty() => {
+            let candidates = format_ids
+                .into_iter()
+                .map(|id| {
+                    (id, describe_arb_pixel_format(extra, extensions, hdc, id))
+                })
+                .collect();
+            hook.select(candidates).ok_or(())?
+        }
+        _ => {
+            let id = *format_ids.first().ok_or(())?;
+            (id, describe_arb_pixel_format(extra, extensions, hdc, id))
+        }
+    };
+    pf_desc.debug_assert_satisfies(reqs);
 
-    if num_formats == 0 {
-        return Err(());
-    }
+    Ok((format_id, pf_desc))
+}
+
+/// The most formats given to a selection hook, which is more than any
+/// driver exposes for a window.
+const MAX_SELECTABLE_FORMATS: usize = 256;
 
+/// Describes a pixel format with `WGL_ARB_pixel_format`.
+unsafe fn describe_arb_pixel_format(
+    extra: &gl::wgl_extra::Wgl,
+    extensions: &str,
+    hdc: HDC,
+    format_id: c_int,
+) -> PixelFormat {
     let get_info = |attrib: u32| {
         let mut value = mem::uninitialized();
         extra.GetPixelFormatAttribivARB(
             hdc as *const _,
-            format_id as c_int,
+            format_id,
             0,
             1,
             [attrib as c_int].as_ptr(),
@@ -744,7 +1150,39 @@ unsafe fn choose_arb_pixel_format(
         value as u32
     };
 
-    let pf_desc = PixelFormat {
+    let (sample_buffers, samples) = if extensions
+        .split(' ')
+        .find(|&i| i == "WGL_ARB_multisample")
+        .is_some()
+    {
+        let sample_buffers = get_info(gl::wgl_extra::SAMPLE_BUFFERS_ARB);
+        let samples = match get_info(gl::wgl_extra::SAMPLES_ARB) {
+            0 | 1 => None,
+            _ if sample_buffers == 0 => None,
+            a => Some(a as u16),
+        };
+        (sample_buffers, samples)
+    } else {
+        (0, None)
+    };
+    let (multisampling, coverage_samples) = match samples {
+        Some(samples)
+            if extensions
+                .split(' ')
+                .find(|&i| i == "WGL_NV_multisample_coverage")
+                .is_some() =>
+        {
+            let color = get_info(gl::wgl_extra::COLOR_SAMPLES_NV) as u16;
+            if color != 0 && color < samples {
+                (Some(color), Some(samples))
+            } else {
+                (Some(samples), None)
+            }
+        }
+        samples => (samples, None),
+    };
+
+    PixelFormat {
         hardware_accelerated: get_info(gl::wgl_extra::ACCELERATION_ARB)
             != gl::wgl_extra::NO_ACCELERATION_ARB,
         color_bits: get_info(gl::wgl_extra::RED_BITS_ARB) as u8
@@ -755,20 +1193,9 @@ unsafe fn choose_arb_pixel_format(
         stencil_bits: get_info(gl::wgl_extra::STENCIL_BITS_ARB) as u8,
         stereoscopy: get_info(gl::wgl_extra::STEREO_ARB) != 0,
         double_buffer: get_info(gl::wgl_extra::DOUBLE_BUFFER_ARB) != 0,
-        multisampling: {
-            if extensions
-                .split(' ')
-                .find(|&i| i == "WGL_ARB_multisample")
-                .is_some()
-            {
-                match get_info(gl::wgl_extra::SAMPLES_ARB) {
-                    0 => None,
-                    a => Some(a as u16),
-                }
-            } else {
-                None
-            }
-        },
+        sample_buffers: sample_buffers as u8,
+        multisampling,
+        coverage_samples,
         srgb: if extensions
             .split(' ')
             .find(|&i| i == "WGL_ARB_framebuffer_sRGB")
@@ -784,9 +1211,11 @@ unsafe fn choose_arb_pixel_format(
         } else {
             false
         },
-    };
-
-    Ok((format_id, pf_desc))
+        transparent: None,
+        native_renderable: false,
+        x11_visual_type: None,
+        native_id: Some(format_id as u32),
+    }
 }
 
 /// Calls `SetPixelFormat` on a window.
@@ -834,167 +1263,3 @@ unsafe fn load_opengl32_dll() -> Result<HMODULE, CreationError> {
 
     Ok(lib)
 }
-
-/// Loads the WGL functions that are not guaranteed to be supported.
-///
-/// The `window` must be passed because the driver can vary depending on the
-/// window's characteristics.
-unsafe fn load_extra_functions(
-    window: HWND,
-) -> Result<gl::wgl_extra::Wgl, CreationError> {
-    let (ex_style, style) = (
-        WS_EX_APPWINDOW,
-        WS_POPUP | WS_CLIPSIBLINGS | WS_CLIPCHILDREN,
-    );
-
-    // creating a dummy invisible window
-    let dummy_window = {
-        // getting the rect of the real window
-        let rect = {
-            let mut placement: WINDOWPLACEMENT = mem::zeroed();
-            placement.length = mem::size_of::<WINDOWPLACEMENT>() as UINT;
-            if GetWindowPlacement(window, &mut placement) == 0 {
-                panic!();
-            }
-            placement.rcNormalPosition
-        };
-
-        // getting the class name of the real window
-        let mut class_name = [0u16; 128];
-        if GetClassNameW(window, class_name.as_mut_ptr(), 128) == 0 {
-            return Err(CreationError::OsError(format!(
-                "GetClassNameW function failed: {}",
-                format!("{}", io::Error::last_os_error())
-            )));
-        }
-
-        // access to class information of the real window
-        let instance = GetModuleHandleW(ptr::null());
-        let mut class: WNDCLASSEXW = mem::zeroed();
-
-        if GetClassInfoExW(instance, class_name.as_ptr(), &mut class) == 0 {
-            return Err(CreationError::OsError(format!(
-                "GetClassInfoExW function failed: {}",
-                format!("{}", io::Error::last_os_error())
-            )));
-        }
-
-        // register a new class for the dummy window,
-        // similar to the class of the real window but with a different callback
-        let class_name = OsStr::new("WglDummy Class")
-            .encode_wide()
-            .chain(Some(0).into_iter())
-            .collect::<Vec<_>>();
-
-        class.cbSize = mem::size_of::<WNDCLASSEXW>() as UINT;
-        class.lpszClassName = class_name.as_ptr();
-        class.lpfnWndProc = Some(DefWindowProcW);
-
-        // this shouldn't fail if the registration of the real window class
-        // worked. multiple registrations of the window class trigger an
-        // error which we want to ignore silently (e.g for multi-window
-        // setups)
-        RegisterClassExW(&class);
-
-        // this dummy window should match the real one enough to get the same
-        // OpenGL driver
-        let title = OsStr::new("dummy window")
-            .encode_wide()
-            .chain(Some(0).into_iter())
-            .collect::<Vec<_>>();
-        let win = CreateWindowExW(
-            ex_style,
-            class_name.as_ptr(),
-            title.as_ptr() as LPCWSTR,
-            style,
-            CW_USEDEFAULT,
-            CW_USEDEFAULT,
-            rect.right - rect.left,
-            rect.bottom - rect.top,
-            ptr::null_mut(),
-            ptr::null_mut(),
-            GetModuleHandleW(ptr::null()),
-            ptr::null_mut(),
-        );
-
-        if win.is_null() {
-            return Err(CreationError::OsError(format!(
-                "CreateWindowEx function failed: {}",
-                format!("{}", io::Error::last_os_error())
-            )));
-        }
-
-        let hdc = GetDC(win);
-        if hdc.is_null() {
-            let err = Err(CreationError::OsError(format!(
-                "GetDC function failed: {}",
-                format!("{}", io::Error::last_os_error())
-            )));
-            return err;
-        }
-
-        WindowWrapper(win, hdc)
-    };
-
-    // getting the pixel format that we will use and setting it
-    {
-        let id = choose_dummy_pixel_format(dummy_window.1)?;
-        set_pixel_format(dummy_window.1, id)?;
-    }
-
-    // creating the dummy OpenGL context and making it current
-    let dummy_context = create_context(None, dummy_window.0, dummy_window.1)?;
-    let _current_context =
-        CurrentContextGuard::make_current(dummy_window.1, dummy_context.0)?;
-
-    // loading the extra WGL functions
-    Ok(gl::wgl_extra::Wgl::load_with(|addr| {
-        let addr = CString::new(addr.as_bytes()).unwrap();
-        let addr = addr.as_ptr();
-        gl::wgl::GetProcAddress(addr) as *const c_void
-    }))
-}
-
-/// This function chooses a pixel format that is likely to be provided by
-/// the main video driver of the system.
-fn choose_dummy_pixel_format(hdc: HDC) -> Result<c_int, CreationError> {
-    // building the descriptor to pass to ChoosePixelFormat
-    let descriptor = PIXELFORMATDESCRIPTOR {
-        nSize: mem::size_of::<PIXELFORMATDESCRIPTOR>() as u16,
-        nVersion: 1,
-        dwFlags: PFD_DRAW_TO_WINDOW | PFD_SUPPORT_OPENGL | PFD_DOUBLEBUFFER,
-        iPixelType: PFD_TYPE_RGBA,
-        cColorBits: 24,
-        cRedBits: 0,
-        cRedShift: 0,
-        cGreenBits: 0,
-        cGreenShift: 0,
-        cBlueBits: 0,
-        cBlueShift: 0,
-        cAlphaBits: 8,
-        cAlphaShift: 0,
-        cAccumBits: 0,
-        cAccumRedBits: 0,
-        cAccumGreenBits: 0,
-        cAccumBlueBits: 0,
-        cAccumAlphaBits: 0,
-        cDepthBits: 24,
-        cStencilBits: 8,
-        cAuxBuffers: 0,
-        iLayerType: PFD_MAIN_PLANE,
-        bReserved: 0,
-        dwLayerMask: 0,
-        dwVisibleMask: 0,
-        dwDamageMask: 0,
-    };
-
-    // now querying
-    let pf_id = unsafe { ChoosePixelFormat(hdc, &descriptor) };
-    if pf_id == 0 {
-        return Err(CreationError::OsError(
-            "No available pixel format".to_owned(),
-        ));
-    }
-
-    Ok(pf_id)
-}
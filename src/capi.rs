@@ -0,0 +1,452 @@
+#![cfg(feature = "capi")]
+// the safety requirements are the same for every function, see below
+#![allow(clippy::missing_safety_doc)]
+
+//! A C interface to the contexts of glutin, enabled by the `capi` feature,
+//! so that engines with a C or C++ core can reuse its platform code.
+//!
+//! Objects are handed out as opaque pointers, which must be freed with the
+//! matching `_free` function. Every function returns a `GlutinStatus`, and
+//! `glutin_last_error_message` describes the last failure on the calling
+//! thread. The declarations are in `capi/glutin.h`, and a dynamic library
+//! is built with `cargo rustc --release --features capi --crate-type cdylib`.
+//!
+//! The contexts have the same threading rules as in Rust: the events loop
+//! and the windows stay on the thread that created them, which must be the
+//! main thread on macOS.
+//!
+//! # Safety
+//!
+//! Null pointers are rejected with `GLUTIN_INVALID_ARGUMENT`, but the other
+//! pointers must have been handed out by this module and not freed yet, and
+//! the strings must be nul-terminated.
+
+use super::*;
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::{c_char, c_int, c_void};
+use std::panic::{self, AssertUnwindSafe};
+use std::ptr;
+
+/// The outcome of a call, `GLUTIN_OK` or the kind of failure.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum GlutinStatus {
+    Ok = 0,
+    /// A pointer was null or a value out of range.
+    InvalidArgument = 1,
+    NotSupported = 2,
+    NoAvailablePixelFormat = 3,
+    VersionNotSupported = 4,
+    ContextLost = 5,
+    DisplayLost = 6,
+    NotCurrent = 7,
+    FunctionUnavailable = 8,
+    OsError = 9,
+    /// glutin panicked, the objects involved shouldn't be used anymore.
+    Panic = 10,
+}
+
+/// The client APIs of `glutin_builder_set_gl_version`.
+pub const GLUTIN_API_OPENGL: c_int = 0;
+pub const GLUTIN_API_OPENGL_ES: c_int = 1;
+
+/// The events gathered by `glutin_events_loop_poll`.
+#[repr(C)]
+#[derive(Debug, Default, Copy, Clone)]
+pub struct GlutinEvents {
+    /// Non-zero if a window was asked to close.
+    pub close_requested: c_int,
+    /// Non-zero if the window was resized, in which case the context was
+    /// resized too.
+    pub resized: c_int,
+    /// The size of the window in physical pixels, if it was resized.
+    pub width: u32,
+    pub height: u32,
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<CString> = RefCell::new(CString::default());
+}
+
+/// Records `message` for `glutin_last_error_message` and returns `status`.
+fn fail(status: GlutinStatus, message: String) -> GlutinStatus {
+    // the messages of the drivers don't contain nul bytes in practice
+    let message = CString::new(message.replace('\0', "")).unwrap_or_default();
+    LAST_ERROR.with(|last| *last.borrow_mut() = message);
+    status
+}
+
+fn invalid(what: &str) -> GlutinStatus {
+    fail(GlutinStatus::InvalidArgument, format!("{} is invalid", what))
+}
+
+fn creation_error(err: CreationError) -> GlutinStatus {
+    let status = match err {
+        CreationError::NotSupported(_)
+        | CreationError::NoBackendAvailable(_)
+        | CreationError::RobustnessNotSupported
+        | CreationError::ProfileNotSupported => GlutinStatus::NotSupported,
+        CreationError::OpenGlVersionNotSupported => {
+            GlutinStatus::VersionNotSupported
+        }
+        CreationError::NoAvailablePixelFormat => {
+            GlutinStatus::NoAvailablePixelFormat
+        }
+        CreationError::IncompatibleSharing => GlutinStatus::InvalidArgument,
+        _ => GlutinStatus::OsError,
+    };
+    fail(status, format!("{}", err))
+}
+
+fn context_error(err: ContextError) -> GlutinStatus {
+    let status = match err {
+        ContextError::ContextLost => GlutinStatus::ContextLost,
+        ContextError::DisplayLost => GlutinStatus::DisplayLost,
+        ContextError::NotCurrent => GlutinStatus::NotCurrent,
+        ContextError::FunctionUnavailable => GlutinStatus::FunctionUnavailable,
+        ContextError::OsError(_) | ContextError::IoError(_) => {
+            GlutinStatus::OsError
+        }
+    };
+    fail(status, format!("{}", err))
+}
+
+/// Runs `f`, turning a panic into `GLUTIN_PANIC` since unwinding into C is
+/// undefined behavior.
+fn guard<F: FnOnce() -> GlutinStatus>(f: F) -> GlutinStatus {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| {
+        fail(GlutinStatus::Panic, "glutin panicked".to_string())
+    })
+}
+
+/// Stores `value` in `out` as an opaque pointer.
+unsafe fn hand_out<T>(out: *mut *mut T, value: T) -> GlutinStatus {
+    *out = Box::into_raw(Box::new(value));
+    GlutinStatus::Ok
+}
+
+/// Returns the message of the last failure on the calling thread, valid
+/// until the next call to glutin on this thread.
+#[no_mangle]
+pub extern "C" fn glutin_last_error_message() -> *const c_char {
+    LAST_ERROR.with(|last| last.borrow().as_ptr())
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_events_loop_new(
+    out: *mut *mut EventsLoop,
+) -> GlutinStatus {
+    if out.is_null() {
+        return invalid("out");
+    }
+    guard(|| hand_out(out, EventsLoop::new()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_events_loop_free(el: *mut EventsLoop) {
+    if !el.is_null() {
+        drop(Box::from_raw(el));
+    }
+}
+
+/// Dispatches the pending events without blocking, resizing `window`, which
+/// may be null, when its window is resized.
+#[no_mangle]
+pub unsafe extern "C" fn glutin_events_loop_poll(
+    el: *mut EventsLoop,
+    window: *const CombinedContext,
+    out: *mut GlutinEvents,
+) -> GlutinStatus {
+    let el = match el.as_mut() {
+        Some(el) => el,
+        None => return invalid("el"),
+    };
+    if out.is_null() {
+        return invalid("out");
+    }
+    let window = window.as_ref();
+    guard(|| {
+        let mut events = GlutinEvents::default();
+        el.poll_events(|event| {
+            if let Event::WindowEvent { event, .. } = event {
+                match event {
+                    WindowEvent::CloseRequested => events.close_requested = 1,
+                    WindowEvent::Resized(size) => {
+                        if let Some(window) = window {
+                            let size =
+                                size.to_physical(window.get_hidpi_factor());
+                            window.resize(size);
+                            events.resized = 1;
+                            events.width = size.width as u32;
+                            events.height = size.height as u32;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+        });
+        *out = events;
+        GlutinStatus::Ok
+    })
+}
+
+/// Creates a builder with the default attributes.
+#[no_mangle]
+pub unsafe extern "C" fn glutin_builder_new(
+    out: *mut *mut ContextBuilder<'static>,
+) -> GlutinStatus {
+    if out.is_null() {
+        return invalid("out");
+    }
+    guard(|| hand_out(out, ContextBuilder::new()))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_builder_free(
+    builder: *mut ContextBuilder<'static>,
+) {
+    if !builder.is_null() {
+        drop(Box::from_raw(builder));
+    }
+}
+
+/// Applies `f` to the builder behind `builder`.
+unsafe fn update_builder<F>(
+    builder: *mut ContextBuilder<'static>,
+    f: F,
+) -> GlutinStatus
+where
+    F: FnOnce(ContextBuilder<'static>) -> ContextBuilder<'static>,
+{
+    let builder = match builder.as_mut() {
+        Some(builder) => builder,
+        None => return invalid("builder"),
+    };
+    guard(|| {
+        *builder = f(builder.clone());
+        GlutinStatus::Ok
+    })
+}
+
+/// Requests a version of `GLUTIN_API_OPENGL` or `GLUTIN_API_OPENGL_ES`.
+#[no_mangle]
+pub unsafe extern "C" fn glutin_builder_set_gl_version(
+    builder: *mut ContextBuilder<'static>,
+    api: c_int,
+    major: u8,
+    minor: u8,
+) -> GlutinStatus {
+    let api = match api {
+        GLUTIN_API_OPENGL => Api::OpenGl,
+        GLUTIN_API_OPENGL_ES => Api::OpenGlEs,
+        _ => return invalid("api"),
+    };
+    update_builder(builder, |b| {
+        b.with_gl(GlRequest::Specific(api, (major, minor)))
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_builder_set_vsync(
+    builder: *mut ContextBuilder<'static>,
+    vsync: c_int,
+) -> GlutinStatus {
+    update_builder(builder, |b| b.with_vsync(vsync != 0))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_builder_set_multisampling(
+    builder: *mut ContextBuilder<'static>,
+    samples: u16,
+) -> GlutinStatus {
+    if samples != 0 && !samples.is_power_of_two() {
+        return invalid("samples");
+    }
+    update_builder(builder, |b| b.with_multisampling(samples))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_builder_set_depth_stencil(
+    builder: *mut ContextBuilder<'static>,
+    depth_bits: u8,
+    stencil_bits: u8,
+) -> GlutinStatus {
+    update_builder(builder, |b| {
+        b.with_depth_buffer(depth_bits)
+            .with_stencil_buffer(stencil_bits)
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_builder_set_srgb(
+    builder: *mut ContextBuilder<'static>,
+    srgb: c_int,
+) -> GlutinStatus {
+    update_builder(builder, |b| b.with_srgb(srgb != 0))
+}
+
+/// Builds a headless context, see `ContextBuilder::build_headless`. `el`
+/// may be null to build it without an events loop, see
+/// `ContextBuilder::build_compute`.
+#[no_mangle]
+pub unsafe extern "C" fn glutin_context_new(
+    el: *const EventsLoop,
+    builder: *const ContextBuilder<'static>,
+    out: *mut *mut Context,
+) -> GlutinStatus {
+    let builder = match builder.as_ref() {
+        Some(builder) => builder.clone(),
+        None => return invalid("builder"),
+    };
+    if out.is_null() {
+        return invalid("out");
+    }
+    let el = el.as_ref();
+    guard(|| {
+        let res = match el {
+            Some(el) => builder.build_headless(el),
+            None => builder.build_compute(),
+        };
+        match res {
+            Ok(context) => hand_out(out, context),
+            Err(err) => creation_error(err),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_context_free(context: *mut Context) {
+    if !context.is_null() {
+        drop(Box::from_raw(context));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_context_make_current(
+    context: *const Context,
+) -> GlutinStatus {
+    match context.as_ref() {
+        Some(context) => guard(|| {
+            context
+                .make_current()
+                .map_or_else(context_error, |()| GlutinStatus::Ok)
+        }),
+        None => invalid("context"),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_context_make_not_current(
+    context: *const Context,
+) -> GlutinStatus {
+    match context.as_ref() {
+        Some(context) => guard(|| {
+            context
+                .make_not_current()
+                .map_or_else(context_error, |()| GlutinStatus::Ok)
+        }),
+        None => invalid("context"),
+    }
+}
+
+/// Stores in `out` the address of the OpenGL function `name`, or null if
+/// it is unavailable.
+#[no_mangle]
+pub unsafe extern "C" fn glutin_context_get_proc_address(
+    context: *const Context,
+    name: *const c_char,
+    out: *mut *const c_void,
+) -> GlutinStatus {
+    let context = match context.as_ref() {
+        Some(context) => context,
+        None => return invalid("context"),
+    };
+    if name.is_null() || out.is_null() {
+        return invalid("name or out");
+    }
+    let name = match CStr::from_ptr(name).to_str() {
+        Ok(name) => name,
+        Err(_) => return invalid("name"),
+    };
+    guard(|| {
+        *out = context.get_proc_address(name) as *const c_void;
+        GlutinStatus::Ok
+    })
+}
+
+/// Builds a window titled `title`, of `width` by `height` logical pixels,
+/// along with its context.
+#[no_mangle]
+pub unsafe extern "C" fn glutin_window_context_new(
+    el: *const EventsLoop,
+    builder: *const ContextBuilder<'static>,
+    title: *const c_char,
+    width: u32,
+    height: u32,
+    out: *mut *mut CombinedContext,
+) -> GlutinStatus {
+    let el = match el.as_ref() {
+        Some(el) => el,
+        None => return invalid("el"),
+    };
+    let builder = match builder.as_ref() {
+        Some(builder) => builder.clone(),
+        None => return invalid("builder"),
+    };
+    if out.is_null() {
+        return invalid("out");
+    }
+    let title = if title.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(title).to_string_lossy().into_owned()
+    };
+    guard(|| {
+        let wb = WindowBuilder::new()
+            .with_title(title)
+            .with_dimensions(dpi::LogicalSize::new(
+                f64::from(width),
+                f64::from(height),
+            ));
+        match builder.build_combined(wb, el) {
+            Ok(context) => hand_out(out, context),
+            Err(err) => creation_error(err),
+        }
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_window_context_free(
+    context: *mut CombinedContext,
+) {
+    if !context.is_null() {
+        drop(Box::from_raw(context));
+    }
+}
+
+/// Returns the context of the window, owned by it.
+#[no_mangle]
+pub unsafe extern "C" fn glutin_window_context_context(
+    context: *const CombinedContext,
+) -> *const Context {
+    match context.as_ref() {
+        Some(context) => context.context(),
+        None => ptr::null(),
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn glutin_window_context_swap_buffers(
+    context: *const CombinedContext,
+) -> GlutinStatus {
+    match context.as_ref() {
+        Some(context) => guard(|| {
+            context
+                .swap_buffers()
+                .map_or_else(context_error, |()| GlutinStatus::Ok)
+        }),
+        None => invalid("context"),
+    }
+}
@@ -0,0 +1,409 @@
+use super::*;
+
+use gl::{
+    load, query_gl_version, GL_BACK, GL_COLOR_ATTACHMENT0, GL_FRAMEBUFFER,
+    GL_FRAMEBUFFER_BINDING, GL_FRAMEBUFFER_SRGB, GL_FRONT, GL_MAP_READ_BIT,
+    GL_PACK_ALIGNMENT, GL_PACK_ROW_LENGTH, GL_PACK_SKIP_PIXELS,
+    GL_PACK_SKIP_ROWS, GL_PIXEL_PACK_BUFFER, GL_PIXEL_PACK_BUFFER_BINDING,
+    GL_READ_BUFFER, GL_READ_FRAMEBUFFER, GL_READ_FRAMEBUFFER_BINDING,
+    GL_READ_ONLY, GL_RGBA, GL_STREAM_READ, GL_UNSIGNED_BYTE,
+};
+
+use std::os::raw::{c_int, c_uint, c_void};
+use std::{mem, ptr, slice};
+
+/// The few OpenGL functions needed to read the framebuffer back.
+#[derive(Clone)]
+struct CaptureGl {
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+    get_integerv: Option<unsafe extern "system" fn(c_uint, *mut c_int)>,
+    is_enabled: Option<unsafe extern "system" fn(c_uint) -> u8>,
+    enable: Option<unsafe extern "system" fn(c_uint)>,
+    disable: Option<unsafe extern "system" fn(c_uint)>,
+    pixel_storei: Option<unsafe extern "system" fn(c_uint, c_int)>,
+    read_pixels: Option<
+        unsafe extern "system" fn(
+            c_int,
+            c_int,
+            c_int,
+            c_int,
+            c_uint,
+            c_uint,
+            *mut c_void,
+        ),
+    >,
+    bind_framebuffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    read_buffer: Option<unsafe extern "system" fn(c_uint)>,
+    gen_buffers: Option<unsafe extern "system" fn(c_int, *mut c_uint)>,
+    delete_buffers: Option<unsafe extern "system" fn(c_int, *const c_uint)>,
+    bind_buffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    buffer_data:
+        Option<unsafe extern "system" fn(c_uint, isize, *const c_void, c_uint)>,
+    map_buffer_range: Option<
+        unsafe extern "system" fn(c_uint, isize, isize, c_uint) -> *mut c_void,
+    >,
+    map_buffer:
+        Option<unsafe extern "system" fn(c_uint, c_uint) -> *mut c_void>,
+    unmap_buffer: Option<unsafe extern "system" fn(c_uint) -> u8>,
+}
+
+unsafe impl GlLoader for CaptureGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        CaptureGl {
+            get_string: load(loadfn("glGetString")),
+            get_integerv: load(loadfn("glGetIntegerv")),
+            is_enabled: load(loadfn("glIsEnabled")),
+            enable: load(loadfn("glEnable")),
+            disable: load(loadfn("glDisable")),
+            pixel_storei: load(loadfn("glPixelStorei")),
+            read_pixels: load(loadfn("glReadPixels")),
+            bind_framebuffer: load(loadfn("glBindFramebuffer")),
+            read_buffer: load(loadfn("glReadBuffer")),
+            gen_buffers: load(loadfn("glGenBuffers")),
+            delete_buffers: load(loadfn("glDeleteBuffers")),
+            bind_buffer: load(loadfn("glBindBuffer")),
+            buffer_data: load(loadfn("glBufferData")),
+            map_buffer_range: load(loadfn("glMapBufferRange")),
+            map_buffer: load(loadfn("glMapBuffer")),
+            unmap_buffer: load(loadfn("glUnmapBuffer")),
+        }
+    }
+}
+
+/// What the OpenGL version of the context allows.
+struct Features {
+    /// OpenGL 3 or OpenGL ES 3, which have `GL_READ_FRAMEBUFFER`.
+    gl3: bool,
+    es: bool,
+    pixel_pack_buffer: bool,
+}
+
+impl Features {
+    unsafe fn query(gl: &CaptureGl) -> Features {
+        let (es, major, minor) = query_gl_version(gl.get_string);
+
+        let gl3 = major >= 3;
+        let pixel_pack_buffer = (gl3 || (!es && (major, minor) >= (2, 1)))
+            && gl.gen_buffers.is_some()
+            && gl.delete_buffers.is_some()
+            && gl.bind_buffer.is_some()
+            && gl.buffer_data.is_some()
+            && gl.unmap_buffer.is_some()
+            && (gl.map_buffer_range.is_some() || gl.map_buffer.is_some());
+
+        Features {
+            gl3,
+            es,
+            pixel_pack_buffer,
+        }
+    }
+}
+
+/// The contents of a framebuffer read back with `CombinedContext::capture`
+/// or `SeparatedContext::capture`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Screenshot {
+    /// The width of the image, in pixels.
+    pub width: u32,
+
+    /// The height of the image, in pixels.
+    pub height: u32,
+
+    /// The RGBA pixels, 8 bits per channel, starting with the top row and
+    /// without any padding between rows.
+    ///
+    /// The values are the ones stored in the framebuffer, which the screen
+    /// shows as sRGB. An sRGB framebuffer is read without decoding its
+    /// values to linear, even if `GL_FRAMEBUFFER_SRGB` is enabled, and the
+    /// values of a linear framebuffer are returned as they are, without any
+    /// conversion. The alpha is 255 if the framebuffer has no alpha
+    /// channel.
+    pub pixels: Vec<u8>,
+}
+
+/// A capture started with `CombinedContext::start_capture` or
+/// `SeparatedContext::start_capture`.
+///
+/// When pixel pack buffers are supported, the pixels are copied
+/// asynchronously by the GPU until `finish` is called. Otherwise, they
+/// have already been read.
+pub struct PendingScreenshot<'a> {
+    context: &'a Context,
+    gl: CaptureGl,
+    width: u32,
+    height: u32,
+    state: PendingState,
+}
+
+enum PendingState {
+    Buffer(c_uint),
+    Done(Vec<u8>),
+}
+
+/// The framebuffer a capture reads from.
+#[derive(Debug, Copy, Clone)]
+pub(crate) enum ReadSource {
+    /// The default framebuffer, from its back buffer if it has one.
+    Default { double_buffer: bool, srgb: bool },
+    /// The first color attachment of a framebuffer object.
+    Framebuffer(c_uint),
+}
+
+impl<'a> PendingScreenshot<'a> {
+    /// Starts reading the framebuffer of `context` back, which must be
+    /// current.
+    pub(crate) fn start(
+        context: &'a Context,
+        pixel_format: &PixelFormat,
+        size: dpi::PhysicalSize,
+    ) -> Result<Self, ContextError> {
+        let source = ReadSource::Default {
+            double_buffer: pixel_format.double_buffer,
+            srgb: pixel_format.srgb,
+        };
+        PendingScreenshot::start_from(context, source, size)
+    }
+
+    /// Starts reading `source` back, `context` being current.
+    pub(crate) fn start_from(
+        context: &'a Context,
+        source: ReadSource,
+        size: dpi::PhysicalSize,
+    ) -> Result<Self, ContextError> {
+        let gl = context.load_gl::<CaptureGl>()?;
+        let (get_integerv, pixel_storei, read_pixels) =
+            match (gl.get_integerv, gl.pixel_storei, gl.read_pixels) {
+                (Some(g), Some(p), Some(r)) => (g, p, r),
+                _ => return Err(ContextError::FunctionUnavailable),
+            };
+        let (width, height): (u32, u32) = size.into();
+        let len = width as usize * height as usize * 4;
+
+        unsafe {
+            let features = Features::query(&gl);
+            let get = |pname| {
+                let mut value = 0;
+                get_integerv(pname, &mut value);
+                value
+            };
+
+            // read the source whatever is bound, with the default packing,
+            // and restore everything afterwards
+            let (fb_target, fb_binding) = if features.gl3 {
+                (GL_READ_FRAMEBUFFER, GL_READ_FRAMEBUFFER_BINDING)
+            } else {
+                (GL_FRAMEBUFFER, GL_FRAMEBUFFER_BINDING)
+            };
+            let bind_framebuffer = gl.bind_framebuffer;
+            let framebuffer = match source {
+                ReadSource::Default { .. } => 0,
+                ReadSource::Framebuffer(framebuffer) => framebuffer,
+            };
+            let previous_fb = bind_framebuffer.map(|bind| {
+                let previous = get(fb_binding);
+                bind(fb_target, framebuffer);
+                previous
+            });
+
+            let read_buffer =
+                gl.read_buffer.filter(|_| features.gl3 || !features.es);
+            let previous_read_buffer = read_buffer.map(|read_buffer| {
+                let previous = get(GL_READ_BUFFER);
+                match source {
+                    // ES only has a back buffer, even when single buffered
+                    ReadSource::Default { double_buffer, .. }
+                        if double_buffer || features.es =>
+                    {
+                        read_buffer(GL_BACK)
+                    }
+                    ReadSource::Default { .. } => read_buffer(GL_FRONT),
+                    ReadSource::Framebuffer(_) => {
+                        read_buffer(GL_COLOR_ATTACHMENT0)
+                    }
+                }
+                previous
+            });
+
+            // desktop OpenGL decodes sRGB framebuffers to linear when reading
+            // them with `GL_FRAMEBUFFER_SRGB` enabled, OpenGL ES never does
+            let srgb_decode = match (source, gl.is_enabled, gl.disable) {
+                (
+                    ReadSource::Default { srgb: true, .. },
+                    Some(is_enabled),
+                    Some(disable),
+                ) if features.gl3 && !features.es => {
+                    let enabled = is_enabled(GL_FRAMEBUFFER_SRGB) != 0;
+                    if enabled {
+                        disable(GL_FRAMEBUFFER_SRGB);
+                    }
+                    enabled
+                }
+                _ => false,
+            };
+
+            let mut packing = vec![(GL_PACK_ALIGNMENT, 1)];
+            if features.gl3 || !features.es {
+                packing.push((GL_PACK_ROW_LENGTH, 0));
+                packing.push((GL_PACK_SKIP_ROWS, 0));
+                packing.push((GL_PACK_SKIP_PIXELS, 0));
+            }
+            let previous_packing = packing
+                .iter()
+                .map(|&(pname, value)| {
+                    let previous = get(pname);
+                    pixel_storei(pname, value);
+                    (pname, previous)
+                })
+                .collect::<Vec<_>>();
+
+            let state = if features.pixel_pack_buffer {
+                let bind_buffer = gl.bind_buffer.unwrap();
+                let previous_buffer = get(GL_PIXEL_PACK_BUFFER_BINDING);
+                let mut buffer = 0;
+                gl.gen_buffers.unwrap()(1, &mut buffer);
+                bind_buffer(GL_PIXEL_PACK_BUFFER, buffer);
+                gl.buffer_data.unwrap()(
+                    GL_PIXEL_PACK_BUFFER,
+                    len as isize,
+                    ptr::null(),
+                    GL_STREAM_READ,
+                );
+                read_pixels(
+                    0,
+                    0,
+                    width as c_int,
+                    height as c_int,
+                    GL_RGBA,
+                    GL_UNSIGNED_BYTE,
+                    ptr::null_mut(),
+                );
+                bind_buffer(GL_PIXEL_PACK_BUFFER, previous_buffer as c_uint);
+                PendingState::Buffer(buffer)
+            } else {
+                let mut pixels = vec![0u8; len];
+                read_pixels(
+                    0,
+                    0,
+                    width as c_int,
+                    height as c_int,
+                    GL_RGBA,
+                    GL_UNSIGNED_BYTE,
+                    pixels.as_mut_ptr() as *mut _,
+                );
+                PendingState::Done(pixels)
+            };
+
+            for (pname, previous) in previous_packing {
+                pixel_storei(pname, previous);
+            }
+            if let (true, Some(enable)) = (srgb_decode, gl.enable) {
+                enable(GL_FRAMEBUFFER_SRGB);
+            }
+            if let (Some(read_buffer), Some(previous)) =
+                (read_buffer, previous_read_buffer)
+            {
+                read_buffer(previous as c_uint);
+            }
+            if let (Some(bind), Some(previous)) =
+                (bind_framebuffer, previous_fb)
+            {
+                bind(fb_target, previous as c_uint);
+            }
+
+            Ok(PendingScreenshot {
+                context,
+                gl,
+                width,
+                height,
+                state,
+            })
+        }
+    }
+
+    /// Waits for the pixels and returns them.
+    ///
+    /// The context must be current on the calling thread.
+    pub fn finish(mut self) -> Result<Screenshot, ContextError> {
+        if !self.context.is_current() {
+            return Err(ContextError::NotCurrent);
+        }
+        let row = self.width as usize * 4;
+        let len = row * self.height as usize;
+
+        let pixels = match mem::replace(
+            &mut self.state,
+            PendingState::Done(Vec::new()),
+        ) {
+            PendingState::Done(pixels) => pixels,
+            PendingState::Buffer(buffer) => unsafe {
+                let gl = &self.gl;
+                let bind_buffer = gl.bind_buffer.unwrap();
+                let mut previous_buffer = 0;
+                gl.get_integerv.unwrap()(
+                    GL_PIXEL_PACK_BUFFER_BINDING,
+                    &mut previous_buffer,
+                );
+                bind_buffer(GL_PIXEL_PACK_BUFFER, buffer);
+
+                let data = match gl.map_buffer_range {
+                    Some(map) => map(
+                        GL_PIXEL_PACK_BUFFER,
+                        0,
+                        len as isize,
+                        GL_MAP_READ_BIT,
+                    ),
+                    None => gl.map_buffer.unwrap()(
+                        GL_PIXEL_PACK_BUFFER,
+                        GL_READ_ONLY,
+                    ),
+                };
+                let pixels = if data.is_null() {
+                    None
+                } else {
+                    let pixels =
+                        slice::from_raw_parts(data as *const u8, len).to_vec();
+                    gl.unmap_buffer.unwrap()(GL_PIXEL_PACK_BUFFER);
+                    Some(pixels)
+                };
+
+                bind_buffer(GL_PIXEL_PACK_BUFFER, previous_buffer as c_uint);
+                gl.delete_buffers.unwrap()(1, &buffer);
+                match pixels {
+                    Some(pixels) => pixels,
+                    None => {
+                        return Err(ContextError::OsError(
+                            "failed to map the pixel pack buffer".to_string(),
+                        ));
+                    }
+                }
+            },
+        };
+
+        // OpenGL starts with the bottom row
+        let mut flipped = Vec::with_capacity(len);
+        for line in pixels.chunks(row.max(1)).rev() {
+            flipped.extend_from_slice(line);
+        }
+
+        Ok(Screenshot {
+            width: self.width,
+            height: self.height,
+            pixels: flipped,
+        })
+    }
+}
+
+impl<'a> Drop for PendingScreenshot<'a> {
+    fn drop(&mut self) {
+        if let PendingState::Buffer(buffer) = self.state {
+            // the buffer is leaked if the context isn't current anymore,
+            // which is harmless
+            if self.context.is_current() {
+                unsafe { self.gl.delete_buffers.unwrap()(1, &buffer) };
+            }
+        }
+    }
+}
@@ -1,5 +1,13 @@
 use super::*;
 
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How long `swap_buffers` waits instead of swapping while rendering is
+/// paused, so that render loops don't spin.
+pub(crate) const PAUSED_FRAME_MS: u64 = 100;
+
 /// Represents an OpenGL context and the `Window` with which it is associated.
 ///
 /// # Example
@@ -31,9 +39,67 @@ use super::*;
 /// }
 /// # }
 /// ```
+///
+/// # Rendering from another thread
+///
+/// A `CombinedContext` can be shared with a rendering thread through an
+/// `Arc`, while the events loop keeps running on the main thread. The
+/// context is made current and swapped on the rendering thread, and the main
+/// thread forwards it the `Resized` events, so that `resize` is called where
+/// the context is current. See the `render_thread` example.
+///
+/// The updates of the view that macOS requires from the main thread are sent
+/// there without waiting, and on Wayland, resizing is synchronized with the
+/// swaps. The context must be made not current on the rendering thread
+/// before it is dropped elsewhere.
 pub struct CombinedContext {
     context: Context,
+    /// The hidden context sharing the objects of `context`, built with
+    /// `ContextBuilder::with_upload_context`.
+    upload_context: Option<Context>,
     window: Window,
+    /// Whether `resize` makes the context current again.
+    remake_current_on_resize: bool,
+    /// Whether `swap_buffers` waits instead of swapping.
+    paused: AtomicBool,
+    /// The size of the window before `set_fullscreen` made it fullscreen,
+    /// restored when leaving fullscreen.
+    windowed_size: Mutex<Option<dpi::PhysicalSize>>,
+    /// The gamma ramps of the monitors before `set_gamma_ramp` changed them,
+    /// restored on drop.
+    #[cfg(any(
+        target_os = "windows",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    saved_gamma: std::sync::Mutex<Option<platform::SavedGamma>>,
+    /// The size the context was last resized to.
+    #[cfg(feature = "strict")]
+    strict_size: Mutex<Option<dpi::PhysicalSize>>,
+}
+
+/// Destroys the context of a `CombinedContext`, then its window, returning
+/// the failures that dropping it ignores.
+///
+/// The context is released if it is current on the calling thread, and its
+/// surface is destroyed before the native window it renders to. Dropping a
+/// `CombinedContext` follows the same order.
+///
+/// See `Context::destroy` for details.
+pub fn teardown(combined: CombinedContext) -> Result<(), ContextError> {
+    let CombinedContext {
+        context,
+        upload_context,
+        window,
+        ..
+    } = combined;
+    let upload_res = upload_context.map_or(Ok(()), Context::destroy);
+    let res = context.destroy().and(upload_res);
+    drop(window);
+    res
 }
 
 impl CombinedContext {
@@ -54,13 +120,48 @@ impl CombinedContext {
         el: &EventsLoop,
     ) -> Result<Self, CreationError> {
         let ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
         let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
-        platform::Context::new(wb, el, &pf_reqs, &gl_attr).map(
-            |(window, context)| CombinedContext {
-                window,
-                context: Context { context },
-            },
-        )
+        let (window, context) =
+            platform::Context::new(wb, el, &pf_reqs, &gl_attr)?;
+        let context = Context::from_platform(context, sharing);
+
+        let upload_context = if pf_reqs.upload_context {
+            let upload_attr = GlAttributes {
+                sharing: Some(&context.context),
+                vsync: false,
+                ..gl_attr.clone()
+            };
+            let upload_reqs = PixelFormatRequirements {
+                upload_context: false,
+                ..pf_reqs.clone()
+            };
+            let upload =
+                platform::Context::new_context(el, &upload_reqs, &upload_attr)?;
+            Some(Context::from_platform(upload, Some(&context)))
+        } else {
+            None
+        };
+
+        Ok(CombinedContext {
+            window,
+            context,
+            upload_context,
+            remake_current_on_resize: pf_reqs.remake_current_on_resize,
+            paused: AtomicBool::new(false),
+            windowed_size: Mutex::new(None),
+            #[cfg(any(
+                target_os = "windows",
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            ))]
+            saved_gamma: std::sync::Mutex::new(None),
+            #[cfg(feature = "strict")]
+            strict_size: Mutex::new(None),
+        })
     }
 
     /// Borrow the inner `Window`.
@@ -73,6 +174,43 @@ impl CombinedContext {
         &self.context
     }
 
+    /// Borrow the hidden context sharing the objects of the inner `Context`,
+    /// if it was built with `ContextBuilder::with_upload_context`.
+    pub fn upload_context(&self) -> Option<&Context> {
+        self.upload_context.as_ref()
+    }
+
+    /// Returns the group of contexts this context shares its objects with.
+    ///
+    /// See `Context::share_group_id` for details.
+    pub fn share_group_id(&self) -> ShareGroupId {
+        self.context.share_group_id()
+    }
+
+    /// Releases the context if it is current on the calling thread.
+    ///
+    /// See `Context::make_not_current` for details.
+    ///
+    /// # Safety
+    ///
+    /// See `Context::make_not_current`.
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        self.context.make_not_current()
+    }
+
+    /// Makes the context current until the returned guard is dropped.
+    ///
+    /// See `Context::make_current_scoped` for details.
+    ///
+    /// # Safety
+    ///
+    /// See `Context::make_current_scoped`.
+    pub unsafe fn make_current_scoped(
+        &self,
+    ) -> Result<CurrentGuard<'_>, ContextError> {
+        self.context.make_current_scoped()
+    }
+
     /// Swaps the buffers in case of double or triple buffering.
     ///
     /// You should call this function every time you have finished rendering, or
@@ -83,14 +221,435 @@ impl CombinedContext {
     /// override your vsync settings, which means that you can't know in
     /// advance whether `swap_buffers` will block or not.
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        if self.is_paused() {
+            self.wait_paused_frame();
+            return Ok(());
+        }
+        #[cfg(feature = "strict")]
+        self.strict_checks();
         self.context.context.swap_buffers()
     }
 
+    /// Swaps the buffers like `swap_buffers`, telling the compositor that
+    /// only the given rectangles changed since the last swap.
+    ///
+    /// An empty slice means that the whole surface changed. The damage is
+    /// only passed along with `EGL_KHR_swap_buffers_with_damage` or
+    /// `EGL_EXT_swap_buffers_with_damage`, other backends swap the whole
+    /// surface. See `DamageTracker` to find out which part of the back buffer
+    /// has to be redrawn.
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        if self.is_paused() {
+            self.wait_paused_frame();
+            return Ok(());
+        }
+        #[cfg(feature = "strict")]
+        self.strict_checks();
+        self.context.context.swap_buffers_with_damage(rects)
+    }
+
+    /// Pauses or resumes the presentation of the frames, typically when the
+    /// window is minimized or hidden, and when it is shown again.
+    ///
+    /// While paused, `swap_buffers` and `swap_buffers_with_damage` don't swap
+    /// the buffers, which would block forever on hidden surfaces on some
+    /// platforms or return at once on others. They wait for about a tenth of
+    /// a second instead and return `Ok`, so that render loops neither hang
+    /// nor spin. See `get_surface_visibility` to find out when to pause.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - On Wayland, the wait ends early when the compositor releases the
+    ///   pending frame callback, which it does once the surface is shown.
+    /// - On Emscripten, swapping doesn't wait, since the browser already
+    ///   throttles the pages that aren't shown.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Returns whether rendering is paused. See `set_paused`.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    fn wait_paused_frame(&self) {
+        let timeout = Duration::from_millis(PAUSED_FRAME_MS);
+        self.context.context.wait_paused_frame(timeout)
+    }
+
+    /// Makes the context current if it isn't already, draws a frame with
+    /// `draw` and swaps the buffers.
+    ///
+    /// This is the same as calling `make_current`, `swap_buffers` and the
+    /// drawing code separately, without making the context current again
+    /// when it already is. `draw` isn't called if the context can't be made
+    /// current, and its result is dropped if the buffers can't be swapped.
+    /// A `ContextError::ContextLost` means that the context must be
+    /// recreated.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate glutin;
+    /// # fn main() {
+    /// let el = glutin::EventsLoop::new();
+    /// let wb = glutin::WindowBuilder::new();
+    /// let combined_context = glutin::ContextBuilder::new()
+    ///     .build_combined(wb, &el)
+    ///     .unwrap();
+    ///
+    /// loop {
+    ///     unsafe {
+    ///         combined_context.render_frame(|_context| {
+    ///             // draw everything here
+    ///         })
+    ///     }
+    ///     .unwrap();
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// Like `make_current`, this replaces the context that was current on
+    /// the calling thread.
+    pub unsafe fn render_frame<F, R>(&self, draw: F) -> Result<R, ContextError>
+    where
+        F: FnOnce(&Context) -> R,
+    {
+        if !self.context.is_current() {
+            self.context.make_current()?;
+        }
+        let ret = draw(&self.context);
+        self.swap_buffers()?;
+        Ok(ret)
+    }
+
     /// Returns the pixel format of the main framebuffer of the context.
     pub fn get_pixel_format(&self) -> PixelFormat {
         self.context.context.get_pixel_format()
     }
 
+    /// Reads the contents of the window back, for example to take a
+    /// screenshot.
+    ///
+    /// This must be called after drawing the frame and before swapping the
+    /// buffers, since the contents of the back buffer are undefined
+    /// afterwards. The context must be current. The bound framebuffer, read
+    /// buffer and packing parameters are left untouched.
+    pub fn capture(&self) -> Result<Screenshot, ContextError> {
+        self.start_capture()?.finish()
+    }
+
+    /// Starts reading the contents of the window back, without waiting for
+    /// the GPU when pixel pack buffers are supported.
+    ///
+    /// See `capture` for details. Call `PendingScreenshot::finish` later,
+    /// for example after swapping the buffers, to get the pixels.
+    pub fn start_capture(&self) -> Result<PendingScreenshot<'_>, ContextError> {
+        let dpi_factor = self.window.get_hidpi_factor();
+        let size = match self.window.get_inner_size() {
+            Some(size) => size.to_physical(dpi_factor),
+            None => {
+                return Err(ContextError::OsError(
+                    "the window no longer exists".to_string(),
+                ));
+            }
+        };
+        PendingScreenshot::start(&self.context, &self.get_pixel_format(), size)
+    }
+
+    /// Queries the properties of the surface this context renders to.
+    ///
+    /// See `Context::get_surface_info` for details.
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        self.context.get_surface_info()
+    }
+
+    /// Returns whether the window can currently be seen, so that rendering
+    /// can be throttled or paused while it is hidden.
+    ///
+    /// This is a poll: the state may change right after it is returned, and
+    /// `Visible` is returned whenever the platform can't tell otherwise.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** only reports windows that are unmapped or iconified, not
+    ///   the ones covered by other windows.
+    /// - **Wayland:** the compositor doesn't tell, so `Occluded` is returned
+    ///   once the last frame callback has been pending for a second. The
+    ///   callbacks are dispatched along with the window events, so the events
+    ///   loop must be polled for the state to go back to `Visible`.
+    /// - **Windows:** minimized windows and windows cloaked by DWM, such as
+    ///   the ones on another virtual desktop, are reported.
+    /// - **macOS:** uses the occlusion state of the window.
+    /// - **Android, iOS and Emscripten:** returns
+    ///   `ContextError::FunctionUnavailable`.
+    pub fn get_surface_visibility(
+        &self,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        self.context.context.get_surface_visibility(&self.window)
+    }
+
+    /// See `GammaRampExt::get_gamma_ramp`.
+    #[cfg(any(
+        target_os = "windows",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    pub(crate) fn get_gamma_ramp(&self) -> Result<GammaRamp, ContextError> {
+        self.context.context.get_gamma_ramp(&self.window)
+    }
+
+    /// See `GammaRampExt::set_gamma_ramp`.
+    #[cfg(any(
+        target_os = "windows",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    pub(crate) fn set_gamma_ramp(
+        &self,
+        ramp: &GammaRamp,
+    ) -> Result<(), ContextError> {
+        let mut saved = self
+            .saved_gamma
+            .lock()
+            .unwrap_or_else(|err| err.into_inner());
+        self.context
+            .context
+            .set_gamma_ramp(&self.window, ramp, &mut saved)
+    }
+
+    /// Returns whether the frames of the window are synchronized to the
+    /// vertical blank by a compositor whatever the swap interval, in which
+    /// case they never tear and disabling vsync only keeps `swap_buffers`
+    /// from blocking. Applications can use it to warn that a "vsync off"
+    /// setting won't remove the latency of the compositor.
+    ///
+    /// When `false` is returned, the swap interval decides, so tearing is
+    /// possible with an interval of `0`.
+    ///
+    /// This is a best-effort poll: the state may change when the window is
+    /// resized, made fullscreen, or when the compositor is restarted.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** checks whether a compositing manager is running on the
+    ///   screen of the window, and whether the window asks to bypass it.
+    ///   Compositors that unredirect fullscreen windows on their own aren't
+    ///   detected.
+    /// - **Wayland:** always returns `true`.
+    /// - **Windows:** checks whether DWM composition is enabled, and returns
+    ///   `false` for windows that cover their whole monitor, since the
+    ///   drivers present them as exclusive fullscreen.
+    /// - **macOS, Android, iOS and Emscripten:** returns
+    ///   `ContextError::FunctionUnavailable`.
+    pub fn is_vsync_forced(&self) -> Result<bool, ContextError> {
+        self.context.context.is_vsync_forced(&self.window)
+    }
+
+    /// Returns the backend and display the context was created on.
+    ///
+    /// See `Context::display_token` for details.
+    pub fn display_token(&self) -> DisplayToken {
+        self.context.display_token()
+    }
+
+    /// Returns the behavior the context was actually created with when it
+    /// stops being current.
+    ///
+    /// See `Context::get_release_behavior` for details.
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        self.context.get_release_behavior()
+    }
+
+    /// Flushes the commands of the context with `glFlush`.
+    ///
+    /// See `Context::flush` for details.
+    pub fn flush(&self) -> Result<(), ContextError> {
+        self.context.flush()
+    }
+
+    /// Waits with `glFinish` until the commands of the context are executed.
+    ///
+    /// See `Context::finish` for details.
+    pub fn finish(&self) -> Result<(), ContextError> {
+        self.context.finish()
+    }
+
+    /// Sets whether `make_not_current` flushes the context before releasing
+    /// it when it was created with `ReleaseBehavior::None`.
+    ///
+    /// See `Context::set_flush_on_release` for details.
+    pub fn set_flush_on_release(&self, flush: bool) {
+        self.context.set_flush_on_release(flush)
+    }
+
+    /// Returns the workarounds for known driver bugs that were applied when
+    /// creating the context.
+    ///
+    /// See `Context::get_driver_workarounds` for details.
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        self.context.get_driver_workarounds()
+    }
+
+    /// Returns what was given up to create the surface of the window.
+    ///
+    /// See `Context::get_surface_downgrades` for details.
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        self.context.get_surface_downgrades()
+    }
+
+    /// Sets what happens to the color buffer after the buffers are swapped.
+    ///
+    /// See `Context::set_swap_behavior` for details.
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        self.context.set_swap_behavior(behavior)
+    }
+
+    /// Returns whether the render buffer of the surface can be switched.
+    ///
+    /// See `Context::is_render_buffer_mutable` for details.
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        self.context.is_render_buffer_mutable()
+    }
+
+    /// Switches the surface between single and double buffering.
+    ///
+    /// See `Context::set_render_buffer` for details.
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        self.context.set_render_buffer(render_buffer)
+    }
+
+    /// Sets the HDR metadata of the window.
+    ///
+    /// See `Context::set_hdr_metadata` for details.
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &HdrMetadata,
+    ) -> Result<(), ContextError> {
+        self.context.set_hdr_metadata(metadata)
+    }
+
+    /// Copies the state selected by `mask` from `other` into this context.
+    ///
+    /// See `Context::copy_state_from` for details.
+    pub fn copy_state_from(
+        &self,
+        other: &Context,
+        mask: u32,
+    ) -> Result<(), ContextError> {
+        self.context.copy_state_from(other, mask)
+    }
+
+    /// Adds the window to a swap group and binds the group to a swap
+    /// barrier.
+    ///
+    /// See `Context::join_swap_group` for details.
+    pub fn join_swap_group(
+        &self,
+        group: u32,
+        barrier: u32,
+    ) -> Result<(), ContextError> {
+        self.context.join_swap_group(group, barrier)
+    }
+
+    /// Returns the number of swap groups and swap barriers available.
+    ///
+    /// See `Context::get_max_swap_groups` for details.
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        self.context.get_max_swap_groups()
+    }
+
+    /// Queries the swap intervals the window accepts.
+    ///
+    /// See `Context::get_swap_interval_range` for details.
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        self.context.get_swap_interval_range()
+    }
+
+    /// Queries the swap intervals the window accepts and whether they can
+    /// be trusted to pace frames.
+    ///
+    /// On top of the quirks of the driver, the interval is reported as
+    /// unreliable with `SwapIntervalQuirk::Compositor` when
+    /// `is_vsync_forced` returns `true`.
+    ///
+    /// See `Context::get_swap_interval_caps` for details.
+    pub fn get_swap_interval_caps(
+        &self,
+    ) -> Result<SwapIntervalCaps, ContextError> {
+        let caps = self.context.get_swap_interval_caps()?;
+        match self.is_vsync_forced() {
+            Ok(true) => Ok(caps.with_quirk(SwapIntervalQuirk::Compositor)),
+            Ok(false) | Err(ContextError::FunctionUnavailable) => Ok(caps),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Sets the swap interval of the window.
+    ///
+    /// See `Context::set_swap_interval` for details.
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        self.context.set_swap_interval(interval)
+    }
+
+    /// Sets the swap interval of the window, checked against the range of
+    /// the window according to `policy`.
+    ///
+    /// See `Context::set_swap_interval_with_policy` for details.
+    pub fn set_swap_interval_with_policy(
+        &self,
+        interval: u32,
+        policy: SwapIntervalPolicy,
+    ) -> Result<u32, ContextError> {
+        self.context.set_swap_interval_with_policy(interval, policy)
+    }
+
+    /// Schedules the swaps of the window on every `divisor`th vertical
+    /// blank.
+    ///
+    /// See `Context::set_swap_msc` for details.
+    pub fn set_swap_msc(
+        &self,
+        divisor: u32,
+        remainder: u32,
+    ) -> Result<(), ContextError> {
+        self.context.set_swap_msc(divisor, remainder)
+    }
+
+    /// Blocks until the next vertical blank of the display the window is
+    /// on, without swapping the buffers.
+    ///
+    /// See `Context::wait_for_vsync` for details.
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        self.context.wait_for_vsync()
+    }
+
+    /// Queries how many frames were presented, dropped and missed so far.
+    ///
+    /// See `Context::get_present_stats` for details.
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        self.context.get_present_stats()
+    }
+
     /// Resize the context.
     ///
     /// Some platforms (macOS, Wayland) require being manually updated when
@@ -98,10 +657,100 @@ impl CombinedContext {
     ///
     /// The easiest way of doing this is to take every `Resized` window event
     /// that is received with a `LogicalSize` and convert it to a
-    /// `PhysicalSize` and pass it into this function.
+    /// `PhysicalSize` and pass it into this function. Calling it everywhere
+    /// is always correct: it does nothing where it isn't needed, and
+    /// `needs_manual_resize` tells whether that's the case.
+    ///
+    /// See `ContextBuilder::with_remake_current_on_resize` for the drivers
+    /// that also need the context to be made current again.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** resizes the `wl_egl_window`, which otherwise keeps the
+    ///   size it was created with.
+    /// - **macOS:** updates the `NSOpenGLContext`, except for ANGLE whose
+    ///   surfaces follow the bounds of the layer.
+    /// - **X11, Windows, DRM, Android, iOS, Emscripten:** a no-op, the
+    ///   surface follows the window by itself.
     pub fn resize(&self, size: dpi::PhysicalSize) {
         let (width, height) = size.into();
         self.context.context.resize(width, height);
+        self.resized(size);
+    }
+
+    /// Returns whether `resize` has to be called when the window is resized.
+    ///
+    /// This is true on Wayland and macOS, and whenever
+    /// `ContextBuilder::with_remake_current_on_resize` was requested.
+    /// Toolkits that already track the size of the window elsewhere can use
+    /// this to skip the calls that would do nothing.
+    pub fn needs_manual_resize(&self) -> bool {
+        self.remake_current_on_resize || self.context.context.needs_resize()
+    }
+
+    /// Finishes a resize once the platform context was resized.
+    pub(crate) fn resized(&self, size: dpi::PhysicalSize) {
+        #[cfg(feature = "strict")]
+        {
+            *self.strict_size.lock().unwrap() = Some(size);
+        }
+        #[cfg(not(feature = "strict"))]
+        let _ = size;
+        if self.remake_current_on_resize && self.context.is_current() {
+            // a failure shows up again on the next swap
+            let _ = unsafe { self.context.make_current() };
+        }
+    }
+
+    /// Sets the window to fullscreen on the given monitor, or back to
+    /// windowed mode, and resizes the context to match.
+    ///
+    /// Prefer this over calling `Window::set_fullscreen` and `resize`
+    /// separately: the context is resized to the monitor's resolution right
+    /// away instead of rendering a frame at the old size while waiting for the
+    /// `Resized` event. That event should still be handled, since the window
+    /// manager is free to pick another size.
+    ///
+    /// When leaving fullscreen, the context is resized to the size the
+    /// window had before this method made it fullscreen.
+    ///
+    /// This is borderless fullscreen: the display mode of the monitor is left
+    /// untouched, so the context always has the monitor's current
+    /// resolution.
+    pub fn set_fullscreen(&self, monitor: Option<MonitorId>) {
+        let inner_size = || {
+            let dpi_factor = self.window.get_hidpi_factor();
+            self.window
+                .get_inner_size()
+                .map(|size| size.to_physical(dpi_factor))
+        };
+
+        // the window only reports its new size once the window manager has
+        // handled the request, so the windowed size is saved beforehand
+        let mut windowed_size = self.windowed_size.lock().unwrap();
+        let size = match monitor {
+            Some(ref monitor) => {
+                if windowed_size.is_none() {
+                    *windowed_size = inner_size();
+                }
+                Some(monitor.get_dimensions())
+            }
+            None => windowed_size.take().or_else(inner_size),
+        };
+        drop(windowed_size);
+
+        self.window.set_fullscreen(monitor);
+        if let Some(size) = size {
+            self.resize(size);
+        }
+    }
+}
+
+#[cfg(feature = "strict")]
+impl CombinedContext {
+    fn strict_checks(&self) {
+        strict::before_swap_buffers(&self.context);
+        strict::check_resized(&self.window, &self.strict_size);
     }
 }
 
@@ -1,5 +1,16 @@
 use super::*;
 
+use gl::load;
+
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::os::raw::c_void;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
 /// Represents an OpenGL context.
 ///
 /// A `Context` is normally associated with a single Window, however `Context`s
@@ -24,11 +35,112 @@ use super::*;
 /// ```
 pub struct Context {
     pub(crate) context: platform::Context,
+    gl_cache: Mutex<GlCache>,
+    id: ContextId,
+    share_group: ShareGroupId,
+    share_parent: Option<ContextId>,
+    flush_on_release: AtomicBool,
+}
+
+/// Identifies a `Context` created through glutin, unique within the process.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ContextId(usize);
+
+/// Identifies a group of contexts sharing their objects, see
+/// `Context::share_group_id`.
+///
+/// It is the `ContextId` of the first context of the group, the one that
+/// was created without sharing.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ShareGroupId(ContextId);
+
+impl ShareGroupId {
+    /// Returns the id of the context the group was started with, which may
+    /// have been dropped since.
+    pub fn root(&self) -> ContextId {
+        self.0
+    }
+}
+
+/// Identifies the backend and the display a context was created on, as
+/// returned by `Context::display_token`.
+///
+/// Contexts can only share their objects with contexts of the same token:
+/// building a context sharing with one of another token fails with
+/// `CreationError::IncompatibleSharing` before the driver is involved.
+/// Contexts of the same token may still fail to share for driver-specific
+/// reasons, such as mismatched robustness.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct DisplayToken {
+    backend: &'static str,
+    display: u64,
+}
+
+impl DisplayToken {
+    #[inline]
+    pub(crate) fn new(backend: &'static str, display: u64) -> Self {
+        DisplayToken { backend, display }
+    }
+}
+
+/// Keeps a context current until dropped, then makes current again what was
+/// current before, see `Context::make_current_scoped`.
+pub struct CurrentGuard<'a> {
+    context: &'a Context,
+    previous: platform::CurrentState,
+}
+
+impl<'a> Drop for CurrentGuard<'a> {
+    fn drop(&mut self) {
+        unsafe { self.context.context.restore_current(&self.previous) };
+        #[cfg(feature = "strict")]
+        strict::made_not_current(self.context.id.0);
+    }
+}
+
+/// The bindings loaded by `Context::load_gl`, keyed by their type.
+#[derive(Default)]
+struct GlCache(HashMap<TypeId, Box<dyn Any>>);
+
+/// The functions called by `Context::flush` and `Context::finish`.
+#[derive(Clone)]
+struct FlushGl {
+    flush: Option<unsafe extern "system" fn()>,
+    finish: Option<unsafe extern "system" fn()>,
 }
 
+unsafe impl GlLoader for FlushGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        FlushGl {
+            flush: load(loadfn("glFlush")),
+            finish: load(loadfn("glFinish")),
+        }
+    }
+}
+
+// Implementors of `GlLoader` guarantee that their bindings can be used from
+// any thread.
+unsafe impl Send for GlCache {}
+
 impl ContextTrait for Context {
     unsafe fn make_current(&self) -> Result<(), ContextError> {
-        self.context.make_current()
+        #[cfg(feature = "strict")]
+        strict::before_make_current(self.id.0);
+        let res = self.context.make_current();
+        if let Err(ContextError::ContextLost) = res {
+            // the functions of a lost context may not be valid anymore
+            self.gl_cache.lock().unwrap().0.clear();
+        }
+        #[cfg(feature = "strict")]
+        {
+            if res.is_ok() {
+                strict::made_current(self.id.0);
+            }
+        }
+        res
     }
 
     fn is_current(&self) -> bool {
@@ -44,6 +156,24 @@ impl ContextTrait for Context {
     }
 }
 
+#[cfg(feature = "strict")]
+impl Drop for Context {
+    fn drop(&mut self) {
+        strict::on_drop(self.id.0);
+    }
+}
+
+impl fmt::Debug for Context {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Context")
+            .field("id", &self.id)
+            .field("share_group", &self.share_group)
+            .field("share_parent", &self.share_parent)
+            .field("api", &self.get_api())
+            .finish()
+    }
+}
+
 impl Context {
     /// Builds the given GL context.
     ///
@@ -60,8 +190,632 @@ impl Context {
         cb: ContextBuilder,
     ) -> Result<Self, CreationError> {
         let ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
         let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
         platform::Context::new_context(el, &pf_reqs, &gl_attr)
-            .map(|context| Context { context })
+            .map(|context| Context::from_platform(context, sharing))
+    }
+
+    /// Builds a headless context without an events loop.
+    ///
+    /// See `ContextBuilder::build_compute` for details.
+    pub fn new_compute(cb: ContextBuilder) -> Result<Self, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        platform::Context::new_compute(&pf_reqs, &gl_attr)
+            .map(|context| Context::from_platform(context, sharing))
+    }
+
+    pub(crate) fn from_platform(
+        context: platform::Context,
+        sharing: Option<&Context>,
+    ) -> Self {
+        let id = ContextId(NEXT_ID.fetch_add(1, Ordering::Relaxed));
+        Context {
+            context,
+            gl_cache: Mutex::new(GlCache::default()),
+            id,
+            share_group: sharing
+                .map_or(ShareGroupId(id), |parent| parent.share_group),
+            share_parent: sharing.map(|parent| parent.id),
+            flush_on_release: AtomicBool::new(false),
+        }
+    }
+
+    /// Returns the id of the context, unique among the contexts created
+    /// through glutin in this process.
+    pub fn id(&self) -> ContextId {
+        self.id
+    }
+
+    /// Returns the group of contexts this context shares its objects with,
+    /// so that applications juggling many shared contexts can log or check
+    /// their topology.
+    ///
+    /// Contexts built with `ContextBuilder::with_shared_lists` join the
+    /// group of the context they share with, and the others start a new
+    /// group. Two contexts can use each other's textures, buffers and other
+    /// objects if and only if they have the same group.
+    ///
+    /// Contexts wrapped with `RawContextExt` always start a new group, since
+    /// glutin doesn't know what they were created with.
+    pub fn share_group_id(&self) -> ShareGroupId {
+        self.share_group
+    }
+
+    /// Returns the id of the context this context was built to share with,
+    /// if any, which may have been dropped since.
+    pub fn share_parent(&self) -> Option<ContextId> {
+        self.share_parent
+    }
+
+    /// Returns the backend and display the context was created on, so that
+    /// whether two contexts can share their objects can be checked before
+    /// building one with `ContextBuilder::with_shared_lists`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **X11:** GLX contexts of the same screen have the same token even
+    ///   through different connections to the X server.
+    /// - **Emscripten:** contexts never share, so every context has its own
+    ///   token.
+    pub fn display_token(&self) -> DisplayToken {
+        self.context.display_token()
+    }
+
+    /// Loads the OpenGL bindings `T`, such as the `Gl` struct generated by
+    /// `gl_generator`, with the functions of this context.
+    ///
+    /// The bindings are loaded once and cached, so calling this again with
+    /// the same type only clones them. The cache is cleared if the context
+    /// is lost.
+    ///
+    /// Returns `ContextError::NotCurrent` if the context isn't current on the
+    /// calling thread, since some platforms return functions that only work
+    /// with the context that was current when they were loaded.
+    pub fn load_gl<T: GlLoader>(&self) -> Result<T, ContextError> {
+        if !self.is_current() {
+            return Err(ContextError::NotCurrent);
+        }
+
+        let mut cache = self.gl_cache.lock().unwrap();
+        let entry = cache.0.entry(TypeId::of::<T>()).or_insert_with(|| {
+            Box::new(T::load_with(|symbol| {
+                self.get_proc_address(symbol) as *const _
+            }))
+        });
+        Ok(entry.downcast_ref::<T>().unwrap().clone())
+    }
+
+    /// Makes the context current until the returned guard is dropped, which
+    /// makes current again the context and surfaces that were current on the
+    /// calling thread before, or releases the context if none was.
+    ///
+    /// This lets libraries render with their own context without disturbing
+    /// the one of the application hosting them.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **EGL:** only the context of the client API of this context, OpenGL
+    ///   or OpenGL ES, is saved and restored.
+    /// - **X11:** a GLX context only restores the GLX context that was
+    ///   current, and an EGL context the EGL one.
+    /// - **OSMesa:** the previous context renders to its last buffer again,
+    ///   which is assumed to hold `GL_UNSIGNED_BYTE` components.
+    ///
+    /// # Safety
+    ///
+    /// The guards must be dropped in the reverse order they were created on
+    /// a thread.
+    pub unsafe fn make_current_scoped(
+        &self,
+    ) -> Result<CurrentGuard<'_>, ContextError> {
+        let previous = self.context.current_state();
+        self.make_current()?;
+        Ok(CurrentGuard {
+            context: self,
+            previous,
+        })
+    }
+
+    /// Releases the context if it is current on the calling thread, so that
+    /// it can be made current on another thread.
+    ///
+    /// Does nothing if the context isn't current.
+    ///
+    /// # Safety
+    ///
+    /// No OpenGL function may be called on this thread afterwards until a
+    /// context is made current again.
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if self.flush_on_release.load(Ordering::Relaxed)
+            && self.get_release_behavior() == ReleaseBehavior::None
+            && self.is_current()
+        {
+            self.flush()?;
+        }
+        let res = self.context.make_not_current();
+        #[cfg(feature = "strict")]
+        {
+            if res.is_ok() {
+                strict::made_not_current(self.id.0);
+            }
+        }
+        res
+    }
+
+    /// Destroys the context and its surface now, returning the failures
+    /// that dropping it ignores.
+    ///
+    /// A context is released first if it is current on the calling thread.
+    /// The surface must be destroyed before the native window it renders to,
+    /// otherwise the driver fails with `EGL_BAD_NATIVE_WINDOW`: drop or
+    /// destroy the context before the window, or use `teardown` to destroy a
+    /// `CombinedContext` in the right order.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only EGL reports failures. The other backends destroy their contexts
+    /// with functions that can't fail, and always return `Ok(())`.
+    pub fn destroy(self) -> Result<(), ContextError> {
+        self.context.destroy()
+    }
+
+    /// Resizes the pbuffer backing a headless context.
+    ///
+    /// Pbuffers are created with a fixed size, so this creates a new pbuffer
+    /// with the same config and, if the context was current, rebinds it
+    /// before destroying the old one.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context isn't
+    /// backed by a pbuffer. Currently, only headless contexts using EGL on
+    /// Windows and Android are.
+    pub fn resize_pbuffer(
+        &self,
+        size: dpi::PhysicalSize,
+    ) -> Result<(), ContextError> {
+        let (width, height) = size.into();
+        self.context.resize_pbuffer(width, height, false).map(|_| ())
+    }
+
+    /// Resizes the pbuffer backing a headless context like `resize_pbuffer`,
+    /// except that if a pbuffer of this size can't be allocated, the driver
+    /// creates the largest one it can instead of failing, as thumbnailers
+    /// on GPUs with little memory need.
+    ///
+    /// Returns the size of the new pbuffer, which is never larger than
+    /// `size`. This relies on `EGL_LARGEST_PBUFFER`, so it is available
+    /// wherever `resize_pbuffer` is.
+    pub fn resize_pbuffer_largest_available(
+        &self,
+        size: dpi::PhysicalSize,
+    ) -> Result<dpi::PhysicalSize, ContextError> {
+        let (width, height) = size.into();
+        self.context
+            .resize_pbuffer(width, height, true)
+            .map(|size| size.into())
+    }
+
+    /// Returns the size of the pbuffer backing a headless context, as
+    /// reported by the driver.
+    ///
+    /// Returns `None` if the context isn't backed by a pbuffer.
+    pub fn get_pbuffer_size(&self) -> Option<dpi::PhysicalSize> {
+        self.context
+            .get_pbuffer_size()
+            .map(|(width, height)| (width, height).into())
+    }
+
+    /// Queries the largest pbuffer the config of this context allows, so
+    /// that sizes can be clamped before calling `resize_pbuffer`.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context doesn't
+    /// use EGL or GLX, and `ContextError::OsError` if the driver fails to
+    /// report a limit.
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        self.context.get_pbuffer_limits()
+    }
+
+    /// Chooses the mipmap level of the pbuffer backing a headless context
+    /// that is rendered to, the base level being `0`.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` unless the context was
+    /// built with a `PbufferTexture` with mipmaps, see
+    /// `ContextBuilder::with_pbuffer_texture`.
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        level: i32,
+    ) -> Result<(), ContextError> {
+        self.context.set_pbuffer_mipmap_level(level)
+    }
+
+    /// Binds the color buffer of the pbuffer backing this headless context
+    /// to the texture bound to `GL_TEXTURE_2D` in the context current on the
+    /// calling thread, which is usually another one sharing its objects.
+    ///
+    /// The pbuffer can't be rendered to until it is released with
+    /// `release_pbuffer_from_texture`. Returns
+    /// `ContextError::FunctionUnavailable` unless the context was built with
+    /// `ContextBuilder::with_pbuffer_texture`, and `ContextError::NotCurrent`
+    /// if no context is current on the calling thread.
+    pub fn bind_pbuffer_to_texture(&self) -> Result<(), ContextError> {
+        self.context.bind_pbuffer_tex_image(true)
+    }
+
+    /// Releases the pbuffer from the texture it was bound to with
+    /// `bind_pbuffer_to_texture`, so that it can be rendered to again.
+    pub fn release_pbuffer_from_texture(&self) -> Result<(), ContextError> {
+        self.context.bind_pbuffer_tex_image(false)
+    }
+
+    /// Queries the video memory of the GPU, for example to budget texture
+    /// streaming.
+    ///
+    /// This relies on `GL_NVX_gpu_memory_info` on NVIDIA and on
+    /// `GL_ATI_meminfo` on AMD, and returns
+    /// `ContextError::FunctionUnavailable` if neither is supported. Returns
+    /// `ContextError::NotCurrent` if the context isn't current on the
+    /// calling thread.
+    pub fn get_memory_info(&self) -> Result<MemoryInfo, ContextError> {
+        MemoryInfo::query(self)
+    }
+
+    /// Queries the properties of the surface this context renders to.
+    ///
+    /// Properties the platform can't report are set to `None`. Returns
+    /// `ContextError::FunctionUnavailable` on macOS, iOS and Emscripten.
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        self.context.get_surface_info()
+    }
+
+    /// Returns the behavior the context was actually created with when it
+    /// stops being current.
+    ///
+    /// `ReleaseBehavior::None` is only honored if the platform supports
+    /// `{EGL_KHR,GLX_ARB,WGL_ARB}_context_flush_control`, otherwise this
+    /// returns `ReleaseBehavior::Flush`.
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        self.context.get_release_behavior()
+    }
+
+    /// Flushes the commands of the context with `glFlush`, so that the
+    /// driver starts executing them.
+    ///
+    /// Fails with `ContextError::NotCurrent` if the context isn't current on
+    /// the calling thread.
+    pub fn flush(&self) -> Result<(), ContextError> {
+        let gl = self.load_gl::<FlushGl>()?;
+        let flush = gl.flush.ok_or(ContextError::FunctionUnavailable)?;
+        unsafe { flush() };
+        Ok(())
+    }
+
+    /// Waits with `glFinish` until the commands of the context are executed.
+    ///
+    /// Fails with `ContextError::NotCurrent` if the context isn't current on
+    /// the calling thread.
+    pub fn finish(&self) -> Result<(), ContextError> {
+        let gl = self.load_gl::<FlushGl>()?;
+        let finish = gl.finish.ok_or(ContextError::FunctionUnavailable)?;
+        unsafe { finish() };
+        Ok(())
+    }
+
+    /// Sets whether `make_not_current` flushes the context before releasing
+    /// it when it was created with `ReleaseBehavior::None`, which is off by
+    /// default.
+    ///
+    /// With `ReleaseBehavior::None` the driver doesn't flush the commands of
+    /// a context when it stops being current, so commands that weren't
+    /// flushed may never reach the objects shared with other contexts, or be
+    /// executed out of order with theirs. Contexts created with
+    /// `ReleaseBehavior::Flush` are flushed by the driver and aren't
+    /// affected.
+    ///
+    /// Only `make_not_current` flushes: making another context current on
+    /// the same thread releases this one without it.
+    pub fn set_flush_on_release(&self, flush: bool) {
+        self.flush_on_release.store(flush, Ordering::Relaxed);
+    }
+
+    /// Returns the workarounds for known driver bugs that were applied when
+    /// creating the context.
+    ///
+    /// Only the workarounds allowed with
+    /// `ContextBuilder::with_driver_workarounds` can be applied. Every field
+    /// is `false` on platforms that don't use EGL or GLX.
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        self.context.get_driver_workarounds()
+    }
+
+    /// Returns what was given up to create the surface of the context, the
+    /// driver having no memory for it.
+    ///
+    /// This is only allowed with
+    /// `ContextBuilder::with_downgrade_on_alloc_failure`, and only happens
+    /// with EGL, otherwise the list is empty. The pixel format of the
+    /// context describes the config that was used in the end.
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        self.context.get_surface_downgrades()
+    }
+
+    /// Sets what happens to the color buffer of the surface this context
+    /// renders to after the buffers are swapped.
+    ///
+    /// `SwapBehavior::Preserved` is only available if the context was built
+    /// with `ContextBuilder::with_swap_behavior_preserved`, otherwise
+    /// `ContextError::FunctionUnavailable` is returned. This is also returned
+    /// on platforms that don't use EGL.
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        self.context.set_swap_behavior(behavior)
+    }
+
+    /// Returns whether the render buffer of the surface this context renders
+    /// to can be switched with `set_render_buffer`.
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        self.context.is_render_buffer_mutable()
+    }
+
+    /// Switches the surface this context renders to between rendering to
+    /// the back buffer and rendering directly to the front buffer, which
+    /// takes effect after the next swap.
+    ///
+    /// With `RenderBuffer::Single`, the rendering goes to the buffer being
+    /// displayed, and `swap_buffers` only flushes it, which lowers the
+    /// latency at the cost of tearing. The current render buffer is returned
+    /// by `get_surface_info`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Requires `EGL_KHR_mutable_render_buffer` and a context built with
+    /// `ContextBuilder::with_mutable_render_buffer`, which
+    /// `is_render_buffer_mutable` checks. Otherwise, and on the platforms
+    /// that don't use EGL, `ContextError::FunctionUnavailable` is returned.
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        self.context.set_render_buffer(render_buffer)
+    }
+
+    /// Sets the HDR metadata of the surface this context renders to, which
+    /// is sent to the compositor or the display along with the next frames.
+    ///
+    /// The mastering display values require
+    /// `EGL_EXT_surface_SMPTE2086_metadata` and the content light levels
+    /// `EGL_EXT_surface_CTA861_3_metadata`. The values of an extension that
+    /// isn't supported are left out, and `ContextError::FunctionUnavailable`
+    /// is returned if neither is. This is also returned on platforms that
+    /// don't use EGL and for surfaceless contexts.
+    ///
+    /// The metadata only matters for surfaces in an HDR color space, such
+    /// as the ones of video players targeting HDR displays.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Windows:** only available with EGL, such as ANGLE. WGL has no
+    ///   equivalent, and the metadata of DXGI swap chains, through
+    ///   `IDXGISwapChain4::SetHDRMetaData`, is out of reach since glutin
+    ///   doesn't present through DXGI.
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &HdrMetadata,
+    ) -> Result<(), ContextError> {
+        self.context.set_hdr_metadata(metadata)
+    }
+
+    /// Adds the surface this context renders to to the swap group `group`,
+    /// so that its buffer swaps happen at the same time as the other
+    /// surfaces of the group, and binds the group to the swap barrier
+    /// `barrier`, which synchronizes the swaps of every group bound to it,
+    /// possibly across GPUs and machines.
+    ///
+    /// Passing `0` as `group` removes the surface from its group, and `0` as
+    /// `barrier` unbinds the group from its barrier. See
+    /// `get_max_swap_groups` for the valid values.
+    ///
+    /// This requires `GLX_NV_swap_group` or `WGL_NV_swap_group`, otherwise
+    /// `ContextError::FunctionUnavailable` is returned.
+    pub fn join_swap_group(
+        &self,
+        group: u32,
+        barrier: u32,
+    ) -> Result<(), ContextError> {
+        self.context.join_swap_group(group, barrier)
+    }
+
+    /// Copies the state selected by `mask` from `other` into this context.
+    ///
+    /// `mask` takes the same bits as `glPushAttrib`, for example
+    /// `GL_ALL_ATTRIB_BITS`. This context must not be current on any thread
+    /// and both contexts must have been created by the same backend on the
+    /// same display.
+    ///
+    /// This is only supported by GLX and WGL, otherwise
+    /// `ContextError::FunctionUnavailable` is returned.
+    pub fn copy_state_from(
+        &self,
+        other: &Context,
+        mask: u32,
+    ) -> Result<(), ContextError> {
+        self.context.copy_state_from(&other.context, mask)
+    }
+
+    /// Returns the number of swap groups and swap barriers available, as
+    /// `(max_groups, max_barriers)`.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if swap groups are not
+    /// supported.
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        self.context.get_max_swap_groups()
+    }
+
+    /// Queries the swap intervals the surface this context renders to
+    /// accepts.
+    ///
+    /// The range is only known at runtime, as it depends on the surface and
+    /// the display rather than on what was requested when building the
+    /// context.
+    ///
+    /// This is supported by EGL and by GLX with `GLX_EXT_swap_control`,
+    /// otherwise `ContextError::FunctionUnavailable` is returned.
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        self.context.get_swap_interval_range()
+    }
+
+    /// Queries the swap intervals the surface accepts, like
+    /// `get_swap_interval_range`, along with whether the driver can be
+    /// trusted to honor them, so that frame pacing can fall back to a timer
+    /// when it can't.
+    ///
+    /// The interval is reported as unreliable when it can't be set, when the
+    /// environment variables of Mesa or NVIDIA override it, and with the
+    /// software renderers of Mesa. Use
+    /// `CombinedContext::get_swap_interval_caps` to also account for the
+    /// compositor of the window.
+    ///
+    /// Returns `ContextError::NotCurrent` if the context isn't current on the
+    /// calling thread.
+    pub fn get_swap_interval_caps(
+        &self,
+    ) -> Result<SwapIntervalCaps, ContextError> {
+        SwapIntervalCaps::query(self)
+    }
+
+    /// Queries the vendor, renderer and version strings of the driver, and
+    /// the version of Mesa when it is the driver.
+    ///
+    /// Returns `ContextError::NotCurrent` if the context isn't current on the
+    /// calling thread.
+    pub fn get_driver_info(&self) -> Result<DriverInfo, ContextError> {
+        DriverInfo::query(self)
+    }
+
+    /// Sets the number of vertical blanks to wait for before the buffers are
+    /// swapped, `0` disabling vsync. The accepted values are given by
+    /// `get_swap_interval_range`.
+    ///
+    /// With EGL, GLX and WGL the interval belongs to the surface bound to
+    /// the context rather than to the context itself. It is applied right
+    /// away if the context is current on the calling thread, and otherwise
+    /// the next time it is made current. It is also applied again whenever
+    /// the context is made current with another surface, as when a new
+    /// surface is created on Android.
+    ///
+    /// Without vsync requested on the builder, the interval of the driver is
+    /// left alone until this is called. `ContextError::FunctionUnavailable`
+    /// is returned for contexts without a surface, with OSMesa, on iOS and
+    /// emscripten, and when the platform has no swap control extension.
+    /// `GLX_SGI_swap_control` can't disable vsync.
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        self.context.set_swap_interval(interval)
+    }
+
+    /// Sets the swap interval like `set_swap_interval`, checking it against
+    /// `get_swap_interval_range` first, and returns the interval that was
+    /// set.
+    ///
+    /// `policy` decides what happens when the interval is outside the range
+    /// of the surface: it is refused, replaced by the closest one in the
+    /// range, or passed to the driver anyway. The interval is passed as is
+    /// when the range isn't known.
+    pub fn set_swap_interval_with_policy(
+        &self,
+        interval: u32,
+        policy: SwapIntervalPolicy,
+    ) -> Result<u32, ContextError> {
+        let range = match policy {
+            SwapIntervalPolicy::Ignore => None,
+            _ => match self.get_swap_interval_range() {
+                Ok(range) => Some(range),
+                Err(ContextError::FunctionUnavailable) => None,
+                Err(err) => return Err(err),
+            },
+        };
+        let interval = match range {
+            Some(range) if interval < range.min || interval > range.max => {
+                if policy == SwapIntervalPolicy::Strict {
+                    return Err(ContextError::OsError(format!(
+                        "the swap interval {} is outside the range {}..={} \
+                         of the surface",
+                        interval, range.min, range.max
+                    )));
+                }
+                interval.max(range.min).min(range.max)
+            }
+            _ => interval,
+        };
+        self.context.set_swap_interval(interval)?;
+        Ok(interval)
+    }
+
+    /// Schedules the swaps on the vertical blanks whose count, modulo
+    /// `divisor`, is `remainder`, instead of after a number of vertical
+    /// blanks.
+    ///
+    /// Each swap waits for the next such vertical blank, so the frames stay
+    /// in phase with the display even when one is late. For example, a 24
+    /// fps video is played on a 60 Hz display with a 3:2 cadence by
+    /// alternating the divisor and remainder between frames.
+    ///
+    /// The swap interval is ignored while the swaps are scheduled this way.
+    /// Passing `0` as `divisor` goes back to swapping according to the swap
+    /// interval. `ContextError::OsError` is returned if `remainder` isn't
+    /// smaller than a non-zero `divisor`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only supported by GLX with `GLX_OML_sync_control`, through
+    /// `glXSwapBuffersMscOML`. `ContextError::FunctionUnavailable` is
+    /// returned otherwise.
+    pub fn set_swap_msc(
+        &self,
+        divisor: u32,
+        remainder: u32,
+    ) -> Result<(), ContextError> {
+        self.context.set_swap_msc(divisor, remainder)
+    }
+
+    /// Blocks until the next vertical blank of the display the window is
+    /// on, without swapping the buffers.
+    ///
+    /// Applications that only present when something changed can still
+    /// align their work with the display this way, for the lowest latency,
+    /// instead of swapping a frame that didn't change.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - GLX: `glXWaitForMscOML`, with `GLX_OML_sync_control`.
+    /// - Wayland: waits for a frame callback, which the events loop must
+    ///   dispatch from another thread, and returns after a second if the
+    ///   surface is occluded.
+    /// - Windows: `DwmFlush`, which needs the desktop composition.
+    ///
+    /// `ContextError::FunctionUnavailable` is returned elsewhere, including
+    /// by headless contexts.
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        self.context.wait_for_vsync()
+    }
+
+    /// Queries how many frames were presented, dropped and missed so far,
+    /// so that latency and judder sensitive applications can measure the
+    /// frames they lose.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - Windows: `DwmGetCompositionTimingInfo`, which needs the desktop
+    ///   composition. Since Windows 8.1 the statistics are the ones of the
+    ///   whole composition rather than of the window.
+    ///
+    /// `ContextError::FunctionUnavailable` is returned elsewhere, including
+    /// by headless contexts.
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        self.context.get_present_stats()
     }
 }
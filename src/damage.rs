@@ -0,0 +1,211 @@
+use super::*;
+
+use std::collections::VecDeque;
+
+/// The number of frames whose damage is remembered. Back buffers older than
+/// this are redrawn entirely.
+const MAX_TRACKED_FRAMES: usize = 8;
+
+/// Remembers the damage of the last frames to find out which part of the
+/// back buffer has to be redrawn, based on the age of its contents.
+///
+/// The age of the back buffer is reported by `Context::get_surface_info`
+/// when `EGL_EXT_buffer_age` or `GLX_EXT_buffer_age` is available. When it
+/// is unknown, the whole surface has to be redrawn.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// # fn main() {
+/// let el = glutin::EventsLoop::new();
+/// let wb = glutin::WindowBuilder::new();
+/// let combined_context = glutin::ContextBuilder::new()
+///     .build_combined(wb, &el)
+///     .unwrap();
+/// let mut tracker = glutin::DamageTracker::new();
+///
+/// loop {
+///     // the part of the window that changed since the previous frame
+///     let damage = [glutin::Rect {
+///         x: 0,
+///         y: 0,
+///         width: 100,
+///         height: 20,
+///     }];
+///
+///     let age = combined_context
+///         .context()
+///         .get_surface_info()
+///         .ok()
+///         .and_then(|info| info.buffer_age);
+///     match tracker.get_redraw_region(&damage, age) {
+///         Some(_rects) => (), // only redraw these rectangles
+///         None => (),         // redraw everything
+///     }
+///
+///     tracker.swap_buffers(&combined_context, &damage).unwrap();
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct DamageTracker {
+    /// The damage of the previous frames, most recent first.
+    history: VecDeque<Vec<Rect>>,
+}
+
+impl DamageTracker {
+    /// Builds a tracker without any history.
+    pub fn new() -> Self {
+        DamageTracker {
+            history: VecDeque::with_capacity(MAX_TRACKED_FRAMES),
+        }
+    }
+
+    /// Returns the rectangles of the back buffer to redraw for a frame that
+    /// changes `damage`, when the contents of the back buffer are
+    /// `buffer_age` frames old.
+    ///
+    /// These are the damage of the frame along with the damage of the frames
+    /// the back buffer missed. `None` means that the whole surface has to be
+    /// redrawn, which is the case if the age is unknown, `0`, or older than
+    /// the frames the tracker remembers, or if one of these frames damaged
+    /// the whole surface.
+    pub fn get_redraw_region(
+        &self,
+        damage: &[Rect],
+        buffer_age: Option<u32>,
+    ) -> Option<Vec<Rect>> {
+        let missed = match buffer_age {
+            Some(age) if age > 0 => age as usize - 1,
+            _ => return None,
+        };
+        if missed > self.history.len() {
+            return None;
+        }
+
+        // an empty damage covers the whole surface
+        if damage.is_empty() {
+            return None;
+        }
+        let mut rects = damage.to_vec();
+        for frame in self.history.iter().take(missed) {
+            if frame.is_empty() {
+                return None;
+            }
+            rects.extend_from_slice(frame);
+        }
+        Some(rects)
+    }
+
+    /// Records the damage of a frame whose buffers were swapped.
+    ///
+    /// This has to be called once per frame when the buffers are swapped
+    /// without going through `DamageTracker::swap_buffers`, for example with
+    /// a `SeparatedContext`. An empty slice means that the whole surface
+    /// changed.
+    pub fn push_frame(&mut self, damage: &[Rect]) {
+        if self.history.len() == MAX_TRACKED_FRAMES {
+            self.history.pop_back();
+        }
+        self.history.push_front(damage.to_vec());
+    }
+
+    /// Swaps the buffers of the context while passing the damage along,
+    /// then records the damage of the frame.
+    ///
+    /// Nothing is recorded if the swap fails, since the back buffer wasn't
+    /// presented.
+    pub fn swap_buffers(
+        &mut self,
+        context: &CombinedContext,
+        damage: &[Rect],
+    ) -> Result<(), ContextError> {
+        context.swap_buffers_with_damage(damage)?;
+        self.push_frame(damage);
+        Ok(())
+    }
+
+    /// Forgets the damage of the previous frames, so that the whole surface
+    /// is redrawn until new history is recorded.
+    ///
+    /// This should be called when the surface is resized.
+    pub fn reset(&mut self) {
+        self.history.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: u32) -> Rect {
+        Rect {
+            x,
+            y: 0,
+            width: 10,
+            height: 10,
+        }
+    }
+
+    #[test]
+    fn redraw_region_covers_missed_frames() {
+        let mut tracker = DamageTracker::new();
+        tracker.push_frame(&[rect(0)]);
+        tracker.push_frame(&[rect(10)]);
+
+        assert_eq!(
+            tracker.get_redraw_region(&[rect(20)], Some(1)),
+            Some(vec![rect(20)]),
+        );
+        // the most recent frame comes first
+        assert_eq!(
+            tracker.get_redraw_region(&[rect(20)], Some(3)),
+            Some(vec![rect(20), rect(10), rect(0)]),
+        );
+    }
+
+    #[test]
+    fn unknown_or_old_buffers_are_redrawn() {
+        let mut tracker = DamageTracker::new();
+        tracker.push_frame(&[rect(0)]);
+        tracker.push_frame(&[rect(10)]);
+
+        assert_eq!(tracker.get_redraw_region(&[rect(20)], None), None);
+        assert_eq!(tracker.get_redraw_region(&[rect(20)], Some(0)), None);
+        assert_eq!(tracker.get_redraw_region(&[rect(20)], Some(4)), None);
+    }
+
+    #[test]
+    fn empty_damage_covers_the_surface() {
+        let mut tracker = DamageTracker::new();
+        assert_eq!(tracker.get_redraw_region(&[], Some(1)), None);
+
+        tracker.push_frame(&[]);
+        tracker.push_frame(&[rect(10)]);
+        assert!(tracker.get_redraw_region(&[rect(20)], Some(2)).is_some());
+        assert_eq!(tracker.get_redraw_region(&[rect(20)], Some(3)), None);
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut tracker = DamageTracker::new();
+        for x in 0..MAX_TRACKED_FRAMES as u32 + 2 {
+            tracker.push_frame(&[rect(x)]);
+        }
+        let age = MAX_TRACKED_FRAMES as u32 + 1;
+        assert_eq!(
+            tracker
+                .get_redraw_region(&[rect(100)], Some(age))
+                .map(|rects| rects.len()),
+            Some(MAX_TRACKED_FRAMES + 1),
+        );
+        assert_eq!(
+            tracker.get_redraw_region(&[rect(100)], Some(age + 1)),
+            None,
+        );
+
+        tracker.reset();
+        assert_eq!(tracker.get_redraw_region(&[rect(100)], Some(2)), None);
+    }
+}
@@ -0,0 +1,245 @@
+use super::*;
+
+use gl::{load, GL_RENDERER, GL_VENDOR, GL_VERSION};
+
+use std::env;
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_uint, c_void};
+
+/// The renderers of Mesa drawing on the CPU, which have no vertical blank to
+/// wait for.
+const SOFTWARE_RENDERERS: &[&str] = &["llvmpipe", "softpipe", "swrast"];
+
+/// The only OpenGL function needed to identify the driver.
+#[derive(Clone)]
+struct DriverGl {
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+}
+
+unsafe impl GlLoader for DriverGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        DriverGl {
+            get_string: load(loadfn("glGetString")),
+        }
+    }
+}
+
+/// The driver behind a context, as reported by `Context::get_driver_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DriverInfo {
+    /// The `GL_VENDOR` string.
+    pub vendor: String,
+
+    /// The `GL_RENDERER` string, which names the GPU.
+    pub renderer: String,
+
+    /// The `GL_VERSION` string.
+    pub version: String,
+
+    /// The version of Mesa as `(major, minor, patch)`, parsed from the
+    /// `GL_VERSION` string. `None` if the driver isn't Mesa.
+    pub mesa_version: Option<(u32, u32, u32)>,
+}
+
+impl DriverInfo {
+    /// Queries the driver of `context`, which must be current.
+    pub(crate) fn query(context: &Context) -> Result<Self, ContextError> {
+        let gl = context.load_gl::<DriverGl>()?;
+        let get_string = match gl.get_string {
+            Some(get_string) => get_string,
+            None => return Err(ContextError::FunctionUnavailable),
+        };
+
+        let string = |name| unsafe {
+            let p = get_string(name);
+            if p.is_null() {
+                String::new()
+            } else {
+                CStr::from_ptr(p as *const c_char)
+                    .to_string_lossy()
+                    .into_owned()
+            }
+        };
+        let version = string(GL_VERSION);
+        Ok(DriverInfo {
+            vendor: string(GL_VENDOR),
+            renderer: string(GL_RENDERER),
+            mesa_version: parse_mesa_version(&version),
+            version,
+        })
+    }
+
+    /// Whether the driver is Mesa.
+    pub fn is_mesa(&self) -> bool {
+        self.mesa_version.is_some()
+    }
+
+    /// Whether the driver renders on the CPU.
+    pub fn is_software(&self) -> bool {
+        let renderer = self.renderer.to_lowercase();
+        SOFTWARE_RENDERERS.iter().any(|name| renderer.contains(name))
+            || self.renderer == "GDI Generic"
+    }
+
+    /// Whether the driver is the proprietary one of NVIDIA.
+    fn is_nvidia(&self) -> bool {
+        !self.is_mesa() && self.vendor.starts_with("NVIDIA")
+    }
+}
+
+/// Parses the version following `Mesa` in a `GL_VERSION` string, such as
+/// `4.6 (Core Profile) Mesa 23.2.1-1ubuntu3` or `OpenGL ES 3.2 Mesa 20.0`.
+fn parse_mesa_version(version: &str) -> Option<(u32, u32, u32)> {
+    let start = version.find("Mesa ")? + "Mesa ".len();
+    let mut numbers = version[start..]
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .next()?
+        .split('.')
+        .map(|n| n.parse::<u32>().ok());
+    let major = numbers.next()??;
+    let minor = numbers.next().unwrap_or(Some(0))?;
+    let patch = numbers.next().unwrap_or(Some(0)).unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// Why the swap interval of a surface can't be relied on to pace frames.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SwapIntervalQuirk {
+    /// The interval can't be set or queried.
+    Unsupported,
+
+    /// The user overrides the interval of the application through the
+    /// environment: `vblank_mode` set to `0` or `3` with Mesa, or
+    /// `__GL_SYNC_TO_VBLANK` with the NVIDIA driver.
+    EnvironmentOverride,
+
+    /// The renderer draws on the CPU and swaps without waiting for the
+    /// display, such as llvmpipe.
+    SoftwareRenderer,
+
+    /// A compositor presents the frames, so an interval of `0` still waits
+    /// for it, and swaps may block or stop being paced while the window is
+    /// hidden. This is always the case on Wayland, and for windows that
+    /// aren't fullscreen when DWM composition is enabled on Windows.
+    Compositor,
+}
+
+/// Whether frames can be paced by the swap interval.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SwapIntervalReliability {
+    /// The driver is expected to honor the interval, so swapping the buffers
+    /// paces the frames.
+    Reliable,
+
+    /// The interval may be ignored or overridden, so frames should be paced
+    /// with a timer instead.
+    Unreliable(SwapIntervalQuirk),
+}
+
+/// The swap intervals a surface accepts and how far they can be trusted, as
+/// reported by `Context::get_swap_interval_caps`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SwapIntervalCaps {
+    /// The intervals the surface accepts. `None` if they aren't known.
+    pub range: Option<SwapIntervalRange>,
+
+    /// Whether the driver honors the interval.
+    pub reliability: SwapIntervalReliability,
+}
+
+impl SwapIntervalCaps {
+    /// Queries the swap interval capabilities of `context`, which must be
+    /// current.
+    pub(crate) fn query(context: &Context) -> Result<Self, ContextError> {
+        let range = match context.get_swap_interval_range() {
+            Ok(range) => Some(range),
+            Err(ContextError::FunctionUnavailable) => None,
+            Err(err) => return Err(err),
+        };
+        let driver = DriverInfo::query(context)?;
+
+        let quirk = if range.is_none() {
+            Some(SwapIntervalQuirk::Unsupported)
+        } else if is_overridden(&driver) {
+            Some(SwapIntervalQuirk::EnvironmentOverride)
+        } else if driver.is_software() {
+            Some(SwapIntervalQuirk::SoftwareRenderer)
+        } else {
+            None
+        };
+        Ok(SwapIntervalCaps {
+            range,
+            reliability: match quirk {
+                Some(quirk) => SwapIntervalReliability::Unreliable(quirk),
+                None => SwapIntervalReliability::Reliable,
+            },
+        })
+    }
+
+    /// Marks the interval as unreliable because of `quirk`, unless it
+    /// already is.
+    pub(crate) fn with_quirk(mut self, quirk: SwapIntervalQuirk) -> Self {
+        if self.reliability == SwapIntervalReliability::Reliable {
+            self.reliability = SwapIntervalReliability::Unreliable(quirk);
+        }
+        self
+    }
+}
+
+/// Whether the environment variables of the driver force vsync on or off.
+fn is_overridden(driver: &DriverInfo) -> bool {
+    if driver.is_mesa() {
+        // `1` and `2` only change the default interval
+        match env::var("vblank_mode") {
+            Ok(mode) => mode == "0" || mode == "3",
+            Err(_) => false,
+        }
+    } else if driver.is_nvidia() {
+        env::var_os("__GL_SYNC_TO_VBLANK").is_some()
+    } else {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mesa_versions() {
+        let versions = [
+            ("4.6 (Core Profile) Mesa 23.1.0-devel", (23, 1, 0)),
+            ("4.6 (Core Profile) Mesa 23.2.1-1ubuntu3", (23, 2, 1)),
+            ("OpenGL ES 3.2 Mesa 20.0.8", (20, 0, 8)),
+            ("OpenGL ES 3.2 Mesa 20.0", (20, 0, 0)),
+            ("3.1 Mesa 10", (10, 0, 0)),
+            ("4.5 Mesa 22.3.6 (git-a1b2c3d)", (22, 3, 6)),
+        ];
+        for &(version, expected) in &versions {
+            let parsed = parse_mesa_version(version);
+            assert_eq!(parsed, Some(expected), "{}", version);
+        }
+    }
+
+    #[test]
+    fn non_mesa_versions() {
+        let versions = [
+            "",
+            "4.6.0 NVIDIA 535.54.03",
+            "4.6.0 Compatibility Profile Context 23.10.2.230926",
+            "OpenGL ES 3.2 v1.r32p1-01eac0.efd03e3b5c4d",
+            "2.1 Metal - 83.1",
+            // a truncated or garbled version
+            "4.6 Mesa ",
+            "4.6 Mesa devel",
+            "4.6 Mesa 23.",
+            "4.6 Mesa 99999999999.0",
+        ];
+        for version in &versions {
+            assert_eq!(parse_mesa_version(version), None, "{}", version);
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use super::*;
+
+/// The severity of an `EglDebugMessage`, from the most to the least severe.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum EglMessageType {
+    /// The driver is in an unusable state, for example after losing the
+    /// device.
+    Critical,
+    /// A command failed, the error `eglGetError` would return.
+    Error,
+    /// A command succeeded, but in a way that may not be what was meant.
+    Warning,
+    /// Anything else the driver has to say.
+    Info,
+}
+
+/// A message reported by EGL to the callback set with
+/// `set_egl_debug_callback`.
+#[derive(Debug, Clone)]
+pub struct EglDebugMessage {
+    /// The error raised by the command, such as `0x3009` for
+    /// `EGL_BAD_MATCH`, or `0x3000` (`EGL_SUCCESS`) for the messages that
+    /// aren't about an error.
+    pub error: u32,
+    /// The name of the command that raised the message, such as
+    /// `eglMakeCurrent`.
+    pub command: String,
+    pub message_type: EglMessageType,
+    /// The label given with `ContextTraitExt::set_egl_label` to the context
+    /// or the surface the message is about, if any.
+    pub object_label: Option<String>,
+    /// The explanation of the driver.
+    pub message: String,
+}
+
+impl EglDebugMessage {
+    /// Returns the name of `error`, such as `EGL_BAD_MATCH`.
+    pub fn error_name(&self) -> Option<&'static str> {
+        let name = match self.error {
+            0x3000 => "EGL_SUCCESS",
+            0x3001 => "EGL_NOT_INITIALIZED",
+            0x3002 => "EGL_BAD_ACCESS",
+            0x3003 => "EGL_BAD_ALLOC",
+            0x3004 => "EGL_BAD_ATTRIBUTE",
+            0x3005 => "EGL_BAD_CONFIG",
+            0x3006 => "EGL_BAD_CONTEXT",
+            0x3007 => "EGL_BAD_CURRENT_SURFACE",
+            0x3008 => "EGL_BAD_DISPLAY",
+            0x3009 => "EGL_BAD_MATCH",
+            0x300A => "EGL_BAD_NATIVE_PIXMAP",
+            0x300B => "EGL_BAD_NATIVE_WINDOW",
+            0x300C => "EGL_BAD_PARAMETER",
+            0x300D => "EGL_BAD_SURFACE",
+            0x300E => "EGL_CONTEXT_LOST",
+            _ => return None,
+        };
+        Some(name)
+    }
+}
+
+/// Sets the function EGL reports its errors and messages to, through
+/// `EGL_KHR_debug`, replacing the previous one.
+///
+/// Unlike the error codes returned by `eglGetError` after the fact, the
+/// messages tell which command failed and why, along with the label of the
+/// object involved, see `ContextTraitExt::set_egl_label`. The messages up to
+/// `max_type` are reported: `EglMessageType::Error` reports the errors and
+/// the critical messages, which is what EGL reports by default.
+///
+/// The function may be called from any thread that uses EGL, while an EGL
+/// command is running, and must not call EGL itself.
+///
+/// Returns `ContextError::FunctionUnavailable` if EGL can't be loaded or
+/// doesn't support `EGL_KHR_debug`. The function is forgotten by EGL when it
+/// is reloaded by `reload_symbols`, and must be set again afterwards.
+///
+/// ## Platform-specific
+///
+/// Only available where glutin uses EGL: on Linux, Windows and macOS with
+/// an EGL implementation such as ANGLE, and on Android. Returns
+/// `ContextError::FunctionUnavailable` on iOS and Emscripten.
+pub fn set_egl_debug_callback<F>(
+    max_type: EglMessageType,
+    callback: F,
+) -> Result<(), ContextError>
+where
+    F: Fn(&EglDebugMessage) + Send + Sync + 'static,
+{
+    #[cfg(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        api::egl::debug::set_callback(max_type, Some(Arc::new(callback)))
+    }
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        let _ = (max_type, callback);
+        Err(ContextError::FunctionUnavailable)
+    }
+}
+
+/// Removes the function set with `set_egl_debug_callback`, after which EGL
+/// only reports its errors through `eglGetError`.
+pub fn remove_egl_debug_callback() -> Result<(), ContextError> {
+    #[cfg(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        api::egl::debug::set_callback(EglMessageType::Error, None)
+    }
+    #[cfg(not(any(
+        target_os = "windows",
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "android",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        Err(ContextError::FunctionUnavailable)
+    }
+}
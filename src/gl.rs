@@ -0,0 +1,138 @@
+//! The OpenGL functions and enums used by the helpers of glutin that
+//! call OpenGL themselves.
+
+use std::mem;
+use std::os::raw::{c_int, c_uint, c_void};
+
+pub(crate) const GL_ACTIVE_TEXTURE: c_uint = 0x84E0;
+pub(crate) const GL_ARRAY_BUFFER: c_uint = 0x8892;
+pub(crate) const GL_ARRAY_BUFFER_BINDING: c_uint = 0x8894;
+pub(crate) const GL_BACK: c_uint = 0x0405;
+pub(crate) const GL_BLEND: c_uint = 0x0BE2;
+pub(crate) const GL_COLOR_ATTACHMENT0: c_uint = 0x8CE0;
+pub(crate) const GL_COLOR_BUFFER_BIT: c_uint = 0x4000;
+pub(crate) const GL_CULL_FACE: c_uint = 0x0B44;
+pub(crate) const GL_CURRENT_PROGRAM: c_uint = 0x8B8D;
+pub(crate) const GL_DEPTH24_STENCIL8: c_uint = 0x88F0;
+pub(crate) const GL_DEPTH_ATTACHMENT: c_uint = 0x8D00;
+pub(crate) const GL_DEPTH_COMPONENT16: c_uint = 0x81A5;
+pub(crate) const GL_DEPTH_STENCIL_ATTACHMENT: c_uint = 0x821A;
+pub(crate) const GL_DEPTH_TEST: c_uint = 0x0B71;
+pub(crate) const GL_DRAW_FRAMEBUFFER: c_uint = 0x8CA9;
+pub(crate) const GL_EXTENSIONS: c_uint = 0x1F03;
+pub(crate) const GL_FRAMEBUFFER: c_uint = 0x8D40;
+pub(crate) const GL_FRAMEBUFFER_BINDING: c_uint = 0x8CA6;
+pub(crate) const GL_FRAMEBUFFER_COMPLETE: c_uint = 0x8CD5;
+pub(crate) const GL_FRAMEBUFFER_SRGB: c_uint = 0x8DB9;
+pub(crate) const GL_FRONT: c_uint = 0x0404;
+pub(crate) const GL_GPU_DISJOINT_EXT: c_uint = 0x8FBB;
+pub(crate) const GL_GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX: c_uint =
+    0x9049;
+pub(crate) const GL_GPU_MEMORY_INFO_DEDICATED_VIDMEM_NVX: c_uint = 0x9047;
+pub(crate) const GL_GPU_MEMORY_INFO_EVICTED_MEMORY_NVX: c_uint = 0x904B;
+pub(crate) const GL_MAP_READ_BIT: c_uint = 0x0001;
+pub(crate) const GL_MAX_SAMPLES: c_uint = 0x8D57;
+pub(crate) const GL_NEAREST: c_uint = 0x2600;
+pub(crate) const GL_NUM_EXTENSIONS: c_uint = 0x821D;
+pub(crate) const GL_PACK_ALIGNMENT: c_uint = 0x0D05;
+pub(crate) const GL_PACK_ROW_LENGTH: c_uint = 0x0D02;
+pub(crate) const GL_PACK_SKIP_PIXELS: c_uint = 0x0D04;
+pub(crate) const GL_PACK_SKIP_ROWS: c_uint = 0x0D03;
+pub(crate) const GL_PIXEL_PACK_BUFFER: c_uint = 0x88EB;
+pub(crate) const GL_PIXEL_PACK_BUFFER_BINDING: c_uint = 0x88ED;
+pub(crate) const GL_QUERY_RESULT: c_uint = 0x8866;
+pub(crate) const GL_QUERY_RESULT_AVAILABLE: c_uint = 0x8867;
+pub(crate) const GL_READ_BUFFER: c_uint = 0x0C02;
+pub(crate) const GL_READ_FRAMEBUFFER: c_uint = 0x8CA8;
+pub(crate) const GL_READ_FRAMEBUFFER_BINDING: c_uint = 0x8CAA;
+pub(crate) const GL_READ_ONLY: c_uint = 0x88B8;
+pub(crate) const GL_RENDERBUFFER: c_uint = 0x8D41;
+pub(crate) const GL_RENDERBUFFER_BINDING: c_uint = 0x8CA7;
+pub(crate) const GL_RENDERER: c_uint = 0x1F01;
+pub(crate) const GL_RGBA: c_uint = 0x1908;
+pub(crate) const GL_RGBA8: c_uint = 0x8058;
+pub(crate) const GL_SCISSOR_BOX: c_uint = 0x0C10;
+pub(crate) const GL_SCISSOR_TEST: c_uint = 0x0C11;
+pub(crate) const GL_STENCIL_ATTACHMENT: c_uint = 0x8D20;
+pub(crate) const GL_STENCIL_TEST: c_uint = 0x0B90;
+pub(crate) const GL_STREAM_READ: c_uint = 0x88E1;
+pub(crate) const GL_TEXTURE_FREE_MEMORY_ATI: c_uint = 0x87FC;
+pub(crate) const GL_TIMESTAMP: c_uint = 0x8E28;
+pub(crate) const GL_UNSIGNED_BYTE: c_uint = 0x1401;
+pub(crate) const GL_VENDOR: c_uint = 0x1F00;
+pub(crate) const GL_VERSION: c_uint = 0x1F02;
+pub(crate) const GL_VERTEX_ARRAY_BINDING: c_uint = 0x85B5;
+pub(crate) const GL_VIEWPORT: c_uint = 0x0BA2;
+
+/// Casts the address of an OpenGL function, as returned by the load
+/// function of a `GlLoader`, to the function pointer `T`. Returns `None` if
+/// the function isn't available.
+pub(crate) fn load<T: Copy>(p: *const c_void) -> Option<T> {
+    assert_eq!(mem::size_of::<T>(), mem::size_of::<*const c_void>());
+    if p.is_null() {
+        None
+    } else {
+        Some(unsafe { mem::transmute_copy::<*const c_void, T>(&p) })
+    }
+}
+
+/// Returns whether the current context uses OpenGL ES, along with its major
+/// and minor versions.
+pub(crate) unsafe fn query_gl_version(
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+) -> (bool, u32, u32) {
+    let version = get_string
+        .map(|f| f(GL_VERSION))
+        .filter(|p| !p.is_null())
+        .map(|p| {
+            std::ffi::CStr::from_ptr(p as *const _)
+                .to_string_lossy()
+                .into_owned()
+        })
+        .unwrap_or_default();
+
+    let es = version.starts_with("OpenGL ES");
+    let mut numbers = version
+        .trim_start_matches("OpenGL ES-CM ")
+        .trim_start_matches("OpenGL ES-CL ")
+        .trim_start_matches("OpenGL ES ")
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse::<u32>().ok());
+    let major = numbers.next().unwrap_or(1);
+    let minor = numbers.next().unwrap_or(0);
+    (es, major, minor)
+}
+
+/// Returns whether the current context supports the OpenGL extension
+/// `name`.
+pub(crate) unsafe fn has_gl_extension(
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+    get_stringi: Option<unsafe extern "system" fn(c_uint, c_uint) -> *const u8>,
+    get_integerv: Option<unsafe extern "system" fn(c_uint, *mut c_int)>,
+    name: &str,
+) -> bool {
+    let (_, major, _) = query_gl_version(get_string);
+    // `GL_EXTENSIONS` can't be passed to `glGetString` in core profiles
+    if let (true, Some(get_stringi), Some(get_integerv)) =
+        (major >= 3, get_stringi, get_integerv)
+    {
+        let mut count = 0;
+        get_integerv(GL_NUM_EXTENSIONS, &mut count);
+        return (0..count as c_uint)
+            .map(|i| get_stringi(GL_EXTENSIONS, i))
+            .filter(|p| !p.is_null())
+            .map(|p| std::ffi::CStr::from_ptr(p as *const _))
+            .any(|ext| ext.to_bytes() == name.as_bytes());
+    }
+
+    get_string
+        .map(|f| f(GL_EXTENSIONS))
+        .filter(|p| !p.is_null())
+        .map(|p| {
+            std::ffi::CStr::from_ptr(p as *const _)
+                .to_string_lossy()
+                .split(' ')
+                .any(|ext| ext == name)
+        })
+        .unwrap_or(false)
+}
@@ -0,0 +1,380 @@
+use super::*;
+
+use gl::{
+    load, GL_GPU_DISJOINT_EXT, GL_QUERY_RESULT, GL_QUERY_RESULT_AVAILABLE,
+    GL_TIMESTAMP,
+};
+
+use std::collections::VecDeque;
+use std::os::raw::{c_int, c_uint, c_void};
+use std::time::Duration;
+
+/// The OpenGL functions needed for timer queries, from OpenGL 3.3,
+/// `GL_ARB_timer_query` or `GL_EXT_disjoint_timer_query`.
+#[derive(Clone)]
+struct TimerGl {
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+    get_stringi: Option<unsafe extern "system" fn(c_uint, c_uint) -> *const u8>,
+    get_integerv: Option<unsafe extern "system" fn(c_uint, *mut c_int)>,
+    gen_queries: Option<unsafe extern "system" fn(c_int, *mut c_uint)>,
+    delete_queries: Option<unsafe extern "system" fn(c_int, *const c_uint)>,
+    query_counter: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    get_query_objectiv:
+        Option<unsafe extern "system" fn(c_uint, c_uint, *mut c_int)>,
+    get_query_objectui64v:
+        Option<unsafe extern "system" fn(c_uint, c_uint, *mut u64)>,
+}
+
+unsafe impl GlLoader for TimerGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        // OpenGL ES only has the functions of the extension
+        let mut either = |core: &'static str, ext: &'static str| {
+            let p = loadfn(core);
+            if p.is_null() {
+                loadfn(ext)
+            } else {
+                p
+            }
+        };
+
+        let gen_queries = either("glGenQueries", "glGenQueriesEXT");
+        let delete_queries = either("glDeleteQueries", "glDeleteQueriesEXT");
+        let query_counter = either("glQueryCounter", "glQueryCounterEXT");
+        let get_query_objectiv =
+            either("glGetQueryObjectiv", "glGetQueryObjectivEXT");
+        let get_query_objectui64v =
+            either("glGetQueryObjectui64v", "glGetQueryObjectui64vEXT");
+
+        TimerGl {
+            get_string: load(loadfn("glGetString")),
+            get_stringi: load(loadfn("glGetStringi")),
+            get_integerv: load(loadfn("glGetIntegerv")),
+            gen_queries: load(gen_queries),
+            delete_queries: load(delete_queries),
+            query_counter: load(query_counter),
+            get_query_objectiv: load(get_query_objectiv),
+            get_query_objectui64v: load(get_query_objectui64v),
+        }
+    }
+}
+
+impl TimerGl {
+    /// Whether the current context supports timestamp queries, and whether
+    /// it reports disjoint operations.
+    unsafe fn support(&self) -> (bool, bool) {
+        let has_extension = |name| {
+            gl::has_gl_extension(
+                self.get_string,
+                self.get_stringi,
+                self.get_integerv,
+                name,
+            )
+        };
+        let (es, major, minor) = gl::query_gl_version(self.get_string);
+        let disjoint = has_extension("GL_EXT_disjoint_timer_query");
+        let supported = if es {
+            disjoint
+        } else {
+            (major, minor) >= (3, 3) || has_extension("GL_ARB_timer_query")
+        };
+        let loaded = self.get_integerv.is_some()
+            && self.gen_queries.is_some()
+            && self.delete_queries.is_some()
+            && self.query_counter.is_some()
+            && self.get_query_objectiv.is_some()
+            && self.get_query_objectui64v.is_some();
+        (supported && loaded, disjoint)
+    }
+
+    /// Reads and clears the disjoint flag of the context.
+    unsafe fn take_disjoint(&self) -> bool {
+        let mut disjoint = 0;
+        self.get_integerv.unwrap()(GL_GPU_DISJOINT_EXT, &mut disjoint);
+        disjoint != 0
+    }
+}
+
+/// A span of GPU time measured by a `GpuTimer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GpuTiming {
+    /// The label given to `GpuTimer::begin`.
+    pub label: String,
+
+    /// How many scopes enclosed this one when it began, 0 for the outermost
+    /// ones.
+    pub depth: usize,
+
+    /// The GPU timestamp at which the commands before the scope completed,
+    /// in nanoseconds. Only meaningful relative to the other timestamps of
+    /// the same context.
+    pub start: u64,
+
+    /// The GPU timestamp at which the commands of the scope completed, in
+    /// nanoseconds.
+    pub end: u64,
+
+    /// Whether the GPU went through a disjoint operation, such as a change
+    /// of its clock frequency or a power event, while the scope was timed,
+    /// in which case the timestamps can't be trusted.
+    ///
+    /// Only drivers supporting `GL_EXT_disjoint_timer_query`, mostly OpenGL
+    /// ES ones, report these operations.
+    pub disjoint: bool,
+}
+
+impl GpuTiming {
+    /// The time the GPU spent between the start and the end of the scope.
+    pub fn duration(&self) -> Duration {
+        let nanos = self.end.saturating_sub(self.start);
+        Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+    }
+}
+
+/// A scope whose start was recorded.
+struct OpenScope {
+    label: String,
+    start: c_uint,
+    disjoint: bool,
+}
+
+/// A scope whose end was recorded, waiting for the GPU to reach it.
+struct PendingScope {
+    label: String,
+    depth: usize,
+    start: c_uint,
+    end: c_uint,
+    disjoint: bool,
+}
+
+/// Measures the GPU time spent on the commands of a context, with
+/// timestamp queries, without stalling the CPU.
+///
+/// Scopes are recorded with `begin` and `end`, or `scope`, and may be
+/// nested. Their timings become available a few frames later, once the GPU
+/// has executed them, and are retrieved with `poll`, in the order the
+/// scopes ended.
+///
+/// Query objects aren't shared between contexts, so a timer belongs to the
+/// context it was created with, and all its methods must be given that
+/// context. They return `ContextError::NotCurrent` if it isn't current, so
+/// that with several windows, each with their own context and timer, the
+/// render cost of each of them can be measured while switching between
+/// them with `make_current`.
+///
+/// Requires OpenGL 3.3, `GL_ARB_timer_query`, or
+/// `GL_EXT_disjoint_timer_query` on OpenGL ES. The latter also reports when
+/// timings can't be trusted, see `GpuTiming::disjoint`.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// # use glutin::ContextTrait;
+/// # fn main() {
+/// # let el = glutin::EventsLoop::new();
+/// # let wb = glutin::WindowBuilder::new();
+/// # let context = glutin::ContextBuilder::new()
+/// #     .build_combined(wb, &el)
+/// #     .unwrap();
+/// # unsafe { context.make_current().unwrap() };
+/// let mut timer = glutin::GpuTimer::new(context.context()).unwrap();
+///
+/// loop {
+///     timer
+///         .scope(context.context(), "scene", || {
+///             // draw the scene
+///         })
+///         .unwrap();
+///     context.swap_buffers().unwrap();
+///
+///     for timing in timer.poll(context.context()).unwrap() {
+///         if !timing.disjoint {
+///             println!("{}: {:?}", timing.label, timing.duration());
+///         }
+///     }
+/// }
+/// # }
+/// ```
+pub struct GpuTimer {
+    reports_disjoint: bool,
+    /// Query objects that can be reused.
+    free: Vec<c_uint>,
+    /// The scopes that began, the innermost last.
+    open: Vec<OpenScope>,
+    /// The scopes that ended, the oldest first.
+    pending: VecDeque<PendingScope>,
+}
+
+impl GpuTimer {
+    /// Creates a timer for `context`, which must be current.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context doesn't
+    /// support timestamp queries.
+    pub fn new(context: &Context) -> Result<GpuTimer, ContextError> {
+        let gl = context.load_gl::<TimerGl>()?;
+        let (supported, reports_disjoint) = unsafe { gl.support() };
+        if !supported {
+            return Err(ContextError::FunctionUnavailable);
+        }
+        if reports_disjoint {
+            // clears what happened before the timer existed
+            unsafe { gl.take_disjoint() };
+        }
+        Ok(GpuTimer {
+            reports_disjoint,
+            free: Vec::new(),
+            open: Vec::new(),
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Begins a scope named `label`, inside the current one if any.
+    pub fn begin<S: Into<String>>(
+        &mut self,
+        context: &Context,
+        label: S,
+    ) -> Result<(), ContextError> {
+        let gl = context.load_gl::<TimerGl>()?;
+        let start = unsafe { self.record(&gl) };
+        self.open.push(OpenScope {
+            label: label.into(),
+            start,
+            disjoint: false,
+        });
+        Ok(())
+    }
+
+    /// Ends the innermost scope.
+    ///
+    /// Returns `ContextError::OsError` if no scope began.
+    pub fn end(&mut self, context: &Context) -> Result<(), ContextError> {
+        let gl = context.load_gl::<TimerGl>()?;
+        let scope = match self.open.pop() {
+            Some(scope) => scope,
+            None => {
+                return Err(ContextError::OsError(
+                    "no GPU timer scope to end".to_string(),
+                ))
+            }
+        };
+        let end = unsafe { self.record(&gl) };
+        self.pending.push_back(PendingScope {
+            label: scope.label,
+            depth: self.open.len(),
+            start: scope.start,
+            end,
+            disjoint: scope.disjoint,
+        });
+        Ok(())
+    }
+
+    /// Times the commands issued by `f` in a scope named `label`.
+    pub fn scope<S, F, R>(
+        &mut self,
+        context: &Context,
+        label: S,
+        f: F,
+    ) -> Result<R, ContextError>
+    where
+        S: Into<String>,
+        F: FnOnce() -> R,
+    {
+        self.begin(context, label)?;
+        let ret = f();
+        self.end(context)?;
+        Ok(ret)
+    }
+
+    /// Returns the timings of the scopes the GPU is done with, without
+    /// waiting for the others.
+    pub fn poll(
+        &mut self,
+        context: &Context,
+    ) -> Result<Vec<GpuTiming>, ContextError> {
+        let gl = context.load_gl::<TimerGl>()?;
+        let get_query_objectiv = gl.get_query_objectiv.unwrap();
+        let get_query_objectui64v = gl.get_query_objectui64v.unwrap();
+
+        unsafe {
+            // the flag covers everything since it was last read, including
+            // the scopes that are still open
+            if self.reports_disjoint && gl.take_disjoint() {
+                for scope in &mut self.pending {
+                    scope.disjoint = true;
+                }
+                for scope in &mut self.open {
+                    scope.disjoint = true;
+                }
+            }
+
+            let mut timings = Vec::new();
+            while let Some(scope) = self.pending.pop_front() {
+                // the queries complete in order, so the start of the scope is
+                // available once its end is
+                let mut available = 0;
+                get_query_objectiv(
+                    scope.end,
+                    GL_QUERY_RESULT_AVAILABLE,
+                    &mut available,
+                );
+                if available == 0 {
+                    self.pending.push_front(scope);
+                    break;
+                }
+
+                let (mut start, mut end) = (0, 0);
+                get_query_objectui64v(scope.start, GL_QUERY_RESULT, &mut start);
+                get_query_objectui64v(scope.end, GL_QUERY_RESULT, &mut end);
+                self.free.push(scope.start);
+                self.free.push(scope.end);
+                timings.push(GpuTiming {
+                    label: scope.label,
+                    depth: scope.depth,
+                    start,
+                    end,
+                    disjoint: scope.disjoint,
+                });
+            }
+            Ok(timings)
+        }
+    }
+
+    /// Deletes the query objects of the timer, discarding the scopes that
+    /// weren't polled.
+    ///
+    /// Dropping the timer instead leaks them until the context is
+    /// destroyed, since it can't know whether its context is current.
+    pub fn delete(mut self, context: &Context) -> Result<(), ContextError> {
+        let gl = context.load_gl::<TimerGl>()?;
+        let mut queries: Vec<_> = self.free.drain(..).collect();
+        queries.extend(self.open.drain(..).map(|scope| scope.start));
+        for scope in self.pending.drain(..) {
+            queries.push(scope.start);
+            queries.push(scope.end);
+        }
+        unsafe {
+            gl.delete_queries.unwrap()(
+                queries.len() as c_int,
+                queries.as_ptr(),
+            );
+        }
+        Ok(())
+    }
+
+    /// Records the current GPU timestamp in a query object.
+    unsafe fn record(&mut self, gl: &TimerGl) -> c_uint {
+        let query = match self.free.pop() {
+            Some(query) => query,
+            None => {
+                let mut query = 0;
+                gl.gen_queries.unwrap()(1, &mut query);
+                query
+            }
+        };
+        gl.query_counter.unwrap()(query, GL_TIMESTAMP);
+        query
+    }
+}
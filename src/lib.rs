@@ -25,6 +25,16 @@
 //! You can, of course, create an OpenGL `Context` separately from an existing
 //! window, however that may result in an suboptimal configuration of the window
 //! on some platforms. In that case use "SeparatedContext".
+//!
+//! # Shipping an EGL implementation
+//!
+//! With the `bundled-egl` feature, glutin looks for an EGL implementation
+//! next to the executable, such as ANGLE or SwiftShader, before the one of
+//! the system. The libraries must be named `libEGL` and `libGLESv2` with the
+//! extension of the platform, and may also be in the `Frameworks` directory
+//! of an application bundle on macOS. When they are found, contexts are
+//! built with them first, even on X11, Windows and macOS where GLX, WGL and
+//! NSOpenGL are otherwise preferred.
 
 #[macro_use]
 extern crate lazy_static;
@@ -51,8 +61,12 @@ extern crate core_foundation;
 #[cfg(target_os = "macos")]
 extern crate core_graphics;
 extern crate libc;
+#[cfg(feature = "serde")]
+#[macro_use]
+extern crate serde;
 #[cfg(any(
     target_os = "windows",
+    target_os = "macos",
     target_os = "linux",
     target_os = "dragonfly",
     target_os = "freebsd",
@@ -79,18 +93,55 @@ extern crate winit;
 extern crate x11_dl;
 
 mod api;
+pub mod capi;
+mod capture;
 mod combined;
 mod context;
+mod damage;
+mod driver;
+mod egl_debug;
+mod gl;
+mod gpu_timer;
+mod memory;
+mod offline;
 mod platform;
+mod pool;
+mod probe;
+mod reload;
+mod resolve;
 mod separated;
+mod strict;
+mod virtual_context;
 
 pub mod os;
 
-pub use combined::CombinedContext;
-pub use context::Context;
+pub use capture::{PendingScreenshot, Screenshot};
+pub use combined::{teardown, CombinedContext};
+pub use context::{
+    Context, ContextId, CurrentGuard, DisplayToken, ShareGroupId,
+};
+pub use damage::DamageTracker;
+pub use driver::{
+    DriverInfo, SwapIntervalCaps, SwapIntervalQuirk, SwapIntervalReliability,
+};
+pub use egl_debug::{
+    remove_egl_debug_callback, set_egl_debug_callback, EglDebugMessage,
+    EglMessageType,
+};
+pub use gpu_timer::{GpuTimer, GpuTiming};
+pub use memory::MemoryInfo;
+pub use offline::{FrameTime, OfflineRenderer};
+pub use pool::{ContextPool, PooledContext};
+pub use probe::{probe, Backend, BackendInfo};
+pub use reload::{preload, reload_symbols};
+pub use resolve::MultisampleResolver;
 pub use separated::SeparatedContext;
+pub use virtual_context::{
+    VirtualContext, VirtualContextHost, VirtualContextStats,
+};
 
 use std::io;
+use std::sync::Arc;
 pub use winit::{
     dpi, AvailableMonitorsIter, AxisId, ButtonId, ControlFlow,
     CreationError as WindowCreationError, DeviceEvent, DeviceId, ElementState,
@@ -112,13 +163,52 @@ where
     fn is_current(&self) -> bool;
 
     /// Returns the address of an OpenGL function.
+    ///
+    /// Both core and extension functions can be queried. Core functions are
+    /// looked up in `opengl32.dll` with WGL, and in the client library
+    /// (`libGL` or `libGLESv2`) with EGL versions older than 1.5 that don't
+    /// support `EGL_KHR_get_all_proc_addresses`.
+    ///
+    /// Returns null if the function isn't found. Some platforms return
+    /// addresses for functions the context doesn't support, so check the
+    /// version and extensions of the context before calling them.
     fn get_proc_address(&self, addr: &str) -> *const ();
 
     /// Returns the OpenGL API being used.
     fn get_api(&self) -> Api;
 }
 
+/// OpenGL bindings that can be loaded by `Context::load_gl`.
+///
+/// This is meant to be implemented for the struct generated by
+/// `gl_generator`'s `StructGenerator`:
+///
+/// ```ignore
+/// unsafe impl glutin::GlLoader for gl::Gl {
+///     fn load_with<F>(loadfn: F) -> Self
+///     where
+///         F: FnMut(&'static str) -> *const std::os::raw::c_void,
+///     {
+///         gl::Gl::load_with(loadfn)
+///     }
+/// }
+/// ```
+///
+/// # Safety
+///
+/// The bindings are cached by the context, which can be shared between
+/// threads, so they must be safe to use from any thread. This is the case of
+/// bindings that only hold function pointers.
+pub unsafe trait GlLoader: Clone + 'static {
+    /// Loads the bindings, calling `loadfn` to get the address of each
+    /// function.
+    fn load_with<F>(loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const std::os::raw::c_void;
+}
+
 /// Object that allows you to build `Context`s.
+#[derive(Clone)]
 pub struct ContextBuilder<'a> {
     /// The attributes to use to create the context.
     pub gl_attr: GlAttributes<&'a Context>,
@@ -135,6 +225,30 @@ impl<'a> ContextBuilder<'a> {
         }
     }
 
+    /// Initializes a `ContextBuilder` for a context that only dispatches
+    /// compute shaders and never presents anything.
+    ///
+    /// The context has no depth, stencil or multisample buffers, is created
+    /// with `Robustness::NoError` when the debug flag isn't set, and vsync
+    /// is left alone. Build it with `build_compute` to avoid creating an
+    /// events loop, or with `build_headless`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// With EGL, no surface is created if `EGL_KHR_surfaceless_context` is
+    /// supported, along with `GL_OES_surfaceless_context` for OpenGL ES
+    /// contexts, and a 1x1 pbuffer is used otherwise. GLX, WGL and CGL
+    /// contexts are built as usual but skip setting up the swap interval.
+    pub fn for_compute() -> Self {
+        let mut builder = ContextBuilder::new()
+            .with_depth_buffer(0)
+            .with_stencil_buffer(0)
+            .with_multisampling(0)
+            .with_gl_robustness(Robustness::NoError);
+        builder.pf_reqs.compute_only = true;
+        builder
+    }
+
     /// Sets how the backend should choose the OpenGL API and version.
     #[inline]
     pub fn with_gl(mut self, request: GlRequest) -> Self {
@@ -149,6 +263,18 @@ impl<'a> ContextBuilder<'a> {
         self
     }
 
+    /// Sets whether the OpenGL context should be forward-compatible, removing
+    /// the functionality deprecated by the requested version.
+    ///
+    /// The default value is `false`. This only has an effect on desktop
+    /// OpenGL 3.0 and above. On macOS, core profile contexts are always
+    /// forward-compatible.
+    #[inline]
+    pub fn with_gl_forward_compatible(mut self, flag: bool) -> Self {
+        self.gl_attr.forward_compatible = flag;
+        self
+    }
+
     /// Sets the *debug* flag for the OpenGL context.
     ///
     /// The default value for this flag is `cfg!(debug_assertions)`, which means
@@ -176,6 +302,15 @@ impl<'a> ContextBuilder<'a> {
         self
     }
 
+    /// Sets what vsync waits for, when enabled.
+    ///
+    /// The default is `VsyncSource::Driver`.
+    #[inline]
+    pub fn with_vsync_source(mut self, source: VsyncSource) -> Self {
+        self.pf_reqs.vsync_source = source;
+        self
+    }
+
     /// Share the display lists with the given `Context`.
     #[inline]
     pub fn with_shared_lists(mut self, other: &'a Context) -> Self {
@@ -183,21 +318,42 @@ impl<'a> ContextBuilder<'a> {
         self
     }
 
-    /// Sets the multisampling level to request. A value of `0` indicates that
-    /// multisampling must not be enabled.
+    /// Sets the multisampling level to request. A value of `0` or `1`
+    /// indicates that multisampling must not be enabled, since a single
+    /// sample per pixel isn't multisampling.
     ///
     /// # Panic
     ///
-    /// Will panic if `samples` is not a power of two.
+    /// Will panic if `samples` is neither `0` nor a power of two.
     #[inline]
     pub fn with_multisampling(mut self, samples: u16) -> Self {
-        self.pf_reqs.multisampling = match samples {
-            0 => None,
-            _ => {
-                assert!(samples.is_power_of_two());
-                Some(samples)
-            }
-        };
+        assert!(samples == 0 || samples.is_power_of_two());
+        self.pf_reqs.multisampling = Some(samples);
+        self.pf_reqs.coverage_samples = None;
+        self
+    }
+
+    /// Requests coverage sampling (CSAA on NVIDIA, EQAA on AMD), which
+    /// stores `color` samples per pixel but resolves the coverage of
+    /// primitives with `coverage` samples. For example, 8x CSAA is 4 color
+    /// samples and 8 coverage samples.
+    ///
+    /// This relies on `GLX_NV_multisample_coverage`,
+    /// `WGL_NV_multisample_coverage` or `EGL_NV_coverage_sample`. Without
+    /// them, regular multisampling with `color` samples is requested
+    /// instead. `PixelFormat::coverage_samples` tells which one was
+    /// obtained.
+    ///
+    /// # Panic
+    ///
+    /// Will panic if `color` or `coverage` is not a power of two, or if
+    /// `coverage` is smaller than `color`.
+    #[inline]
+    pub fn with_coverage_samples(mut self, color: u16, coverage: u16) -> Self {
+        assert!(color.is_power_of_two() && coverage.is_power_of_two());
+        assert!(coverage >= color);
+        self.pf_reqs.multisampling = Some(color);
+        self.pf_reqs.coverage_samples = Some(coverage);
         self
     }
 
@@ -233,6 +389,15 @@ impl<'a> ContextBuilder<'a> {
     /// Sets whether sRGB should be enabled on the window.
     ///
     /// The default value is `false`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// With GLX and WGL, only sRGB-capable pixel formats are considered.
+    /// With EGL, the color space is an attribute of the surface rather than
+    /// of the config, so the config is chosen regardless and the window or
+    /// pbuffer surface is created with `EGL_GL_COLORSPACE_SRGB` when EGL 1.5
+    /// or `EGL_KHR_gl_colorspace` is available. `PixelFormat::srgb` tells
+    /// whether it was.
     #[inline]
     pub fn with_srgb(mut self, srgb_enabled: bool) -> Self {
         self.pf_reqs.srgb = srgb_enabled;
@@ -250,12 +415,416 @@ impl<'a> ContextBuilder<'a> {
     ///   * MacOS
     ///   * Linux using GLX with X
     ///   * Windows using WGL
+    ///   * Linux, Windows and Android using EGL, where single buffering is
+    ///     requested through `EGL_RENDER_BUFFER` or, when available,
+    ///     `EGL_KHR_mutable_render_buffer`
     #[inline]
     pub fn with_double_buffer(mut self, double_buffer: Option<bool>) -> Self {
         self.pf_reqs.double_buffer = double_buffer;
         self
     }
 
+    /// Sets the framebuffer level to render to. `0` is the main plane,
+    /// positive levels are overlay planes drawn above it and negative levels
+    /// underlay planes drawn below it.
+    ///
+    /// The default value is `0`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option will be taken into account on the following platforms:
+    ///
+    ///   * Linux using GLX with X, through `GLX_LEVEL`
+    ///   * Windows using WGL, through `WGL_ARB_create_context`
+    ///   * Linux, Windows and Android using EGL, through `EGL_LEVEL`
+    ///
+    /// Elsewhere, building a context on another plane than the main one
+    /// fails with `CreationError::NotSupported`. Few drivers expose other
+    /// planes, so `CreationError::NoAvailablePixelFormat` is the usual
+    /// outcome even on those platforms.
+    #[inline]
+    pub fn with_plane(mut self, plane: i32) -> Self {
+        self.pf_reqs.plane = plane;
+        self
+    }
+
+    /// Sets the kind of transparency the framebuffer config must have, for
+    /// overlay planes chosen with `with_plane` that show the planes below
+    /// them through some of their pixels. The transparent value of the
+    /// config is given by `PixelFormat::transparent`.
+    ///
+    /// The default value is `TransparentType::None`, which only selects
+    /// opaque configs.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option will be taken into account on the following platforms:
+    ///
+    ///   * Linux using GLX with X, through `GLX_TRANSPARENT_TYPE`
+    ///   * Linux, Windows and Android using EGL, through
+    ///     `EGL_TRANSPARENT_TYPE`, which has no `Index` type
+    ///
+    /// Elsewhere, requiring a transparent config fails with
+    /// `CreationError::NotSupported`.
+    #[inline]
+    pub fn with_transparent_type(
+        mut self,
+        transparent_type: TransparentType,
+    ) -> Self {
+        self.pf_reqs.transparent_type = transparent_type;
+        self
+    }
+
+    /// Sets whether the native rendering API of the platform must be able
+    /// to render to the surface, such as to mix the core drawing requests of
+    /// X11 with OpenGL in the same window. `PixelFormat::native_renderable`
+    /// tells whether the config chosen supports it.
+    ///
+    /// The default value is `None`, which accepts any config.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option will be taken into account on the following platforms:
+    ///
+    ///   * Linux, Windows and Android using EGL, through
+    ///     `EGL_NATIVE_RENDERABLE`
+    ///
+    /// GLX always requires `GLX_X_RENDERABLE`, whose configs X11 can render
+    /// to, so requiring `Some(false)` never finds a config there.
+    #[inline]
+    pub fn with_native_renderable(mut self, renderable: Option<bool>) -> Self {
+        self.pf_reqs.native_renderable = renderable;
+        self
+    }
+
+    /// Sets the class of X11 visual the framebuffer config must have, such
+    /// as `X11VisualType::DirectColor` for applications loading their own
+    /// colormaps. `PixelFormat::x11_visual_type` gives the class of the
+    /// config chosen.
+    ///
+    /// The default value is `None`, which selects `TrueColor` visuals with
+    /// GLX, and any visual with EGL.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option will be taken into account on the following platforms:
+    ///
+    ///   * Linux using GLX with X, through `GLX_X_VISUAL_TYPE`
+    ///   * Linux using EGL with X, through `EGL_NATIVE_VISUAL_TYPE`
+    #[inline]
+    pub fn with_x11_visual_type(
+        mut self,
+        visual_type: Option<X11VisualType>,
+    ) -> Self {
+        self.pf_reqs.x11_visual_type = visual_type;
+        self
+    }
+
+    /// Sets the behavior of the context when it stops being current.
+    ///
+    /// The default value is `ReleaseBehavior::Flush`. If the platform can't
+    /// honor `ReleaseBehavior::None`, the context is created with
+    /// `ReleaseBehavior::Flush` instead, which can be checked with
+    /// `Context::get_release_behavior`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option will be taken into account on the following platforms:
+    ///
+    ///   * Linux using GLX with X or using EGL
+    ///   * Windows using WGL or EGL
+    ///   * Android using EGL
+    ///
+    /// On macOS, requesting `ReleaseBehavior::None` makes context creation
+    /// fail.
+    #[inline]
+    pub fn with_release_behavior(mut self, behavior: ReleaseBehavior) -> Self {
+        self.pf_reqs.release_behavior = behavior;
+        self
+    }
+
+    /// Sets which workarounds for known driver bugs glutin may apply when
+    /// creating the context.
+    ///
+    /// Every workaround is allowed by default. Workarounds are only applied
+    /// when the bug they work around is detected, and the ones that were
+    /// applied can be checked with `Context::get_driver_workarounds`.
+    #[inline]
+    pub fn with_driver_workarounds(
+        mut self,
+        workarounds: DriverWorkarounds,
+    ) -> Self {
+        self.pf_reqs.driver_workarounds = workarounds;
+        self
+    }
+
+    /// Sets whether the pixel format must support preserving the contents of
+    /// the color buffer across swaps.
+    ///
+    /// This is required to later call `Context::set_swap_behavior` with
+    /// `SwapBehavior::Preserved`.
+    ///
+    /// The default value is `false`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option will be taken into account on the following platforms:
+    ///
+    ///   * Linux using EGL with either X or Wayland
+    ///   * Windows using EGL
+    ///   * Android using EGL
+    #[inline]
+    pub fn with_swap_behavior_preserved(mut self, preserved: bool) -> Self {
+        self.pf_reqs.swap_behavior_preserved = preserved;
+        self
+    }
+
+    /// Sets whether the pixel format must let the window surface switch
+    /// between single and double buffering after its creation, with
+    /// `EGL_KHR_mutable_render_buffer`.
+    ///
+    /// This is required to later call `Context::set_render_buffer`, such as
+    /// to render to the front buffer in the low latency loops of VR.
+    /// Context creation fails with `CreationError::NotSupported` if the
+    /// extension is unavailable.
+    ///
+    /// The default value is `false`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option will be taken into account on the following platforms:
+    ///
+    ///   * Linux using EGL with either X or Wayland
+    ///   * Windows using EGL
+    ///   * Android using EGL
+    #[inline]
+    pub fn with_mutable_render_buffer(mut self, mutable: bool) -> Self {
+        self.pf_reqs.mutable_render_buffer = mutable;
+        self
+    }
+
+    /// Sets whether `resize` makes the context current again after resizing
+    /// it, when it is current on the calling thread.
+    ///
+    /// Some drivers only pick the new size of the drawable up when the
+    /// context is bound again, and keep rendering at the previous size until
+    /// then. This performs the whole sequence the backend needs instead of
+    /// leaving it to the caller.
+    ///
+    /// The default value is `false`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **macOS:** the `NSOpenGLContext` is updated, then made current
+    ///   again.
+    /// - **Wayland:** the `wl_egl_window` is resized, then the EGL context
+    ///   is made current again, which some versions of Mesa need.
+    /// - **Others:** the context is made current again, which is harmless.
+    #[inline]
+    pub fn with_remake_current_on_resize(mut self, remake: bool) -> Self {
+        self.pf_reqs.remake_current_on_resize = remake;
+        self
+    }
+
+    /// Sets whether `build_combined` also builds a hidden context sharing
+    /// the objects of the window's context, returned by
+    /// `CombinedContext::upload_context`.
+    ///
+    /// The upload context is built with the same requirements as the
+    /// window's context, which keeps them compatible for sharing on every
+    /// backend, but without a window or vsync. It is meant to be made
+    /// current on another thread to upload textures and buffers while the
+    /// window's context renders. Objects created by one context are only
+    /// guaranteed to be visible to the other after a fence, or a flush
+    /// followed by a rebind.
+    ///
+    /// The default value is `false`. Other builders ignore this option.
+    #[inline]
+    pub fn with_upload_context(mut self, upload: bool) -> Self {
+        self.pf_reqs.upload_context = upload;
+        self
+    }
+
+    /// Sets whether glutin chooses the config itself, with rules that don't
+    /// depend on the driver.
+    ///
+    /// Some drivers omit configs from the results of `eglChooseConfig` or
+    /// sort them wrongly, notably by alpha or sample count. With strict
+    /// selection, every config of the display is listed with
+    /// `eglGetConfigs` and filtered with the matching rules of the EGL
+    /// specification. The one picked has no caveat if possible, then the
+    /// fewest samples and the fewest color, alpha, depth and stencil bits
+    /// meeting the requirements, and the smallest `EGL_CONFIG_ID` among
+    /// equals, so the same request gives the same kind of config everywhere.
+    ///
+    /// The default value is `false`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option is only taken into account with EGL.
+    #[inline]
+    pub fn with_strict_selection(mut self, strict: bool) -> Self {
+        self.pf_reqs.strict_selection = strict;
+        self
+    }
+
+    /// Sets whether a surface the driver has no memory for is created with
+    /// lesser requirements instead of failing, as happens on mobile devices
+    /// under memory pressure.
+    ///
+    /// When creating the surface fails with `EGL_BAD_ALLOC`, multisampling
+    /// is dropped first, then the depth and stencil buffers are made
+    /// smaller, one config at a time, and a pbuffer is finally halved until
+    /// it fits. The color buffer is never changed. What had to be given up
+    /// is returned by `Context::get_surface_downgrades`.
+    ///
+    /// The default value is `false`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option is only taken into account with EGL.
+    #[inline]
+    pub fn with_downgrade_on_alloc_failure(mut self, downgrade: bool) -> Self {
+        self.pf_reqs.downgrade_on_alloc_failure = downgrade;
+        self
+    }
+
+    /// Sets whether the pbuffer of a headless context can be bound to a
+    /// texture, for render-to-texture on old drivers without framebuffer
+    /// objects.
+    ///
+    /// The config must then support binding pbuffers to textures, so this is
+    /// only meant for headless contexts. The pbuffer is bound with
+    /// `Context::bind_pbuffer_to_texture`, and the mipmap level rendered to
+    /// is chosen with `Context::set_pbuffer_mipmap_level`.
+    ///
+    /// The default value is `None`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option is only taken into account with EGL, whose pbuffers can
+    /// only be bound to 2D textures. Headless contexts are then never built
+    /// without a pbuffer.
+    #[inline]
+    pub fn with_pbuffer_texture(
+        mut self,
+        texture: Option<PbufferTexture>,
+    ) -> Self {
+        self.pf_reqs.pbuffer_texture = texture;
+        self
+    }
+
+    /// Adds an attribute to the ones the config of the context is chosen
+    /// with, for the attributes of EGL extensions that glutin doesn't
+    /// expose, such as `EGL_RECORDABLE_ANDROID`.
+    ///
+    /// The value replaces the one glutin would have given the attribute.
+    /// Building the context fails if the display doesn't support the
+    /// attribute.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option is only taken into account with EGL.
+    #[inline]
+    pub fn with_egl_config_attrib(mut self, attrib: u32, value: i32) -> Self {
+        self.pf_reqs.egl_config_attribs.push((attrib, value));
+        self
+    }
+
+    /// Adds an attribute to the ones the window surface of the context is
+    /// created with, such as `EGL_RENDER_BUFFER` or the attributes of EGL
+    /// extensions that glutin doesn't expose.
+    ///
+    /// The value replaces the one glutin would have given the attribute.
+    /// Headless contexts ignore this option.
+    ///
+    /// ## Platform-specific
+    ///
+    /// This option is only taken into account with EGL.
+    #[inline]
+    pub fn with_egl_surface_attrib(mut self, attrib: u32, value: i32) -> Self {
+        self.pf_reqs.egl_surface_attribs.push((attrib, value));
+        self
+    }
+
+    /// Sets a function that picks the config to build the context with, for
+    /// rankings that the other requirements can't express, such as
+    /// preferring 16 bits of depth on mobile GPUs.
+    ///
+    /// The function is given the descriptions of the configs meeting the
+    /// other requirements, ordered from the best match according to the
+    /// backend. It returns the index of the one to use, or `None` to reject
+    /// all of them, in which case building the context fails with
+    /// `CreationError::NoAvailablePixelFormat` unless the backend can try
+    /// other requirements. It isn't called when no config matches.
+    ///
+    /// # Panic
+    ///
+    /// Building the context panics if the function returns an index out of
+    /// bounds.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **EGL and GLX:** every matching config is given, along with strict
+    ///   selection.
+    /// - **WGL:** every matching format is given with
+    ///   `WGL_ARB_pixel_format`, otherwise only the one chosen by
+    ///   `ChoosePixelFormat`.
+    /// - **macOS:** only the format chosen by CGL or NSOpenGL is given.
+    /// - **OSMesa, iOS and Emscripten:** the function isn't called.
+    #[inline]
+    pub fn with_selection_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&[PixelFormat]) -> Option<usize> + Send + Sync + 'static,
+    {
+        self.pf_reqs.selection_hook = Some(SelectionHook::new(hook));
+        self
+    }
+
+    /// Requests the pixel format described by `format`, usually one that
+    /// `get_pixel_format` returned on a previous run, so that an
+    /// application can remember the format that worked for the user.
+    ///
+    /// The requirements are set to the attributes of `format`, and the
+    /// config is picked by a selection hook that prefers the one with the
+    /// same attributes and `native_id`, then one with the same attributes,
+    /// then the best match. This replaces the function given to
+    /// `with_selection_hook`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// The configs are only told apart where the selection hook is called,
+    /// see `with_selection_hook`. Elsewhere, only the requirements are
+    /// taken into account.
+    #[inline]
+    pub fn with_saved_pixel_format(mut self, format: &PixelFormat) -> Self {
+        self.pf_reqs.hardware_accelerated = Some(format.hardware_accelerated);
+        self.pf_reqs.color_bits = Some(format.color_bits);
+        self.pf_reqs.alpha_bits = Some(format.alpha_bits);
+        self.pf_reqs.depth_bits = Some(format.depth_bits);
+        self.pf_reqs.stencil_bits = Some(format.stencil_bits);
+        self.pf_reqs.stereoscopy = format.stereoscopy;
+        self.pf_reqs.double_buffer = Some(format.double_buffer);
+        self.pf_reqs.multisampling = Some(format.multisampling.unwrap_or(0));
+        self.pf_reqs.coverage_samples = format.coverage_samples;
+        self.pf_reqs.srgb = format.srgb;
+        self.pf_reqs.native_renderable = Some(format.native_renderable);
+        self.pf_reqs.x11_visual_type = format.x11_visual_type;
+
+        let saved = format.clone();
+        self.with_selection_hook(move |formats| {
+            let same_attributes = |format: &PixelFormat| PixelFormat {
+                native_id: saved.native_id,
+                ..format.clone()
+            } == saved;
+            formats
+                .iter()
+                .position(|format| *format == saved)
+                .or_else(|| formats.iter().position(same_attributes))
+                .or(Some(0))
+        })
+    }
+
     /// Sets whether hardware acceleration is required.
     ///
     /// The default value is `Some(true)`
@@ -285,6 +854,25 @@ impl<'a> ContextBuilder<'a> {
         Context::new(el, self)
     }
 
+    /// Builds a headless context without an events loop, so that it can be
+    /// created from any thread. Meant for builders made with `for_compute`.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Linux:** requires EGL, with `EGL_MESA_platform_surfaceless` to
+    ///   run without a display server. GLX needs an X11 connection, so it is
+    ///   only available through `build_headless`.
+    /// - **Windows:** requires EGL, such as ANGLE. WGL needs a window, so it
+    ///   is only available through `build_headless`.
+    /// - **macOS:** builds a context with CGL alone, which needs neither
+    ///   Cocoa nor the main thread, like `build_headless`. It has no default
+    ///   framebuffer, so it renders to framebuffer objects.
+    /// - **Android:** builds an EGL context, like `build_headless`.
+    /// - **iOS and Emscripten:** returns `CreationError::NotSupported`.
+    pub fn build_compute(self) -> Result<Context, CreationError> {
+        Context::new_compute(self)
+    }
+
     /// Builds a context and it's associated window.
     pub fn build_combined(
         self,
@@ -313,9 +901,14 @@ pub enum CreationError {
     NoBackendAvailable(Box<std::error::Error + Send>),
     RobustnessNotSupported,
     OpenGlVersionNotSupported,
+    /// The requested OpenGL profile or forward-compatibility isn't available.
+    ProfileNotSupported,
     NoAvailablePixelFormat,
     PlatformSpecific(String),
     Window(WindowCreationError),
+    /// The context to share objects with was created on another backend or
+    /// display. See `DisplayToken`.
+    IncompatibleSharing,
     /// We received two errors, instead of one.
     CreationErrorPair(Box<CreationError>, Box<CreationError>),
 }
@@ -332,12 +925,17 @@ impl CreationError {
             CreationError::OpenGlVersionNotSupported => {
                 "The requested OpenGL version is not supported."
             }
+            CreationError::ProfileNotSupported => {
+                "The requested OpenGL profile is not supported."
+            }
             CreationError::NoAvailablePixelFormat => {
                 "Couldn't find any pixel format that matches the criteria."
             }
             CreationError::PlatformSpecific(ref text) => &text,
-            CreationError::Window(ref err) => {
-                std::error::Error::description(err)
+            CreationError::Window(_) => "Couldn't create the window",
+            CreationError::IncompatibleSharing => {
+                "The context to share with was created on another backend or \
+                 display."
             }
             CreationError::CreationErrorPair(ref _err1, ref _err2) => {
                 "Received two errors."
@@ -362,9 +960,6 @@ impl std::fmt::Display for CreationError {
             write!(formatter, "\"")?;
         }
 
-        if let &CreationError::NotSupported(msg) = self {
-            write!(formatter, ": {}", msg)?;
-        }
         if let Some(err) = std::error::Error::source(self) {
             write!(formatter, ": {}", err)?;
         }
@@ -377,7 +972,7 @@ impl std::error::Error for CreationError {
         self.to_string()
     }
 
-    fn cause(&self) -> Option<&std::error::Error> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match *self {
             CreationError::NoBackendAvailable(ref err) => Some(&**err),
             CreationError::Window(ref err) => Some(err),
@@ -399,15 +994,30 @@ pub enum ContextError {
     OsError(String),
     IoError(io::Error),
     ContextLost,
+    /// The requested operation isn't available for this context or platform.
+    FunctionUnavailable,
+    /// The operation requires the context to be current on the calling
+    /// thread.
+    NotCurrent,
+    /// The connection to the display is gone, for example because the
+    /// Wayland compositor restarted or the GPU was unplugged.
+    ///
+    /// Every context and window of the events loop has to be dropped and
+    /// rebuilt along with a new `EventsLoop`. On X11, Xlib terminates the
+    /// process when the connection to the server is lost, so this is never
+    /// returned when using GLX.
+    DisplayLost,
 }
 
 impl ContextError {
     fn to_string(&self) -> &str {
-        use std::error::Error;
         match *self {
             ContextError::OsError(ref string) => string,
-            ContextError::IoError(ref err) => err.description(),
+            ContextError::IoError(_) => "I/O error",
             ContextError::ContextLost => "Context lost",
+            ContextError::FunctionUnavailable => "Function unavailable",
+            ContextError::NotCurrent => "Context not current",
+            ContextError::DisplayLost => "Display lost",
         }
     }
 }
@@ -417,7 +1027,11 @@ impl std::fmt::Display for ContextError {
         &self,
         formatter: &mut std::fmt::Formatter,
     ) -> Result<(), std::fmt::Error> {
-        formatter.write_str(self.to_string())
+        formatter.write_str(self.to_string())?;
+        if let Some(err) = std::error::Error::source(self) {
+            write!(formatter, ": {}", err)?;
+        }
+        Ok(())
     }
 }
 
@@ -425,6 +1039,13 @@ impl std::error::Error for ContextError {
     fn description(&self) -> &str {
         self.to_string()
     }
+
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match *self {
+            ContextError::IoError(ref err) => Some(err),
+            _ => None,
+        }
+    }
 }
 
 /// All APIs related to OpenGL that you can possibly get while using glutin.
@@ -540,9 +1161,495 @@ pub enum ReleaseBehavior {
     Flush,
 }
 
-/// Describes a possible format. Unused.
+/// Whether a context created by another library is destroyed along with the
+/// `Context` wrapping it.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Ownership {
+    /// The context is left alone when the `Context` is dropped, for its
+    /// creator to destroy afterwards.
+    Borrowed,
+
+    /// The context is destroyed along with the `Context`, like the ones
+    /// created by glutin.
+    Owned,
+}
+
+/// Workarounds for known driver bugs and limitations.
+///
+/// Each field tells whether a workaround is allowed when passed to
+/// `ContextBuilder::with_driver_workarounds`, or whether it was applied when
+/// returned by `Context::get_driver_workarounds`.
+///
+/// More workarounds may be added, so start from `DriverWorkarounds::none()`
+/// or `DriverWorkarounds::default()` and change the fields you need.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DriverWorkarounds {
+    /// EGL only: if creating the context fails with `EGL_BAD_ATTRIBUTE`,
+    /// try again without `EGL_CONTEXT_FLAGS_KHR`, as some implementations
+    /// (for example the Android emulator) advertise
+    /// `EGL_KHR_create_context` but reject this attribute.
+    ///
+    /// The flags are only dropped if they aren't required, that is when
+    /// neither a forward compatible context through
+    /// `EGL_KHR_create_context` nor mandatory robustness was requested.
+    pub egl_retry_without_context_flags: bool,
+
+    /// GLX only: if `GLX_ARB_create_context` isn't supported, as is the
+    /// case of some remote or old X servers, create the context with
+    /// `glXCreateNewContext` instead of failing, falling back to indirect
+    /// rendering if needed.
+    ///
+    /// The requested version, debug flag and optional robustness are then
+    /// ignored and the context gets whatever version the server provides.
+    pub glx_legacy_create_context: bool,
+
+    #[doc(hidden)]
+    pub __non_exhaustive: (),
+}
+
+impl DriverWorkarounds {
+    /// Returns a set with every workaround disabled.
+    #[inline]
+    pub fn none() -> Self {
+        DriverWorkarounds {
+            egl_retry_without_context_flags: false,
+            glx_legacy_create_context: false,
+            __non_exhaustive: (),
+        }
+    }
+}
+
+impl Default for DriverWorkarounds {
+    /// Returns a set with every workaround enabled.
+    #[inline]
+    fn default() -> Self {
+        DriverWorkarounds {
+            egl_retry_without_context_flags: true,
+            glx_legacy_create_context: true,
+            __non_exhaustive: (),
+        }
+    }
+}
+
+/// Describes how vsync waits for the vertical blank.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum VsyncSource {
+    /// The driver blocks when swapping the buffers, through the swap
+    /// interval.
+    Driver,
+
+    /// Swapping the buffers returns right away, then waits for the vertical
+    /// blank the compositor presents on, which prevents the driver and the
+    /// compositor from both waiting and causing stutter.
+    ///
+    /// ## Platform-specific
+    ///
+    /// Only supported on X11, through the Present extension. Falls back to
+    /// `Driver` if the extension isn't available and on other platforms.
+    /// The swap interval is left to the driver's default, which may need to
+    /// be disabled through the driver settings.
+    Compositor,
+}
+
+/// Describes which pixels of a framebuffer config are transparent, which
+/// overlay planes use to show the planes below them.
+///
+/// This is unrelated to the transparency of windows with an alpha channel,
+/// which is requested with `WindowBuilder::with_transparency`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TransparentType {
+    /// No pixel is transparent.
+    None,
+
+    /// The pixels of the transparent color are transparent.
+    Rgb,
+
+    /// The pixels of the transparent color index are transparent.
+    ///
+    /// Only GLX has color index configs with this type, and glutin only
+    /// builds RGBA contexts, so requiring it never finds a config.
+    Index,
+}
+
+/// The class of the X11 visual of a framebuffer config, which decides how
+/// its pixels are turned into colors.
+///
+/// Only the classes of RGB visuals are listed, since glutin only builds RGBA
+/// contexts.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum X11VisualType {
+    /// The red, green and blue values are used as they are.
+    TrueColor,
+
+    /// The red, green and blue values go through a colormap that can be
+    /// changed, such as for gamma correction.
+    DirectColor,
+}
+
+/// The transparent value of a framebuffer config, as queried from the
+/// driver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum TransparentPixel {
+    /// The pixels of this color are transparent.
+    Rgb {
+        red: u32,
+        green: u32,
+        blue: u32,
+    },
+
+    /// The pixels of this color index are transparent.
+    Index(u32),
+}
+
+/// The function wrapped by a `SelectionHook`.
+type SelectionFn = dyn Fn(&[PixelFormat]) -> Option<usize> + Send + Sync;
+
+/// Picks the config to build a context with among the ones meeting the
+/// requirements, as set with `ContextBuilder::with_selection_hook`.
+#[derive(Clone)]
+pub struct SelectionHook(Arc<SelectionFn>);
+
+impl SelectionHook {
+    /// Wraps `hook`, which returns the index of the chosen format or `None`
+    /// to reject all of them.
+    pub fn new<F>(hook: F) -> Self
+    where
+        F: Fn(&[PixelFormat]) -> Option<usize> + Send + Sync + 'static,
+    {
+        SelectionHook(Arc::new(hook))
+    }
+
+    /// Picks one of `candidates`, which must not be empty, with the configs
+    /// of the backend along with their descriptions.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the hook returns an index out of bounds.
+    pub(crate) fn select<C>(
+        &self,
+        mut candidates: Vec<(C, PixelFormat)>,
+    ) -> Option<(C, PixelFormat)> {
+        let formats = candidates
+            .iter()
+            .map(|candidate| candidate.1.clone())
+            .collect::<Vec<_>>();
+        let index = (self.0)(&formats)?;
+        assert!(
+            index < candidates.len(),
+            "the selection hook returned {} for {} configs",
+            index,
+            candidates.len()
+        );
+        Some(candidates.swap_remove(index))
+    }
+}
+
+impl std::fmt::Debug for SelectionHook {
+    fn fmt(
+        &self,
+        formatter: &mut std::fmt::Formatter,
+    ) -> Result<(), std::fmt::Error> {
+        formatter.write_str("SelectionHook")
+    }
+}
+
+/// Describes what happens to the contents of the color buffer after the
+/// buffers are swapped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SwapBehavior {
+    /// The contents of the color buffer are left untouched.
+    Preserved,
+
+    /// The contents of the color buffer may be destroyed or changed.
+    Destroyed,
+}
+
+/// The HDR metadata of the content of a surface, which the compositor or
+/// the display uses to map it to the capabilities of the display.
+///
+/// The chromaticity coordinates are in the CIE 1931 color space, and the
+/// luminances in candelas per square meter.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct HdrMetadata {
+    /// The red, green and blue primaries of the display the content was
+    /// mastered on, as defined by SMPTE ST 2086.
+    pub display_primaries: [(f32, f32); 3],
+    /// The white point of the mastering display.
+    pub white_point: (f32, f32),
+    /// The maximum luminance of the mastering display.
+    pub max_luminance: f32,
+    /// The minimum luminance of the mastering display.
+    pub min_luminance: f32,
+    /// The luminance of the brightest pixel of the content (MaxCLL), as
+    /// defined by CTA-861.3.
+    pub max_content_light_level: f32,
+    /// The highest average luminance of a frame of the content (MaxFALL).
+    pub max_frame_average_light_level: f32,
+}
+
+/// Describes which buffer the client API renders into.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum RenderBuffer {
+    /// Rendering goes to the back buffer, which is presented when the buffers
+    /// are swapped.
+    Back,
+
+    /// Rendering goes directly to the only buffer of the surface.
+    Single,
+}
+
+/// Describes the filter used to resolve a multisampled color buffer when the
+/// buffers are swapped.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MultisampleResolve {
+    /// Implementation-defined filter.
+    Default,
+
+    /// One-pixel-wide box filter, weighting all samples equally.
+    Box,
+}
+
+/// Properties of the surface a context renders to, as reported by the driver.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SurfaceInfo {
+    /// Width of the surface, in pixels.
+    pub width: u32,
+
+    /// Height of the surface, in pixels.
+    pub height: u32,
+
+    /// What happens to the color buffer after a swap. `None` if the backend
+    /// can't tell.
+    pub swap_behavior: Option<SwapBehavior>,
+
+    /// Which buffer is rendered into. `None` if the backend can't tell.
+    pub render_buffer: Option<RenderBuffer>,
+
+    /// How the color buffer is resolved on swap. `None` if the backend can't
+    /// tell.
+    pub multisample_resolve: Option<MultisampleResolve>,
+
+    /// Number of frames since the back buffer's contents were last presented,
+    /// with `0` meaning the contents are undefined.
+    ///
+    /// This is only known when the context is current and the
+    /// `EGL_EXT_buffer_age` or `GLX_EXT_buffer_age` extension is available,
+    /// otherwise it is `None`.
+    pub buffer_age: Option<u32>,
+
+    /// Whether the surface is a pbuffer that was created with the largest
+    /// available size.
+    pub largest_pbuffer: bool,
+}
+
+/// The largest pbuffers the config of a context allows, as reported by the
+/// driver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PbufferLimits {
+    /// Maximum width of a pbuffer, in pixels.
+    pub max_width: u32,
+
+    /// Maximum height of a pbuffer, in pixels.
+    pub max_height: u32,
+
+    /// Maximum number of pixels of a pbuffer, which may be less than
+    /// `max_width * max_height`.
+    pub max_pixels: u32,
+}
+
+/// How the pbuffer of a headless context can be bound to a texture, set with
+/// `ContextBuilder::with_pbuffer_texture`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PbufferTexture {
+    /// Whether the texture has an alpha channel, `EGL_TEXTURE_RGBA` rather
+    /// than `EGL_TEXTURE_RGB`.
+    pub alpha: bool,
+
+    /// Whether the pbuffer has room for mipmaps, whose level rendered to is
+    /// chosen with `Context::set_pbuffer_mipmap_level`.
+    pub mipmap: bool,
+}
+
+/// Whether the window a context renders to can be seen, so that rendering can
+/// be throttled or paused while it can't.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SurfaceVisibility {
+    /// At least part of the window may be visible.
+    Visible,
+    /// The window is open but can't be seen, for example because it is
+    /// covered by other windows, on another virtual desktop, or because the
+    /// compositor stopped showing its frames.
+    Occluded,
+    /// The window is minimized.
+    Minimized,
+}
+
+/// A rectangle of a surface, in pixels, with the origin at the bottom left
+/// corner as in OpenGL.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub struct Rect {
+    /// Horizontal position of the left edge.
+    pub x: u32,
+
+    /// Vertical position of the bottom edge.
+    pub y: u32,
+
+    /// Width of the rectangle.
+    pub width: u32,
+
+    /// Height of the rectangle.
+    pub height: u32,
+}
+
+/// The swap intervals a surface accepts, as reported by the driver.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct SwapIntervalRange {
+    /// The smallest number of vertical blanks to wait for between swaps. `0`
+    /// means that swaps aren't synchronized with the display.
+    pub min: u32,
+
+    /// The largest number of vertical blanks to wait for between swaps.
+    pub max: u32,
+
+    /// Whether adaptive vsync is supported, which swaps immediately instead
+    /// of waiting for the next vertical blank when a frame is late.
+    pub adaptive: bool,
+}
+
+/// The frames presented to the display, as reported by
+/// `Context::get_present_stats`.
+///
+/// The counters only grow, so the drops during a stretch of frames are the
+/// difference between the counters before and after it.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct PresentStats {
+    /// The number of refreshes of the display.
+    pub refresh_count: u64,
+
+    /// The number of frames that were displayed.
+    pub frames_displayed: u64,
+
+    /// The number of frames that were never displayed, because a newer frame
+    /// replaced them first.
+    pub frames_dropped: u64,
+
+    /// The number of refreshes that displayed an old frame again because the
+    /// new one was late, which shows as judder.
+    pub frames_missed: u64,
+}
+
+/// What `Context::set_swap_interval_with_policy` does with an interval
+/// outside the `SwapIntervalRange` of the surface.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SwapIntervalPolicy {
+    /// Returns `ContextError::OsError` without changing the interval.
+    Strict,
+
+    /// Uses the accepted interval closest to the one asked for, for example
+    /// `1` for `2` with the many drivers whose largest interval is `1`.
+    ClampToNearest,
+
+    /// Passes the interval to the driver as is, like `set_swap_interval`.
+    /// Most drivers then clamp it themselves.
+    Ignore,
+}
+
+/// A requirement given up to create the surface of a context the driver
+/// had no memory for, see `ContextBuilder::with_downgrade_on_alloc_failure`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SurfaceDowngrade {
+    /// Multisampling was disabled.
+    Multisampling {
+        /// The number of samples of the config given up.
+        samples: u16,
+    },
+
+    /// The depth and stencil buffers were made smaller, or removed.
+    DepthStencil {
+        /// The depth and stencil bits of the config given up.
+        requested: (u8, u8),
+        /// The depth and stencil bits of the config used instead.
+        obtained: (u8, u8),
+    },
+
+    /// The pbuffer was made smaller.
+    PbufferSize {
+        /// The size asked for.
+        requested: (u32, u32),
+        /// The size of the pbuffer created.
+        obtained: (u32, u32),
+    },
+}
+
+/// The gamma ramp of a monitor, which maps the intensities of the frames to
+/// the intensities shown, for each channel.
+///
+/// Entry `i` of a ramp of `n` entries is the intensity shown for the input
+/// intensity `i / (n - 1)`, from `0` to `65535`. The three channels have the
+/// same length, usually 256 entries, although some drivers use more.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GammaRamp {
+    /// The ramp of the red channel.
+    pub red: Vec<u16>,
+
+    /// The ramp of the green channel.
+    pub green: Vec<u16>,
+
+    /// The ramp of the blue channel.
+    pub blue: Vec<u16>,
+}
+
+impl GammaRamp {
+    /// Builds a ramp of `size` entries raising each intensity to the power
+    /// of `1 / gamma`, so that a gamma above `1.0` brightens the picture and
+    /// `1.0` shows it unchanged.
+    pub fn from_gamma(size: usize, gamma: f32) -> Self {
+        let channel: Vec<u16> = (0..size)
+            .map(|i| {
+                let input = i as f32 / (size.max(2) - 1) as f32;
+                (input.powf(1.0 / gamma) * 65535.0).round() as u16
+            })
+            .collect();
+        GammaRamp {
+            red: channel.clone(),
+            green: channel.clone(),
+            blue: channel,
+        }
+    }
+
+    /// Returns the channels resampled to `size` entries each, by picking the
+    /// nearest entry, or `None` if they are empty or don't have the same
+    /// length.
+    pub(crate) fn resample(
+        &self,
+        size: usize,
+    ) -> Option<(Vec<u16>, Vec<u16>, Vec<u16>)> {
+        let len = self.red.len();
+        if len == 0 || self.green.len() != len || self.blue.len() != len {
+            return None;
+        }
+        let resample = |channel: &[u16]| -> Vec<u16> {
+            (0..size)
+                .map(|i| channel[i * (len - 1) / (size.max(2) - 1)])
+                .collect()
+        };
+        Some((
+            resample(&self.red),
+            resample(&self.green),
+            resample(&self.blue),
+        ))
+    }
+}
+
+/// Describes the format of a context, as queried from the driver.
+///
+/// With the `serde` feature, it can be saved to request the same format on
+/// the next run with `ContextBuilder::with_saved_pixel_format`.
 #[allow(missing_docs)]
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct PixelFormat {
     pub hardware_accelerated: bool,
     pub color_bits: u8,
@@ -551,8 +1658,68 @@ pub struct PixelFormat {
     pub stencil_bits: u8,
     pub stereoscopy: bool,
     pub double_buffer: bool,
+    /// The number of multisample buffers, `0` or `1`, as reported by the
+    /// driver along with the number of samples.
+    pub sample_buffers: u8,
+    /// The number of samples per pixel, `None` without a multisample buffer
+    /// or with a single sample.
     pub multisampling: Option<u16>,
+    /// The number of coverage samples per pixel if coverage sampling is
+    /// used, in which case `multisampling` is the number of color samples.
+    pub coverage_samples: Option<u16>,
     pub srgb: bool,
+    /// The transparent value of the config, `None` if it is opaque or if the
+    /// backend can't tell.
+    pub transparent: Option<TransparentPixel>,
+    /// Whether the native rendering API of the platform, such as the core
+    /// drawing requests of X11, can render to the surfaces of the config.
+    /// `false` if the backend can't tell.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub native_renderable: bool,
+    /// The class of the X11 visual of the config, `None` off X11 or if it
+    /// isn't an RGB class.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub x11_visual_type: Option<X11VisualType>,
+    /// The identifier of the config in the backend: `EGL_CONFIG_ID`,
+    /// `GLX_FBCONFIG_ID` or the index of the WGL pixel format. `None` on the
+    /// backends that have none. It only identifies the same config with the
+    /// same driver.
+    pub native_id: Option<u32>,
+}
+
+impl PixelFormat {
+    /// Checks, in debug builds, that the format queried from the driver
+    /// meets the requirements that every backend passes on as hard
+    /// constraints.
+    pub(crate) fn debug_assert_satisfies(
+        &self,
+        reqs: &PixelFormatRequirements,
+    ) {
+        let at_least = |obtained: u8, requested: Option<u8>, what: &str| {
+            debug_assert!(
+                obtained >= requested.unwrap_or(0),
+                "requested {:?} {} bits, got {}",
+                requested,
+                what,
+                obtained
+            );
+        };
+        at_least(self.color_bits, reqs.color_bits, "color");
+        at_least(self.alpha_bits, reqs.alpha_bits, "alpha");
+        at_least(self.depth_bits, reqs.depth_bits, "depth");
+        at_least(self.stencil_bits, reqs.stencil_bits, "stencil");
+
+        // a single sample isn't reported as multisampling
+        if let Some(samples) = reqs.multisampling.filter(|&s| s > 1) {
+            debug_assert!(
+                self.multisampling.unwrap_or(0) >= samples,
+                "requested {} samples, got {:?}",
+                samples,
+                self.multisampling
+            );
+        }
+        debug_assert_eq!(self.stereoscopy, reqs.stereoscopy);
+    }
 }
 
 /// Describes how the backend should choose a pixel format.
@@ -594,9 +1761,15 @@ pub struct PixelFormatRequirements {
 
     /// Contains the minimum number of samples per pixel in the color, depth
     /// and stencil buffers. `None` means "don't care". Default is `None`.
-    /// A value of `Some(0)` indicates that multisampling must not be enabled.
+    /// A value of `Some(0)` or `Some(1)` indicates that multisampling must
+    /// not be enabled.
     pub multisampling: Option<u16>,
 
+    /// The number of coverage samples per pixel, in which case
+    /// `multisampling` is the number of color samples. Ignored if coverage
+    /// sampling isn't supported. Default is `None`.
+    pub coverage_samples: Option<u16>,
+
     /// If true, only stereoscopic formats will be considered. If false, only
     /// non-stereoscopic formats. The default is `false`.
     pub stereoscopy: bool,
@@ -608,9 +1781,98 @@ pub struct PixelFormatRequirements {
     /// The behavior when changing the current context. Default is `Flush`.
     pub release_behavior: ReleaseBehavior,
 
+    /// The workarounds for known driver bugs that may be applied. Default is
+    /// all of them.
+    pub driver_workarounds: DriverWorkarounds,
+
+    /// If true, only formats whose surfaces can preserve the contents of the
+    /// color buffer across swaps will be considered. If false, don't care.
+    /// The default is `false`.
+    pub swap_behavior_preserved: bool,
+
+    /// What vsync waits for. The default is `VsyncSource::Driver`.
+    pub vsync_source: VsyncSource,
+
+    /// The framebuffer level, `0` being the main plane. The default is `0`.
+    pub plane: i32,
+
+    /// If true, the context is only used for compute and never presents, so
+    /// it is built without a surface where possible and the swap interval
+    /// isn't set up. The default is `false`.
+    pub compute_only: bool,
+
+    /// If true, the config must let the render buffer of window surfaces be
+    /// switched after their creation. The default is `false`.
+    pub mutable_render_buffer: bool,
+
+    /// If true, resizing a context current on the calling thread makes it
+    /// current again. The default is `false`.
+    pub remake_current_on_resize: bool,
+
+    /// If true, a `CombinedContext` is built along with a hidden context
+    /// sharing its objects, for uploads from another thread. The default is
+    /// `false`.
+    pub upload_context: bool,
+
+    /// If true, glutin chooses the config itself among all the ones of the
+    /// display instead of asking the driver. The default is `false`.
+    pub strict_selection: bool,
+
+    /// If true, the surface is created with lesser requirements when the
+    /// driver has no memory for it. The default is `false`.
+    pub downgrade_on_alloc_failure: bool,
+
+    /// How the pbuffer of a headless context can be bound to a texture,
+    /// `None` if it can't. The default is `None`.
+    pub pbuffer_texture: Option<PbufferTexture>,
+
+    /// EGL only: the attributes added to the ones the config is chosen with,
+    /// replacing those of the same name. The default is empty.
+    pub egl_config_attribs: Vec<(u32, i32)>,
+
+    /// EGL only: the attributes added to the ones the window surface is
+    /// created with, replacing those of the same name. The default is empty.
+    pub egl_surface_attribs: Vec<(u32, i32)>,
+
+    /// The kind of transparency the config must have. The default is
+    /// `TransparentType::None`.
+    pub transparent_type: TransparentType,
+
+    /// Whether the native rendering API must be able to render to the
+    /// surfaces of the config, `None` if it doesn't matter. The default is
+    /// `None`.
+    pub native_renderable: Option<bool>,
+
+    /// The class of X11 visual the config must have, `None` for the
+    /// default of the backend. The default is `None`.
+    pub x11_visual_type: Option<X11VisualType>,
+
+    /// Picks the config among the ones meeting the other requirements,
+    /// instead of the backend. The default is `None`.
+    pub selection_hook: Option<SelectionHook>,
+
     /// X11 only: set internally to insure a certain visual xid is used when
-    /// choosing the fbconfig.
+    /// choosing the fbconfig. Also used for the GBM format of DRM contexts,
+    /// which is the native visual of their configs.
     pub(crate) x11_visual_xid: Option<std::os::raw::c_ulong>,
+
+    /// X11 only: the screen to build the context on, set with
+    /// `ContextBuilderExt::with_x11_screen`. `None` means the default one.
+    pub(crate) x11_screen: Option<std::os::raw::c_int>,
+
+    /// X11 only: set internally when the context renders to a pixmap rather
+    /// than a window.
+    pub(crate) x11_pixmap: bool,
+}
+
+impl PixelFormatRequirements {
+    /// The number of samples to request, where `Some(0)` means that the
+    /// multisample buffer must be disabled. A single sample is requested as
+    /// none, since it isn't multisampling.
+    pub(crate) fn samples(&self) -> Option<u16> {
+        self.multisampling
+            .map(|samples| if samples > 1 { samples } else { 0 })
+    }
 }
 
 impl Default for PixelFormatRequirements {
@@ -625,10 +1887,30 @@ impl Default for PixelFormatRequirements {
             stencil_bits: Some(8),
             double_buffer: None,
             multisampling: None,
+            coverage_samples: None,
             stereoscopy: false,
             srgb: false,
             release_behavior: ReleaseBehavior::Flush,
+            driver_workarounds: DriverWorkarounds::default(),
+            swap_behavior_preserved: false,
+            vsync_source: VsyncSource::Driver,
+            plane: 0,
+            compute_only: false,
+            mutable_render_buffer: false,
+            remake_current_on_resize: false,
+            upload_context: false,
+            strict_selection: false,
+            downgrade_on_alloc_failure: false,
+            pbuffer_texture: None,
+            egl_config_attribs: Vec::new(),
+            egl_surface_attribs: Vec::new(),
+            transparent_type: TransparentType::None,
+            native_renderable: None,
+            x11_visual_type: None,
+            selection_hook: None,
             x11_visual_xid: None,
+            x11_screen: None,
+            x11_pixmap: false,
         }
     }
 }
@@ -651,6 +1933,11 @@ pub struct GlAttributes<S> {
     /// The default is `None`.
     pub profile: Option<GlProfile>,
 
+    /// Whether the context should be forward-compatible.
+    ///
+    /// The default is `false`.
+    pub forward_compatible: bool,
+
     /// Whether to enable the `debug` flag of the context.
     ///
     /// Debug contexts are usually slower but give better error reporting.
@@ -684,6 +1971,7 @@ impl<S> GlAttributes<S> {
             sharing: self.sharing.map(f),
             version: self.version,
             profile: self.profile,
+            forward_compatible: self.forward_compatible,
             debug: self.debug,
             robustness: self.robustness,
             vsync: self.vsync,
@@ -698,9 +1986,74 @@ impl<S> Default for GlAttributes<S> {
             sharing: None,
             version: GlRequest::Latest,
             profile: None,
+            forward_compatible: false,
             debug: cfg!(debug_assertions),
             robustness: Robustness::NotRobust,
             vsync: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gamma_ramp_endpoints() {
+        for &gamma in &[0.5, 1.0, 2.2] {
+            let ramp = GammaRamp::from_gamma(256, gamma);
+            for channel in &[&ramp.red, &ramp.green, &ramp.blue] {
+                assert_eq!(channel.len(), 256);
+                assert_eq!(channel[0], 0);
+                assert_eq!(channel[255], 65535);
+            }
+        }
+    }
+
+    #[test]
+    fn gamma_ramp_identity() {
+        let ramp = GammaRamp::from_gamma(256, 1.0);
+        let identity: Vec<u16> = (0..256).map(|i| i * 257).collect();
+        assert_eq!(ramp.red, identity);
+        assert_eq!(ramp.green, identity);
+        assert_eq!(ramp.blue, identity);
+
+        // a gamma above 1.0 brightens the picture, below 1.0 darkens it
+        let brighter = GammaRamp::from_gamma(256, 2.2);
+        let darker = GammaRamp::from_gamma(256, 0.5);
+        assert!(brighter.red[128] > identity[128]);
+        assert!(darker.red[128] < identity[128]);
+    }
+
+    #[test]
+    fn gamma_ramp_resample() {
+        let ramp = GammaRamp::from_gamma(256, 1.0);
+        let (red, green, blue) = ramp.resample(256).unwrap();
+        assert_eq!(red, ramp.red);
+        assert_eq!(green, ramp.green);
+        assert_eq!(blue, ramp.blue);
+
+        let (red, _, _) = ramp.resample(1024).unwrap();
+        assert_eq!(red.len(), 1024);
+        assert_eq!((red[0], red[1023]), (0, 65535));
+        assert!(red.windows(2).all(|w| w[0] <= w[1]));
+
+        let (red, _, _) = ramp.resample(16).unwrap();
+        let expected: Vec<u16> = (0..16).map(|i| i * 17 * 257).collect();
+        assert_eq!(red, expected);
+    }
+
+    #[test]
+    fn gamma_ramp_resample_invalid() {
+        let empty = GammaRamp {
+            red: vec![],
+            green: vec![],
+            blue: vec![],
+        };
+        assert_eq!(empty.resample(256), None);
+
+        let mut uneven = GammaRamp::from_gamma(256, 1.0);
+        uneven.blue.pop();
+        assert_eq!(uneven.resample(256), None);
+    }
+}
@@ -0,0 +1,104 @@
+use super::*;
+
+use gl::{
+    load, GL_GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX,
+    GL_GPU_MEMORY_INFO_DEDICATED_VIDMEM_NVX,
+    GL_GPU_MEMORY_INFO_EVICTED_MEMORY_NVX, GL_TEXTURE_FREE_MEMORY_ATI,
+};
+
+use std::os::raw::{c_int, c_uint, c_void};
+
+/// The few OpenGL functions needed to query the memory of the GPU.
+#[derive(Clone)]
+struct MemoryGl {
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+    get_stringi: Option<unsafe extern "system" fn(c_uint, c_uint) -> *const u8>,
+    get_integerv: Option<unsafe extern "system" fn(c_uint, *mut c_int)>,
+}
+
+unsafe impl GlLoader for MemoryGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        MemoryGl {
+            get_string: load(loadfn("glGetString")),
+            get_stringi: load(loadfn("glGetStringi")),
+            get_integerv: load(loadfn("glGetIntegerv")),
+        }
+    }
+}
+
+impl MemoryGl {
+    /// Whether the current context supports the OpenGL extension `name`.
+    unsafe fn has_extension(&self, name: &str) -> bool {
+        gl::has_gl_extension(
+            self.get_string,
+            self.get_stringi,
+            self.get_integerv,
+            name,
+        )
+    }
+}
+
+/// The video memory of the GPU, as reported by `Context::get_memory_info`.
+///
+/// Sizes are in bytes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct MemoryInfo {
+    /// The dedicated video memory of the GPU. `None` if the driver doesn't
+    /// report it.
+    pub total: Option<u64>,
+
+    /// The video memory currently available to the application. AMD drivers
+    /// report the memory available for textures, which is what texture
+    /// streaming budgets are based on.
+    pub available: u64,
+
+    /// The memory evicted from the video memory since the context was
+    /// created, because of memory pressure. `None` if the driver doesn't
+    /// report it.
+    pub evicted: Option<u64>,
+}
+
+impl MemoryInfo {
+    /// Queries the memory of the GPU of `context`, which must be current.
+    pub(crate) fn query(context: &Context) -> Result<Self, ContextError> {
+        let gl = context.load_gl::<MemoryGl>()?;
+        let get_integerv = match gl.get_integerv {
+            Some(get_integerv) => get_integerv,
+            None => return Err(ContextError::FunctionUnavailable),
+        };
+
+        // the drivers report kibibytes
+        let bytes = |kib: c_int| kib.max(0) as u64 * 1024;
+        unsafe {
+            if gl.has_extension("GL_NVX_gpu_memory_info") {
+                let query = |pname| {
+                    let mut value = 0;
+                    get_integerv(pname, &mut value);
+                    bytes(value)
+                };
+                Ok(MemoryInfo {
+                    total: Some(query(GL_GPU_MEMORY_INFO_DEDICATED_VIDMEM_NVX)),
+                    available: query(
+                        GL_GPU_MEMORY_INFO_CURRENT_AVAILABLE_VIDMEM_NVX,
+                    ),
+                    evicted: Some(query(GL_GPU_MEMORY_INFO_EVICTED_MEMORY_NVX)),
+                })
+            } else if gl.has_extension("GL_ATI_meminfo") {
+                // the total free memory, the largest free block, then the same
+                // for the auxiliary memory
+                let mut values = [0; 4];
+                get_integerv(GL_TEXTURE_FREE_MEMORY_ATI, values.as_mut_ptr());
+                Ok(MemoryInfo {
+                    total: None,
+                    available: bytes(values[0]),
+                    evicted: None,
+                })
+            } else {
+                Err(ContextError::FunctionUnavailable)
+            }
+        }
+    }
+}
@@ -0,0 +1,405 @@
+use super::*;
+
+use capture::ReadSource;
+use gl::{
+    load, GL_COLOR_ATTACHMENT0, GL_DEPTH24_STENCIL8, GL_DEPTH_ATTACHMENT,
+    GL_DEPTH_COMPONENT16, GL_FRAMEBUFFER, GL_FRAMEBUFFER_BINDING,
+    GL_FRAMEBUFFER_COMPLETE, GL_RENDERBUFFER, GL_RENDERBUFFER_BINDING,
+    GL_RGBA8, GL_STENCIL_ATTACHMENT,
+};
+
+use std::os::raw::{c_int, c_uint, c_void};
+use std::time::Duration;
+
+/// The OpenGL functions needed to set up the framebuffer object frames are
+/// rendered to.
+#[derive(Clone)]
+struct OfflineGl {
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+    get_integerv: Option<unsafe extern "system" fn(c_uint, *mut c_int)>,
+    viewport: Option<unsafe extern "system" fn(c_int, c_int, c_int, c_int)>,
+    gen_framebuffers: Option<unsafe extern "system" fn(c_int, *mut c_uint)>,
+    delete_framebuffers:
+        Option<unsafe extern "system" fn(c_int, *const c_uint)>,
+    bind_framebuffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    check_framebuffer_status:
+        Option<unsafe extern "system" fn(c_uint) -> c_uint>,
+    gen_renderbuffers: Option<unsafe extern "system" fn(c_int, *mut c_uint)>,
+    delete_renderbuffers:
+        Option<unsafe extern "system" fn(c_int, *const c_uint)>,
+    bind_renderbuffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    renderbuffer_storage:
+        Option<unsafe extern "system" fn(c_uint, c_uint, c_int, c_int)>,
+    framebuffer_renderbuffer:
+        Option<unsafe extern "system" fn(c_uint, c_uint, c_uint, c_uint)>,
+}
+
+unsafe impl GlLoader for OfflineGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        OfflineGl {
+            get_string: load(loadfn("glGetString")),
+            get_integerv: load(loadfn("glGetIntegerv")),
+            viewport: load(loadfn("glViewport")),
+            gen_framebuffers: load(loadfn("glGenFramebuffers")),
+            delete_framebuffers: load(loadfn("glDeleteFramebuffers")),
+            bind_framebuffer: load(loadfn("glBindFramebuffer")),
+            check_framebuffer_status: load(loadfn(
+                "glCheckFramebufferStatus",
+            )),
+            gen_renderbuffers: load(loadfn("glGenRenderbuffers")),
+            delete_renderbuffers: load(loadfn("glDeleteRenderbuffers")),
+            bind_renderbuffer: load(loadfn("glBindRenderbuffer")),
+            renderbuffer_storage: load(loadfn("glRenderbufferStorage")),
+            framebuffer_renderbuffer: load(loadfn(
+                "glFramebufferRenderbuffer",
+            )),
+        }
+    }
+}
+
+/// The framebuffer object the frames are rendered to.
+struct Target {
+    gl: OfflineGl,
+    framebuffer: c_uint,
+    /// The color and depth renderbuffers.
+    renderbuffers: [c_uint; 2],
+}
+
+impl Target {
+    /// Creates the framebuffer object, the context being current.
+    unsafe fn new(
+        context: &Context,
+        width: u32,
+        height: u32,
+    ) -> Result<Target, ContextError> {
+        let gl = context.load_gl::<OfflineGl>()?;
+        let (
+            get_integerv,
+            gen_framebuffers,
+            bind_framebuffer,
+            check_framebuffer_status,
+            gen_renderbuffers,
+            bind_renderbuffer,
+            renderbuffer_storage,
+            framebuffer_renderbuffer,
+        ) = match (
+            gl.get_integerv,
+            gl.gen_framebuffers,
+            gl.bind_framebuffer,
+            gl.check_framebuffer_status,
+            gl.gen_renderbuffers,
+            gl.bind_renderbuffer,
+            gl.renderbuffer_storage,
+            gl.framebuffer_renderbuffer,
+        ) {
+            (
+                Some(a),
+                Some(b),
+                Some(c),
+                Some(d),
+                Some(e),
+                Some(f),
+                Some(g),
+                Some(h),
+            ) => (a, b, c, d, e, f, g, h),
+            _ => return Err(ContextError::FunctionUnavailable),
+        };
+        let (es, major, _) = gl::query_gl_version(gl.get_string);
+
+        let mut previous_fb = 0;
+        get_integerv(GL_FRAMEBUFFER_BINDING, &mut previous_fb);
+        let mut previous_rb = 0;
+        get_integerv(GL_RENDERBUFFER_BINDING, &mut previous_rb);
+
+        let mut framebuffer = 0;
+        gen_framebuffers(1, &mut framebuffer);
+        bind_framebuffer(GL_FRAMEBUFFER, framebuffer);
+        let mut renderbuffers = [0; 2];
+        gen_renderbuffers(2, renderbuffers.as_mut_ptr());
+
+        bind_renderbuffer(GL_RENDERBUFFER, renderbuffers[0]);
+        renderbuffer_storage(
+            GL_RENDERBUFFER,
+            GL_RGBA8,
+            width as c_int,
+            height as c_int,
+        );
+        framebuffer_renderbuffer(
+            GL_FRAMEBUFFER,
+            GL_COLOR_ATTACHMENT0,
+            GL_RENDERBUFFER,
+            renderbuffers[0],
+        );
+
+        // OpenGL ES 2 has no packed depth and stencil formats
+        bind_renderbuffer(GL_RENDERBUFFER, renderbuffers[1]);
+        if es && major < 3 {
+            renderbuffer_storage(
+                GL_RENDERBUFFER,
+                GL_DEPTH_COMPONENT16,
+                width as c_int,
+                height as c_int,
+            );
+        } else {
+            renderbuffer_storage(
+                GL_RENDERBUFFER,
+                GL_DEPTH24_STENCIL8,
+                width as c_int,
+                height as c_int,
+            );
+            framebuffer_renderbuffer(
+                GL_FRAMEBUFFER,
+                GL_STENCIL_ATTACHMENT,
+                GL_RENDERBUFFER,
+                renderbuffers[1],
+            );
+        }
+        framebuffer_renderbuffer(
+            GL_FRAMEBUFFER,
+            GL_DEPTH_ATTACHMENT,
+            GL_RENDERBUFFER,
+            renderbuffers[1],
+        );
+
+        let status = check_framebuffer_status(GL_FRAMEBUFFER);
+        bind_renderbuffer(GL_RENDERBUFFER, previous_rb as c_uint);
+        bind_framebuffer(GL_FRAMEBUFFER, previous_fb as c_uint);
+
+        let target = Target {
+            gl,
+            framebuffer,
+            renderbuffers,
+        };
+        if status != GL_FRAMEBUFFER_COMPLETE {
+            target.delete();
+            return Err(ContextError::OsError(format!(
+                "the framebuffer of the frames is incomplete (0x{:x})",
+                status
+            )));
+        }
+        Ok(target)
+    }
+
+    /// Deletes the framebuffer object, the context being current.
+    unsafe fn delete(&self) {
+        if let Some(delete_framebuffers) = self.gl.delete_framebuffers {
+            delete_framebuffers(1, &self.framebuffer);
+        }
+        if let Some(delete_renderbuffers) = self.gl.delete_renderbuffers {
+            delete_renderbuffers(2, self.renderbuffers.as_ptr());
+        }
+    }
+}
+
+/// The position of a frame in the timeline of an `OfflineRenderer`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FrameTime {
+    /// The number of frames rendered before this one.
+    pub index: u64,
+
+    /// The time of the frame since the first one, which is `index` divided
+    /// by the frame rate.
+    pub time: Duration,
+}
+
+/// Renders frames at a virtual frame rate with a headless context, without
+/// a window or an events loop, and reads each of them back.
+///
+/// The frames are rendered to a framebuffer object of the requested size,
+/// with a depth buffer, and a stencil buffer except on OpenGL ES 2. It is
+/// bound, along with a viewport covering it, before the drawing callback is
+/// called. The time of the frames only depends on their index and the frame
+/// rate, whatever the time rendering takes, which suits video renderers and
+/// screenshot services.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// # fn main() {
+/// let cb = glutin::ContextBuilder::new();
+/// let size = glutin::dpi::PhysicalSize::new(1920.0, 1080.0);
+/// let mut renderer = glutin::OfflineRenderer::new(cb, size, 60.0).unwrap();
+///
+/// // two seconds of video
+/// unsafe {
+///     renderer.render(
+///         120,
+///         |_context, _time| {
+///             // draw the frame at `time.time`
+///         },
+///         |_time, _frame| {
+///             // encode `frame.pixels`
+///         },
+///     )
+/// }
+/// .unwrap();
+/// # }
+/// ```
+pub struct OfflineRenderer {
+    context: Context,
+    width: u32,
+    height: u32,
+    frame_rate: f64,
+    next_frame: u64,
+    /// Created on the first frame, when the context is current.
+    target: Option<Target>,
+}
+
+impl OfflineRenderer {
+    /// Builds a headless context with `build_compute`, which needs no
+    /// events loop, and a renderer of frames of `size` pixels at
+    /// `frame_rate` frames per second.
+    ///
+    /// See `ContextBuilder::build_compute` for the platforms that support
+    /// it. Other headless contexts are used with `from_context`.
+    pub fn new(
+        cb: ContextBuilder,
+        size: dpi::PhysicalSize,
+        frame_rate: f64,
+    ) -> Result<Self, CreationError> {
+        let context = cb.build_compute()?;
+        Ok(OfflineRenderer::from_context(context, size, frame_rate))
+    }
+
+    /// Renders frames of `size` pixels at `frame_rate` frames per second
+    /// with `context`, such as a context built with `build_headless`, or an
+    /// OSMesa or surfaceless context.
+    pub fn from_context(
+        context: Context,
+        size: dpi::PhysicalSize,
+        frame_rate: f64,
+    ) -> Self {
+        let (width, height) = size.into();
+        OfflineRenderer {
+            context,
+            width,
+            height,
+            frame_rate,
+            next_frame: 0,
+            target: None,
+        }
+    }
+
+    /// Borrow the inner GL `Context`.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns the time of the next frame.
+    pub fn next_frame_time(&self) -> FrameTime {
+        let nanos = self.next_frame as f64 * 1e9 / self.frame_rate;
+        FrameTime {
+            index: self.next_frame,
+            time: Duration::from_nanos(nanos.round() as u64),
+        }
+    }
+
+    /// Returns the name of the framebuffer object the frames are rendered
+    /// to, once the first frame is rendered.
+    pub fn framebuffer(&self) -> Option<u32> {
+        self.target.as_ref().map(|target| target.framebuffer)
+    }
+
+    /// Renders the next frame with `draw` and reads it back.
+    ///
+    /// The context is made current if it isn't.
+    ///
+    /// # Safety
+    ///
+    /// Like `make_current`, this replaces the context that was current on
+    /// the calling thread.
+    pub unsafe fn render_frame<F>(
+        &mut self,
+        draw: F,
+    ) -> Result<Screenshot, ContextError>
+    where
+        F: FnOnce(&Context, FrameTime),
+    {
+        let mut draw = Some(draw);
+        let mut screenshot = None;
+        self.render(
+            1,
+            |context, time| draw.take().unwrap()(context, time),
+            |_, frame| screenshot = Some(frame),
+        )?;
+        Ok(screenshot.unwrap())
+    }
+
+    /// Renders the next `count` frames with `draw`, passing each of them to
+    /// `output` once read back.
+    ///
+    /// Each frame is read back asynchronously while the next one is drawn,
+    /// when pixel pack buffers are supported. The context is made current if
+    /// it isn't.
+    ///
+    /// # Safety
+    ///
+    /// Like `make_current`, this replaces the context that was current on
+    /// the calling thread.
+    pub unsafe fn render<F, G>(
+        &mut self,
+        count: u64,
+        mut draw: F,
+        mut output: G,
+    ) -> Result<(), ContextError>
+    where
+        F: FnMut(&Context, FrameTime),
+        G: FnMut(FrameTime, Screenshot),
+    {
+        if !self.context.is_current() {
+            self.context.make_current()?;
+        }
+        if self.target.is_none() {
+            let target = Target::new(&self.context, self.width, self.height)?;
+            self.target = Some(target);
+        }
+        let target = self.target.as_ref().unwrap();
+        let size = dpi::PhysicalSize::new(
+            f64::from(self.width),
+            f64::from(self.height),
+        );
+        let source = ReadSource::Framebuffer(target.framebuffer);
+
+        let mut pending: Option<(FrameTime, PendingScreenshot)> = None;
+        for _ in 0..count {
+            let time = self.next_frame_time();
+            target.gl.bind_framebuffer.unwrap()(
+                GL_FRAMEBUFFER,
+                target.framebuffer,
+            );
+            if let Some(viewport) = target.gl.viewport {
+                viewport(0, 0, self.width as c_int, self.height as c_int);
+            }
+            draw(&self.context, time);
+
+            let capture =
+                PendingScreenshot::start_from(&self.context, source, size)?;
+            if let Some((time, previous)) = pending.take() {
+                output(time, previous.finish()?);
+            }
+            pending = Some((time, capture));
+            self.next_frame += 1;
+        }
+        if let Some((time, last)) = pending {
+            output(time, last.finish()?);
+        }
+        Ok(())
+    }
+
+}
+
+impl Drop for OfflineRenderer {
+    fn drop(&mut self) {
+        if let Some(ref target) = self.target {
+            // the objects are leaked if the context isn't current anymore,
+            // and destroyed along with it anyway
+            if self.context.is_current() {
+                unsafe { target.delete() };
+            }
+        }
+    }
+}
@@ -5,10 +5,19 @@ pub use winit::os::android::{WindowBuilderExt, WindowExt};
 pub use api::egl::ffi::EGLContext;
 
 use os::ContextTraitExt;
+use platform;
 use Context;
+use ContextBuilder;
+use ContextError;
+use CreationError;
+use Ownership;
 
 use std::os::raw;
 
+// from `EGL_ANDROID_recordable` and `EGL_ANDROID_framebuffer_target`
+const EGL_RECORDABLE_ANDROID: u32 = 0x3142;
+const EGL_FRAMEBUFFER_TARGET_ANDROID: u32 = 0x3147;
+
 impl ContextTraitExt for Context {
     type Handle = EGLContext;
 
@@ -21,4 +30,214 @@ impl ContextTraitExt for Context {
     unsafe fn get_egl_display(&self) -> Option<*const raw::c_void> {
         Some(self.context.get_egl_display())
     }
+
+    #[inline]
+    fn egl_display_tracks_references(&self) -> Option<bool> {
+        Some(self.context.egl_display_tracks_references())
+    }
+
+    #[inline]
+    fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        self.context.set_egl_label(label)
+    }
+}
+
+/// A transform applied by the compositor to the buffers of a window.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SurfaceTransform {
+    /// The buffers are presented as they are.
+    Identity,
+
+    /// The buffers are rotated by 90 degrees clockwise.
+    Rotate90,
+
+    /// The buffers are rotated by 180 degrees.
+    Rotate180,
+
+    /// The buffers are rotated by 270 degrees clockwise.
+    Rotate270,
+}
+
+impl SurfaceTransform {
+    /// Returns the clockwise rotation, in degrees, the application must apply
+    /// to its projection matrix so that its output appears upright once the
+    /// compositor applies this transform.
+    #[inline]
+    pub fn degrees(&self) -> u32 {
+        match *self {
+            SurfaceTransform::Identity => 0,
+            SurfaceTransform::Rotate90 => 90,
+            SurfaceTransform::Rotate180 => 180,
+            SurfaceTransform::Rotate270 => 270,
+        }
+    }
+}
+
+/// Additional methods on `Context` that are specific to Android.
+pub trait ContextExt {
+    /// Enables or disables `EGL_ANDROID_front_buffer_auto_refresh`, which
+    /// makes the compositor pick up rendering to the front buffer without
+    /// swapping the buffers.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the extension isn't
+    /// supported or if the context is headless.
+    fn set_front_buffer_auto_refresh(
+        &self,
+        enabled: bool,
+    ) -> Result<(), ContextError>;
+
+    /// Hints the compositor that the application renders with the given
+    /// transform already applied, which avoids an extra composition pass
+    /// when the display is rotated.
+    ///
+    /// The transform should match the rotation of the display, as reported by
+    /// `Display.getRotation`, and the application is then responsible for
+    /// rotating its projection by `SurfaceTransform::degrees`.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` before API level 26 or if
+    /// the context is headless.
+    fn set_buffers_transform(
+        &self,
+        transform: SurfaceTransform,
+    ) -> Result<(), ContextError>;
+}
+
+impl ContextExt for Context {
+    #[inline]
+    fn set_front_buffer_auto_refresh(
+        &self,
+        enabled: bool,
+    ) -> Result<(), ContextError> {
+        self.context.set_front_buffer_auto_refresh(enabled)
+    }
+
+    #[inline]
+    fn set_buffers_transform(
+        &self,
+        transform: SurfaceTransform,
+    ) -> Result<(), ContextError> {
+        self.context.set_buffers_transform(transform)
+    }
+}
+
+/// Additional methods on `ContextBuilder` that are specific to Android.
+pub trait ContextBuilderExt {
+    /// Sets whether the config must let the window be recorded, such as by
+    /// the input surface of a `MediaCodec` encoder, with
+    /// `EGL_ANDROID_recordable`.
+    ///
+    /// Building the context fails if the extension isn't supported.
+    fn with_recordable(self, recordable: bool) -> Self;
+
+    /// Sets whether the config must be compatible with the framebuffer of
+    /// the display, for compositors, with `EGL_ANDROID_framebuffer_target`.
+    ///
+    /// Building the context fails if the extension isn't supported.
+    fn with_framebuffer_target(self, target: bool) -> Self;
+}
+
+impl<'a> ContextBuilderExt for ContextBuilder<'a> {
+    #[inline]
+    fn with_recordable(self, recordable: bool) -> Self {
+        self.with_egl_config_attrib(EGL_RECORDABLE_ANDROID, recordable as i32)
+    }
+
+    #[inline]
+    fn with_framebuffer_target(self, target: bool) -> Self {
+        self.with_egl_config_attrib(
+            EGL_FRAMEBUFFER_TARGET_ANDROID,
+            target as i32,
+        )
+    }
+}
+
+/// Builds contexts rendering to native windows other than the one of the
+/// activity.
+pub trait NativeWindowContextExt {
+    /// Builds a context rendering to `native_window`, an `ANativeWindow`
+    /// obtained with `ANativeWindow_fromSurface` from the `Surface` of a
+    /// `SurfaceTexture` or from the input surface of a `MediaCodec` encoder,
+    /// so that frames can be rendered straight into the encoder.
+    ///
+    /// Encoders need a recordable config, which is requested with
+    /// `ContextBuilderExt::with_recordable`. The window doesn't follow the
+    /// lifecycle of the activity, so the context keeps rendering to it
+    /// while the activity is paused.
+    ///
+    /// # Safety
+    ///
+    /// `native_window` must be a valid `ANativeWindow` that outlives the
+    /// context. The caller keeps the reference acquired by
+    /// `ANativeWindow_fromSurface`, and must release it with
+    /// `ANativeWindow_release` after dropping the context.
+    unsafe fn new_native_window(
+        cb: ContextBuilder,
+        native_window: *const raw::c_void,
+    ) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+}
+
+impl NativeWindowContextExt for Context {
+    #[inline]
+    unsafe fn new_native_window(
+        cb: ContextBuilder,
+        native_window: *const raw::c_void,
+    ) -> Result<Self, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        platform::Context::new_native_window(&pf_reqs, &gl_attr, native_window)
+            .map(|context| Context::from_platform(context, sharing))
+    }
+}
+
+/// Wraps contexts created by another library, such as a game engine or the
+/// host of a plugin, in a `Context`.
+pub trait RawContextExt {
+    /// Wraps an EGL context.
+    ///
+    /// The pixel format is described by `config`, or by the config the
+    /// context was created with if it is null. If `surface` isn't null, the
+    /// context is made current with it and its buffers can be swapped,
+    /// otherwise it is made current without a surface, which requires
+    /// `EGL_KHR_surfaceless_context`, and `GL_OES_surfaceless_context` for
+    /// OpenGL ES contexts. `CreationError::NotSupported` is returned if the
+    /// context can't be made current without a surface.
+    ///
+    /// With `Ownership::Owned`, the context and surface are destroyed when
+    /// the `Context` is dropped. Either way, the display is terminated on
+    /// drop if it counts its references, which balances the `eglInitialize`
+    /// call of glutin.
+    ///
+    /// # Safety
+    ///
+    /// `display`, `context`, `config` and `surface` must be valid handles of
+    /// the same display. With `Ownership::Borrowed`, they must outlive the
+    /// `Context`.
+    unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: EGLContext,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+}
+
+impl RawContextExt for Context {
+    #[inline]
+    unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: EGLContext,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        platform::Context::from_raw_egl(
+            display, context, config, surface, ownership,
+        )
+        .map(|context| Context::from_platform(context, None))
+    }
 }
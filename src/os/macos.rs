@@ -6,10 +6,19 @@ pub use winit::os::macos::WindowBuilderExt;
 pub use winit::os::macos::WindowExt;
 
 use os::ContextTraitExt;
+use platform;
 use Context;
+use ContextBuilder;
+use ContextError;
+use CreationError;
+use Ownership;
+use SeparatedContext;
 
 use std::os::raw::c_void;
 
+/// The handle is the `NSOpenGLContext` of a context rendering to a view, the
+/// `CGLContextObj` of a headless one, and the `EGLContext` with the
+/// `bundled-egl` feature.
 impl ContextTraitExt for Context {
     type Handle = *mut c_void;
 
@@ -20,6 +29,150 @@ impl ContextTraitExt for Context {
 
     #[inline]
     unsafe fn get_egl_display(&self) -> Option<*const c_void> {
-        None
+        self.context.get_egl_display()
+    }
+
+    #[inline]
+    fn egl_display_tracks_references(&self) -> Option<bool> {
+        self.context.egl_display_tracks_references()
+    }
+
+    #[inline]
+    fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        self.context.set_egl_label(label)
+    }
+}
+
+/// Additional methods on `Context` that are specific to macOS.
+pub trait ContextExt {
+    /// Uses an `IOSurface` as the storage of the texture bound to `target`,
+    /// which is usually `GL_TEXTURE_RECTANGLE`.
+    ///
+    /// This is how images are shared between processes: one of them creates
+    /// the surface with `IOSurfaceCreate` and sends it to the other with
+    /// `IOSurfaceCreateMachPort`, then both bind it to a texture. The
+    /// surface must hold `width` by `height` BGRA pixels, which are seen as
+    /// `GL_RGBA`.
+    ///
+    /// # Safety
+    ///
+    /// The context must be current and `io_surface` must be a valid
+    /// `IOSurfaceRef`.
+    unsafe fn bind_io_surface(
+        &self,
+        io_surface: *mut c_void,
+        target: u32,
+        width: u32,
+        height: u32,
+        plane: u32,
+    ) -> Result<(), ContextError>;
+}
+
+impl ContextExt for Context {
+    #[inline]
+    unsafe fn bind_io_surface(
+        &self,
+        io_surface: *mut c_void,
+        target: u32,
+        width: u32,
+        height: u32,
+        plane: u32,
+    ) -> Result<(), ContextError> {
+        self.context
+            .bind_io_surface(io_surface, target, width, height, plane)
+    }
+}
+
+/// Wraps contexts created by another library in a `Context`.
+pub trait RawContextExt {
+    /// Wraps a context of the EGL shipped with the executable, such as one
+    /// of ANGLE created by the engine embedding glutin.
+    ///
+    /// The pixel format is described by `config`, or by the config the
+    /// context was created with if it is null. If `surface` isn't null, the
+    /// context is made current with it and its buffers can be swapped,
+    /// otherwise it is made current without a surface, which requires
+    /// `EGL_KHR_surfaceless_context`, and `GL_OES_surfaceless_context` for
+    /// OpenGL ES contexts. `CreationError::NotSupported` is returned if the
+    /// context can't be made current without a surface.
+    ///
+    /// With `Ownership::Owned`, the context and surface are destroyed when
+    /// the `Context` is dropped. Either way, the display is terminated on
+    /// drop if it counts its references, which balances the `eglInitialize`
+    /// call of glutin.
+    ///
+    /// Returns `CreationError::NotSupported` without the `bundled-egl`
+    /// feature, or if no EGL library was found. `NSOpenGLContext`s and
+    /// `CGLContextObj`s can't be wrapped.
+    ///
+    /// # Safety
+    ///
+    /// `display`, `context`, `config` and `surface` must be valid handles of
+    /// the same display. With `Ownership::Borrowed`, they must outlive the
+    /// `Context`.
+    unsafe fn from_raw_egl(
+        display: *const c_void,
+        context: *const c_void,
+        config: *const c_void,
+        surface: *const c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+}
+
+impl RawContextExt for Context {
+    #[inline]
+    unsafe fn from_raw_egl(
+        display: *const c_void,
+        context: *const c_void,
+        config: *const c_void,
+        surface: *const c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        platform::Context::from_raw_egl(
+            display, context, config, surface, ownership,
+        )
+        .map(|context| Context::from_platform(context, None))
+    }
+}
+
+/// Additional methods on `ContextBuilder` that are specific to macOS.
+pub trait ContextBuilderExt {
+    /// Builds a context rendering to an existing `NSView`, without an
+    /// `EventsLoop`, for example to embed OpenGL into a Cocoa UI whose window
+    /// isn't owned by winit, such as the editor of an audio plugin.
+    ///
+    /// The view may be layer-backed, in which case AppKit creates the
+    /// `CAOpenGLLayer` the context renders to. `SeparatedContext::resize`
+    /// has to be called whenever the view is resized.
+    ///
+    /// # Safety
+    ///
+    /// `ns_view` must be a valid `NSView` that outlives the context.
+    unsafe fn build_raw_context(
+        self,
+        ns_view: *mut c_void,
+    ) -> Result<SeparatedContext, CreationError>;
+}
+
+impl<'a> ContextBuilderExt for ContextBuilder<'a> {
+    #[inline]
+    unsafe fn build_raw_context(
+        self,
+        ns_view: *mut c_void,
+    ) -> Result<SeparatedContext, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = self;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        platform::Context::new_raw_context(
+            ns_view as _,
+            false,
+            &pf_reqs,
+            &gl_attr,
+        )
+        .map(|context| {
+            SeparatedContext::from_platform(context, &pf_reqs, sharing)
+        })
     }
 }
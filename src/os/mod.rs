@@ -15,6 +15,8 @@ pub mod macos;
 pub mod unix;
 pub mod windows;
 
+use ContextError;
+
 use std::os::raw;
 
 /// Platform-specific extensions for OpenGL contexts.
@@ -31,4 +33,24 @@ pub trait ContextTraitExt {
     /// Return `None` if the context doesn't use EGL.
     // The pointer will become invalid when the context is destroyed.
     unsafe fn get_egl_display(&self) -> Option<*const raw::c_void>;
+
+    /// Returns whether the `EGLDisplay` of this context counts its
+    /// references, which glutin asks for when `EGL_KHR_display_reference` is
+    /// available.
+    ///
+    /// Such a display is terminated when the context is dropped. Other
+    /// displays are left initialized, since `eglGetDisplay` returns them to
+    /// every user of the native display and terminating them would destroy
+    /// the contexts of the others.
+    ///
+    /// Return `None` if the context doesn't use EGL.
+    fn egl_display_tracks_references(&self) -> Option<bool>;
+
+    /// Labels the `EGLContext` of this context and its surface, so that the
+    /// messages about them given to the function set with
+    /// `set_egl_debug_callback` can tell them apart.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context doesn't
+    /// use EGL or if `EGL_KHR_debug` isn't supported.
+    fn set_egl_label(&self, label: &str) -> Result<(), ContextError>;
 }
@@ -7,8 +7,12 @@
 ))]
 
 pub use api::egl::ffi::EGLContext;
+pub use api::egl::{
+    DmaBuf, DmaBufPlane, EglImage, EglImageBuilder, ImageColorspace,
+};
 pub use api::glx::ffi::GLXContext;
-pub use platform::RawHandle;
+pub use api::glx::ffi::XVisualInfo;
+pub use platform::{OsMesaContextExt, PresentFeedback, RawHandle};
 
 pub use winit::os::unix::EventsLoopExt;
 pub use winit::os::unix::MonitorIdExt;
@@ -18,9 +22,22 @@ pub use winit::os::unix::XNotSupported;
 pub use winit::os::unix::XWindowType;
 
 use os::ContextTraitExt;
+use platform;
+use CombinedContext;
 use Context;
+use ContextBuilder;
+use ContextError;
+use CreationError;
+use dpi;
+use EventsLoop;
+use GammaRamp;
+use Ownership;
+use SeparatedContext;
+use WindowBuilder;
 
 use std::os::raw;
+use std::os::unix::io::RawFd;
+use std::sync::mpsc::Receiver;
 
 impl ContextTraitExt for Context {
     type Handle = RawHandle;
@@ -34,4 +51,502 @@ impl ContextTraitExt for Context {
     unsafe fn get_egl_display(&self) -> Option<*const raw::c_void> {
         self.context.get_egl_display()
     }
+
+    #[inline]
+    fn egl_display_tracks_references(&self) -> Option<bool> {
+        self.context.egl_display_tracks_references()
+    }
+
+    #[inline]
+    fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        self.context.set_egl_label(label)
+    }
+}
+
+/// Additional methods on `Context` that are specific to unix.
+pub trait ContextExt {
+    /// Exports a 2D texture of this context as DMA-BUF file descriptors,
+    /// so that it can be imported by a context of another process with
+    /// `import_dmabuf`.
+    ///
+    /// The texture must be complete and `width` by `height` pixels big. The
+    /// file descriptors are sent to the other process over a Unix socket,
+    /// along with the rest of the `DmaBuf`, and must be closed by the
+    /// caller. Synchronizing the accesses to the image, for example with a
+    /// fence, is up to the application.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context doesn't use
+    /// EGL or if `EGL_KHR_gl_texture_2D_image` or
+    /// `EGL_MESA_image_dma_buf_export` isn't supported.
+    ///
+    /// # Safety
+    ///
+    /// `texture` must be the name of a texture of this context.
+    unsafe fn export_dmabuf(
+        &self,
+        texture: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<DmaBuf, ContextError>;
+
+    /// Imports an image exported with `export_dmabuf`, possibly by another
+    /// process.
+    ///
+    /// The returned `EglImage` is bound to a texture with
+    /// `glEGLImageTargetTexture2DOES`. The file descriptors can be closed
+    /// once it's created.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context doesn't use
+    /// EGL or if `EGL_EXT_image_dma_buf_import` isn't supported, along with
+    /// `EGL_EXT_image_dma_buf_import_modifiers` if the modifier is explicit.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptors must be valid DMA-BUFs described by `buf`.
+    unsafe fn import_dmabuf(
+        &self,
+        buf: &DmaBuf,
+    ) -> Result<EglImage<'_>, ContextError>;
+
+    /// Imports an image like `import_dmabuf`, with the parameters of
+    /// `builder`.
+    ///
+    /// Also returns `ContextError::FunctionUnavailable` if a colorspace is
+    /// set and `EGL_EXT_image_gl_colorspace` isn't supported.
+    ///
+    /// # Safety
+    ///
+    /// The file descriptors must be valid DMA-BUFs described by the
+    /// `DmaBuf` of `builder`.
+    unsafe fn import_image(
+        &self,
+        builder: &EglImageBuilder<'_>,
+    ) -> Result<EglImage<'_>, ContextError>;
+
+    /// Returns a receiver of the timings at which the frames swapped from
+    /// now on are presented, like the presentation-time protocol of Wayland.
+    ///
+    /// The timings come from the `PresentCompleteNotify` events of the
+    /// Present extension, which are read when the buffers are swapped: the
+    /// timing of a frame is received after one of the next swaps. Their
+    /// `serial` is the one returned by
+    /// `SwapBuffersExt::swap_buffers_with_serial`. Calling this again
+    /// disconnects the previous receiver.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context doesn't
+    /// render to an X11 window or if the Present extension isn't available.
+    /// No timing is received if the driver doesn't present the frames
+    /// through the Present extension, which Mesa does with DRI3.
+    fn present_feedback(
+        &self,
+    ) -> Result<Receiver<PresentFeedback>, ContextError>;
+}
+
+impl ContextExt for Context {
+    #[inline]
+    unsafe fn export_dmabuf(
+        &self,
+        texture: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<DmaBuf, ContextError> {
+        self.context.export_dmabuf(texture, width, height)
+    }
+
+    #[inline]
+    unsafe fn import_dmabuf(
+        &self,
+        buf: &DmaBuf,
+    ) -> Result<EglImage<'_>, ContextError> {
+        self.context.import_image(&EglImageBuilder::new(buf))
+    }
+
+    #[inline]
+    unsafe fn import_image(
+        &self,
+        builder: &EglImageBuilder<'_>,
+    ) -> Result<EglImage<'_>, ContextError> {
+        self.context.import_image(builder)
+    }
+
+    #[inline]
+    fn present_feedback(
+        &self,
+    ) -> Result<Receiver<PresentFeedback>, ContextError> {
+        self.context.present_feedback()
+    }
+}
+
+/// Wraps contexts created by another library, such as a toolkit or the host
+/// of a plugin, in a `Context`.
+pub trait RawContextExt {
+    /// Wraps an EGL context.
+    ///
+    /// The pixel format is described by `config`, or by the config the
+    /// context was created with if it is null. If `surface` isn't null, the
+    /// context is made current with it and its buffers can be swapped,
+    /// otherwise it is made current without a surface, which requires
+    /// `EGL_KHR_surfaceless_context`, and `GL_OES_surfaceless_context` for
+    /// OpenGL ES contexts. `CreationError::NotSupported` is returned if the
+    /// context can't be made current without a surface.
+    ///
+    /// With `Ownership::Owned`, the context and surface are destroyed when
+    /// the `Context` is dropped. Either way, the display is terminated on
+    /// drop if it counts its references, which balances the `eglInitialize`
+    /// call of glutin.
+    ///
+    /// GLX contexts can't be wrapped, since glutin's GLX contexts own their
+    /// connection to the X server.
+    ///
+    /// # Safety
+    ///
+    /// `display`, `context`, `config` and `surface` must be valid handles of
+    /// the same display. With `Ownership::Borrowed`, they must outlive the
+    /// `Context`.
+    unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: EGLContext,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+}
+
+impl RawContextExt for Context {
+    #[inline]
+    unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: EGLContext,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        platform::Context::from_raw_egl(
+            display, context, config, surface, ownership,
+        )
+        .map(|context| Context::from_platform(context, None))
+    }
+}
+
+/// Builds contexts without a display server, through Mesa.
+pub trait SurfacelessContextExt {
+    /// Builds an EGL context on the surfaceless platform of Mesa
+    /// (`EGL_MESA_platform_surfaceless`), which needs neither X11 nor
+    /// Wayland, so that servers without a window system can render with
+    /// their GPU.
+    ///
+    /// The context renders to a 1x1 pbuffer, like `new_osmesa`, so it
+    /// doesn't require `EGL_KHR_surfaceless_context`. Render to framebuffer
+    /// objects for anything larger.
+    ///
+    /// Returns `CreationError::NotSupported` if libEGL or the extension is
+    /// unavailable.
+    fn new_surfaceless(cb: ContextBuilder) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+}
+
+impl SurfacelessContextExt for Context {
+    #[inline]
+    fn new_surfaceless(cb: ContextBuilder) -> Result<Self, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        platform::Context::new_surfaceless(&pf_reqs, &gl_attr)
+            .map(|context| Context::from_platform(context, sharing))
+    }
+}
+
+/// Builds contexts rendering to X11 pixmaps.
+pub trait PixmapContextExt {
+    /// Creates a pixmap of the given size and builds a context rendering to
+    /// it, so that the pixmap path of the driver can be exercised without
+    /// any X11 code. The pixmap is freed along with the context.
+    ///
+    /// Pixmaps only have a front buffer, so the double buffering and vsync
+    /// options of the builder are ignored.
+    ///
+    /// Returns `CreationError::NotSupported` on Wayland.
+    fn new_x11_pixmap(
+        el: &EventsLoop,
+        cb: ContextBuilder,
+        size: dpi::PhysicalSize,
+    ) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+}
+
+impl PixmapContextExt for Context {
+    #[inline]
+    fn new_x11_pixmap(
+        el: &EventsLoop,
+        cb: ContextBuilder,
+        size: dpi::PhysicalSize,
+    ) -> Result<Self, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        let dimensions: (u32, u32) = size.into();
+        platform::Context::new_x11_pixmap(el, dimensions, &pf_reqs, &gl_attr)
+            .map(|context| Context::from_platform(context, sharing))
+    }
+}
+
+/// Additional methods on `CombinedContext` and `SeparatedContext` that are
+/// specific to unix.
+pub trait SwapBuffersExt {
+    /// Swaps the buffers like `swap_buffers`, returning the serial of the
+    /// swap, which identifies the frame in the `PresentFeedback` received
+    /// from `ContextExt::present_feedback`.
+    ///
+    /// Serials start at `1` and increase by one on each swap. `0` is
+    /// returned when the present feedback isn't available.
+    fn swap_buffers_with_serial(&self) -> Result<u64, ContextError>;
+}
+
+impl SwapBuffersExt for CombinedContext {
+    #[inline]
+    fn swap_buffers_with_serial(&self) -> Result<u64, ContextError> {
+        self.context().context.swap_buffers_with_serial()
+    }
+}
+
+impl SwapBuffersExt for SeparatedContext {
+    #[inline]
+    fn swap_buffers_with_serial(&self) -> Result<u64, ContextError> {
+        self.context().context.swap_buffers_with_serial()
+    }
+}
+
+/// Gamma ramps of the monitor a window is on, for games with an in-game
+/// gamma setting.
+pub trait GammaRampExt {
+    /// Returns the gamma ramp of the monitor the window is on.
+    ///
+    /// The ramp comes from the first CRTC of the monitor, through XRandR.
+    /// Returns `ContextError::FunctionUnavailable` on Wayland, where clients
+    /// can't change the gamma ramps.
+    fn get_gamma_ramp(&self) -> Result<GammaRamp, ContextError>;
+
+    /// Sets the gamma ramp of the monitor the window is on, typically while
+    /// the window is fullscreen on it. The ramp is resampled to the size of
+    /// the ramps of the CRTCs of the monitor.
+    ///
+    /// The ramp each CRTC had before the first change is restored when the
+    /// `CombinedContext` is dropped, so that the desktop isn't left darkened.
+    /// Nothing is restored if the process is killed.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` on Wayland, and
+    /// `ContextError::OsError` if the channels don't have the same length.
+    fn set_gamma_ramp(&self, ramp: &GammaRamp) -> Result<(), ContextError>;
+}
+
+impl GammaRampExt for CombinedContext {
+    #[inline]
+    fn get_gamma_ramp(&self) -> Result<GammaRamp, ContextError> {
+        CombinedContext::get_gamma_ramp(self)
+    }
+
+    #[inline]
+    fn set_gamma_ramp(&self, ramp: &GammaRamp) -> Result<(), ContextError> {
+        CombinedContext::set_gamma_ramp(self, ramp)
+    }
+}
+
+/// Resizing with an offset, which is specific to Wayland.
+pub trait ResizeExt {
+    /// Resizes the context like `resize`, moving the content of the surface
+    /// by `dx` and `dy` in buffer coordinates, as a window resized from its
+    /// top or left edge needs to keep its content in place.
+    ///
+    /// The `wl_egl_window` of the context is resized with
+    /// `wl_egl_window_resize`, which takes effect with the next swap.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the context doesn't
+    /// render to a Wayland window.
+    fn resize_with_offset(
+        &self,
+        size: dpi::PhysicalSize,
+        dx: i32,
+        dy: i32,
+    ) -> Result<(), ContextError>;
+}
+
+impl ResizeExt for CombinedContext {
+    #[inline]
+    fn resize_with_offset(
+        &self,
+        size: dpi::PhysicalSize,
+        dx: i32,
+        dy: i32,
+    ) -> Result<(), ContextError> {
+        let (width, height) = size.into();
+        self.context()
+            .context
+            .resize_with_offset(width, height, dx, dy)?;
+        self.resized(size);
+        Ok(())
+    }
+}
+
+impl ResizeExt for SeparatedContext {
+    #[inline]
+    fn resize_with_offset(
+        &self,
+        size: dpi::PhysicalSize,
+        dx: i32,
+        dy: i32,
+    ) -> Result<(), ContextError> {
+        let (width, height) = size.into();
+        self.context()
+            .context
+            .resize_with_offset(width, height, dx, dy)?;
+        self.resized();
+        Ok(())
+    }
+}
+
+/// Additional methods on `ContextBuilder` that are specific to unix.
+pub trait ContextBuilderExt {
+    /// Picks the visual of the config that a context built with these
+    /// attributes would use on X11.
+    ///
+    /// The window a context renders to must have the visual of the config
+    /// of the context. `CombinedContext` takes care of it, but a window
+    /// created for a `SeparatedContext` must be given this visual through
+    /// `WindowBuilderExt::with_x11_visual`, otherwise building the context
+    /// may fail or give a different pixel format.
+    ///
+    /// Only the transparency of `wb` is taken into account.
+    ///
+    /// # Example
+    ///
+    /// ```no_run
+    /// # extern crate glutin;
+    /// # use glutin::os::unix::{ContextBuilderExt, WindowBuilderExt};
+    /// # fn main() {
+    /// let el = glutin::EventsLoop::new();
+    /// let cb = glutin::ContextBuilder::new();
+    /// let visual = cb
+    ///     .choose_x11_visual(&glutin::WindowBuilder::new(), &el)
+    ///     .unwrap();
+    ///
+    /// let window = glutin::WindowBuilder::new()
+    ///     .with_x11_visual(&visual as *const _)
+    ///     .build(&el)
+    ///     .unwrap();
+    /// let separated_context = cb.build_separated(&window, &el).unwrap();
+    /// # }
+    /// ```
+    fn choose_x11_visual(
+        &self,
+        wb: &WindowBuilder,
+        el: &EventsLoop,
+    ) -> Result<XVisualInfo, CreationError>;
+
+    /// Builds a context rendering to an existing X11 window, without an
+    /// `EventsLoop`, for example in a plugin whose host owns the window and
+    /// its event loop.
+    ///
+    /// A new connection to the X server named by the `DISPLAY` environment
+    /// variable is opened for the context, so the window must belong to that
+    /// server. The X error handler of the application is kept.
+    ///
+    /// # Safety
+    ///
+    /// `xlib_window` must be a valid window that outlives the context.
+    unsafe fn build_raw_x11_context(
+        self,
+        xlib_window: raw::c_ulong,
+    ) -> Result<SeparatedContext, CreationError>;
+
+    /// Builds the context, and the window of a `CombinedContext`, on the
+    /// given screen of the X server instead of the default one, for example
+    /// to open a window per screen of a multi-screen setup. Building fails
+    /// if the X server has no such screen.
+    ///
+    /// Contexts can only be shared with contexts of the same screen. The
+    /// screen of a `SeparatedContext` is always the one of its window.
+    fn with_x11_screen(self, screen: raw::c_int) -> Self;
+
+    /// Builds a context showing its frames on a connector of a DRM device,
+    /// without a display server, for example on the connector of a
+    /// head-mounted display leased from the compositor with
+    /// `wp_drm_lease_v1` or `RRCreateLease`.
+    ///
+    /// The connector is driven with its preferred mode, whose size is the
+    /// size of the frames, and the buffers are allocated with GBM. Each swap
+    /// flips to the new frame on the next vertical blank and waits for the
+    /// flip, so frames are never torn and the swap interval is always `1`.
+    /// The timings of the flips are received from
+    /// `ContextExt::present_feedback`, with serials returned by
+    /// `SwapBuffersExt::swap_buffers_with_serial`.
+    ///
+    /// Returns `CreationError::NoBackendAvailable` if libdrm or libgbm is
+    /// unavailable, and `CreationError::PlatformSpecific` if the connector
+    /// isn't connected or has no usable CRTC. Contexts can only be shared with
+    /// other DRM contexts.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be an open DRM device, or lease, which is master of the
+    /// connector and outlives the context.
+    unsafe fn build_drm_context(
+        self,
+        fd: RawFd,
+        connector_id: u32,
+    ) -> Result<SeparatedContext, CreationError>;
+}
+
+impl<'a> ContextBuilderExt for ContextBuilder<'a> {
+    #[inline]
+    fn choose_x11_visual(
+        &self,
+        wb: &WindowBuilder,
+        el: &EventsLoop,
+    ) -> Result<XVisualInfo, CreationError> {
+        let gl_attr = self.gl_attr.clone().map_sharing(|ctx| &ctx.context);
+        platform::Context::choose_x11_visual(
+            el,
+            &self.pf_reqs,
+            &gl_attr,
+            wb.window.transparent,
+        )
+    }
+    #[inline]
+    unsafe fn build_raw_x11_context(
+        self,
+        xlib_window: raw::c_ulong,
+    ) -> Result<SeparatedContext, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = self;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        platform::Context::new_raw_x11_context(xlib_window, &pf_reqs, &gl_attr)
+            .map(|context| {
+                SeparatedContext::from_platform(context, &pf_reqs, sharing)
+            })
+    }
+
+    #[inline]
+    fn with_x11_screen(mut self, screen: raw::c_int) -> Self {
+        self.pf_reqs.x11_screen = Some(screen);
+        self
+    }
+
+    #[inline]
+    unsafe fn build_drm_context(
+        self,
+        fd: RawFd,
+        connector_id: u32,
+    ) -> Result<SeparatedContext, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = self;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        platform::Context::new_drm(fd, connector_id, &pf_reqs, &gl_attr)
+            .map(|context| {
+                SeparatedContext::from_platform(context, &pf_reqs, sharing)
+            })
+    }
 }
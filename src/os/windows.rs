@@ -6,12 +6,23 @@ pub use winit::os::windows::{
 };
 
 pub use api::egl::ffi::EGLContext;
+pub use api::wgl::{
+    DxInteropAccess, DxInteropDevice, DxInteropObject, WglInit,
+};
 pub use platform::RawHandle;
 
 use std::os::raw;
 
 use os::ContextTraitExt;
+use platform;
+use CombinedContext;
 use Context;
+use ContextBuilder;
+use ContextError;
+use CreationError;
+use GammaRamp;
+use Ownership;
+use SeparatedContext;
 
 impl ContextTraitExt for Context {
     type Handle = RawHandle;
@@ -25,4 +36,196 @@ impl ContextTraitExt for Context {
     unsafe fn get_egl_display(&self) -> Option<*const raw::c_void> {
         self.context.get_egl_display()
     }
+
+    #[inline]
+    fn egl_display_tracks_references(&self) -> Option<bool> {
+        self.context.egl_display_tracks_references()
+    }
+
+    #[inline]
+    fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        self.context.set_egl_label(label)
+    }
+}
+
+/// Additional methods on `Context` that are specific to Windows.
+pub trait ContextExt {
+    /// Opens a Direct3D device, such as an `ID3D11Device`, so that its
+    /// resources can be shared with this context through
+    /// `WGL_NV_DX_interop`.
+    ///
+    /// Registered objects must be locked with
+    /// `DxInteropDevice::lock_objects` before being used by OpenGL and
+    /// unlocked before being used by Direct3D again.
+    ///
+    /// This is also how images are shared between processes: one of them
+    /// creates a texture with `D3D11_RESOURCE_MISC_SHARED` and sends the
+    /// handle returned by `IDXGIResource::GetSharedHandle` to the other,
+    /// which opens it with `ID3D11Device::OpenSharedResource`. Each of them
+    /// then registers its texture.
+    ///
+    /// Returns `ContextError::FunctionUnavailable` if the extension isn't
+    /// supported or if the context was created with EGL.
+    unsafe fn dx_open_device(
+        &self,
+        dx_device: *mut raw::c_void,
+    ) -> Result<DxInteropDevice, ContextError>;
+}
+
+impl ContextExt for Context {
+    #[inline]
+    unsafe fn dx_open_device(
+        &self,
+        dx_device: *mut raw::c_void,
+    ) -> Result<DxInteropDevice, ContextError> {
+        self.context.dx_open_device(dx_device)
+    }
+}
+
+/// Wraps contexts created by another library, such as a toolkit or the host
+/// of a plugin, in a `Context`.
+pub trait RawContextExt {
+    /// Wraps an EGL context, such as one of ANGLE.
+    ///
+    /// The pixel format is described by `config`, or by the config the
+    /// context was created with if it is null. If `surface` isn't null, the
+    /// context is made current with it and its buffers can be swapped,
+    /// otherwise it is made current without a surface, which requires
+    /// `EGL_KHR_surfaceless_context`, and `GL_OES_surfaceless_context` for
+    /// OpenGL ES contexts. `CreationError::NotSupported` is returned if the
+    /// context can't be made current without a surface.
+    ///
+    /// With `Ownership::Owned`, the context and surface are destroyed when
+    /// the `Context` is dropped. Either way, the display is terminated on
+    /// drop if it counts its references, which balances the `eglInitialize`
+    /// call of glutin.
+    ///
+    /// # Safety
+    ///
+    /// `display`, `context`, `config` and `surface` must be valid handles of
+    /// the same display. With `Ownership::Borrowed`, they must outlive the
+    /// `Context`.
+    unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: EGLContext,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+
+    /// Wraps a WGL context rendering to the window of `hdc`.
+    ///
+    /// The pixel format is the one set on the window. Its multisampling and
+    /// sRGB capability are reported as unavailable, and the context is
+    /// assumed to be a desktop OpenGL one.
+    ///
+    /// With `Ownership::Owned`, the context is deleted when the `Context` is
+    /// dropped. The device context is never released.
+    ///
+    /// # Safety
+    ///
+    /// `hdc` must be a valid `HDC` of a window and `hglrc` a context created
+    /// for it, both outliving the `Context`.
+    unsafe fn from_raw_wgl(
+        hdc: *mut raw::c_void,
+        hglrc: HGLRC,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError>
+    where
+        Self: Sized;
+}
+
+impl RawContextExt for Context {
+    #[inline]
+    unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: EGLContext,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        platform::Context::from_raw_egl(
+            display, context, config, surface, ownership,
+        )
+        .map(|context| Context::from_platform(context, None))
+    }
+
+    #[inline]
+    unsafe fn from_raw_wgl(
+        hdc: *mut raw::c_void,
+        hglrc: HGLRC,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        platform::Context::from_raw_wgl(hdc as _, hglrc, ownership)
+            .map(|context| Context::from_platform(context, None))
+    }
+}
+
+/// Additional methods on `ContextBuilder` that are specific to Windows.
+pub trait ContextBuilderExt {
+    /// Builds a context rendering to an existing window, without an
+    /// `EventsLoop`, for example in a plugin whose host owns the window and
+    /// its event loop.
+    ///
+    /// The pixel format of a window can only be set once, so the window must
+    /// not have been used by another context.
+    ///
+    /// # Safety
+    ///
+    /// `hwnd` must be a valid `HWND` that outlives the context.
+    unsafe fn build_raw_context(
+        self,
+        hwnd: *mut raw::c_void,
+    ) -> Result<SeparatedContext, CreationError>;
+}
+
+impl<'a> ContextBuilderExt for ContextBuilder<'a> {
+    #[inline]
+    unsafe fn build_raw_context(
+        self,
+        hwnd: *mut raw::c_void,
+    ) -> Result<SeparatedContext, CreationError> {
+        let ContextBuilder { pf_reqs, gl_attr } = self;
+        let sharing = gl_attr.sharing;
+        let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
+        platform::Context::new_raw_context(hwnd as _, &pf_reqs, &gl_attr)
+            .map(|context| {
+                SeparatedContext::from_platform(context, &pf_reqs, sharing)
+            })
+    }
+}
+
+/// Gamma ramps of the monitor a window is on, for games with an in-game
+/// gamma setting.
+pub trait GammaRampExt {
+    /// Returns the gamma ramp of the monitor the window is on, through
+    /// `GetDeviceGammaRamp`. The ramps of GDI always have 256 entries.
+    fn get_gamma_ramp(&self) -> Result<GammaRamp, ContextError>;
+
+    /// Sets the gamma ramp of the monitor the window is on, typically while
+    /// the window is fullscreen on it, through `SetDeviceGammaRamp`. The
+    /// ramp is resampled to 256 entries.
+    ///
+    /// The ramp each monitor had before the first change is restored when
+    /// the `CombinedContext` is dropped, so that the desktop isn't left
+    /// darkened. Nothing is restored if the process is killed.
+    ///
+    /// Returns `ContextError::OsError` if the channels don't have the same
+    /// length, or if GDI refuses the ramp, which it does for ramps that
+    /// stray too far from the identity ramp.
+    fn set_gamma_ramp(&self, ramp: &GammaRamp) -> Result<(), ContextError>;
+}
+
+impl GammaRampExt for CombinedContext {
+    #[inline]
+    fn get_gamma_ramp(&self) -> Result<GammaRamp, ContextError> {
+        CombinedContext::get_gamma_ramp(self)
+    }
+
+    #[inline]
+    fn set_gamma_ramp(&self, ramp: &GammaRamp) -> Result<(), ContextError> {
+        CombinedContext::set_gamma_ramp(self, ramp)
+    }
 }
@@ -1,27 +1,63 @@
 #![cfg(target_os = "emscripten")]
 
 use std::ffi::CString;
+use std::time::Duration;
 
-use {Api, ContextError, CreationError, GlAttributes, GlRequest};
+use {Api, BackendInfo, ContextError, CreationError, GlAttributes};
+use {DisplayToken, DriverWorkarounds, GlRequest};
 use {PixelFormat, PixelFormatRequirements};
+use {Rect, RenderBuffer};
+use {PbufferLimits, PresentStats, ReleaseBehavior};
+use {SurfaceInfo, SurfaceVisibility};
+use SurfaceDowngrade;
+use {SwapBehavior, SwapIntervalRange, TransparentType};
 
 use winit;
 
 mod ffi;
 
+/// The context current on a thread before a context was made current by
+/// `Context::make_current_scoped`, which may be `0`.
+pub struct CurrentState(ffi::EMSCRIPTEN_WEBGL_CONTEXT_HANDLE);
+
 pub enum Context {
     Window(ffi::EMSCRIPTEN_WEBGL_CONTEXT_HANDLE),
     WindowedContext(winit::Window, ffi::EMSCRIPTEN_WEBGL_CONTEXT_HANDLE),
 }
 
+/// See the docs in the crate root file.
+pub fn probe() -> Vec<BackendInfo> {
+    Vec::new()
+}
+
+/// Nothing is loaded at runtime.
+pub unsafe fn reload_symbols() -> Result<(), usize> {
+    Ok(())
+}
+
+/// Nothing is loaded at runtime.
+pub fn preload() {}
+
 impl Context {
     #[inline]
     pub fn new(
         wb: winit::WindowBuilder,
         el: &winit::EventsLoop,
-        _pf_reqs: &PixelFormatRequirements,
+        pf_reqs: &PixelFormatRequirements,
         gl_attr: &GlAttributes<&Context>,
     ) -> Result<(winit::Window, Self), CreationError> {
+        if pf_reqs.plane != 0 {
+            return Err(CreationError::NotSupported(
+                "layer planes are not supported in WebGL",
+            ));
+        }
+
+        if pf_reqs.transparent_type != TransparentType::None {
+            return Err(CreationError::NotSupported(
+                "transparent configs are not supported in WebGL",
+            ));
+        }
+
         let window = wb.build(el)?;
 
         let gl_attr = gl_attr.clone().map_sharing(|_| {
@@ -40,6 +76,10 @@ impl Context {
         // setting the attributes
         if let GlRequest::Specific(Api::WebGl, (major, minor)) = gl_attr.version
         {
+            // WebGL 1.0 and 2.0 are the only versions
+            if minor != 0 || major < 1 || major > 2 {
+                return Err(CreationError::OpenGlVersionNotSupported);
+            }
             attributes.majorVersion = major as _;
             attributes.minorVersion = minor as _;
         }
@@ -77,6 +117,17 @@ impl Context {
         })
     }
 
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn new_compute(
+        _pf_reqs: &PixelFormatRequirements,
+        _gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        Err(CreationError::NotSupported(
+            "contexts can't be built without a canvas on Emscripten",
+        ))
+    }
+
     /// See the docs in the crate root file.
     #[inline]
     pub fn new_separated(
@@ -96,6 +147,185 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub fn needs_resize(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn resize_pbuffer(
+        &self,
+        _width: u32,
+        _height: u32,
+        _largest: bool,
+    ) -> Result<(u32, u32), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_pbuffer_size(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_surface_visibility(
+        &self,
+        _window: &winit::Window,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn is_vsync_forced(
+        &self,
+        _window: &winit::Window,
+    ) -> Result<bool, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    /// Contexts never share, so each has its own token.
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        let handle = unsafe { self.raw_handle() };
+        DisplayToken::new("webgl", handle as u64)
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        ReleaseBehavior::Flush
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        DriverWorkarounds::none()
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        Vec::new()
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        Ok(())
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        _behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        _render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        _metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn join_swap_group(
+        &self,
+        _group: u32,
+        _barrier: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn copy_state_from(
+        &self,
+        _other: &Context,
+        _mask: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_swap_msc(
+        &self,
+        _divisor: u32,
+        _remainder: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        _: i32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn bind_pbuffer_tex_image(&self, _: bool) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, _: u32) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub unsafe fn current_state(&self) -> CurrentState {
+        CurrentState(ffi::emscripten_webgl_get_current_context())
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        ffi::emscripten_webgl_make_context_current(state.0);
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         // TOOD: check if == EMSCRIPTEN_RESULT
@@ -103,6 +333,14 @@ impl Context {
         Ok(())
     }
 
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        if self.is_current() {
+            ffi::emscripten_webgl_make_context_current(0);
+        }
+        Ok(())
+    }
+
     #[inline]
     pub fn is_current(&self) -> bool {
         unsafe {
@@ -127,6 +365,19 @@ impl Context {
         Ok(())
     }
 
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        _rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        self.swap_buffers()
+    }
+
+    /// Blocking would freeze the page, which the browser already throttles
+    /// when it isn't shown.
+    #[inline]
+    pub fn wait_paused_frame(&self, _timeout: Duration) {}
+
     #[inline]
     pub fn get_api(&self) -> Api {
         Api::WebGl
@@ -143,8 +394,14 @@ impl Context {
             stencil_bits: 8,
             stereoscopy: false,
             double_buffer: true,
+            sample_buffers: 0,
             multisampling: None,
+            coverage_samples: None,
             srgb: true,
+            transparent: None,
+            native_renderable: false,
+            x11_visual_type: None,
+            native_id: None,
         }
     }
 
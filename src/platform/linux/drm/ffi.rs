@@ -0,0 +1,243 @@
+#![allow(non_camel_case_types)]
+
+//! The parts of libdrm and libgbm needed to show frames on a connector,
+//! loaded at runtime.
+
+use libloading::Library;
+
+use std::io;
+use std::os::raw::{c_char, c_int, c_uint, c_void};
+
+pub const DRM_MODE_CONNECTED: c_int = 1;
+pub const DRM_MODE_TYPE_PREFERRED: u32 = 1 << 3;
+pub const DRM_MODE_PAGE_FLIP_EVENT: u32 = 0x01;
+const DRM_DISPLAY_MODE_LEN: usize = 32;
+
+/// `fourcc_code('X', 'R', '2', '4')`
+pub const GBM_FORMAT_XRGB8888: u32 = 0x3432_5258;
+/// `fourcc_code('A', 'R', '2', '4')`
+pub const GBM_FORMAT_ARGB8888: u32 = 0x3432_5241;
+pub const GBM_BO_USE_SCANOUT: u32 = 1 << 0;
+pub const GBM_BO_USE_RENDERING: u32 = 1 << 2;
+
+#[repr(C)]
+pub struct drmModeRes {
+    pub count_fbs: c_int,
+    pub fbs: *mut u32,
+    pub count_crtcs: c_int,
+    pub crtcs: *mut u32,
+    pub count_connectors: c_int,
+    pub connectors: *mut u32,
+    pub count_encoders: c_int,
+    pub encoders: *mut u32,
+    pub min_width: u32,
+    pub max_width: u32,
+    pub min_height: u32,
+    pub max_height: u32,
+}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct drmModeModeInfo {
+    pub clock: u32,
+    pub hdisplay: u16,
+    pub hsync_start: u16,
+    pub hsync_end: u16,
+    pub htotal: u16,
+    pub hskew: u16,
+    pub vdisplay: u16,
+    pub vsync_start: u16,
+    pub vsync_end: u16,
+    pub vtotal: u16,
+    pub vscan: u16,
+    pub vrefresh: u32,
+    pub flags: u32,
+    pub type_: u32,
+    pub name: [c_char; DRM_DISPLAY_MODE_LEN],
+}
+
+#[repr(C)]
+pub struct drmModeConnector {
+    pub connector_id: u32,
+    pub encoder_id: u32,
+    pub connector_type: u32,
+    pub connector_type_id: u32,
+    pub connection: c_int,
+    pub mm_width: u32,
+    pub mm_height: u32,
+    pub subpixel: c_int,
+    pub count_modes: c_int,
+    pub modes: *mut drmModeModeInfo,
+    pub count_props: c_int,
+    pub props: *mut u32,
+    pub prop_values: *mut u64,
+    pub count_encoders: c_int,
+    pub encoders: *mut u32,
+}
+
+#[repr(C)]
+pub struct drmModeEncoder {
+    pub encoder_id: u32,
+    pub encoder_type: u32,
+    pub crtc_id: u32,
+    pub possible_crtcs: u32,
+    pub possible_clones: u32,
+}
+
+#[repr(C)]
+pub struct drmModeCrtc {
+    pub crtc_id: u32,
+    pub buffer_id: u32,
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub mode_valid: c_int,
+    pub mode: drmModeModeInfo,
+    pub gamma_size: c_int,
+}
+
+pub type drm_handler_fn =
+    extern "C" fn(c_int, c_uint, c_uint, c_uint, *mut c_void);
+
+/// Version 2 of `drmEventContext`, the last one without the handlers that
+/// only recent versions of libdrm know.
+#[repr(C)]
+pub struct drmEventContext {
+    pub version: c_int,
+    pub vblank_handler: Option<drm_handler_fn>,
+    pub page_flip_handler: Option<drm_handler_fn>,
+}
+
+pub enum gbm_device {}
+pub enum gbm_surface {}
+pub enum gbm_bo {}
+
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub union gbm_bo_handle {
+    pub ptr: *mut c_void,
+    pub s32: i32,
+    pub u32: u32,
+    pub s64: i64,
+    pub u64: u64,
+}
+
+/// Copies a function out of `library`.
+unsafe fn load<T: Copy>(library: &Library, name: &[u8]) -> io::Result<T> {
+    library.get::<T>(name).map(|symbol| *symbol)
+}
+
+pub struct Drm {
+    pub mode_get_resources: unsafe extern "C" fn(c_int) -> *mut drmModeRes,
+    pub mode_free_resources: unsafe extern "C" fn(*mut drmModeRes),
+    pub mode_get_connector:
+        unsafe extern "C" fn(c_int, u32) -> *mut drmModeConnector,
+    pub mode_free_connector: unsafe extern "C" fn(*mut drmModeConnector),
+    pub mode_get_encoder:
+        unsafe extern "C" fn(c_int, u32) -> *mut drmModeEncoder,
+    pub mode_free_encoder: unsafe extern "C" fn(*mut drmModeEncoder),
+    pub mode_get_crtc: unsafe extern "C" fn(c_int, u32) -> *mut drmModeCrtc,
+    pub mode_free_crtc: unsafe extern "C" fn(*mut drmModeCrtc),
+    pub mode_set_crtc: unsafe extern "C" fn(
+        c_int,
+        u32,
+        u32,
+        u32,
+        u32,
+        *mut u32,
+        c_int,
+        *mut drmModeModeInfo,
+    ) -> c_int,
+    pub mode_add_fb: unsafe extern "C" fn(
+        c_int,
+        u32,
+        u32,
+        u8,
+        u8,
+        u32,
+        u32,
+        *mut u32,
+    ) -> c_int,
+    pub mode_rm_fb: unsafe extern "C" fn(c_int, u32) -> c_int,
+    pub mode_page_flip:
+        unsafe extern "C" fn(c_int, u32, u32, u32, *mut c_void) -> c_int,
+    pub handle_event:
+        unsafe extern "C" fn(c_int, *mut drmEventContext) -> c_int,
+    _library: Library,
+}
+
+impl Drm {
+    pub fn open() -> io::Result<Self> {
+        let library = Library::new("libdrm.so.2")?;
+        unsafe {
+            Ok(Drm {
+                mode_get_resources: load(&library, b"drmModeGetResources\0")?,
+                mode_free_resources: load(
+                    &library,
+                    b"drmModeFreeResources\0",
+                )?,
+                mode_get_connector: load(&library, b"drmModeGetConnector\0")?,
+                mode_free_connector: load(
+                    &library,
+                    b"drmModeFreeConnector\0",
+                )?,
+                mode_get_encoder: load(&library, b"drmModeGetEncoder\0")?,
+                mode_free_encoder: load(&library, b"drmModeFreeEncoder\0")?,
+                mode_get_crtc: load(&library, b"drmModeGetCrtc\0")?,
+                mode_free_crtc: load(&library, b"drmModeFreeCrtc\0")?,
+                mode_set_crtc: load(&library, b"drmModeSetCrtc\0")?,
+                mode_add_fb: load(&library, b"drmModeAddFB\0")?,
+                mode_rm_fb: load(&library, b"drmModeRmFB\0")?,
+                mode_page_flip: load(&library, b"drmModePageFlip\0")?,
+                handle_event: load(&library, b"drmHandleEvent\0")?,
+                _library: library,
+            })
+        }
+    }
+}
+
+pub struct Gbm {
+    pub create_device: unsafe extern "C" fn(c_int) -> *mut gbm_device,
+    pub device_destroy: unsafe extern "C" fn(*mut gbm_device),
+    pub surface_create: unsafe extern "C" fn(
+        *mut gbm_device,
+        u32,
+        u32,
+        u32,
+        u32,
+    ) -> *mut gbm_surface,
+    pub surface_destroy: unsafe extern "C" fn(*mut gbm_surface),
+    pub surface_lock_front_buffer:
+        unsafe extern "C" fn(*mut gbm_surface) -> *mut gbm_bo,
+    pub surface_release_buffer:
+        unsafe extern "C" fn(*mut gbm_surface, *mut gbm_bo),
+    pub bo_get_handle: unsafe extern "C" fn(*mut gbm_bo) -> gbm_bo_handle,
+    pub bo_get_stride: unsafe extern "C" fn(*mut gbm_bo) -> u32,
+    _library: Library,
+}
+
+impl Gbm {
+    pub fn open() -> io::Result<Self> {
+        let library = Library::new("libgbm.so.1")?;
+        unsafe {
+            Ok(Gbm {
+                create_device: load(&library, b"gbm_create_device\0")?,
+                device_destroy: load(&library, b"gbm_device_destroy\0")?,
+                surface_create: load(&library, b"gbm_surface_create\0")?,
+                surface_destroy: load(&library, b"gbm_surface_destroy\0")?,
+                surface_lock_front_buffer: load(
+                    &library,
+                    b"gbm_surface_lock_front_buffer\0",
+                )?,
+                surface_release_buffer: load(
+                    &library,
+                    b"gbm_surface_release_buffer\0",
+                )?,
+                bo_get_handle: load(&library, b"gbm_bo_get_handle\0")?,
+                bo_get_stride: load(&library, b"gbm_bo_get_stride\0")?,
+                _library: library,
+            })
+        }
+    }
+}
@@ -0,0 +1,500 @@
+//! Direct rendering to a connector of a DRM device, without a display
+//! server, as VR compositors do with the connector of a headset leased to
+//! them.
+//!
+//! The frames are rendered to a `gbm_surface` through EGL, and each buffer
+//! swap flips the front buffer of the surface onto the CRTC driving the
+//! connector, on the next vertical blank.
+
+use api::egl::{self, Context as EglContext};
+use {ContextError, CreationError, GlAttributes, PixelFormatRequirements};
+
+use super::present::PresentFeedback;
+
+use libc;
+
+use std::io;
+use std::ops::Deref;
+use std::os::raw::{c_int, c_uint, c_ulong, c_void};
+use std::os::unix::io::RawFd;
+use std::slice;
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+
+mod ffi;
+
+pub struct Context {
+    /// Declared first, since the EGL surface must be destroyed before the
+    /// `gbm_surface` it renders to.
+    context: EglContext,
+    output: Output,
+}
+
+/// Derefs to the EGL context, whose methods that present the frames are
+/// replaced.
+impl Deref for Context {
+    type Target = EglContext;
+
+    fn deref(&self) -> &EglContext {
+        &self.context
+    }
+}
+
+impl Context {
+    /// Builds a context showing its frames on `connector_id`, in its
+    /// preferred mode.
+    ///
+    /// # Safety
+    ///
+    /// `fd` must be a DRM device, or a lease of one, that outlives the
+    /// context.
+    pub unsafe fn new(
+        fd: RawFd,
+        connector_id: u32,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        let drm = ffi::Drm::open()
+            .map_err(|err| CreationError::NoBackendAvailable(Box::new(err)))?;
+        let gbm = ffi::Gbm::open()
+            .map_err(|err| CreationError::NoBackendAvailable(Box::new(err)))?;
+        let (crtc_id, mode) = choose_crtc(&drm, fd, connector_id)?;
+
+        // the scanout buffers are `XRGB8888` unless alpha was requested
+        let (format, depth) = if pf_reqs.alpha_bits.unwrap_or(0) > 0 {
+            (ffi::GBM_FORMAT_ARGB8888, 32)
+        } else {
+            (ffi::GBM_FORMAT_XRGB8888, 24)
+        };
+
+        let device = (gbm.create_device)(fd);
+        if device.is_null() {
+            return Err(CreationError::OsError(
+                "`gbm_create_device` failed".to_string(),
+            ));
+        }
+        let surface = (gbm.surface_create)(
+            device,
+            u32::from(mode.hdisplay),
+            u32::from(mode.vdisplay),
+            format,
+            ffi::GBM_BO_USE_SCANOUT | ffi::GBM_BO_USE_RENDERING,
+        );
+        if surface.is_null() {
+            (gbm.device_destroy)(device);
+            return Err(CreationError::OsError(
+                "`gbm_surface_create` failed".to_string(),
+            ));
+        }
+
+        let saved_crtc = (drm.mode_get_crtc)(fd, crtc_id);
+        let output = Output {
+            drm,
+            gbm,
+            fd,
+            device,
+            surface,
+            connector_id,
+            crtc_id,
+            mode,
+            depth,
+            saved_crtc,
+            state: Mutex::new(FlipState {
+                front: None,
+                mode_set: false,
+                swaps: 0,
+                feedback: None,
+            }),
+        };
+
+        // the config must render in the format of the surface
+        let mut pf_reqs = pf_reqs.clone();
+        pf_reqs.x11_visual_xid = Some(format as c_ulong);
+        let gl_attr = gl_attr.clone().map_sharing(|c| &c.context);
+        let native_display =
+            egl::NativeDisplay::Gbm(Some(device as *const _));
+        let context = EglContext::new(&pf_reqs, &gl_attr, native_display)
+            .and_then(|p| p.finish(surface as *const _))?;
+
+        Ok(Context { context, output })
+    }
+
+    /// The mode is set by the context, so the surface never changes size.
+    #[inline]
+    pub fn resize(&self, _width: u32, _height: u32) {}
+
+    #[inline]
+    pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        self.swap_buffers_with_serial().map(|_| ())
+    }
+
+    /// The whole buffer is flipped onto the CRTC, so the damage doesn't
+    /// matter.
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        _rects: &[::Rect],
+    ) -> Result<(), ContextError> {
+        self.swap_buffers()
+    }
+
+    /// Swaps the buffers and shows the new front buffer, returning the
+    /// serial of the swap in the `PresentFeedback`s.
+    pub fn swap_buffers_with_serial(&self) -> Result<u64, ContextError> {
+        let mut state = self.output.state.lock().unwrap();
+        self.context.swap_buffers()?;
+        unsafe { self.output.present(&mut state) }
+    }
+
+    /// Returns a receiver of the timings of the page flips, reported by the
+    /// kernel.
+    pub fn present_feedback(
+        &self,
+    ) -> Result<Receiver<PresentFeedback>, ContextError> {
+        let (sender, receiver) = channel();
+        self.output.state.lock().unwrap().feedback = Some(sender);
+        Ok(receiver)
+    }
+}
+
+/// The buffers shown on the connector.
+struct FlipState {
+    /// The buffer on screen and its framebuffer, released once the next one
+    /// replaces it.
+    front: Option<(*mut ffi::gbm_bo, u32)>,
+    /// Whether the CRTC was set up, which the first frame does instead of
+    /// flipping.
+    mode_set: bool,
+    swaps: u64,
+    feedback: Option<Sender<PresentFeedback>>,
+}
+
+/// The GBM surface and the CRTC it is shown on.
+struct Output {
+    drm: ffi::Drm,
+    gbm: ffi::Gbm,
+    fd: RawFd,
+    device: *mut ffi::gbm_device,
+    surface: *mut ffi::gbm_surface,
+    connector_id: u32,
+    crtc_id: u32,
+    mode: ffi::drmModeModeInfo,
+    /// The depth of the framebuffers, which gives their format along with
+    /// the 32 bits per pixel.
+    depth: u8,
+    /// The configuration of the CRTC before the context took it over, if
+    /// it could be queried.
+    saved_crtc: *mut ffi::drmModeCrtc,
+    state: Mutex<FlipState>,
+}
+
+// the pointers are only used with `state` locked, or on drop
+unsafe impl Send for Output {}
+unsafe impl Sync for Output {}
+
+impl Output {
+    /// Shows the front buffer the EGL surface just swapped to, waiting for
+    /// the vertical blank.
+    unsafe fn present(
+        &self,
+        state: &mut FlipState,
+    ) -> Result<u64, ContextError> {
+        let bo = (self.gbm.surface_lock_front_buffer)(self.surface);
+        if bo.is_null() {
+            return Err(ContextError::OsError(
+                "`gbm_surface_lock_front_buffer` failed".to_string(),
+            ));
+        }
+        let fb = match self.add_fb(bo) {
+            Ok(fb) => fb,
+            Err(err) => {
+                (self.gbm.surface_release_buffer)(self.surface, bo);
+                return Err(err);
+            }
+        };
+
+        let flip = if state.mode_set {
+            self.page_flip(fb)
+        } else {
+            self.set_crtc(fb).map(|_| None)
+        };
+        let flip = match flip {
+            Ok(flip) => flip,
+            Err(err) => {
+                (self.drm.mode_rm_fb)(self.fd, fb);
+                (self.gbm.surface_release_buffer)(self.surface, bo);
+                return Err(err);
+            }
+        };
+        state.mode_set = true;
+
+        // the previous buffer is off the screen now
+        if let Some((bo, fb)) = state.front.replace((bo, fb)) {
+            (self.drm.mode_rm_fb)(self.fd, fb);
+            (self.gbm.surface_release_buffer)(self.surface, bo);
+        }
+
+        state.swaps += 1;
+        let serial = state.swaps;
+        if let (Some((sequence, sec, usec)), Some(feedback)) =
+            (flip, state.feedback.as_ref())
+        {
+            let feedback = feedback.send(PresentFeedback {
+                serial,
+                ust: u64::from(sec) * 1_000_000 + u64::from(usec),
+                msc: u64::from(sequence),
+                zero_copy: true,
+            });
+            if feedback.is_err() {
+                state.feedback = None;
+            }
+        }
+        Ok(serial)
+    }
+
+    unsafe fn add_fb(&self, bo: *mut ffi::gbm_bo) -> Result<u32, ContextError> {
+        let handle = (self.gbm.bo_get_handle)(bo).u32;
+        let stride = (self.gbm.bo_get_stride)(bo);
+        let mut fb = 0;
+        let res = (self.drm.mode_add_fb)(
+            self.fd,
+            u32::from(self.mode.hdisplay),
+            u32::from(self.mode.vdisplay),
+            self.depth,
+            32,
+            stride,
+            handle,
+            &mut fb,
+        );
+        if res != 0 {
+            return Err(ContextError::OsError(format!(
+                "`drmModeAddFB` failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        Ok(fb)
+    }
+
+    unsafe fn set_crtc(&self, fb: u32) -> Result<(), ContextError> {
+        let mut connector_id = self.connector_id;
+        let mut mode = self.mode;
+        let res = (self.drm.mode_set_crtc)(
+            self.fd,
+            self.crtc_id,
+            fb,
+            0,
+            0,
+            &mut connector_id,
+            1,
+            &mut mode,
+        );
+        if res != 0 {
+            return Err(ContextError::OsError(format!(
+                "`drmModeSetCrtc` failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+        Ok(())
+    }
+
+    /// Flips `fb` onto the CRTC and waits for the flip, returning the
+    /// vertical blank counter and the time it happened at.
+    unsafe fn page_flip(
+        &self,
+        fb: u32,
+    ) -> Result<Option<(c_uint, c_uint, c_uint)>, ContextError> {
+        let mut flip: Option<(c_uint, c_uint, c_uint)> = None;
+        let res = (self.drm.mode_page_flip)(
+            self.fd,
+            self.crtc_id,
+            fb,
+            ffi::DRM_MODE_PAGE_FLIP_EVENT,
+            &mut flip as *mut _ as *mut c_void,
+        );
+        if res != 0 {
+            return Err(ContextError::OsError(format!(
+                "`drmModePageFlip` failed: {}",
+                io::Error::last_os_error()
+            )));
+        }
+
+        let mut events = ffi::drmEventContext {
+            version: 2,
+            vblank_handler: None,
+            page_flip_handler: Some(page_flip_handler),
+        };
+        while flip.is_none() {
+            // the fd may be non-blocking
+            let mut pollfd = libc::pollfd {
+                fd: self.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            if libc::poll(&mut pollfd, 1, -1) < 0
+                || (self.drm.handle_event)(self.fd, &mut events) != 0
+            {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    continue;
+                }
+                return Err(ContextError::OsError(format!(
+                    "waiting for the page flip failed: {}",
+                    err
+                )));
+            }
+        }
+        Ok(flip)
+    }
+}
+
+extern "C" fn page_flip_handler(
+    _fd: c_int,
+    sequence: c_uint,
+    sec: c_uint,
+    usec: c_uint,
+    data: *mut c_void,
+) {
+    let flip = data as *mut Option<(c_uint, c_uint, c_uint)>;
+    unsafe { *flip = Some((sequence, sec, usec)) };
+}
+
+impl Drop for Output {
+    fn drop(&mut self) {
+        let state = match self.state.get_mut() {
+            Ok(state) => state,
+            Err(err) => err.into_inner(),
+        };
+        unsafe {
+            // give the CRTC back the way it was found, before its
+            // framebuffer goes away
+            if state.mode_set && !self.saved_crtc.is_null() {
+                let saved = &mut *self.saved_crtc;
+                (self.drm.mode_set_crtc)(
+                    self.fd,
+                    saved.crtc_id,
+                    saved.buffer_id,
+                    saved.x,
+                    saved.y,
+                    &mut self.connector_id,
+                    1,
+                    &mut saved.mode,
+                );
+            }
+            if let Some((bo, fb)) = state.front.take() {
+                (self.drm.mode_rm_fb)(self.fd, fb);
+                (self.gbm.surface_release_buffer)(self.surface, bo);
+            }
+            if !self.saved_crtc.is_null() {
+                (self.drm.mode_free_crtc)(self.saved_crtc);
+            }
+            (self.gbm.surface_destroy)(self.surface);
+            (self.gbm.device_destroy)(self.device);
+        }
+    }
+}
+
+/// Finds the CRTC driving `connector_id`, or one that can, and the mode to
+/// drive it with: the preferred one of the connector, or its first one.
+unsafe fn choose_crtc(
+    drm: &ffi::Drm,
+    fd: RawFd,
+    connector_id: u32,
+) -> Result<(u32, ffi::drmModeModeInfo), CreationError> {
+    let connector = (drm.mode_get_connector)(fd, connector_id);
+    if connector.is_null() {
+        return Err(CreationError::PlatformSpecific(format!(
+            "DRM connector {} not found",
+            connector_id
+        )));
+    }
+    let res = choose_connector_crtc(drm, fd, &*connector);
+    (drm.mode_free_connector)(connector);
+    res
+}
+
+unsafe fn choose_connector_crtc(
+    drm: &ffi::Drm,
+    fd: RawFd,
+    connector: &ffi::drmModeConnector,
+) -> Result<(u32, ffi::drmModeModeInfo), CreationError> {
+    if connector.connection != ffi::DRM_MODE_CONNECTED {
+        return Err(CreationError::PlatformSpecific(format!(
+            "DRM connector {} isn't connected",
+            connector.connector_id
+        )));
+    }
+
+    let modes = as_slice(connector.modes, connector.count_modes);
+    let mode = modes
+        .iter()
+        .find(|mode| mode.type_ & ffi::DRM_MODE_TYPE_PREFERRED != 0)
+        .or_else(|| modes.first());
+    let mode = match mode {
+        Some(mode) => *mode,
+        None => {
+            return Err(CreationError::PlatformSpecific(format!(
+                "DRM connector {} has no modes",
+                connector.connector_id
+            )));
+        }
+    };
+
+    // the CRTC already driving the connector
+    if connector.encoder_id != 0 {
+        let encoder = (drm.mode_get_encoder)(fd, connector.encoder_id);
+        if !encoder.is_null() {
+            let crtc_id = (*encoder).crtc_id;
+            (drm.mode_free_encoder)(encoder);
+            if crtc_id != 0 {
+                return Ok((crtc_id, mode));
+            }
+        }
+    }
+
+    // otherwise a CRTC one of its encoders can drive, which the resources
+    // list in the order of the bits of `possible_crtcs`
+    let resources = (drm.mode_get_resources)(fd);
+    if resources.is_null() {
+        return Err(CreationError::OsError(format!(
+            "`drmModeGetResources` failed: {}",
+            io::Error::last_os_error()
+        )));
+    }
+    let crtcs = as_slice((*resources).crtcs, (*resources).count_crtcs);
+    let encoders = as_slice(connector.encoders, connector.count_encoders);
+    let mut crtc_id = None;
+    for &encoder_id in encoders {
+        let encoder = (drm.mode_get_encoder)(fd, encoder_id);
+        if encoder.is_null() {
+            continue;
+        }
+        let possible = (*encoder).possible_crtcs;
+        (drm.mode_free_encoder)(encoder);
+        crtc_id = crtcs
+            .iter()
+            .enumerate()
+            .find(|&(i, _)| i < 32 && possible & (1 << i) != 0)
+            .map(|(_, &crtc)| crtc);
+        if crtc_id.is_some() {
+            break;
+        }
+    }
+    (drm.mode_free_resources)(resources);
+
+    match crtc_id {
+        Some(crtc_id) => Ok((crtc_id, mode)),
+        None => Err(CreationError::PlatformSpecific(format!(
+            "no CRTC can drive DRM connector {}",
+            connector.connector_id
+        ))),
+    }
+}
+
+/// The arrays of libdrm may be null when they're empty.
+unsafe fn as_slice<'a, T>(data: *const T, count: c_int) -> &'a [T] {
+    if data.is_null() || count <= 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, count as usize)
+    }
+}
@@ -0,0 +1,190 @@
+//! The gamma ramps of the CRTCs showing a monitor, through XRandR.
+
+use super::x11::XConnection;
+use {ContextError, GammaRamp};
+
+use winit;
+use winit::os::unix::x11::ffi;
+
+use std::slice;
+use std::sync::Arc;
+
+/// The gamma ramps the CRTCs had before glutin changed them, which are
+/// restored when this is dropped.
+pub struct SavedGamma {
+    xconn: Arc<XConnection>,
+    crtcs: Vec<(ffi::RRCrtc, GammaRamp)>,
+}
+
+impl SavedGamma {
+    pub fn new(xconn: Arc<XConnection>) -> Self {
+        SavedGamma {
+            xconn,
+            crtcs: Vec::new(),
+        }
+    }
+}
+
+impl Drop for SavedGamma {
+    fn drop(&mut self) {
+        for &(crtc, ref ramp) in &self.crtcs {
+            unsafe {
+                let _ = set_crtc_gamma(&self.xconn, crtc, ramp);
+            }
+        }
+        unsafe {
+            (self.xconn.xlib.XFlush)(self.xconn.display);
+        }
+    }
+}
+
+/// Returns the gamma ramp of the monitor `window` is on.
+pub fn get_gamma_ramp(
+    xconn: &XConnection,
+    window: &winit::Window,
+) -> Result<GammaRamp, ContextError> {
+    unsafe {
+        let crtcs = monitor_crtcs(xconn, window)?;
+        get_crtc_gamma(xconn, crtcs[0])
+    }
+}
+
+/// Sets the gamma ramp of the monitor `window` is on, saving the ramps of
+/// its CRTCs in `saved` first unless they already are.
+pub fn set_gamma_ramp(
+    xconn: &Arc<XConnection>,
+    window: &winit::Window,
+    ramp: &GammaRamp,
+    saved: &mut Option<SavedGamma>,
+) -> Result<(), ContextError> {
+    unsafe {
+        let crtcs = monitor_crtcs(xconn, window)?;
+        let saved =
+            saved.get_or_insert_with(|| SavedGamma::new(xconn.clone()));
+        for &crtc in &crtcs {
+            if saved.crtcs.iter().all(|&(other, _)| other != crtc) {
+                let original = get_crtc_gamma(xconn, crtc)?;
+                saved.crtcs.push((crtc, original));
+            }
+            set_crtc_gamma(xconn, crtc, ramp)?;
+        }
+        (xconn.xlib.XFlush)(xconn.display);
+    }
+    Ok(())
+}
+
+/// The active CRTCs inside the monitor `window` is on, several of them for
+/// monitors made of several tiles.
+unsafe fn monitor_crtcs(
+    xconn: &XConnection,
+    window: &winit::Window,
+) -> Result<Vec<ffi::RRCrtc>, ContextError> {
+    let monitor = window.get_current_monitor();
+    let (x, y): (i32, i32) = monitor.get_position().into();
+    let (width, height): (u32, u32) = monitor.get_dimensions().into();
+
+    let root = (xconn.xlib.XDefaultRootWindow)(xconn.display);
+    let resources =
+        (xconn.xrandr.XRRGetScreenResourcesCurrent)(xconn.display, root);
+    if resources.is_null() {
+        return Err(ContextError::OsError(
+            "`XRRGetScreenResourcesCurrent` failed".to_string(),
+        ));
+    }
+    let mut crtcs = Vec::new();
+    for &crtc in as_slice((*resources).crtcs, (*resources).ncrtc) {
+        let info =
+            (xconn.xrandr.XRRGetCrtcInfo)(xconn.display, resources, crtc);
+        if info.is_null() {
+            continue;
+        }
+        let info_ref = &*info;
+        if info_ref.noutput > 0
+            && info_ref.width > 0
+            && info_ref.x >= x
+            && info_ref.y >= y
+            && i64::from(info_ref.x) + i64::from(info_ref.width)
+                <= i64::from(x) + i64::from(width)
+            && i64::from(info_ref.y) + i64::from(info_ref.height)
+                <= i64::from(y) + i64::from(height)
+        {
+            crtcs.push(crtc);
+        }
+        (xconn.xrandr.XRRFreeCrtcInfo)(info);
+    }
+    (xconn.xrandr.XRRFreeScreenResources)(resources);
+
+    if crtcs.is_empty() {
+        Err(ContextError::OsError(
+            "no CRTC shows the monitor of the window".to_string(),
+        ))
+    } else {
+        Ok(crtcs)
+    }
+}
+
+unsafe fn get_crtc_gamma(
+    xconn: &XConnection,
+    crtc: ffi::RRCrtc,
+) -> Result<GammaRamp, ContextError> {
+    let gamma = (xconn.xrandr.XRRGetCrtcGamma)(xconn.display, crtc);
+    if gamma.is_null() {
+        return Err(ContextError::OsError(
+            "`XRRGetCrtcGamma` failed".to_string(),
+        ));
+    }
+    let size = (*gamma).size;
+    let ramp = GammaRamp {
+        red: as_slice((*gamma).red, size).to_vec(),
+        green: as_slice((*gamma).green, size).to_vec(),
+        blue: as_slice((*gamma).blue, size).to_vec(),
+    };
+    (xconn.xrandr.XRRFreeGamma)(gamma);
+    Ok(ramp)
+}
+
+unsafe fn set_crtc_gamma(
+    xconn: &XConnection,
+    crtc: ffi::RRCrtc,
+    ramp: &GammaRamp,
+) -> Result<(), ContextError> {
+    let size = (xconn.xrandr.XRRGetCrtcGammaSize)(xconn.display, crtc);
+    if size <= 0 {
+        return Err(ContextError::FunctionUnavailable);
+    }
+    let (red, green, blue) = match ramp.resample(size as usize) {
+        Some(channels) => channels,
+        None => {
+            return Err(ContextError::OsError(
+                "the channels of the gamma ramp must have the same length"
+                    .to_string(),
+            ));
+        }
+    };
+
+    let gamma = (xconn.xrandr.XRRAllocGamma)(size);
+    if gamma.is_null() {
+        return Err(ContextError::OsError(
+            "`XRRAllocGamma` failed".to_string(),
+        ));
+    }
+    let len = size as usize;
+    slice::from_raw_parts_mut((*gamma).red, len).copy_from_slice(&red);
+    slice::from_raw_parts_mut((*gamma).green, len).copy_from_slice(&green);
+    slice::from_raw_parts_mut((*gamma).blue, len).copy_from_slice(&blue);
+    (xconn.xrandr.XRRSetCrtcGamma)(xconn.display, crtc, gamma);
+    (xconn.xrandr.XRRFreeGamma)(gamma);
+
+    xconn.check_errors().map_err(|err| {
+        ContextError::OsError(format!("`XRRSetCrtcGamma` failed: {:?}", err))
+    })
+}
+
+/// The arrays of Xrandr may be null when they're empty.
+unsafe fn as_slice<'a, T>(data: *const T, count: i32) -> &'a [T] {
+    if data.is_null() || count <= 0 {
+        &[]
+    } else {
+        slice::from_raw_parts(data, count as usize)
+    }
+}
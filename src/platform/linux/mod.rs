@@ -10,18 +10,31 @@ use self::x11::X11Context;
 use api::egl;
 use api::glx;
 use {
-    ContextError, CreationError, GlAttributes, PixelFormat,
-    PixelFormatRequirements,
+    BackendInfo, ContextError, CreationError, DisplayToken, DriverWorkarounds,
+    GammaRamp, GlAttributes, Ownership, PbufferLimits, PixelFormat,
+    PixelFormatRequirements, PresentStats, Rect, ReleaseBehavior,
+    RenderBuffer, SurfaceDowngrade, SurfaceInfo, SurfaceVisibility,
+    SwapBehavior, SwapIntervalRange,
 };
 
 use winit;
 use winit::os::unix::EventsLoopExt;
 
+mod drm;
+mod gamma;
+mod present;
 mod wayland;
 mod x11;
 use api::osmesa;
 
+pub use self::gamma::SavedGamma;
+pub use self::present::PresentFeedback;
+
 use std::os::raw;
+use std::os::unix::io::RawFd;
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::Duration;
 
 /// Context handles available on Unix-like platforms.
 #[derive(Clone, Debug)]
@@ -30,18 +43,58 @@ pub enum RawHandle {
     Egl(egl::ffi::EGLContext),
 }
 
+/// What was current on a thread before a context was made current by
+/// `Context::make_current_scoped`.
+pub enum CurrentState {
+    Glx(glx::CurrentState),
+    Egl(egl::CurrentState),
+    OsMesa(osmesa::CurrentState),
+}
+
 pub enum ContextType {
     X11,
     Wayland,
     OsMesa,
+    Egl,
+    Drm,
 }
 
 pub enum Context {
     WindowedX11(x11::Context),
-    HeadlessX11(winit::Window, x11::Context),
+    /// The window is `None` when the context renders to a pixmap.
+    HeadlessX11(Option<winit::Window>, x11::Context),
     WindowedWayland(wayland::Context),
-    HeadlessWayland(winit::Window, wayland::Context),
+    /// The context comes first so that it is dropped before the window.
+    HeadlessWayland(wayland::Context, winit::Window),
     OsMesa(osmesa::OsMesaContext),
+    /// An EGL context without a window: either surfaceless, without a
+    /// display server, or created by another library.
+    Egl(egl::Context),
+    /// Shows its frames on a connector of a DRM device.
+    Drm(drm::Context),
+}
+
+/// See the docs in the crate root file.
+pub fn probe() -> Vec<BackendInfo> {
+    glx::probe().into_iter().chain(egl::probe()).collect()
+}
+
+/// See the docs in the crate root file. Returns the number of contexts
+/// alive if there are any.
+pub unsafe fn reload_symbols() -> Result<(), usize> {
+    let live = egl::live_contexts() + glx::live_contexts();
+    if live != 0 {
+        return Err(live);
+    }
+    egl::reload_symbols();
+    glx::reload_symbols();
+    Ok(())
+}
+
+/// See the docs in the crate root file.
+pub fn preload() {
+    egl::preload();
+    glx::preload();
 }
 
 impl Context {
@@ -50,31 +103,24 @@ impl Context {
         ct: ContextType,
     ) -> Result<(), CreationError> {
         if let Some(c) = *c {
-            match ct {
-                ContextType::OsMesa => match *c {
-                    Context::OsMesa(_) => Ok(()),
-                    _ => {
-                        let msg = "Cannot share an OSMesa context with a non-OSMesa context";
-                        return Err(CreationError::PlatformSpecific(msg.into()));
-                    }
-                },
-                ContextType::X11 => match *c {
-                    Context::WindowedX11(_) | Context::HeadlessX11(_, _) => {
-                        Ok(())
-                    }
-                    _ => {
-                        let msg = "Cannot share an X11 context with a non-X11 context";
-                        return Err(CreationError::PlatformSpecific(msg.into()));
-                    }
-                },
-                ContextType::Wayland => match *c {
+            let compatible = match ct {
+                ContextType::OsMesa => matches!(*c, Context::OsMesa(_)),
+                ContextType::X11 => matches!(
+                    *c,
+                    Context::WindowedX11(_) | Context::HeadlessX11(_, _)
+                ),
+                ContextType::Egl => matches!(*c, Context::Egl(_)),
+                ContextType::Wayland => matches!(
+                    *c,
                     Context::WindowedWayland(_)
-                    | Context::HeadlessWayland(_, _) => Ok(()),
-                    _ => {
-                        let msg = "Cannot share a Wayland context with a non-Wayland context";
-                        return Err(CreationError::PlatformSpecific(msg.into()));
-                    }
-                },
+                        | Context::HeadlessWayland(_, _)
+                ),
+                ContextType::Drm => matches!(*c, Context::Drm(_)),
+            };
+            if compatible {
+                Ok(())
+            } else {
+                Err(CreationError::IncompatibleSharing)
             }
         } else {
             Ok(())
@@ -93,7 +139,7 @@ impl Context {
 
             let gl_attr = gl_attr.clone().map_sharing(|ctx| match ctx {
                 &Context::WindowedWayland(ref ctx)
-                | &Context::HeadlessWayland(_, ref ctx) => ctx,
+                | &Context::HeadlessWayland(ref ctx, _) => ctx,
                 _ => unreachable!(),
             });
             wayland::Context::new(wb, el, pf_reqs, &gl_attr).map(
@@ -118,17 +164,23 @@ impl Context {
         pf_reqs: &PixelFormatRequirements,
         gl_attr: &GlAttributes<&Context>,
     ) -> Result<Self, CreationError> {
+        if pf_reqs.compute_only {
+            if let Ok(context) = Context::new_compute(pf_reqs, gl_attr) {
+                return Ok(context);
+            }
+        }
+
         let wb = winit::WindowBuilder::new().with_visibility(false);
 
         if el.is_wayland() {
             Context::is_compatible(&gl_attr.sharing, ContextType::Wayland)?;
             let gl_attr = gl_attr.clone().map_sharing(|ctx| match ctx {
                 &Context::WindowedWayland(ref ctx)
-                | &Context::HeadlessWayland(_, ref ctx) => ctx,
+                | &Context::HeadlessWayland(ref ctx, _) => ctx,
                 _ => unreachable!(),
             });
             wayland::Context::new(wb, &el, pf_reqs, &gl_attr).map(
-                |(window, context)| Context::HeadlessWayland(window, context),
+                |(window, context)| Context::HeadlessWayland(context, window),
             )
         } else {
             Context::is_compatible(&gl_attr.sharing, ContextType::X11)?;
@@ -138,8 +190,94 @@ impl Context {
                 _ => unreachable!(),
             });
             x11::Context::new(wb, &el, pf_reqs, &gl_attr)
-                .map(|(window, context)| Context::HeadlessX11(window, context))
+                .map(|(window, context)| {
+                    Context::HeadlessX11(Some(window), context)
+                })
+        }
+    }
+
+    /// Builds a context rendering to a new X11 pixmap.
+    #[inline]
+    pub fn new_x11_pixmap(
+        el: &winit::EventsLoop,
+        dimensions: (u32, u32),
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        if el.is_wayland() {
+            return Err(CreationError::NotSupported(
+                "X11 pixmaps are not available on Wayland",
+            ));
+        }
+
+        Context::is_compatible(&gl_attr.sharing, ContextType::X11)?;
+        let gl_attr = gl_attr.clone().map_sharing(|ctx| match ctx {
+            &Context::WindowedX11(ref ctx)
+            | &Context::HeadlessX11(_, ref ctx) => ctx,
+            _ => unreachable!(),
+        });
+        x11::Context::new_pixmap(el, dimensions, pf_reqs, &gl_attr)
+            .map(|context| Context::HeadlessX11(None, context))
+    }
+
+    /// Builds an EGL context without a surface or display server.
+    #[inline]
+    pub fn new_compute(
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        Context::is_compatible(&gl_attr.sharing, ContextType::Egl)?;
+        let gl_attr = gl_attr.clone().map_sharing(|ctx| match *ctx {
+            Context::Egl(ref ctx) => ctx,
+            _ => unreachable!(),
+        });
+        if egl::EGL.is_none() {
+            return Err(CreationError::NotSupported("libEGL not available"));
+        }
+        egl::Context::new(pf_reqs, &gl_attr, egl::NativeDisplay::Surfaceless)
+            .and_then(|prototype| prototype.finish_surfaceless())
+            .map(Context::Egl)
+    }
+
+    /// Builds an EGL context with a 1x1 pbuffer on the surfaceless platform
+    /// of Mesa.
+    #[inline]
+    pub fn new_surfaceless(
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        Context::is_compatible(&gl_attr.sharing, ContextType::Egl)?;
+        let gl_attr = gl_attr.clone().map_sharing(|ctx| match *ctx {
+            Context::Egl(ref ctx) => ctx,
+            _ => unreachable!(),
+        });
+        if !egl::has_surfaceless_platform() {
+            return Err(CreationError::NotSupported(
+                "EGL_MESA_platform_surfaceless not available",
+            ));
         }
+        egl::Context::new(pf_reqs, &gl_attr, egl::NativeDisplay::Surfaceless)
+            .and_then(|prototype| prototype.finish_pbuffer((1, 1)))
+            .map(Context::Egl)
+    }
+
+    #[inline]
+    pub fn choose_x11_visual(
+        el: &winit::EventsLoop,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+        transparent: bool,
+    ) -> Result<glx::ffi::XVisualInfo, CreationError> {
+        if el.is_wayland() {
+            return Err(CreationError::NotSupported(
+                "X11 visuals are not available on Wayland",
+            ));
+        }
+
+        let mut gl_attr = gl_attr.clone();
+        gl_attr.sharing = None;
+        let gl_attr = gl_attr.map_sharing(|_| unreachable!());
+        x11::Context::choose_visual(el, pf_reqs, &gl_attr, transparent)
     }
 
     #[inline]
@@ -154,7 +292,7 @@ impl Context {
 
             let gl_attr = gl_attr.clone().map_sharing(|ctx| match ctx {
                 &Context::WindowedWayland(ref ctx)
-                | &Context::HeadlessWayland(_, ref ctx) => ctx,
+                | &Context::HeadlessWayland(ref ctx, _) => ctx,
                 _ => unreachable!(),
             });
             wayland::Context::new_separated(window, el, pf_reqs, &gl_attr)
@@ -171,11 +309,127 @@ impl Context {
         }
     }
 
+    /// Builds a context rendering to an X11 window, without an events loop.
+    #[inline]
+    pub unsafe fn new_raw_x11_context(
+        xlib_window: raw::c_ulong,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        Context::is_compatible(&gl_attr.sharing, ContextType::X11)?;
+        let gl_attr = gl_attr.clone().map_sharing(|ctx| match ctx {
+            &Context::WindowedX11(ref ctx)
+            | &Context::HeadlessX11(_, ref ctx) => ctx,
+            _ => unreachable!(),
+        });
+        x11::Context::new_raw_context(xlib_window, pf_reqs, &gl_attr)
+            .map(Context::WindowedX11)
+    }
+
+    /// Builds a context showing its frames on a connector of a DRM device.
+    #[inline]
+    pub unsafe fn new_drm(
+        fd: RawFd,
+        connector_id: u32,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        Context::is_compatible(&gl_attr.sharing, ContextType::Drm)?;
+        let gl_attr = gl_attr.clone().map_sharing(|ctx| match *ctx {
+            Context::Drm(ref ctx) => ctx,
+            _ => unreachable!(),
+        });
+        drm::Context::new(fd, connector_id, pf_reqs, &gl_attr).map(Context::Drm)
+    }
+
     #[inline]
     pub fn resize(&self, width: u32, height: u32) {
         match *self {
             Context::WindowedX11(_) => (),
             Context::WindowedWayland(ref ctx) => ctx.resize(width, height),
+            Context::Drm(ref ctx) => ctx.resize(width, height),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Whether `resize` has to be called for the surface to follow the size
+    /// of the window.
+    #[inline]
+    pub fn needs_resize(&self) -> bool {
+        match *self {
+            // the `wl_egl_window` keeps its size until it's told otherwise
+            Context::WindowedWayland(_) => true,
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn resize_with_offset(
+        &self,
+        width: u32,
+        height: u32,
+        dx: i32,
+        dy: i32,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedWayland(ref ctx) => {
+                ctx.resize_with_offset(width, height, dx, dy);
+                Ok(())
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn resize_pbuffer(
+        &self,
+        _width: u32,
+        _height: u32,
+        _largest: bool,
+    ) -> Result<(u32, u32), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_pbuffer_size(&self) -> Option<(u32, u32)> {
+        None
+    }
+
+    #[inline]
+    pub unsafe fn current_state(&self) -> CurrentState {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.current_state(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                CurrentState::Egl(ctx.current_state())
+            }
+            Context::OsMesa(_) => {
+                CurrentState::OsMesa(osmesa::OsMesaContext::current_state())
+            }
+            Context::Egl(ref ctx) => CurrentState::Egl(ctx.current_state()),
+            Context::Drm(ref ctx) => CurrentState::Egl(ctx.current_state()),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        match (self, state) {
+            (Context::WindowedX11(ctx), _)
+            | (Context::HeadlessX11(_, ctx), _) => ctx.restore_current(state),
+            (Context::WindowedWayland(ctx), CurrentState::Egl(state))
+            | (Context::HeadlessWayland(ctx, _), CurrentState::Egl(state)) => {
+                ctx.restore_current(state)
+            }
+            (Context::OsMesa(ctx), CurrentState::OsMesa(state)) => {
+                ctx.restore_current(state)
+            }
+            (Context::Egl(ctx), CurrentState::Egl(state)) => {
+                ctx.restore_current(state)
+            }
+            (Context::Drm(ctx), CurrentState::Egl(state)) => {
+                ctx.restore_current(state)
+            }
             _ => unreachable!(),
         }
     }
@@ -186,8 +440,23 @@ impl Context {
             Context::WindowedX11(ref ctx)
             | Context::HeadlessX11(_, ref ctx) => ctx.make_current(),
             Context::WindowedWayland(ref ctx)
-            | Context::HeadlessWayland(_, ref ctx) => ctx.make_current(),
+            | Context::HeadlessWayland(ref ctx, _) => ctx.make_current(),
             Context::OsMesa(ref ctx) => ctx.make_current(),
+            Context::Egl(ref ctx) => ctx.make_current(),
+            Context::Drm(ref ctx) => ctx.make_current(),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.make_not_current(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => ctx.make_not_current(),
+            Context::OsMesa(ref ctx) => ctx.make_not_current(),
+            Context::Egl(ref ctx) => ctx.make_not_current(),
+            Context::Drm(ref ctx) => ctx.make_not_current(),
         }
     }
 
@@ -197,8 +466,10 @@ impl Context {
             Context::WindowedX11(ref ctx)
             | Context::HeadlessX11(_, ref ctx) => ctx.is_current(),
             Context::WindowedWayland(ref ctx)
-            | Context::HeadlessWayland(_, ref ctx) => ctx.is_current(),
+            | Context::HeadlessWayland(ref ctx, _) => ctx.is_current(),
             Context::OsMesa(ref ctx) => ctx.is_current(),
+            Context::Egl(ref ctx) => ctx.is_current(),
+            Context::Drm(ref ctx) => ctx.is_current(),
         }
     }
 
@@ -208,10 +479,12 @@ impl Context {
             Context::WindowedX11(ref ctx)
             | Context::HeadlessX11(_, ref ctx) => ctx.get_proc_address(addr),
             Context::WindowedWayland(ref ctx)
-            | Context::HeadlessWayland(_, ref ctx) => {
+            | Context::HeadlessWayland(ref ctx, _) => {
                 ctx.get_proc_address(addr)
             }
             Context::OsMesa(ref ctx) => ctx.get_proc_address(addr),
+            Context::Egl(ref ctx) => ctx.get_proc_address(addr),
+            Context::Drm(ref ctx) => ctx.get_proc_address(addr),
         }
     }
 
@@ -220,18 +493,76 @@ impl Context {
         match *self {
             Context::WindowedX11(ref ctx) => ctx.swap_buffers(),
             Context::WindowedWayland(ref ctx) => ctx.swap_buffers(),
+            Context::Egl(ref ctx) => ctx.swap_buffers(),
+            Context::Drm(ref ctx) => ctx.swap_buffers(),
+            _ => unreachable!(),
+        }
+    }
+
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => {
+                ctx.swap_buffers_with_damage(rects)
+            }
+            Context::WindowedWayland(ref ctx) => {
+                ctx.swap_buffers_with_damage(rects)
+            }
+            Context::Egl(ref ctx) => ctx.swap_buffers_with_damage(rects),
+            Context::Drm(ref ctx) => ctx.swap_buffers_with_damage(rects),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Waits for about `timeout`, in place of a swap while rendering is
+    /// paused.
+    #[inline]
+    pub fn wait_paused_frame(&self, timeout: Duration) {
+        match *self {
+            Context::WindowedWayland(ref ctx) => {
+                ctx.wait_frame_callback(timeout)
+            }
+            _ => thread::sleep(timeout),
+        }
+    }
+
+    #[inline]
+    pub fn swap_buffers_with_serial(&self) -> Result<u64, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => ctx.swap_buffers_with_serial(),
+            Context::WindowedWayland(ref ctx) => {
+                ctx.swap_buffers().map(|_| 0)
+            }
+            Context::Egl(ref ctx) => ctx.swap_buffers().map(|_| 0),
+            Context::Drm(ref ctx) => ctx.swap_buffers_with_serial(),
             _ => unreachable!(),
         }
     }
 
+    #[inline]
+    pub fn present_feedback(
+        &self,
+    ) -> Result<Receiver<PresentFeedback>, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => ctx.present_feedback(),
+            Context::Drm(ref ctx) => ctx.present_feedback(),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
     #[inline]
     pub fn get_api(&self) -> ::Api {
         match *self {
             Context::WindowedX11(ref ctx)
             | Context::HeadlessX11(_, ref ctx) => ctx.get_api(),
             Context::WindowedWayland(ref ctx)
-            | Context::HeadlessWayland(_, ref ctx) => ctx.get_api(),
+            | Context::HeadlessWayland(ref ctx, _) => ctx.get_api(),
             Context::OsMesa(ref ctx) => ctx.get_api(),
+            Context::Egl(ref ctx) => ctx.get_api(),
+            Context::Drm(ref ctx) => ctx.get_api(),
         }
     }
 
@@ -240,10 +571,389 @@ impl Context {
         match *self {
             Context::WindowedX11(ref ctx) => ctx.get_pixel_format(),
             Context::WindowedWayland(ref ctx) => ctx.get_pixel_format(),
+            Context::Egl(ref ctx) => ctx.get_pixel_format(),
+            Context::Drm(ref ctx) => ctx.get_pixel_format(),
             _ => unreachable!(),
         }
     }
 
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.display_token(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => ctx.display_token(),
+            Context::OsMesa(ref ctx) => ctx.display_token(),
+            Context::Egl(ref ctx) => ctx.display_token(),
+            Context::Drm(ref ctx) => ctx.display_token(),
+        }
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.get_release_behavior(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.get_release_behavior()
+            }
+            Context::OsMesa(ref ctx) => ctx.get_release_behavior(),
+            Context::Egl(ref ctx) => ctx.get_release_behavior(),
+            Context::Drm(ref ctx) => ctx.get_release_behavior(),
+        }
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.get_driver_workarounds(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.get_driver_workarounds()
+            }
+            Context::OsMesa(_) => DriverWorkarounds::none(),
+            Context::Egl(ref ctx) => ctx.get_driver_workarounds(),
+            Context::Drm(ref ctx) => ctx.get_driver_workarounds(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.get_surface_downgrades(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.get_surface_downgrades()
+            }
+            Context::OsMesa(_) => Vec::new(),
+            Context::Egl(ref ctx) => ctx.get_surface_downgrades(),
+            Context::Drm(ref ctx) => ctx.get_surface_downgrades(),
+        }
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.destroy(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => ctx.destroy(),
+            Context::OsMesa(_) => Ok(()),
+            Context::Egl(ref ctx) => ctx.destroy(),
+            Context::Drm(ref ctx) => ctx.destroy(),
+        }
+    }
+
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.get_pbuffer_limits(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => ctx.get_pbuffer_limits(),
+            Context::Egl(ref ctx) => ctx.get_pbuffer_limits(),
+            Context::Drm(ref ctx) => ctx.get_pbuffer_limits(),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.get_surface_info(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => ctx.get_surface_info(),
+            Context::OsMesa(ref ctx) => ctx.get_surface_info(),
+            Context::Egl(ref ctx) => ctx.get_surface_info(),
+            Context::Drm(ref ctx) => ctx.get_surface_info(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_visibility(
+        &self,
+        _window: &winit::Window,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => ctx.get_surface_visibility(),
+            Context::WindowedWayland(ref ctx) => ctx.get_surface_visibility(),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_gamma_ramp(
+        &self,
+        window: &winit::Window,
+    ) -> Result<GammaRamp, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => ctx.get_gamma_ramp(window),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_gamma_ramp(
+        &self,
+        window: &winit::Window,
+        ramp: &GammaRamp,
+        saved: &mut Option<SavedGamma>,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => {
+                ctx.set_gamma_ramp(window, ramp, saved)
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn is_vsync_forced(
+        &self,
+        _window: &winit::Window,
+    ) -> Result<bool, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => ctx.is_vsync_forced(),
+            // every frame goes through the compositor
+            Context::WindowedWayland(_) => Ok(true),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.set_swap_behavior(behavior)
+            }
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.set_swap_behavior(behavior)
+            }
+            Context::Egl(ref ctx) => ctx.set_swap_behavior(behavior),
+            Context::Drm(ref ctx) => ctx.set_swap_behavior(behavior),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.is_render_buffer_mutable()
+            }
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.is_render_buffer_mutable()
+            }
+            Context::Egl(ref ctx) => ctx.is_render_buffer_mutable(),
+            Context::Drm(ref ctx) => ctx.is_render_buffer_mutable(),
+            Context::OsMesa(_) => false,
+        }
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.set_render_buffer(render_buffer)
+            }
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.set_render_buffer(render_buffer)
+            }
+            Context::Egl(ref ctx) => ctx.set_render_buffer(render_buffer),
+            Context::Drm(ref ctx) => ctx.set_render_buffer(render_buffer),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.set_hdr_metadata(metadata)
+            }
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.set_hdr_metadata(metadata)
+            }
+            Context::Egl(ref ctx) => ctx.set_hdr_metadata(metadata),
+            Context::Drm(ref ctx) => ctx.set_hdr_metadata(metadata),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn join_swap_group(
+        &self,
+        group: u32,
+        barrier: u32,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.join_swap_group(group, barrier)
+            }
+            Context::WindowedWayland(_)
+            | Context::HeadlessWayland(_, _)
+            | Context::OsMesa(_)
+            | Context::Egl(_)
+            | Context::Drm(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_msc(
+        &self,
+        divisor: u32,
+        remainder: u32,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.set_swap_msc(divisor, remainder)
+            }
+            Context::WindowedWayland(_)
+            | Context::HeadlessWayland(_, _)
+            | Context::OsMesa(_)
+            | Context::Egl(_)
+            | Context::Drm(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx) => ctx.wait_for_vsync(),
+            Context::WindowedWayland(ref ctx) => ctx.wait_for_vsync(),
+            Context::HeadlessX11(_, _)
+            | Context::HeadlessWayland(_, _)
+            | Context::OsMesa(_)
+            | Context::Egl(_)
+            | Context::Drm(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        level: i32,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref ctx) => ctx.set_pbuffer_mipmap_level(level),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn bind_pbuffer_tex_image(
+        &self,
+        bind: bool,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref ctx) => ctx.bind_pbuffer_tex_image(bind),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.get_swap_interval_range(),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.get_swap_interval_range()
+            }
+            Context::Egl(ref ctx) => ctx.get_swap_interval_range(),
+            Context::Drm(ref ctx) => ctx.get_swap_interval_range(),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.set_swap_interval(interval)
+            }
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.set_swap_interval(interval)
+            }
+            Context::Egl(ref ctx) => ctx.set_swap_interval(interval),
+            Context::Drm(ref ctx) => ctx.set_swap_interval(interval),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn copy_state_from(
+        &self,
+        other: &Context,
+        mask: u32,
+    ) -> Result<(), ContextError> {
+        match (self, other) {
+            (
+                &Context::WindowedX11(ref ctx),
+                &Context::WindowedX11(ref other),
+            )
+            | (
+                &Context::WindowedX11(ref ctx),
+                &Context::HeadlessX11(_, ref other),
+            )
+            | (
+                &Context::HeadlessX11(_, ref ctx),
+                &Context::WindowedX11(ref other),
+            )
+            | (
+                &Context::HeadlessX11(_, ref ctx),
+                &Context::HeadlessX11(_, ref other),
+            ) => ctx.copy_state_from(other, mask),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.get_max_swap_groups(),
+            Context::WindowedWayland(_)
+            | Context::HeadlessWayland(_, _)
+            | Context::OsMesa(_)
+            | Context::Egl(_)
+            | Context::Drm(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> RawHandle {
         match *self {
@@ -254,10 +964,12 @@ impl Context {
                 X11Context::None => panic!(),
             },
             Context::WindowedWayland(ref ctx)
-            | Context::HeadlessWayland(_, ref ctx) => {
+            | Context::HeadlessWayland(ref ctx, _) => {
                 RawHandle::Egl(ctx.raw_handle())
             }
             Context::OsMesa(ref ctx) => RawHandle::Egl(ctx.raw_handle()),
+            Context::Egl(ref ctx) => RawHandle::Egl(ctx.raw_handle()),
+            Context::Drm(ref ctx) => RawHandle::Egl(ctx.raw_handle()),
         }
     }
 
@@ -267,11 +979,100 @@ impl Context {
             Context::WindowedX11(ref ctx)
             | Context::HeadlessX11(_, ref ctx) => ctx.get_egl_display(),
             Context::WindowedWayland(ref ctx)
-            | Context::HeadlessWayland(_, ref ctx) => ctx.get_egl_display(),
+            | Context::HeadlessWayland(ref ctx, _) => ctx.get_egl_display(),
+            Context::Egl(ref ctx) => Some(ctx.get_egl_display()),
+            Context::Drm(ref ctx) => Some(ctx.get_egl_display()),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.set_egl_label(label),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => ctx.set_egl_label(label),
+            Context::Egl(ref ctx) => ctx.set_label(label),
+            Context::Drm(ref ctx) => ctx.set_label(label),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn egl_display_tracks_references(&self) -> Option<bool> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.egl_display_tracks_references()
+            }
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.egl_display_tracks_references()
+            }
+            Context::Egl(ref ctx) => Some(ctx.egl_display_tracks_references()),
+            Context::Drm(ref ctx) => {
+                Some(ctx.egl_display_tracks_references())
+            }
             _ => None,
         }
     }
 
+    #[inline]
+    pub unsafe fn export_dmabuf(
+        &self,
+        texture: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<egl::DmaBuf, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => {
+                ctx.export_dmabuf(texture, width, height)
+            }
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => {
+                ctx.export_dmabuf(texture, width, height)
+            }
+            Context::Egl(ref ctx) => {
+                ctx.export_dmabuf(texture, width, height)
+            }
+            Context::Drm(ref ctx) => {
+                ctx.export_dmabuf(texture, width, height)
+            }
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn import_image(
+        &self,
+        builder: &egl::EglImageBuilder<'_>,
+    ) -> Result<egl::EglImage<'_>, ContextError> {
+        match *self {
+            Context::WindowedX11(ref ctx)
+            | Context::HeadlessX11(_, ref ctx) => ctx.import_image(builder),
+            Context::WindowedWayland(ref ctx)
+            | Context::HeadlessWayland(ref ctx, _) => ctx.import_image(builder),
+            Context::Egl(ref ctx) => ctx.import_image(builder),
+            Context::Drm(ref ctx) => ctx.import_image(builder),
+            Context::OsMesa(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    /// Wraps an EGL context created by another library.
+    #[inline]
+    pub unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: *const raw::c_void,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        egl::Context::from_raw(display, context, config, surface, ownership)
+            .map(Context::Egl)
+    }
+
     #[inline]
     fn new_osmesa(
         pf_reqs: &PixelFormatRequirements,
@@ -305,8 +1106,9 @@ impl OsMesaContextExt for crate::Context {
         Self: Sized,
     {
         let crate::ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
         let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
         Context::new_osmesa(&pf_reqs, &gl_attr)
-            .map(|context| crate::Context { context })
+            .map(|context| crate::Context::from_platform(context, sharing))
     }
 }
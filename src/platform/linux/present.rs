@@ -0,0 +1,207 @@
+use api::glx::ffi;
+
+use x11_dl::xpresent;
+
+use std::os::raw::{c_int, c_uint};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::Mutex;
+use std::{mem, ptr};
+
+/// The timing of a presented frame, from the Present extension.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct PresentFeedback {
+    /// The serial of the buffer swap that was presented, as returned by
+    /// `SwapBuffersExt::swap_buffers_with_serial`.
+    pub serial: u64,
+
+    /// The time at which the frame was presented, in microseconds.
+    pub ust: u64,
+
+    /// The value of the vertical blank counter when the frame was presented.
+    pub msc: u64,
+
+    /// Whether the buffer was flipped onto the screen instead of copied.
+    pub zero_copy: bool,
+}
+
+struct State {
+    /// The serial of the last `PresentNotifyMSC` request.
+    msc_serial: u32,
+    /// The number of swaps since the feedback was enabled.
+    swaps: u64,
+    /// The number of swaps whose completion was received.
+    completed: u64,
+    feedback: Option<Sender<PresentFeedback>>,
+}
+
+/// Listens to the Present extension on a window, to pace the buffer swaps on
+/// its vertical blanks instead of the swap interval of the driver, and to
+/// report when the swapped buffers are presented.
+///
+/// Under a compositor, the driver may wait for the vertical blank a second
+/// time while the compositor does, which causes stutter.
+pub struct PresentSync {
+    xlib: ffi::Xlib,
+    xpresent: xpresent::Xpresent,
+    /// A connection of our own, so that the events of the Present extension
+    /// don't go through the events loop.
+    display: *mut ffi::Display,
+    opcode: c_int,
+    window: ffi::Window,
+    /// Whether the swaps wait for the vertical blank.
+    vsync: bool,
+    state: Mutex<State>,
+}
+
+impl PresentSync {
+    /// Connects to the X server, returning `None` if the Present extension
+    /// isn't available.
+    pub fn new(vsync: bool) -> Option<Self> {
+        let xlib = ffi::Xlib::open().ok()?;
+        let xpresent = xpresent::Xpresent::open().ok()?;
+
+        unsafe {
+            let display = (xlib.XOpenDisplay)(ptr::null());
+            if display.is_null() {
+                return None;
+            }
+
+            let (mut opcode, mut event_base, mut error_base) = (0, 0, 0);
+            if (xpresent.XPresentQueryExtension)(
+                display,
+                &mut opcode,
+                &mut event_base,
+                &mut error_base,
+            ) == 0
+            {
+                (xlib.XCloseDisplay)(display);
+                return None;
+            }
+
+            Some(PresentSync {
+                xlib,
+                xpresent,
+                display,
+                opcode,
+                window: 0,
+                vsync,
+                state: Mutex::new(State {
+                    msc_serial: 0,
+                    swaps: 0,
+                    completed: 0,
+                    feedback: None,
+                }),
+            })
+        }
+    }
+
+    /// Starts listening to the vertical blanks of `window`.
+    pub fn set_window(&mut self, window: ffi::Window) {
+        unsafe {
+            self.window = window;
+            (self.xpresent.XPresentSelectInput)(
+                self.display,
+                window,
+                xpresent::PresentCompleteNotifyMask as c_uint,
+            );
+            (self.xlib.XFlush)(self.display);
+        }
+    }
+
+    /// Returns a receiver of the timings of the frames presented from now
+    /// on. The previous receiver, if any, stops receiving them.
+    pub fn subscribe(&self) -> Receiver<PresentFeedback> {
+        let (sender, receiver) = channel();
+        let mut state = self.state.lock().unwrap();
+        state.feedback = Some(sender);
+        receiver
+    }
+
+    /// Records a buffer swap, then waits for the next vertical blank of the
+    /// window if vsync is enabled. Returns the serial of the swap.
+    pub fn after_swap(&self) -> u64 {
+        let mut state = self.state.lock().unwrap();
+        state.swaps += 1;
+        let serial = state.swaps;
+
+        if self.vsync {
+            state.msc_serial = state.msc_serial.wrapping_add(1);
+            unsafe {
+                // the next MSC, whatever the current one is
+                (self.xpresent.XPresentNotifyMSC)(
+                    self.display,
+                    self.window,
+                    state.msc_serial,
+                    0,
+                    1,
+                    0,
+                );
+                (self.xlib.XFlush)(self.display);
+                while !self.next_event(&mut state, true) {}
+            }
+        }
+
+        // delivers the feedback of the previous swaps
+        unsafe {
+            while (self.xlib.XPending)(self.display) > 0 {
+                self.next_event(&mut state, false);
+            }
+        }
+        serial
+    }
+
+    /// Handles the next event of the connection, blocking until there is
+    /// one. Returns whether it is the completion of the last
+    /// `PresentNotifyMSC` request.
+    unsafe fn next_event(&self, state: &mut State, waiting: bool) -> bool {
+        let mut event: ffi::XEvent = mem::zeroed();
+        (self.xlib.XNextEvent)(self.display, &mut event);
+        if event.get_type() != ffi::GenericEvent {
+            return false;
+        }
+
+        let mut cookie = event.generic_event_cookie;
+        if cookie.extension != self.opcode
+            || (self.xlib.XGetEventData)(self.display, &mut cookie) == 0
+        {
+            return false;
+        }
+        let mut done = false;
+        if cookie.evtype == xpresent::PresentCompleteNotify {
+            let complete =
+                &*(cookie.data as *const xpresent::XPresentCompleteNotifyEvent);
+            match complete.kind as c_int {
+                xpresent::PresentCompleteKindNotifyMSC => {
+                    done = waiting
+                        && complete.serial_number == state.msc_serial;
+                }
+                // the swaps of the driver complete in order
+                xpresent::PresentCompleteKindPixmap => {
+                    state.completed += 1;
+                    let feedback = PresentFeedback {
+                        serial: state.completed,
+                        ust: complete.ust,
+                        msc: complete.msc,
+                        zero_copy: complete.mode as c_int
+                            == xpresent::PresentCompleteModeFlip,
+                    };
+                    if let Some(ref sender) = state.feedback {
+                        let _ = sender.send(feedback);
+                    }
+                }
+                _ => (),
+            }
+        }
+        (self.xlib.XFreeEventData)(self.display, &mut cookie);
+        done
+    }
+}
+
+impl Drop for PresentSync {
+    fn drop(&mut self) {
+        // this also stops listening to the window
+        unsafe {
+            (self.xlib.XCloseDisplay)(self.display);
+        }
+    }
+}
@@ -1,17 +1,52 @@
 use api::egl::{self, ffi, Context as EglContext};
 use std::os::raw;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
 use wayland_client::egl as wegl;
+use wayland_client::protocol::wl_surface::{RequestsTrait, WlSurface};
+use wayland_client::sys::client::{wl_display, WAYLAND_CLIENT_HANDLE};
+use wayland_client::Proxy;
 use winit;
 use winit::os::unix::WindowExt;
 use {
-    ContextError, CreationError, GlAttributes, PixelFormat,
-    PixelFormatRequirements,
+    ContextError, CreationError, DisplayToken, DriverWorkarounds,
+    GlAttributes, PbufferLimits, PixelFormat, PixelFormatRequirements, Rect,
+    ReleaseBehavior, RenderBuffer, SurfaceDowngrade, SurfaceInfo,
+    SurfaceVisibility, SwapBehavior, SwapIntervalRange,
 };
 
+/// How long a frame callback can stay pending before the surface is
+/// considered occluded.
+const FRAME_CALLBACK_TIMEOUT_MS: u64 = 1000;
+
+/// How often the frame callback is checked for while rendering is paused.
+const FRAME_CALLBACK_POLL_MS: u64 = 5;
+
+/// The display of the window, whose requests are flushed by the events loop
+/// except those sent by `wait_for_vsync`, which can't wait for it.
+struct Display(*mut wl_display);
+
+// libwayland-client can be used from any thread.
+unsafe impl Send for Display {}
+unsafe impl Sync for Display {}
+
 pub struct Context {
-    egl_surface: Arc<wegl::WlEglSurface>,
+    /// Declared first, since the EGL surface must be destroyed before the
+    /// `wl_egl_window` it renders to.
     context: EglContext,
+    /// Owned by the context, which creates and resizes it, since neither
+    /// winit nor the application know about it.
+    egl_surface: Arc<wegl::WlEglSurface>,
+    /// Held while the `wl_egl_window` is resized or swapped, which Mesa
+    /// doesn't synchronize, so that the window can be resized from another
+    /// thread than the one rendering.
+    egl_surface_lock: Mutex<()>,
+    surface: Proxy<WlSurface>,
+    /// When the pending frame callback was requested, if any. Compositors
+    /// hold the frame callbacks of surfaces that aren't shown.
+    frame_requested: Arc<Mutex<Option<Instant>>>,
+    display: Display,
 }
 
 impl Context {
@@ -34,8 +69,13 @@ impl Context {
         pf_reqs: &PixelFormatRequirements,
         gl_attr: &GlAttributes<&Context>,
     ) -> Result<Self, CreationError> {
-        let logical_size = window.get_inner_size().unwrap();
-        let (w, h) = (logical_size.width, logical_size.height);
+        // winit sets the buffer scale of the surface to the HiDPI factor, so
+        // the buffers must have the physical size
+        let (w, h): (u32, u32) = window
+            .get_inner_size()
+            .unwrap()
+            .to_physical(window.get_hidpi_factor())
+            .into();
         let surface = window.get_wayland_surface();
         let surface = match surface {
             Some(s) => s,
@@ -50,24 +90,52 @@ impl Context {
                 h as i32,
             )
         };
+        let display = window.get_wayland_display().unwrap();
         let context = {
             let gl_attr = gl_attr.clone().map_sharing(|c| &c.context);
-            let native_display = egl::NativeDisplay::Wayland(Some(
-                window.get_wayland_display().unwrap() as *const _,
-            ));
+            let native_display =
+                egl::NativeDisplay::Wayland(Some(display as *const _));
             EglContext::new(pf_reqs, &gl_attr, native_display)
                 .and_then(|p| p.finish(egl_surface.ptr() as *const _))?
         };
         let context = Context {
-            egl_surface: Arc::new(egl_surface),
             context: context,
+            egl_surface: Arc::new(egl_surface),
+            egl_surface_lock: Mutex::new(()),
+            surface: unsafe { Proxy::from_c_ptr(surface as *mut _) },
+            frame_requested: Arc::new(Mutex::new(None)),
+            display: Display(display as *mut _),
         };
         Ok(context)
     }
 
     #[inline]
     pub fn resize(&self, width: u32, height: u32) {
-        self.egl_surface.resize(width as i32, height as i32, 0, 0);
+        self.resize_with_offset(width, height, 0, 0);
+    }
+
+    /// Resizes the `wl_egl_window`, moving the content of the surface by
+    /// `dx` and `dy`. Both take effect with the next swap.
+    #[inline]
+    pub fn resize_with_offset(
+        &self,
+        width: u32,
+        height: u32,
+        dx: i32,
+        dy: i32,
+    ) {
+        let _lock = self.egl_surface_lock.lock().unwrap();
+        self.egl_surface.resize(width as i32, height as i32, dx, dy);
+    }
+
+    #[inline]
+    pub unsafe fn current_state(&self) -> egl::CurrentState {
+        self.context.current_state()
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &egl::CurrentState) {
+        self.context.restore_current(state)
     }
 
     #[inline]
@@ -75,6 +143,11 @@ impl Context {
         self.context.make_current()
     }
 
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        self.context.make_not_current()
+    }
+
     #[inline]
     pub fn is_current(&self) -> bool {
         self.context.is_current()
@@ -87,9 +160,87 @@ impl Context {
 
     #[inline]
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        self.request_frame();
+        let _lock = self.egl_surface_lock.lock().unwrap();
         self.context.swap_buffers()
     }
 
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        self.request_frame();
+        let _lock = self.egl_surface_lock.lock().unwrap();
+        self.context.swap_buffers_with_damage(rects)
+    }
+
+    /// Requests a frame callback, committed by the next swap, unless one is
+    /// already pending. It is dispatched along with the events of the
+    /// window.
+    fn request_frame(&self) {
+        let mut requested = self.frame_requested.lock().unwrap();
+        if requested.is_some() {
+            return;
+        }
+        let frame_requested = self.frame_requested.clone();
+        let callback = self.surface.frame(|callback| {
+            callback.implement(
+                move |_, _| *frame_requested.lock().unwrap() = None,
+                (),
+            )
+        });
+        if callback.is_ok() {
+            *requested = Some(Instant::now());
+        }
+    }
+
+    /// Waits until `timeout` elapses or the pending frame callback is
+    /// released, which the events loop does once the surface is shown.
+    pub fn wait_frame_callback(&self, timeout: Duration) {
+        if self.frame_requested.lock().unwrap().is_none() {
+            thread::sleep(timeout);
+            return;
+        }
+        let start = Instant::now();
+        while start.elapsed() < timeout {
+            thread::sleep(Duration::from_millis(FRAME_CALLBACK_POLL_MS));
+            if self.frame_requested.lock().unwrap().is_none() {
+                return;
+            }
+        }
+    }
+
+    /// Commits the surface with a frame callback but without a new buffer,
+    /// and waits for the compositor to release the callback when it next
+    /// repaints, or for the timeout after which the surface is considered
+    /// occluded.
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        {
+            let _lock = self.egl_surface_lock.lock().unwrap();
+            self.request_frame();
+            self.surface.commit();
+            // if the socket is full, the events loop sends the rest
+            let flush = &WAYLAND_CLIENT_HANDLE.wl_display_flush;
+            unsafe { flush(self.display.0) };
+        }
+        let timeout = Duration::from_millis(FRAME_CALLBACK_TIMEOUT_MS);
+        self.wait_frame_callback(timeout);
+        Ok(())
+    }
+
+    pub fn get_surface_visibility(
+        &self,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        let timeout = Duration::from_millis(FRAME_CALLBACK_TIMEOUT_MS);
+        match *self.frame_requested.lock().unwrap() {
+            Some(since) if since.elapsed() > timeout => {
+                Ok(SurfaceVisibility::Occluded)
+            }
+            _ => Ok(SurfaceVisibility::Visible),
+        }
+    }
+
     #[inline]
     pub fn get_api(&self) -> ::Api {
         self.context.get_api()
@@ -100,6 +251,82 @@ impl Context {
         self.context.get_pixel_format().clone()
     }
 
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        self.context.get_pbuffer_limits()
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        self.context.get_surface_info()
+    }
+
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        self.context.display_token()
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        self.context.get_release_behavior()
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        self.context.get_driver_workarounds()
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        self.context.get_surface_downgrades()
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        self.context.destroy()
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        self.context.get_swap_interval_range()
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        self.context.set_swap_interval(interval)
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        self.context.set_swap_behavior(behavior)
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        self.context.is_render_buffer_mutable()
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        self.context.set_render_buffer(render_buffer)
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        self.context.set_hdr_metadata(metadata)
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> ffi::EGLContext {
         self.context.raw_handle()
@@ -109,4 +336,32 @@ impl Context {
     pub unsafe fn get_egl_display(&self) -> Option<*const raw::c_void> {
         Some(self.context.get_egl_display())
     }
+
+    #[inline]
+    pub fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        self.context.set_label(label)
+    }
+
+    #[inline]
+    pub fn egl_display_tracks_references(&self) -> Option<bool> {
+        Some(self.context.egl_display_tracks_references())
+    }
+
+    #[inline]
+    pub unsafe fn export_dmabuf(
+        &self,
+        texture: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<egl::DmaBuf, ContextError> {
+        self.context.export_dmabuf(texture, width, height)
+    }
+
+    #[inline]
+    pub unsafe fn import_image(
+        &self,
+        builder: &egl::EglImageBuilder<'_>,
+    ) -> Result<egl::EglImage<'_>, ContextError> {
+        self.context.import_image(builder)
+    }
 }
@@ -1,21 +1,30 @@
 pub use winit::os::unix::x11::{XConnection, XError, XNotSupported};
 
+use std::ffi::CString;
 use std::os::raw;
-use std::sync::Arc;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex};
 use std::{error, fmt, mem, ptr};
 
 use winit;
 use winit::os::unix::{EventsLoopExt, WindowBuilderExt, WindowExt};
 
 use {
-    Api, ContextError, CreationError, GlAttributes, GlRequest, PixelFormat,
-    PixelFormatRequirements,
+    Api, ContextError, CreationError, DisplayToken, DriverWorkarounds,
+    GammaRamp, GlAttributes, GlRequest, PbufferLimits, PixelFormat,
+    PixelFormatRequirements, Rect, ReleaseBehavior, RenderBuffer,
+    SurfaceDowngrade, SurfaceInfo, SurfaceVisibility, SwapBehavior,
+    SwapIntervalRange, VsyncSource,
 };
 
 use api::egl;
 use api::egl::{Context as EglContext, EGL};
 use api::glx::{ffi, Context as GlxContext, GLX};
 
+use super::gamma::{self, SavedGamma};
+use super::CurrentState;
+use super::present::{PresentFeedback, PresentSync};
+
 #[derive(Debug)]
 struct NoX11Connection;
 
@@ -31,6 +40,87 @@ impl fmt::Display for NoX11Connection {
     }
 }
 
+/// Returns the screen requested with `ContextBuilderExt::with_x11_screen`,
+/// or the default screen of the display.
+fn choose_screen(
+    xconn: &XConnection,
+    pf_reqs: &PixelFormatRequirements,
+) -> Result<raw::c_int, CreationError> {
+    let count = unsafe { (xconn.xlib.XScreenCount)(xconn.display) };
+    match pf_reqs.x11_screen {
+        Some(screen) if screen < 0 || screen >= count => {
+            Err(CreationError::PlatformSpecific(format!(
+                "X screen {} doesn't exist, the display has {} screens",
+                screen, count
+            )))
+        }
+        Some(screen) => Ok(screen),
+        None => Ok(unsafe { (xconn.xlib.XDefaultScreen)(xconn.display) }),
+    }
+}
+
+/// GLX and EGL contexts can't share their objects, so the context shared
+/// with, if any, must use the backend the new one is built with.
+fn check_sharing(
+    gl_attr: &GlAttributes<&Context>,
+    egl: bool,
+) -> Result<(), CreationError> {
+    match gl_attr.sharing.map(|c| &c.context) {
+        Some(&X11Context::Glx(_)) if egl => {
+            Err(CreationError::IncompatibleSharing)
+        }
+        Some(&X11Context::Egl(_)) if !egl => {
+            Err(CreationError::IncompatibleSharing)
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Whether to try an EGL shipped with the executable before GLX for desktop
+/// OpenGL. It was put there to be used, so it is unless a GLX context is
+/// shared.
+fn tries_bundled_egl(gl_attr: &GlAttributes<&Context>) -> bool {
+    EGL.is_some() && egl::is_bundled() && check_sharing(gl_attr, true).is_ok()
+}
+
+enum Prototype<'a> {
+    Glx(::api::glx::ContextPrototype<'a>),
+    Egl(::api::egl::ContextPrototype<'a>),
+}
+
+impl<'a> Prototype<'a> {
+    fn get_visual_infos(&self, xconn: &XConnection) -> ffi::XVisualInfo {
+        match *self {
+            Prototype::Glx(ref p) => *p.get_visual_infos(),
+            Prototype::Egl(ref p) => {
+                let mut template: ffi::XVisualInfo = unsafe { mem::zeroed() };
+                template.visualid = p.get_native_visual_id() as ffi::VisualID;
+
+                let mut num_visuals = 0;
+                let vi = unsafe {
+                    (xconn.xlib.XGetVisualInfo)(
+                        xconn.display,
+                        ffi::VisualIDMask,
+                        &mut template,
+                        &mut num_visuals,
+                    )
+                };
+                xconn
+                    .check_errors()
+                    .expect("Failed to call `XGetVisualInfo`");
+                assert!(!vi.is_null());
+                assert!(num_visuals == 1);
+
+                let vi_copy = unsafe { ptr::read(vi as *const _) };
+                unsafe {
+                    (xconn.xlib.XFree)(vi as *mut _);
+                }
+                vi_copy
+            }
+        }
+    }
+}
+
 pub enum X11Context {
     Glx(GlxContext),
     Egl(EglContext),
@@ -41,6 +131,12 @@ pub struct Context {
     xconn: Arc<XConnection>,
     colormap: ffi::Colormap,
     context: X11Context,
+    window: ffi::Window,
+    /// The pixmap rendered to instead of `window`, owned by the context.
+    pixmap: Option<ffi::Pixmap>,
+    /// Set when the compositor is the vsync source or once the present
+    /// feedback is requested.
+    present: Mutex<Option<PresentSync>>,
 }
 
 unsafe impl Send for Context {}
@@ -53,7 +149,14 @@ impl Drop for Context {
             // context is still the current one
             self.context = X11Context::None;
 
-            (self.xconn.xlib.XFreeColormap)(self.xconn.display, self.colormap);
+            if let Some(pixmap) = self.pixmap {
+                (self.xconn.xlib.XFreePixmap)(self.xconn.display, pixmap);
+            } else {
+                (self.xconn.xlib.XFreeColormap)(
+                    self.xconn.display,
+                    self.colormap,
+                );
+            }
         }
     }
 }
@@ -76,15 +179,20 @@ impl Context {
         };
 
         // Get the screen_id for the window being built.
-        let screen_id = unsafe { (xconn.xlib.XDefaultScreen)(xconn.display) };
+        let screen_id = choose_screen(&xconn, pf_reqs)?;
 
-        // start the context building process
-        enum Prototype<'a> {
-            Glx(::api::glx::ContextPrototype<'a>),
-            Egl(::api::egl::ContextPrototype<'a>),
-        }
+        // when the compositor is the vsync source, the driver mustn't wait
+        let mut present = if gl_attr.vsync
+            && pf_reqs.vsync_source == VsyncSource::Compositor
+        {
+            PresentSync::new(true)
+        } else {
+            None
+        };
 
-        let builder = gl_attr.clone();
+        // start the context building process
+        let mut builder = gl_attr.clone();
+        builder.vsync = gl_attr.vsync && present.is_none();
 
         let builder_glx_u;
         let builder_egl_u;
@@ -94,8 +202,43 @@ impl Context {
             | GlRequest::Specific(Api::OpenGl, _)
             | GlRequest::GlThenGles { .. } => {
                 // GLX should be preferred over EGL, otherwise crashes may occur
-                // on X11 – issue #314
-                if let Some(_) = *GLX {
+                // on X11 – issue #314, unless an EGL was shipped with the
+                // executable
+                if tries_bundled_egl(&builder) {
+                    builder_egl_u =
+                        builder.clone().map_sharing(|c| match c.context {
+                            X11Context::Egl(ref c) => c,
+                            _ => panic!(),
+                        });
+                    let native_display = egl::NativeDisplay::X11(Some(
+                        xconn.display as *const _,
+                    ));
+                    let prototype = EglContext::new(
+                        pf_reqs,
+                        &builder_egl_u,
+                        native_display,
+                    );
+                    match prototype {
+                        Ok(prototype) => Prototype::Egl(prototype),
+                        // like WGL on Windows, GLX takes over unless an EGL
+                        // context is shared
+                        Err(_)
+                            if GLX.is_some() && builder.sharing.is_none() =>
+                        {
+                            builder_glx_u =
+                                builder.map_sharing(|_| unreachable!());
+                            Prototype::Glx(GlxContext::new(
+                                Arc::clone(&xconn),
+                                pf_reqs,
+                                &builder_glx_u,
+                                screen_id,
+                                wb.window.transparent,
+                            )?)
+                        }
+                        Err(err) => return Err(err),
+                    }
+                } else if GLX.is_some() {
+                    check_sharing(&builder, false)?;
                     builder_glx_u = builder.map_sharing(|c| match c.context {
                         X11Context::Glx(ref c) => c,
                         _ => panic!(),
@@ -107,7 +250,8 @@ impl Context {
                         screen_id,
                         wb.window.transparent,
                     )?)
-                } else if let Some(_) = *EGL {
+                } else if EGL.is_some() {
+                    check_sharing(&builder, true)?;
                     builder_egl_u = builder.map_sharing(|c| match c.context {
                         X11Context::Egl(ref c) => c,
                         _ => panic!(),
@@ -127,7 +271,8 @@ impl Context {
                 }
             }
             GlRequest::Specific(Api::OpenGlEs, _) => {
-                if let Some(_) = *EGL {
+                if EGL.is_some() {
+                    check_sharing(&builder, true)?;
                     builder_egl_u = builder.map_sharing(|c| match c.context {
                         X11Context::Egl(ref c) => c,
                         _ => panic!(),
@@ -139,9 +284,24 @@ impl Context {
                             xconn.display as *const _,
                         )),
                     )?)
+                } else if GLX.is_some() {
+                    // GLX can still give us an ES context through
+                    // `GLX_EXT_create_context_es2_profile`.
+                    check_sharing(&builder, false)?;
+                    builder_glx_u = builder.map_sharing(|c| match c.context {
+                        X11Context::Glx(ref c) => c,
+                        _ => panic!(),
+                    });
+                    Prototype::Glx(GlxContext::new(
+                        Arc::clone(&xconn),
+                        pf_reqs,
+                        &builder_glx_u,
+                        screen_id,
+                        wb.window.transparent,
+                    )?)
                 } else {
                     return Err(CreationError::NotSupported(
-                        "libEGL not present",
+                        "both libglx and libEGL not present",
                     ));
                 }
             }
@@ -154,34 +314,7 @@ impl Context {
 
         // getting the `visual_infos` (a struct that contains information about
         // the visual to use)
-        let visual_infos = match context {
-            Prototype::Glx(ref p) => p.get_visual_infos().clone(),
-            Prototype::Egl(ref p) => {
-                let mut template: ffi::XVisualInfo = unsafe { mem::zeroed() };
-                template.visualid = p.get_native_visual_id() as ffi::VisualID;
-
-                let mut num_visuals = 0;
-                let vi = unsafe {
-                    (xconn.xlib.XGetVisualInfo)(
-                        xconn.display,
-                        ffi::VisualIDMask,
-                        &mut template,
-                        &mut num_visuals,
-                    )
-                };
-                xconn
-                    .check_errors()
-                    .expect("Failed to call `XGetVisualInfo`");
-                assert!(!vi.is_null());
-                assert!(num_visuals == 1);
-
-                let vi_copy = unsafe { ptr::read(vi as *const _) };
-                unsafe {
-                    (xconn.xlib.XFree)(vi as *mut _);
-                }
-                vi_copy
-            }
-        };
+        let visual_infos = context.get_visual_infos(&xconn);
 
         let window = wb
             .with_x11_visual(&visual_infos as *const _)
@@ -196,6 +329,9 @@ impl Context {
                 X11Context::Egl(ctx.finish(xlib_window as _)?)
             }
         };
+        if let Some(ref mut present) = present {
+            present.set_window(xlib_window);
+        }
 
         // getting the root window
         let root = unsafe { (xconn.xlib.XDefaultRootWindow)(xconn.display) };
@@ -221,11 +357,200 @@ impl Context {
             xconn: Arc::clone(&xconn),
             context,
             colormap,
+            window: xlib_window,
+            pixmap: None,
+            present: Mutex::new(present),
         };
 
         Ok((window, context))
     }
 
+    /// Picks the visual of the config a context built with these attributes
+    /// would use, so that a window can be created with it beforehand.
+    pub fn choose_visual(
+        el: &winit::EventsLoop,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+        transparent: bool,
+    ) -> Result<ffi::XVisualInfo, CreationError> {
+        let xconn = match el.get_xlib_xconnection() {
+            Some(xconn) => xconn,
+            None => {
+                return Err(CreationError::NoBackendAvailable(Box::new(
+                    NoX11Connection,
+                )));
+            }
+        };
+        let screen_id = choose_screen(&xconn, pf_reqs)?;
+
+        // the context being shared with doesn't matter to the config
+        let mut gl_attr = gl_attr.clone();
+        gl_attr.sharing = None;
+        let glx_attr = gl_attr.clone().map_sharing(|_| unreachable!());
+        let egl_attr = gl_attr.clone().map_sharing(|_| unreachable!());
+
+        // same choice of backend as when building the context
+        let (use_egl, glx_fallback) = match gl_attr.version {
+            GlRequest::Specific(Api::OpenGlEs, _) => (EGL.is_some(), false),
+            GlRequest::Specific(Api::OpenGl, _)
+            | GlRequest::Latest
+            | GlRequest::GlThenGles { .. } => (
+                GLX.is_none() || tries_bundled_egl(&gl_attr),
+                GLX.is_some(),
+            ),
+            GlRequest::Specific(_, _) => {
+                return Err(CreationError::NotSupported(
+                    "requested specific without gl or gles",
+                ));
+            }
+        };
+
+        let egl_prototype = if use_egl {
+            if EGL.is_none() {
+                return Err(CreationError::NotSupported(
+                    "both libglx and libEGL not present",
+                ));
+            }
+            let native_display =
+                egl::NativeDisplay::X11(Some(xconn.display as *const _));
+            Some(EglContext::new(pf_reqs, &egl_attr, native_display))
+        } else {
+            None
+        };
+        let prototype = match egl_prototype {
+            Some(Ok(prototype)) => Prototype::Egl(prototype),
+            Some(Err(err)) if !glx_fallback => return Err(err),
+            _ => Prototype::Glx(GlxContext::new(
+                Arc::clone(&xconn),
+                pf_reqs,
+                &glx_attr,
+                screen_id,
+                transparent,
+            )?),
+        };
+
+        Ok(prototype.get_visual_infos(&xconn))
+    }
+
+    /// Builds a context rendering to a new pixmap of the given size, with the
+    /// depth of the visual of the chosen config.
+    pub fn new_pixmap(
+        el: &winit::EventsLoop,
+        dimensions: (u32, u32),
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        let xconn = match el.get_xlib_xconnection() {
+            Some(xconn) => xconn,
+            None => {
+                return Err(CreationError::NoBackendAvailable(Box::new(
+                    NoX11Connection,
+                )));
+            }
+        };
+        let screen_id = choose_screen(&xconn, pf_reqs)?;
+
+        // pixmaps only have a front buffer, and are never presented
+        let mut pf_reqs = pf_reqs.clone();
+        pf_reqs.x11_pixmap = true;
+        pf_reqs.double_buffer = Some(false);
+        let mut builder = gl_attr.clone();
+        builder.vsync = false;
+
+        // same choice of backend as when building a context on a window
+        let (use_egl, glx_fallback) = match gl_attr.version {
+            GlRequest::Specific(Api::OpenGlEs, _) => (EGL.is_some(), false),
+            GlRequest::Specific(Api::OpenGl, _)
+            | GlRequest::Latest
+            | GlRequest::GlThenGles { .. } => (
+                GLX.is_none() || tries_bundled_egl(&builder),
+                GLX.is_some() && builder.sharing.is_none(),
+            ),
+            GlRequest::Specific(_, _) => {
+                return Err(CreationError::NotSupported(
+                    "requested specific without gl or gles",
+                ));
+            }
+        };
+
+        check_sharing(&builder, use_egl)?;
+        let glx_attr;
+        let egl_attr;
+        let egl_prototype = if use_egl {
+            if EGL.is_none() {
+                return Err(CreationError::NotSupported(
+                    "both libglx and libEGL not present",
+                ));
+            }
+            egl_attr = builder.clone().map_sharing(|c| match c.context {
+                X11Context::Egl(ref c) => c,
+                _ => panic!(),
+            });
+            let native_display =
+                egl::NativeDisplay::X11(Some(xconn.display as *const _));
+            Some(EglContext::new(&pf_reqs, &egl_attr, native_display))
+        } else {
+            None
+        };
+        let prototype = match egl_prototype {
+            Some(Ok(prototype)) => Prototype::Egl(prototype),
+            Some(Err(err)) if !glx_fallback => return Err(err),
+            _ => {
+                glx_attr = builder.map_sharing(|c| match c.context {
+                    X11Context::Glx(ref c) => c,
+                    _ => panic!(),
+                });
+                Prototype::Glx(GlxContext::new(
+                    Arc::clone(&xconn),
+                    &pf_reqs,
+                    &glx_attr,
+                    screen_id,
+                    false,
+                )?)
+            }
+        };
+
+        let visual_infos = prototype.get_visual_infos(&xconn);
+        let pixmap = unsafe {
+            let root = (xconn.xlib.XRootWindow)(xconn.display, screen_id);
+            (xconn.xlib.XCreatePixmap)(
+                xconn.display,
+                root,
+                dimensions.0,
+                dimensions.1,
+                visual_infos.depth as raw::c_uint,
+            )
+        };
+        xconn.check_errors().map_err(|err| {
+            CreationError::OsError(format!("`XCreatePixmap` failed: {:?}", err))
+        })?;
+
+        let context = match prototype {
+            Prototype::Glx(ctx) => {
+                ctx.finish_pixmap(pixmap).map(X11Context::Glx)
+            }
+            Prototype::Egl(ctx) => {
+                ctx.finish_pixmap(pixmap as _).map(X11Context::Egl)
+            }
+        };
+        let context = match context {
+            Ok(context) => context,
+            Err(err) => {
+                unsafe { (xconn.xlib.XFreePixmap)(xconn.display, pixmap) };
+                return Err(err);
+            }
+        };
+
+        Ok(Context {
+            xconn: Arc::clone(&xconn),
+            context,
+            colormap: 0,
+            window: 0,
+            pixmap: Some(pixmap),
+            present: Mutex::new(None),
+        })
+    }
+
     #[inline]
     pub fn new_separated(
         window: &winit::Window,
@@ -242,9 +567,34 @@ impl Context {
             }
         };
 
-        let screen_id = window.get_xlib_screen_id().unwrap();
-
         let xlib_window = window.get_xlib_window().unwrap();
+        Context::new_on_window(xconn, xlib_window, pf_reqs, gl_attr)
+    }
+
+    /// Builds a context rendering to `xlib_window`, which must outlive it,
+    /// through a new connection to the X server.
+    pub unsafe fn new_raw_context(
+        xlib_window: raw::c_ulong,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        let xlib = ffi::Xlib::open()
+            .map_err(|err| CreationError::NoBackendAvailable(Box::new(err)))?;
+        // `XConnection::new` replaces the error handler of the process, so
+        // give it back the one of the application
+        let error_handler = (xlib.XSetErrorHandler)(None);
+        let xconn = XConnection::new(error_handler)
+            .map_err(|err| CreationError::NoBackendAvailable(Box::new(err)))?;
+
+        Context::new_on_window(Arc::new(xconn), xlib_window, pf_reqs, gl_attr)
+    }
+
+    fn new_on_window(
+        xconn: Arc<XConnection>,
+        xlib_window: raw::c_ulong,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
         let attrs = {
             let mut attrs = unsafe { ::std::mem::uninitialized() };
             unsafe {
@@ -256,6 +606,8 @@ impl Context {
             }
             attrs
         };
+        let screen_id =
+            unsafe { (xconn.xlib.XScreenNumberOfScreen)(attrs.screen) };
 
         let visual_xid =
             unsafe { (xconn.xlib.XVisualIDFromVisual)(attrs.visual) };
@@ -263,13 +615,18 @@ impl Context {
         pf_reqs.x11_visual_xid = Some(visual_xid);
         pf_reqs.depth_bits = Some(attrs.depth as _);
 
-        // start the context building process
-        enum Prototype<'a> {
-            Glx(::api::glx::ContextPrototype<'a>),
-            Egl(::api::egl::ContextPrototype<'a>),
-        }
+        // when the compositor is the vsync source, the driver mustn't wait
+        let mut present = if gl_attr.vsync
+            && pf_reqs.vsync_source == VsyncSource::Compositor
+        {
+            PresentSync::new(true)
+        } else {
+            None
+        };
 
-        let builder = gl_attr.clone();
+        // start the context building process
+        let mut builder = gl_attr.clone();
+        builder.vsync = gl_attr.vsync && present.is_none();
 
         let builder_glx_u;
         let builder_egl_u;
@@ -279,8 +636,45 @@ impl Context {
             | GlRequest::Specific(Api::OpenGl, _)
             | GlRequest::GlThenGles { .. } => {
                 // GLX should be preferred over EGL, otherwise crashes may occur
-                // on X11 – issue #314
-                if let Some(_) = *GLX {
+                // on X11 – issue #314, unless an EGL was shipped with the
+                // executable
+                if tries_bundled_egl(&builder) {
+                    builder_egl_u =
+                        builder.clone().map_sharing(|c| match c.context {
+                            X11Context::Egl(ref c) => c,
+                            _ => panic!(),
+                        });
+                    let native_display = egl::NativeDisplay::X11(Some(
+                        xconn.display as *const _,
+                    ));
+                    let prototype = EglContext::new(
+                        &pf_reqs,
+                        &builder_egl_u,
+                        native_display,
+                    );
+                    match prototype {
+                        Ok(prototype) => Prototype::Egl(prototype),
+                        // like WGL on Windows, GLX takes over unless an EGL
+                        // context is shared
+                        Err(_)
+                            if GLX.is_some() && builder.sharing.is_none() =>
+                        {
+                            builder_glx_u =
+                                builder.map_sharing(|_| unreachable!());
+                            Prototype::Glx(GlxContext::new(
+                                Arc::clone(&xconn),
+                                &pf_reqs,
+                                &builder_glx_u,
+                                screen_id,
+                                // We assume they don't want transparency,
+                                // as we can't know.
+                                false,
+                            )?)
+                        }
+                        Err(err) => return Err(err),
+                    }
+                } else if GLX.is_some() {
+                    check_sharing(&builder, false)?;
                     builder_glx_u = builder.map_sharing(|c| match c.context {
                         X11Context::Glx(ref c) => c,
                         _ => panic!(),
@@ -294,7 +688,8 @@ impl Context {
                         // know.
                         false,
                     )?)
-                } else if let Some(_) = *EGL {
+                } else if EGL.is_some() {
+                    check_sharing(&builder, true)?;
                     builder_egl_u = builder.map_sharing(|c| match c.context {
                         X11Context::Egl(ref c) => c,
                         _ => panic!(),
@@ -314,7 +709,8 @@ impl Context {
                 }
             }
             GlRequest::Specific(Api::OpenGlEs, _) => {
-                if let Some(_) = *EGL {
+                if EGL.is_some() {
+                    check_sharing(&builder, true)?;
                     builder_egl_u = builder.map_sharing(|c| match c.context {
                         X11Context::Egl(ref c) => c,
                         _ => panic!(),
@@ -326,9 +722,26 @@ impl Context {
                             xconn.display as *const _,
                         )),
                     )?)
+                } else if GLX.is_some() {
+                    // GLX can still give us an ES context through
+                    // `GLX_EXT_create_context_es2_profile`.
+                    check_sharing(&builder, false)?;
+                    builder_glx_u = builder.map_sharing(|c| match c.context {
+                        X11Context::Glx(ref c) => c,
+                        _ => panic!(),
+                    });
+                    Prototype::Glx(GlxContext::new(
+                        Arc::clone(&xconn),
+                        &pf_reqs,
+                        &builder_glx_u,
+                        screen_id,
+                        // We assume they don't want transparency, as we can't
+                        // know.
+                        false,
+                    )?)
                 } else {
                     return Err(CreationError::NotSupported(
-                        "libEGL not present",
+                        "both libglx and libEGL not present",
                     ));
                 }
             }
@@ -346,6 +759,9 @@ impl Context {
                 X11Context::Egl(ctx.finish(xlib_window as _)?)
             }
         };
+        if let Some(ref mut present) = present {
+            present.set_window(xlib_window);
+        }
 
         // getting the root window
         let root = unsafe { (xconn.xlib.XDefaultRootWindow)(xconn.display) };
@@ -371,11 +787,38 @@ impl Context {
             xconn: Arc::clone(&xconn),
             context,
             colormap,
+            window: xlib_window,
+            pixmap: None,
+            present: Mutex::new(present),
         };
 
         Ok(context)
     }
 
+    #[inline]
+    pub unsafe fn current_state(&self) -> CurrentState {
+        match self.context {
+            X11Context::Glx(_) => {
+                CurrentState::Glx(GlxContext::current_state())
+            }
+            X11Context::Egl(ref ctx) => CurrentState::Egl(ctx.current_state()),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        match (&self.context, state) {
+            (X11Context::Glx(ctx), CurrentState::Glx(state)) => {
+                ctx.restore_current(state)
+            }
+            (X11Context::Egl(ctx), CurrentState::Egl(state)) => {
+                ctx.restore_current(state)
+            }
+            _ => unreachable!(),
+        }
+    }
+
     #[inline]
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         match self.context {
@@ -385,6 +828,15 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.make_not_current(),
+            X11Context::Egl(ref ctx) => ctx.make_not_current(),
+            X11Context::None => Ok(()),
+        }
+    }
+
     #[inline]
     pub fn is_current(&self) -> bool {
         match self.context {
@@ -405,13 +857,206 @@ impl Context {
 
     #[inline]
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        self.swap_buffers_with_serial().map(|_| ())
+    }
+
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.swap_buffers(),
+            X11Context::Egl(ref ctx) => ctx.swap_buffers_with_damage(rects),
+            X11Context::None => Ok(()),
+        }?;
+        self.after_swap();
+        Ok(())
+    }
+
+    /// Swaps the buffers, returning the serial of the swap that the present
+    /// feedback refers to.
+    pub fn swap_buffers_with_serial(&self) -> Result<u64, ContextError> {
         match self.context {
             X11Context::Glx(ref ctx) => ctx.swap_buffers(),
             X11Context::Egl(ref ctx) => ctx.swap_buffers(),
             X11Context::None => Ok(()),
+        }?;
+        Ok(self.after_swap())
+    }
+
+    /// Waits for the vertical blank if the compositor is the vsync source,
+    /// and delivers the present feedback received so far.
+    fn after_swap(&self) -> u64 {
+        match *self.present.lock().unwrap() {
+            Some(ref present) => present.after_swap(),
+            None => 0,
+        }
+    }
+
+    pub fn get_gamma_ramp(
+        &self,
+        window: &winit::Window,
+    ) -> Result<GammaRamp, ContextError> {
+        gamma::get_gamma_ramp(&self.xconn, window)
+    }
+
+    pub fn set_gamma_ramp(
+        &self,
+        window: &winit::Window,
+        ramp: &GammaRamp,
+        saved: &mut Option<SavedGamma>,
+    ) -> Result<(), ContextError> {
+        gamma::set_gamma_ramp(&self.xconn, window, ramp, saved)
+    }
+
+    /// Windows that aren't viewable are either iconified or, with most
+    /// window managers, on another workspace. Whether a viewable window is
+    /// covered is only reported through events that winit doesn't expose.
+    pub fn get_surface_visibility(
+        &self,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        let xlib = &self.xconn.xlib;
+        let display = self.xconn.display;
+        unsafe {
+            let mut attrs: ffi::XWindowAttributes = mem::zeroed();
+            if (xlib.XGetWindowAttributes)(display, self.window, &mut attrs)
+                == 0
+            {
+                return Err(ContextError::OsError(
+                    "XGetWindowAttributes failed".to_string(),
+                ));
+            }
+            if attrs.map_state == ffi::IsViewable {
+                return Ok(SurfaceVisibility::Visible);
+            }
+
+            // the WM_STATE property of ICCCM tells iconified windows apart
+            let wm_state = (xlib.XInternAtom)(
+                display,
+                b"WM_STATE\0".as_ptr() as *const _,
+                ffi::True,
+            );
+            if wm_state == 0 {
+                return Ok(SurfaceVisibility::Occluded);
+            }
+            let mut actual_type = 0;
+            let mut actual_format = 0;
+            let mut items = 0;
+            let mut bytes_after = 0;
+            let mut data = ptr::null_mut();
+            let res = (xlib.XGetWindowProperty)(
+                display,
+                self.window,
+                wm_state,
+                0,
+                1,
+                ffi::False,
+                wm_state,
+                &mut actual_type,
+                &mut actual_format,
+                &mut items,
+                &mut bytes_after,
+                &mut data,
+            );
+            let iconic = res == ffi::Success as raw::c_int
+                && !data.is_null()
+                && actual_format == 32
+                && items >= 1
+                // `IconicState`
+                && *(data as *const raw::c_ulong) == 3;
+            if !data.is_null() {
+                (xlib.XFree)(data as *mut _);
+            }
+
+            if iconic {
+                Ok(SurfaceVisibility::Minimized)
+            } else {
+                Ok(SurfaceVisibility::Occluded)
+            }
         }
     }
 
+    /// A compositing manager owns the `_NET_WM_CM_Sn` selection of its
+    /// screen, and redirects every window unless the window asks to bypass
+    /// it through `_NET_WM_BYPASS_COMPOSITOR`. Compositors may also
+    /// unredirect fullscreen windows on their own, which can't be detected.
+    pub fn is_vsync_forced(&self) -> Result<bool, ContextError> {
+        let xlib = &self.xconn.xlib;
+        let display = self.xconn.display;
+        unsafe {
+            let mut attrs: ffi::XWindowAttributes = mem::zeroed();
+            if (xlib.XGetWindowAttributes)(display, self.window, &mut attrs)
+                == 0
+            {
+                return Err(ContextError::OsError(
+                    "XGetWindowAttributes failed".to_string(),
+                ));
+            }
+            let screen = (xlib.XScreenNumberOfScreen)(attrs.screen);
+            let name =
+                CString::new(format!("_NET_WM_CM_S{}", screen)).unwrap();
+            let selection =
+                (xlib.XInternAtom)(display, name.as_ptr(), ffi::False);
+            if (xlib.XGetSelectionOwner)(display, selection) == 0 {
+                return Ok(false);
+            }
+
+            let bypass = (xlib.XInternAtom)(
+                display,
+                b"_NET_WM_BYPASS_COMPOSITOR\0".as_ptr() as *const _,
+                ffi::True,
+            );
+            if bypass == 0 {
+                return Ok(true);
+            }
+            let mut actual_type = 0;
+            let mut actual_format = 0;
+            let mut items = 0;
+            let mut bytes_after = 0;
+            let mut data = ptr::null_mut();
+            let res = (xlib.XGetWindowProperty)(
+                display,
+                self.window,
+                bypass,
+                0,
+                1,
+                ffi::False,
+                ffi::XA_CARDINAL,
+                &mut actual_type,
+                &mut actual_format,
+                &mut items,
+                &mut bytes_after,
+                &mut data,
+            );
+            // `1` asks to bypass the compositor, `2` not to
+            let bypassed = res == ffi::Success as raw::c_int
+                && !data.is_null()
+                && actual_format == 32
+                && items >= 1
+                && *(data as *const raw::c_ulong) == 1;
+            if !data.is_null() {
+                (xlib.XFree)(data as *mut _);
+            }
+            Ok(!bypassed)
+        }
+    }
+
+    pub fn present_feedback(
+        &self,
+    ) -> Result<Receiver<PresentFeedback>, ContextError> {
+        let mut present = self.present.lock().unwrap();
+        if present.is_none() {
+            let mut sync = match PresentSync::new(false) {
+                Some(sync) => sync,
+                None => return Err(ContextError::FunctionUnavailable),
+            };
+            sync.set_window(self.window);
+            *present = Some(sync);
+        }
+        Ok(present.as_ref().unwrap().subscribe())
+    }
+
     #[inline]
     pub fn get_api(&self) -> Api {
         match self.context {
@@ -430,6 +1075,191 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.display_token(),
+            X11Context::Egl(ref ctx) => ctx.display_token(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.get_release_behavior(),
+            X11Context::Egl(ref ctx) => ctx.get_release_behavior(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.get_driver_workarounds(),
+            X11Context::Egl(ref ctx) => ctx.get_driver_workarounds(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        match self.context {
+            X11Context::Glx(_) => Vec::new(),
+            X11Context::Egl(ref ctx) => ctx.get_surface_downgrades(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Egl(ref ctx) => ctx.destroy(),
+            X11Context::Glx(_) | X11Context::None => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.get_pbuffer_limits(),
+            X11Context::Egl(ref ctx) => ctx.get_pbuffer_limits(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.get_surface_info(),
+            X11Context::Egl(ref ctx) => ctx.get_surface_info(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(_) => Err(ContextError::FunctionUnavailable),
+            X11Context::Egl(ref ctx) => ctx.set_swap_behavior(behavior),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        match self.context {
+            X11Context::Glx(_) => false,
+            X11Context::Egl(ref ctx) => ctx.is_render_buffer_mutable(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(_) => Err(ContextError::FunctionUnavailable),
+            X11Context::Egl(ref ctx) => ctx.set_render_buffer(render_buffer),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(_) => Err(ContextError::FunctionUnavailable),
+            X11Context::Egl(ref ctx) => ctx.set_hdr_metadata(metadata),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn join_swap_group(
+        &self,
+        group: u32,
+        barrier: u32,
+    ) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.join_swap_group(group, barrier),
+            X11Context::Egl(_) => Err(ContextError::FunctionUnavailable),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_msc(
+        &self,
+        divisor: u32,
+        remainder: u32,
+    ) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.set_swap_msc(divisor, remainder),
+            X11Context::Egl(_) => Err(ContextError::FunctionUnavailable),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.wait_for_vsync(),
+            X11Context::Egl(_) => Err(ContextError::FunctionUnavailable),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.get_swap_interval_range(),
+            X11Context::Egl(ref ctx) => ctx.get_swap_interval_range(),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.set_swap_interval(interval),
+            X11Context::Egl(ref ctx) => ctx.set_swap_interval(interval),
+            X11Context::None => panic!(),
+        }
+    }
+
+    #[inline]
+    pub fn copy_state_from(
+        &self,
+        other: &Context,
+        mask: u32,
+    ) -> Result<(), ContextError> {
+        match (&self.context, &other.context) {
+            (X11Context::Glx(ctx), X11Context::Glx(other)) => {
+                ctx.copy_state_from(other, mask)
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        match self.context {
+            X11Context::Glx(ref ctx) => ctx.get_max_swap_groups(),
+            X11Context::Egl(_) => Err(ContextError::FunctionUnavailable),
+            X11Context::None => panic!(),
+        }
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> &X11Context {
         &self.context
@@ -442,4 +1272,48 @@ impl Context {
             _ => None,
         }
     }
+
+    #[inline]
+    pub fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        match self.context {
+            X11Context::Egl(ref ctx) => ctx.set_label(label),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn egl_display_tracks_references(&self) -> Option<bool> {
+        match self.context {
+            X11Context::Egl(ref ctx) => {
+                Some(ctx.egl_display_tracks_references())
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub unsafe fn export_dmabuf(
+        &self,
+        texture: u32,
+        width: u32,
+        height: u32,
+    ) -> Result<egl::DmaBuf, ContextError> {
+        match self.context {
+            X11Context::Egl(ref ctx) => {
+                ctx.export_dmabuf(texture, width, height)
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn import_image(
+        &self,
+        builder: &egl::EglImageBuilder<'_>,
+    ) -> Result<egl::EglImage<'_>, ContextError> {
+        match self.context {
+            X11Context::Egl(ref ctx) => ctx.import_image(builder),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
 }
@@ -1,11 +1,21 @@
+use cgl::{
+    kCGLNoError, kCGLPFAAccelerated, kCGLPFAAlphaSize, kCGLPFAColorSize,
+    kCGLPFADepthSize, kCGLPFADoubleBuffer, kCGLPFASampleBuffers,
+    kCGLPFASamples, kCGLPFAStencilSize, kCGLPFAStereo, CGLChoosePixelFormat,
+    CGLDescribePixelFormat, CGLDestroyPixelFormat, CGLPixelFormatAttribute,
+    CGLPixelFormatObj,
+};
 use cocoa::appkit::*;
-use cocoa::base::nil;
 use CreationError;
 use GlAttributes;
 use GlProfile;
 use GlRequest;
+use PixelFormat;
 use PixelFormatRequirements;
 use ReleaseBehavior;
+use TransparentType;
+
+use std::ptr;
 
 pub fn get_gl_profile<T>(
     opengl: &GlAttributes<&T>,
@@ -19,7 +29,8 @@ pub fn get_gl_profile<T>(
         if version.unwrap_or((2, 1)) < (3, 2) {
             Ok(NSOpenGLProfileVersionLegacy)
         } else {
-            Err(CreationError::OpenGlVersionNotSupported)
+            // only core profiles are available past 2.1
+            Err(CreationError::ProfileNotSupported)
         }
     } else if let Some(v) = version {
         // second, process exact requested version, if any
@@ -58,11 +69,10 @@ pub fn get_gl_profile<T>(
             &[NSOpenGLProfileVersion4_1Core, NSOpenGLProfileVersion3_2Core]
         {
             attributes[current_idx] = profile as u32;
-            let id = unsafe {
-                NSOpenGLPixelFormat::alloc(nil).initWithAttributes_(&attributes)
-            };
-            if id != nil {
-                unsafe { msg_send![id, release] }
+            if let Some(pixel_format) =
+                unsafe { choose_cgl_pixel_format(&attributes) }
+            {
+                unsafe { CGLDestroyPixelFormat(pixel_format) };
                 return Ok(profile);
             }
         }
@@ -119,16 +129,35 @@ pub fn build_nsattributes(
         unimplemented!(); // TODO:
     }
 
+    if pf_reqs.plane != 0 {
+        return Err(CreationError::NotSupported(
+            "layer planes are not supported on macOS",
+        ));
+    }
+
+    if pf_reqs.transparent_type != TransparentType::None {
+        return Err(CreationError::NotSupported(
+            "transparent configs are not supported on macOS",
+        ));
+    }
+
     if pf_reqs.float_color_buffer {
         attributes.push(NSOpenGLPFAColorFloat as u32);
     }
 
-    if let Some(samples) = pf_reqs.multisampling {
-        attributes.push(NSOpenGLPFAMultisample as u32);
-        attributes.push(NSOpenGLPFASampleBuffers as u32);
-        attributes.push(1);
-        attributes.push(NSOpenGLPFASamples as u32);
-        attributes.push(samples as u32);
+    match pf_reqs.samples() {
+        Some(0) => {
+            attributes.push(NSOpenGLPFASampleBuffers as u32);
+            attributes.push(0);
+        }
+        Some(samples) => {
+            attributes.push(NSOpenGLPFAMultisample as u32);
+            attributes.push(NSOpenGLPFASampleBuffers as u32);
+            attributes.push(1);
+            attributes.push(NSOpenGLPFASamples as u32);
+            attributes.push(samples as u32);
+        }
+        None => (),
     }
 
     // attribute list must be null terminated.
@@ -136,3 +165,65 @@ pub fn build_nsattributes(
 
     Ok(attributes)
 }
+
+/// Chooses a CGL pixel format, whose attributes have the same values as
+/// the `NSOpenGLPixelFormatAttribute`s, without going through Cocoa.
+///
+/// The pixel format must be destroyed with `CGLDestroyPixelFormat`.
+pub unsafe fn choose_cgl_pixel_format(
+    attributes: &[u32],
+) -> Option<CGLPixelFormatObj> {
+    let attributes = attributes
+        .iter()
+        .map(|&attribute| attribute as CGLPixelFormatAttribute)
+        .collect::<Vec<_>>();
+    let mut pixel_format = ptr::null_mut();
+    let mut count = 0;
+    let err = CGLChoosePixelFormat(
+        attributes.as_ptr(),
+        &mut pixel_format,
+        &mut count,
+    );
+    if err != kCGLNoError || pixel_format.is_null() {
+        None
+    } else {
+        Some(pixel_format)
+    }
+}
+
+/// Describes the first virtual screen of a CGL pixel format.
+pub unsafe fn describe_cgl_pixel_format(
+    pixel_format: CGLPixelFormatObj,
+) -> PixelFormat {
+    let get_attr = |attrib: CGLPixelFormatAttribute| -> i32 {
+        let mut value = 0;
+        CGLDescribePixelFormat(pixel_format, 0, attrib, &mut value);
+        value
+    };
+
+    PixelFormat {
+        hardware_accelerated: get_attr(kCGLPFAAccelerated) != 0,
+        color_bits: (get_attr(kCGLPFAColorSize) - get_attr(kCGLPFAAlphaSize))
+            as u8,
+        alpha_bits: get_attr(kCGLPFAAlphaSize) as u8,
+        depth_bits: get_attr(kCGLPFADepthSize) as u8,
+        stencil_bits: get_attr(kCGLPFAStencilSize) as u8,
+        stereoscopy: get_attr(kCGLPFAStereo) != 0,
+        double_buffer: get_attr(kCGLPFADoubleBuffer) != 0,
+        sample_buffers: get_attr(kCGLPFASampleBuffers) as u8,
+        multisampling: if get_attr(kCGLPFASampleBuffers) > 0
+            && get_attr(kCGLPFASamples) > 1
+        {
+            Some(get_attr(kCGLPFASamples) as u16)
+        } else {
+            None
+        },
+        // CGL has no coverage sampling
+        coverage_samples: None,
+        srgb: true,
+        transparent: None,
+        native_renderable: false,
+        x11_visual_type: None,
+        native_id: None,
+    }
+}
@@ -2,38 +2,113 @@
 
 pub use winit::MonitorId;
 
+use BackendInfo;
 use ContextError;
 use CreationError;
+use DisplayToken;
+use DriverWorkarounds;
 use GlAttributes;
+use Ownership;
+use PbufferLimits;
 use PixelFormat;
 use PixelFormatRequirements;
+use PresentStats;
+use Rect;
+use ReleaseBehavior;
+use RenderBuffer;
 use Robustness;
+use SurfaceDowngrade;
+use SurfaceInfo;
+use SurfaceVisibility;
+use SwapBehavior;
+use SwapIntervalRange;
 
+use api::egl::{self, Context as EglContext, EGL};
 use cgl::{
-    kCGLCECrashOnRemovedFunctions, kCGLCPSurfaceOpacity, CGLEnable,
-    CGLSetParameter,
+    kCGLCECrashOnRemovedFunctions, kCGLCPSurfaceOpacity, kCGLNoError,
+    CGLContextObj, CGLCreateContext, CGLDestroyContext, CGLDestroyPixelFormat,
+    CGLEnable, CGLGetCurrentContext, CGLSetCurrentContext, CGLSetParameter,
 };
 use cocoa::appkit::{self, NSOpenGLContext, NSOpenGLPixelFormat};
 use cocoa::base::{id, nil};
-use cocoa::foundation::NSAutoreleasePool;
+use cocoa::foundation::{NSAutoreleasePool, NSUInteger};
 use core_foundation::base::TCFType;
 use core_foundation::bundle::{
     CFBundleGetBundleWithIdentifier, CFBundleGetFunctionPointerForName,
 };
 use core_foundation::string::CFString;
-use objc::runtime::{BOOL, NO};
+use objc::runtime::{BOOL, NO, YES};
 use winit;
 use winit::os::macos::WindowExt;
 
 use std::ops::Deref;
 use std::os::raw::c_void;
+use std::ptr;
 use std::str::FromStr;
+use std::thread;
+use std::time::Duration;
 
 mod helpers;
 
+#[link(name = "OpenGL", kind = "framework")]
+extern "C" {
+    fn CGLTexImageIOSurface2D(
+        ctx: *mut c_void,
+        target: u32,
+        internal_format: u32,
+        width: i32,
+        height: i32,
+        format: u32,
+        ty: u32,
+        io_surface: *mut c_void,
+        plane: u32,
+    ) -> i32;
+}
+
+const GL_RGBA: u32 = 0x1908;
+const GL_BGRA: u32 = 0x80E1;
+const GL_UNSIGNED_INT_8_8_8_8_REV: u32 = 0x8367;
+
+/// What was current on a thread before a context was made current by
+/// `Context::make_current_scoped`.
+pub enum CurrentState {
+    Cgl(CGLContextObj),
+    Egl(egl::CurrentState),
+}
+
 pub enum Context {
     WindowedContext(WindowedContext),
     HeadlessContext(HeadlessContext),
+    /// A context of the EGL shipped with the executable, rendering to the
+    /// layer of the view, or created by another library.
+    Egl(EglContext),
+    /// A context of the EGL shipped with the executable, rendering to a
+    /// pbuffer or to no surface at all.
+    HeadlessEgl(EglContext),
+}
+
+// NSOpenGL and CGL contexts can be used from any thread as long as they are
+// current on one thread at a time, which is up to the application as on the
+// other platforms. Only `update` must run on the main thread, and it is sent
+// there.
+unsafe impl Send for Context {}
+unsafe impl Sync for Context {}
+
+/// Sends `update` to `context` on the main thread, since AppKit isn't
+/// thread-safe, without waiting for it so that a main thread waiting for the
+/// rendering thread can't deadlock.
+unsafe fn update_on_main_thread(context: id) {
+    let is_main_thread: BOOL = msg_send![class!(NSThread), isMainThread];
+    if is_main_thread != NO {
+        let _: () = msg_send![context, update];
+    } else {
+        let _: () = msg_send![
+            context,
+            performSelectorOnMainThread: sel!(update)
+            withObject: nil
+            waitUntilDone: NO
+        ];
+    }
 }
 
 pub struct WindowedContext {
@@ -42,8 +117,40 @@ pub struct WindowedContext {
     pixel_format: PixelFormat,
 }
 
+/// A context built with CGL alone, which needs neither Cocoa nor the main
+/// thread. It has no default framebuffer, so it renders to framebuffer
+/// objects.
 pub struct HeadlessContext {
-    context: IdRef,
+    context: CGLContextObj,
+    pixel_format: PixelFormat,
+}
+
+impl Drop for HeadlessContext {
+    fn drop(&mut self) {
+        // this also releases the context if it is current
+        unsafe { CGLDestroyContext(self.context) };
+    }
+}
+
+/// See the docs in the crate root file.
+pub fn probe() -> Vec<BackendInfo> {
+    egl::probe().into_iter().collect()
+}
+
+/// See the docs in the crate root file. Returns the number of contexts
+/// alive if there are any.
+pub unsafe fn reload_symbols() -> Result<(), usize> {
+    let live = egl::live_contexts();
+    if live != 0 {
+        return Err(live);
+    }
+    egl::reload_symbols();
+    Ok(())
+}
+
+/// See the docs in the crate root file.
+pub fn preload() {
+    egl::preload();
 }
 
 impl Context {
@@ -56,11 +163,43 @@ impl Context {
     ) -> Result<(winit::Window, Self), CreationError> {
         let transparent = wb.window.transparent;
         let window = wb.build(el)?;
+        let view = window.get_nsview() as id;
 
+        let context = unsafe {
+            Context::new_raw_context(view, transparent, pf_reqs, gl_attr)?
+        };
+        Ok((window, context))
+    }
+
+    /// Builds a context rendering to `view`, which must outlive it.
+    #[inline]
+    pub unsafe fn new_raw_context(
+        view: id,
+        transparent: bool,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
         if gl_attr.sharing.is_some() {
             unimplemented!()
         }
 
+        // an EGL shipped with the executable, such as ANGLE, renders to the
+        // `CALayer` of the view, and NSOpenGL is only used if it fails
+        if EGL.is_some() {
+            let _: () = msg_send![view, setWantsLayer: YES];
+            let layer: id = msg_send![view, layer];
+            let gl_attr_egl = gl_attr.clone().map_sharing(|_| unreachable!());
+            let context = EglContext::new(
+                pf_reqs,
+                &gl_attr_egl,
+                egl::NativeDisplay::Other(None),
+            )
+            .and_then(|p| p.finish(layer as *const _));
+            if let Ok(context) = context {
+                return Ok(Context::Egl(context));
+            }
+        }
+
         match gl_attr.robustness {
             Robustness::RobustNoResetNotification
             | Robustness::RobustLoseContextOnReset => {
@@ -69,99 +208,111 @@ impl Context {
             _ => (),
         }
 
-        let view = window.get_nsview() as id;
-
         let gl_profile = helpers::get_gl_profile(gl_attr, pf_reqs)?;
         let attributes = helpers::build_nsattributes(pf_reqs, gl_profile)?;
-        unsafe {
-            let pixel_format = IdRef::new(
-                NSOpenGLPixelFormat::alloc(nil)
-                    .initWithAttributes_(&attributes),
-            );
-            let pixel_format = match pixel_format.non_nil() {
-                None => return Err(CreationError::NoAvailablePixelFormat),
-                Some(pf) => pf,
-            };
+        let pixel_format = IdRef::new(
+            NSOpenGLPixelFormat::alloc(nil)
+                .initWithAttributes_(&attributes),
+        );
+        let pixel_format = match pixel_format.non_nil() {
+            None => return Err(CreationError::NoAvailablePixelFormat),
+            Some(pf) => pf,
+        };
 
-            // TODO: Add context sharing
-            let gl_context = IdRef::new(
-                NSOpenGLContext::alloc(nil)
-                    .initWithFormat_shareContext_(*pixel_format, nil),
-            );
-            let gl_context = match gl_context.non_nil() {
-                Some(gl_context) => gl_context,
-                None => {
-                    return Err(CreationError::NotSupported(
-                        "could not open gl context",
-                    ));
-                }
-            };
+        // TODO: Add context sharing
+        let gl_context = IdRef::new(
+            NSOpenGLContext::alloc(nil)
+                .initWithFormat_shareContext_(*pixel_format, nil),
+        );
+        let gl_context = match gl_context.non_nil() {
+            Some(gl_context) => gl_context,
+            None => {
+                return Err(CreationError::NotSupported(
+                    "could not open gl context",
+                ));
+            }
+        };
 
-            let pixel_format = {
-                let get_attr =
-                    |attrib: appkit::NSOpenGLPixelFormatAttribute| -> i32 {
-                        let mut value = 0;
-                        NSOpenGLPixelFormat::getValues_forAttribute_forVirtualScreen_(
+        let pixel_format = {
+            let get_attr =
+                |attrib: appkit::NSOpenGLPixelFormatAttribute| -> i32 {
+                    let mut value = 0;
+                    NSOpenGLPixelFormat::getValues_forAttribute_forVirtualScreen_(
                         *pixel_format,
                         &mut value,
                         attrib,
                         NSOpenGLContext::currentVirtualScreen(*gl_context),
                     );
-                        value
-                    };
-
-                PixelFormat {
-                    hardware_accelerated: get_attr(
-                        appkit::NSOpenGLPFAAccelerated,
-                    ) != 0,
-                    color_bits: (get_attr(appkit::NSOpenGLPFAColorSize)
-                        - get_attr(appkit::NSOpenGLPFAAlphaSize))
-                        as u8,
-                    alpha_bits: get_attr(appkit::NSOpenGLPFAAlphaSize) as u8,
-                    depth_bits: get_attr(appkit::NSOpenGLPFADepthSize) as u8,
-                    stencil_bits: get_attr(appkit::NSOpenGLPFAStencilSize)
-                        as u8,
-                    stereoscopy: get_attr(appkit::NSOpenGLPFAStereo) != 0,
-                    double_buffer: get_attr(appkit::NSOpenGLPFADoubleBuffer)
-                        != 0,
-                    multisampling: if get_attr(appkit::NSOpenGLPFAMultisample)
-                        > 0
-                    {
-                        Some(get_attr(appkit::NSOpenGLPFASamples) as u16)
-                    } else {
-                        None
-                    },
-                    srgb: true,
-                }
-            };
-
-            gl_context.setView_(view);
-            let value = if gl_attr.vsync { 1 } else { 0 };
-            gl_context.setValues_forParameter_(
-                &value,
-                appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval,
-            );
+                    value
+                };
+
+            PixelFormat {
+                hardware_accelerated: get_attr(
+                    appkit::NSOpenGLPFAAccelerated,
+                ) != 0,
+                color_bits: (get_attr(appkit::NSOpenGLPFAColorSize)
+                    - get_attr(appkit::NSOpenGLPFAAlphaSize))
+                    as u8,
+                alpha_bits: get_attr(appkit::NSOpenGLPFAAlphaSize) as u8,
+                depth_bits: get_attr(appkit::NSOpenGLPFADepthSize) as u8,
+                stencil_bits: get_attr(appkit::NSOpenGLPFAStencilSize)
+                    as u8,
+                stereoscopy: get_attr(appkit::NSOpenGLPFAStereo) != 0,
+                double_buffer: get_attr(appkit::NSOpenGLPFADoubleBuffer)
+                    != 0,
+                sample_buffers: get_attr(appkit::NSOpenGLPFASampleBuffers)
+                    as u8,
+                multisampling: if get_attr(appkit::NSOpenGLPFASampleBuffers)
+                    > 0
+                    && get_attr(appkit::NSOpenGLPFASamples) > 1
+                {
+                    Some(get_attr(appkit::NSOpenGLPFASamples) as u16)
+                } else {
+                    None
+                },
+                // NSOpenGL has no coverage sampling
+                coverage_samples: None,
+                srgb: true,
+                transparent: None,
+                native_renderable: false,
+                x11_visual_type: None,
+                native_id: None,
+            }
+        };
 
-            if transparent {
-                let mut opacity = 0;
-                CGLSetParameter(
-                    gl_context.CGLContextObj() as *mut _,
-                    kCGLCPSurfaceOpacity,
-                    &mut opacity,
-                );
+        // NSOpenGL only gives the format it chose
+        if let Some(ref hook) = pf_reqs.selection_hook {
+            if hook.select(vec![((), pixel_format.clone())]).is_none() {
+                return Err(CreationError::NoAvailablePixelFormat);
             }
+        }
+
+        gl_context.setView_(view);
+        let value = if gl_attr.vsync { 1 } else { 0 };
+        gl_context.setValues_forParameter_(
+            &value,
+            appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval,
+        );
 
-            CGLEnable(
+        if transparent {
+            let mut opacity = 0;
+            CGLSetParameter(
                 gl_context.CGLContextObj() as *mut _,
-                kCGLCECrashOnRemovedFunctions,
+                kCGLCPSurfaceOpacity,
+                &mut opacity,
             );
-
-            let context = WindowedContext {
-                context: gl_context,
-                pixel_format: pixel_format,
-            };
-            Ok((window, Context::WindowedContext(context)))
         }
+
+        CGLEnable(
+            gl_context.CGLContextObj() as *mut _,
+            kCGLCECrashOnRemovedFunctions,
+        );
+
+        let context = WindowedContext {
+            context: gl_context,
+            pixel_format: pixel_format,
+        };
+        Ok(Context::WindowedContext(context))
     }
 
     #[inline]
@@ -170,28 +321,91 @@ impl Context {
         pf_reqs: &PixelFormatRequirements,
         gl_attr: &GlAttributes<&Context>,
     ) -> Result<Self, CreationError> {
+        Self::new_compute(pf_reqs, gl_attr)
+    }
+
+    /// Wraps an EGL context created by another library.
+    #[inline]
+    pub unsafe fn from_raw_egl(
+        display: *const c_void,
+        context: *const c_void,
+        config: *const c_void,
+        surface: *const c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        EglContext::from_raw(display, context, config, surface, ownership)
+            .map(Context::Egl)
+    }
+
+    /// See the docs in the crate root file.
+    #[inline]
+    pub fn new_compute(
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        if EGL.is_some() && gl_attr.sharing.is_none() {
+            let gl_attr_egl = gl_attr.clone().map_sharing(|_| unreachable!());
+            let context = EglContext::new(
+                pf_reqs,
+                &gl_attr_egl,
+                egl::NativeDisplay::Other(None),
+            )
+            .and_then(|p| {
+                if pf_reqs.compute_only {
+                    p.finish_surfaceless()
+                } else {
+                    p.finish_pbuffer((1, 1))
+                }
+            });
+            if let Ok(context) = context {
+                return Ok(Context::HeadlessEgl(context));
+            }
+        }
+
+        let share = match gl_attr.sharing {
+            None => ptr::null_mut(),
+            Some(&Context::WindowedContext(ref c)) => unsafe {
+                c.context.CGLContextObj()
+            },
+            Some(&Context::HeadlessContext(ref c)) => c.context,
+            Some(_) => {
+                return Err(CreationError::IncompatibleSharing);
+            }
+        };
+
         let gl_profile = helpers::get_gl_profile(gl_attr, pf_reqs)?;
         let attributes = helpers::build_nsattributes(pf_reqs, gl_profile)?;
-        let context = unsafe {
-            let pixelformat = NSOpenGLPixelFormat::alloc(nil)
-                .initWithAttributes_(&attributes);
-            if pixelformat == nil {
-                return Err(CreationError::OsError(format!(
-                    "Could not create the pixel format"
-                )));
-            }
-            let context = NSOpenGLContext::alloc(nil)
-                .initWithFormat_shareContext_(pixelformat, nil);
-            if context == nil {
+        let headless = unsafe {
+            let pixel_format =
+                match helpers::choose_cgl_pixel_format(&attributes) {
+                    Some(pixel_format) => pixel_format,
+                    None => return Err(CreationError::NoAvailablePixelFormat),
+                };
+            let mut context = ptr::null_mut();
+            let err = CGLCreateContext(pixel_format, share, &mut context);
+            let described = helpers::describe_cgl_pixel_format(pixel_format);
+            // the context keeps its own reference to the pixel format
+            CGLDestroyPixelFormat(pixel_format);
+            if err != kCGLNoError {
                 return Err(CreationError::OsError(format!(
-                    "Could not create the rendering context"
+                    "CGLCreateContext failed: {}",
+                    err
                 )));
             }
 
-            IdRef::new(context)
+            HeadlessContext {
+                context,
+                pixel_format: described,
+            }
         };
 
-        let headless = HeadlessContext { context };
+        // CGL only gives the format it chose
+        if let Some(ref hook) = pf_reqs.selection_hook {
+            let format = headless.pixel_format.clone();
+            if hook.select(vec![((), format)]).is_none() {
+                return Err(CreationError::NoAvailablePixelFormat);
+            }
+        }
 
         Ok(Context::HeadlessContext(headless))
     }
@@ -209,7 +423,311 @@ impl Context {
 
     pub fn resize(&self, _width: u32, _height: u32) {
         match *self {
-            Context::WindowedContext(ref c) => unsafe { c.context.update() },
+            Context::WindowedContext(ref c) => unsafe {
+                update_on_main_thread(*c.context)
+            },
+            // the surfaces of ANGLE follow the bounds of the layer
+            Context::Egl(_) => (),
+            _ => unreachable!(),
+        }
+    }
+
+    #[inline]
+    pub fn needs_resize(&self) -> bool {
+        match *self {
+            Context::WindowedContext(_) => true,
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn resize_pbuffer(
+        &self,
+        width: u32,
+        height: u32,
+        largest: bool,
+    ) -> Result<(u32, u32), ContextError> {
+        match *self {
+            Context::HeadlessEgl(ref c) => {
+                c.resize_pbuffer((width, height), largest)
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_pbuffer_size(&self) -> Option<(u32, u32)> {
+        match *self {
+            Context::HeadlessEgl(ref c) => c
+                .get_surface_info()
+                .ok()
+                .map(|info| (info.width, info.height)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.get_pbuffer_limits()
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.get_surface_info()
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_visibility(
+        &self,
+        window: &winit::Window,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        match *self {
+            Context::WindowedContext(_) | Context::Egl(_) => unsafe {
+                let nswindow = window.get_nswindow() as id;
+                let miniaturized: BOOL = msg_send![nswindow, isMiniaturized];
+                if miniaturized != NO {
+                    return Ok(SurfaceVisibility::Minimized);
+                }
+                let state: NSUInteger = msg_send![nswindow, occlusionState];
+                // `NSWindowOcclusionStateVisible`
+                if state & (1 << 1) != 0 {
+                    Ok(SurfaceVisibility::Visible)
+                } else {
+                    Ok(SurfaceVisibility::Occluded)
+                }
+            },
+            Context::HeadlessContext(_) | Context::HeadlessEgl(_) => {
+                Err(ContextError::FunctionUnavailable)
+            }
+        }
+    }
+
+    #[inline]
+    pub fn is_vsync_forced(
+        &self,
+        _window: &winit::Window,
+    ) -> Result<bool, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.display_token()
+            }
+            _ => DisplayToken::new("cgl", 0),
+        }
+    }
+
+    #[inline]
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.get_release_behavior()
+            }
+            _ => ReleaseBehavior::Flush,
+        }
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.get_driver_workarounds()
+            }
+            _ => DriverWorkarounds::none(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.get_surface_downgrades()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => c.destroy(),
+            _ => Ok(()),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c) => c.set_swap_behavior(behavior),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        match *self {
+            Context::Egl(ref c) => c.is_render_buffer_mutable(),
+            _ => false,
+        }
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c) => c.set_render_buffer(render_buffer),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c) => c.set_hdr_metadata(metadata),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn join_swap_group(
+        &self,
+        _group: u32,
+        _barrier: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn copy_state_from(
+        &self,
+        _other: &Context,
+        _mask: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_swap_msc(
+        &self,
+        _divisor: u32,
+        _remainder: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    #[inline]
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        level: i32,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::HeadlessEgl(ref c) => c.set_pbuffer_mipmap_level(level),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn bind_pbuffer_tex_image(
+        &self,
+        bind: bool,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::HeadlessEgl(ref c) => c.bind_pbuffer_tex_image(bind),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.get_swap_interval_range()
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    /// `NSOpenGLCPSwapInterval` belongs to the context and always applies to
+    /// its view, so it is set right away.
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        match *self {
+            Context::WindowedContext(ref c) => unsafe {
+                let value = interval as i32;
+                c.context.setValues_forParameter_(
+                    &value,
+                    appkit::NSOpenGLContextParameter::NSOpenGLCPSwapInterval,
+                );
+                Ok(())
+            },
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.set_swap_interval(interval)
+            }
+            Context::HeadlessContext(_) => {
+                Err(ContextError::FunctionUnavailable)
+            }
+        }
+    }
+
+    #[inline]
+    pub unsafe fn current_state(&self) -> CurrentState {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                CurrentState::Egl(c.current_state())
+            }
+            // `NSOpenGLContext` makes its CGL context current
+            _ => CurrentState::Cgl(CGLGetCurrentContext()),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        match (self, state) {
+            (Context::Egl(c), CurrentState::Egl(state))
+            | (Context::HeadlessEgl(c), CurrentState::Egl(state)) => {
+                c.restore_current(state)
+            }
+            (_, CurrentState::Cgl(context)) => {
+                CGLSetCurrentContext(*context);
+            }
             _ => unreachable!(),
         }
     }
@@ -218,13 +736,41 @@ impl Context {
     pub unsafe fn make_current(&self) -> Result<(), ContextError> {
         match *self {
             Context::WindowedContext(ref c) => {
-                let _: () = msg_send![*c.context, update];
+                update_on_main_thread(*c.context);
                 c.context.makeCurrentContext();
             }
             Context::HeadlessContext(ref c) => {
-                let _: () = msg_send![*c.context, update];
-                c.context.makeCurrentContext();
+                let err = CGLSetCurrentContext(c.context);
+                if err != kCGLNoError {
+                    return Err(ContextError::OsError(format!(
+                        "CGLSetCurrentContext failed: {}",
+                        err
+                    )));
+                }
+            }
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                return c.make_current();
+            }
+        }
+        Ok(())
+    }
+
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                return c.make_not_current();
+            }
+            Context::HeadlessContext(_) => {
+                if self.is_current() {
+                    CGLSetCurrentContext(ptr::null_mut());
+                }
+                return Ok(());
             }
+            Context::WindowedContext(_) => (),
+        }
+        if self.is_current() {
+            NSOpenGLContext::clearCurrentContext(nil);
         }
         Ok(())
     }
@@ -234,7 +780,12 @@ impl Context {
         unsafe {
             let context = match *self {
                 Context::WindowedContext(ref c) => *c.context,
-                Context::HeadlessContext(ref c) => *c.context,
+                Context::HeadlessContext(ref c) => {
+                    return CGLGetCurrentContext() == c.context;
+                }
+                Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                    return c.is_current();
+                }
             };
 
             let pool = NSAutoreleasePool::new(nil);
@@ -251,6 +802,12 @@ impl Context {
     }
 
     pub fn get_proc_address(&self, addr: &str) -> *const () {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                return c.get_proc_address(addr);
+            }
+            _ => (),
+        }
         let symbol_name: CFString = FromStr::from_str(addr).unwrap();
         let framework_name: CFString =
             FromStr::from_str("com.apple.opengl").unwrap();
@@ -277,30 +834,126 @@ impl Context {
                     c.context.flushBuffer();
                     let _: () = msg_send![pool, release];
                 }
+                Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                    return c.swap_buffers();
+                }
                 Context::HeadlessContext(_) => unreachable!(),
             }
         }
         Ok(())
     }
 
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c) => c.swap_buffers_with_damage(rects),
+            _ => self.swap_buffers(),
+        }
+    }
+
+    /// Waits for about `timeout`, in place of a swap while rendering is
+    /// paused.
+    #[inline]
+    pub fn wait_paused_frame(&self, timeout: Duration) {
+        thread::sleep(timeout)
+    }
+
     #[inline]
     pub fn get_api(&self) -> ::Api {
-        ::Api::OpenGl
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => c.get_api(),
+            _ => ::Api::OpenGl,
+        }
     }
 
     #[inline]
     pub fn get_pixel_format(&self) -> PixelFormat {
         match *self {
             Context::WindowedContext(ref c) => c.pixel_format.clone(),
-            Context::HeadlessContext(_) => unreachable!(),
+            Context::HeadlessContext(ref c) => c.pixel_format.clone(),
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.get_pixel_format()
+            }
         }
     }
 
+    #[inline]
+    pub unsafe fn bind_io_surface(
+        &self,
+        io_surface: *mut c_void,
+        target: u32,
+        width: u32,
+        height: u32,
+        plane: u32,
+    ) -> Result<(), ContextError> {
+        let context = match *self {
+            Context::WindowedContext(ref c) => c.context.CGLContextObj(),
+            Context::HeadlessContext(ref c) => c.context,
+            Context::Egl(_) | Context::HeadlessEgl(_) => {
+                return Err(ContextError::FunctionUnavailable);
+            }
+        };
+        let err = CGLTexImageIOSurface2D(
+            context as *mut _,
+            target,
+            GL_RGBA,
+            width as i32,
+            height as i32,
+            GL_BGRA,
+            GL_UNSIGNED_INT_8_8_8_8_REV,
+            io_surface,
+            plane,
+        );
+        if err != 0 {
+            return Err(ContextError::OsError(format!(
+                "CGLTexImageIOSurface2D failed: {}",
+                err
+            )));
+        }
+        Ok(())
+    }
+
     #[inline]
     pub unsafe fn raw_handle(&self) -> *mut c_void {
         match *self {
             Context::WindowedContext(ref c) => *c.context.deref() as *mut _,
-            Context::HeadlessContext(ref c) => *c.context.deref() as *mut _,
+            Context::HeadlessContext(ref c) => c.context,
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.raw_handle() as *mut _
+            }
+        }
+    }
+
+    #[inline]
+    pub unsafe fn get_egl_display(&self) -> Option<*const c_void> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                Some(c.get_egl_display())
+            }
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                c.set_label(label)
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn egl_display_tracks_references(&self) -> Option<bool> {
+        match *self {
+            Context::Egl(ref c) | Context::HeadlessEgl(ref c) => {
+                Some(c.egl_display_tracks_references())
+            }
+            _ => None,
         }
     }
 }
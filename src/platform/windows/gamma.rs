@@ -0,0 +1,157 @@
+//! The gamma ramps of the monitors, through `GetDeviceGammaRamp`.
+
+use {ContextError, GammaRamp};
+
+use winapi::shared::minwindef::DWORD;
+use winapi::shared::windef::{HDC, HWND};
+use winapi::um::{wingdi, winuser};
+use winit;
+
+use os::windows::WindowExt;
+
+use std::{mem, ptr};
+
+/// The size of the ramps of GDI.
+const RAMP_SIZE: usize = 256;
+
+type DeviceRamp = [[u16; RAMP_SIZE]; 3];
+
+/// The gamma ramps the monitors had before glutin changed them, which are
+/// restored when this is dropped.
+pub struct SavedGamma {
+    monitors: Vec<(Vec<u16>, DeviceRamp)>,
+}
+
+impl Drop for SavedGamma {
+    fn drop(&mut self) {
+        for &mut (ref device, ref mut ramp) in &mut self.monitors {
+            unsafe {
+                if let Some(hdc) = open_monitor(device) {
+                    wingdi::SetDeviceGammaRamp(
+                        hdc,
+                        ramp.as_mut_ptr() as *mut _,
+                    );
+                    wingdi::DeleteDC(hdc);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the gamma ramp of the monitor `window` is on.
+pub fn get_gamma_ramp(
+    window: &winit::Window,
+) -> Result<GammaRamp, ContextError> {
+    unsafe {
+        let device = monitor_device(window)?;
+        let ramp = get_device_ramp(&device)?;
+        Ok(GammaRamp {
+            red: ramp[0].to_vec(),
+            green: ramp[1].to_vec(),
+            blue: ramp[2].to_vec(),
+        })
+    }
+}
+
+/// Sets the gamma ramp of the monitor `window` is on, saving its ramp in
+/// `saved` first unless it already is.
+pub fn set_gamma_ramp(
+    window: &winit::Window,
+    ramp: &GammaRamp,
+    saved: &mut Option<SavedGamma>,
+) -> Result<(), ContextError> {
+    let (red, green, blue) = match ramp.resample(RAMP_SIZE) {
+        Some(channels) => channels,
+        None => {
+            return Err(ContextError::OsError(
+                "the channels of the gamma ramp must have the same length"
+                    .to_string(),
+            ));
+        }
+    };
+    let mut device_ramp: DeviceRamp = [[0; RAMP_SIZE]; 3];
+    device_ramp[0].copy_from_slice(&red);
+    device_ramp[1].copy_from_slice(&green);
+    device_ramp[2].copy_from_slice(&blue);
+
+    unsafe {
+        let device = monitor_device(window)?;
+        let saved = saved.get_or_insert_with(|| SavedGamma {
+            monitors: Vec::new(),
+        });
+        if saved.monitors.iter().all(|&(ref other, _)| *other != device) {
+            let original = get_device_ramp(&device)?;
+            saved.monitors.push((device.clone(), original));
+        }
+
+        let hdc = open_monitor(&device).ok_or_else(|| {
+            ContextError::OsError("`CreateDCW` failed".to_string())
+        })?;
+        let res = wingdi::SetDeviceGammaRamp(
+            hdc,
+            device_ramp.as_mut_ptr() as *mut _,
+        );
+        wingdi::DeleteDC(hdc);
+        // GDI refuses ramps that stray too far from the identity
+        if res == 0 {
+            return Err(ContextError::OsError(
+                "`SetDeviceGammaRamp` failed".to_string(),
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// The null-terminated device name of the monitor `window` is on.
+unsafe fn monitor_device(
+    window: &winit::Window,
+) -> Result<Vec<u16>, ContextError> {
+    let monitor = winuser::MonitorFromWindow(
+        window.get_hwnd() as HWND,
+        winuser::MONITOR_DEFAULTTONEAREST,
+    );
+    let mut info: winuser::MONITORINFOEXW = mem::zeroed();
+    info.cbSize = mem::size_of::<winuser::MONITORINFOEXW>() as DWORD;
+    if winuser::GetMonitorInfoW(monitor, &mut info as *mut _ as *mut _) == 0 {
+        return Err(ContextError::OsError(
+            "`GetMonitorInfoW` failed".to_string(),
+        ));
+    }
+    let len = info
+        .szDevice
+        .iter()
+        .position(|&c| c == 0)
+        .unwrap_or(info.szDevice.len());
+    let mut device = info.szDevice[..len].to_vec();
+    device.push(0);
+    Ok(device)
+}
+
+unsafe fn open_monitor(device: &[u16]) -> Option<HDC> {
+    let hdc = wingdi::CreateDCW(
+        ptr::null(),
+        device.as_ptr(),
+        ptr::null(),
+        ptr::null(),
+    );
+    if hdc.is_null() {
+        None
+    } else {
+        Some(hdc)
+    }
+}
+
+unsafe fn get_device_ramp(device: &[u16]) -> Result<DeviceRamp, ContextError> {
+    let hdc = open_monitor(device).ok_or_else(|| {
+        ContextError::OsError("`CreateDCW` failed".to_string())
+    })?;
+    let mut ramp: DeviceRamp = [[0; RAMP_SIZE]; 3];
+    let res = wingdi::GetDeviceGammaRamp(hdc, ramp.as_mut_ptr() as *mut _);
+    wingdi::DeleteDC(hdc);
+    if res == 0 {
+        return Err(ContextError::OsError(
+            "`GetDeviceGammaRamp` failed".to_string(),
+        ));
+    }
+    Ok(ramp)
+}
@@ -1,25 +1,49 @@
 #![cfg(target_os = "windows")]
 
 use std::os::raw;
-use std::ptr;
+use std::time::Duration;
+use std::{mem, ptr, thread};
 
-use winapi::shared::windef::{HGLRC, HWND};
+use winapi::shared::minwindef::{BOOL, DWORD, FALSE};
+use winapi::shared::windef::{HDC, HGLRC, HWND, RECT};
+use winapi::shared::winerror::SUCCEEDED;
+use winapi::um::{dwmapi, winuser};
 use winit;
 
 use Api;
+use BackendInfo;
 use ContextError;
 use CreationError;
+use DisplayToken;
+use DriverWorkarounds;
+use GammaRamp;
 use GlAttributes;
 use GlRequest;
+use Ownership;
+use PbufferLimits;
 use PixelFormat;
 use PixelFormatRequirements;
+use PresentStats;
+use Rect;
+use ReleaseBehavior;
+use RenderBuffer;
+use SurfaceDowngrade;
+use SurfaceInfo;
+use SurfaceVisibility;
+use SwapBehavior;
+use SwapIntervalRange;
 
 use api::egl;
 use api::egl::Context as EglContext;
 use api::egl::EGL;
 use api::wgl::Context as WglContext;
+use api::wgl::DxInteropDevice;
 use os::windows::WindowExt;
 
+mod gamma;
+
+pub use self::gamma::SavedGamma;
+
 /// Context handles available on Windows.
 #[derive(Clone, Debug)]
 pub enum RawHandle {
@@ -27,6 +51,13 @@ pub enum RawHandle {
     Wgl(HGLRC),
 }
 
+/// What was current on a thread before a context was made current by
+/// `Context::make_current_scoped`.
+pub enum CurrentState {
+    Wgl(::api::wgl::CurrentState),
+    Egl(egl::CurrentState),
+}
+
 pub enum Context {
     /// A regular window
     Egl(EglContext),
@@ -41,6 +72,27 @@ pub enum Context {
 unsafe impl Send for Context {}
 unsafe impl Sync for Context {}
 
+/// See the docs in the crate root file.
+pub fn probe() -> Vec<BackendInfo> {
+    egl::probe().into_iter().collect()
+}
+
+/// See the docs in the crate root file. Returns the number of contexts
+/// alive if there are any.
+pub unsafe fn reload_symbols() -> Result<(), usize> {
+    let live = egl::live_contexts();
+    if live != 0 {
+        return Err(live);
+    }
+    egl::reload_symbols();
+    Ok(())
+}
+
+/// See the docs in the crate root file.
+pub fn preload() {
+    egl::preload();
+}
+
 impl Context {
     /// See the docs in the crate root file.
     #[inline]
@@ -64,9 +116,21 @@ impl Context {
         gl_attr: &GlAttributes<&Self>,
     ) -> Result<Self, CreationError> {
         let w = window.get_hwnd() as HWND;
-        match gl_attr.version {
-            GlRequest::Specific(Api::OpenGlEs, (_major, _minor)) => {
-                match (gl_attr.sharing, &*EGL) {
+        unsafe { Self::new_raw_context(w, pf_reqs, gl_attr) }
+    }
+
+    /// Builds a context rendering to `w`, which must outlive it.
+    #[inline]
+    pub unsafe fn new_raw_context(
+        w: HWND,
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Self>,
+    ) -> Result<Self, CreationError> {
+        // an EGL shipped with the executable is tried for every API, as it
+        // was put there to be used
+        match (gl_attr.version, egl::is_bundled()) {
+            (GlRequest::Specific(Api::OpenGlEs, _), _) | (_, true) => {
+                match (gl_attr.sharing, EGL.as_ref()) {
                     // We must use WGL.
                     (Some(&Context::HiddenWindowWgl(_, _)), _)
                     | (Some(&Context::Wgl(_)), _)
@@ -77,10 +141,8 @@ impl Context {
                                 | Context::Wgl(ref c) => c.get_hglrc(),
                                 _ => unreachable!(),
                             });
-                        unsafe {
-                            WglContext::new(&pf_reqs, &gl_attr_wgl, w)
-                                .map(Context::Wgl)
-                        }
+                        WglContext::new(&pf_reqs, &gl_attr_wgl, w)
+                            .map(Context::Wgl)
                     }
                     // We must use EGL.
                     (Some(_), Some(_)) => {
@@ -116,25 +178,27 @@ impl Context {
                         {
                             Ok(Context::Egl(c))
                         } else {
-                            unsafe {
-                                WglContext::new(&pf_reqs, &gl_attr_wgl, w)
-                                    .map(Context::Wgl)
-                            }
+                            WglContext::new(&pf_reqs, &gl_attr_wgl, w)
+                                .map(Context::Wgl)
                         }
                     }
                     _ => panic!(),
                 }
             }
             _ => {
+                match gl_attr.sharing {
+                    Some(&Context::Wgl(_))
+                    | Some(&Context::HiddenWindowWgl(_, _))
+                    | None => (),
+                    Some(_) => return Err(CreationError::IncompatibleSharing),
+                }
                 let gl_attr_wgl =
                     gl_attr.clone().map_sharing(|ctx| match *ctx {
                         Context::HiddenWindowWgl(_, ref c)
                         | Context::Wgl(ref c) => c.get_hglrc(),
-                        _ => panic!(),
+                        _ => unreachable!(),
                     });
-                unsafe {
-                    WglContext::new(&pf_reqs, &gl_attr_wgl, w).map(Context::Wgl)
-                }
+                WglContext::new(&pf_reqs, &gl_attr_wgl, w).map(Context::Wgl)
             }
         }
     }
@@ -147,7 +211,31 @@ impl Context {
     ) -> Result<Self, CreationError> {
         // if EGL is available, we try using EGL first
         // if EGL returns an error, we try the hidden window method
-        match (gl_attr.sharing, &*EGL) {
+        if let Ok(context) = Self::new_compute(pf_reqs, gl_attr) {
+            return Ok(context);
+        }
+
+        let wb = winit::WindowBuilder::new().with_visibility(false);
+        Self::new(wb, &el, pf_reqs, gl_attr).map(|(window, context)| {
+            match context {
+                Context::Egl(context) => {
+                    Context::HiddenWindowEgl(window, context)
+                }
+                Context::Wgl(context) => {
+                    Context::HiddenWindowWgl(window, context)
+                }
+                _ => unreachable!(),
+            }
+        })
+    }
+
+    /// Builds an EGL pbuffer context, or a surfaceless one for compute,
+    /// without a window.
+    pub fn new_compute(
+        pf_reqs: &PixelFormatRequirements,
+        gl_attr: &GlAttributes<&Context>,
+    ) -> Result<Self, CreationError> {
+        match (gl_attr.sharing, EGL.as_ref()) {
             (None, Some(_))
             | (Some(&Context::Egl(_)), Some(_))
             | (Some(&Context::HiddenWindowEgl(_, _)), Some(_))
@@ -161,35 +249,512 @@ impl Context {
                     });
 
                 let native_display = egl::NativeDisplay::Other(None);
-                let context =
-                    EglContext::new(pf_reqs, &gl_attr_egl, native_display)
-                        .and_then(|prototype| prototype.finish_pbuffer((1, 1)))
-                        .map(|ctx| Context::EglPbuffer(ctx));
+                EglContext::new(pf_reqs, &gl_attr_egl, native_display)
+                    .and_then(|prototype| {
+                        if pf_reqs.compute_only {
+                            prototype.finish_surfaceless()
+                        } else {
+                            prototype.finish_pbuffer((1, 1))
+                        }
+                    })
+                    .map(|ctx| Context::EglPbuffer(ctx))
+            }
+            (_, Some(_)) => Err(CreationError::IncompatibleSharing),
+            (_, None) => Err(CreationError::NotSupported(
+                "WGL contexts can't be built without a window",
+            )),
+        }
+    }
+
+    /// Wraps an EGL context created by another library.
+    #[inline]
+    pub unsafe fn from_raw_egl(
+        display: *const raw::c_void,
+        context: *const raw::c_void,
+        config: *const raw::c_void,
+        surface: *const raw::c_void,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        EglContext::from_raw(display, context, config, surface, ownership)
+            .map(Context::Egl)
+    }
+
+    /// Wraps a WGL context created by another library.
+    #[inline]
+    pub unsafe fn from_raw_wgl(
+        hdc: HDC,
+        hglrc: HGLRC,
+        ownership: Ownership,
+    ) -> Result<Self, CreationError> {
+        WglContext::from_raw(hdc, hglrc, ownership).map(Context::Wgl)
+    }
 
-                if let Ok(context) = context {
-                    return Ok(context);
+    #[inline]
+    pub fn resize(&self, _width: u32, _height: u32) {
+        // Method is for API consistency.
+    }
+
+    #[inline]
+    pub fn needs_resize(&self) -> bool {
+        false
+    }
+
+    #[inline]
+    pub fn resize_pbuffer(
+        &self,
+        width: u32,
+        height: u32,
+        largest: bool,
+    ) -> Result<(u32, u32), ContextError> {
+        match *self {
+            Context::EglPbuffer(ref c) => {
+                c.resize_pbuffer((width, height), largest)
+            }
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_pbuffer_size(&self) -> Option<(u32, u32)> {
+        match *self {
+            Context::EglPbuffer(ref c) => c
+                .get_surface_info()
+                .ok()
+                .map(|info| (info.width, info.height)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    pub fn get_pbuffer_limits(&self) -> Result<PbufferLimits, ContextError> {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => {
+                Err(ContextError::FunctionUnavailable)
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.get_pbuffer_limits(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_info(&self) -> Result<SurfaceInfo, ContextError> {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.get_surface_info()
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.get_surface_info(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_visibility(
+        &self,
+        window: &winit::Window,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        match *self {
+            Context::Egl(_) | Context::Wgl(_) => unsafe {
+                let hwnd = window.get_hwnd() as HWND;
+                if winuser::IsIconic(hwnd) != 0 {
+                    return Ok(SurfaceVisibility::Minimized);
                 }
+                // windows on other virtual desktops are cloaked by DWM
+                let mut cloaked: DWORD = 0;
+                let res = dwmapi::DwmGetWindowAttribute(
+                    hwnd,
+                    dwmapi::DWMWA_CLOAKED,
+                    &mut cloaked as *mut DWORD as *mut _,
+                    mem::size_of::<DWORD>() as DWORD,
+                );
+                if winuser::IsWindowVisible(hwnd) == 0
+                    || (SUCCEEDED(res) && cloaked != 0)
+                {
+                    Ok(SurfaceVisibility::Occluded)
+                } else {
+                    Ok(SurfaceVisibility::Visible)
+                }
+            },
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    /// DWM composes every window while composition is enabled, which is
+    /// always the case since Windows 8, but the drivers take over the
+    /// presentation of the windows that cover their whole monitor as if
+    /// they were exclusive fullscreen.
+    #[inline]
+    pub fn get_gamma_ramp(
+        &self,
+        window: &winit::Window,
+    ) -> Result<GammaRamp, ContextError> {
+        match *self {
+            Context::Egl(_) | Context::Wgl(_) => gamma::get_gamma_ramp(window),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_gamma_ramp(
+        &self,
+        window: &winit::Window,
+        ramp: &GammaRamp,
+        saved: &mut Option<SavedGamma>,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(_) | Context::Wgl(_) => {
+                gamma::set_gamma_ramp(window, ramp, saved)
             }
-            _ => (),
+            _ => Err(ContextError::FunctionUnavailable),
         }
+    }
 
-        let wb = winit::WindowBuilder::new().with_visibility(false);
-        Self::new(wb, &el, pf_reqs, gl_attr).map(|(window, context)| {
-            match context {
-                Context::Egl(context) => {
-                    Context::HiddenWindowEgl(window, context)
+    #[inline]
+    pub fn is_vsync_forced(
+        &self,
+        window: &winit::Window,
+    ) -> Result<bool, ContextError> {
+        match *self {
+            Context::Egl(_) | Context::Wgl(_) => unsafe {
+                let mut enabled: BOOL = FALSE;
+                let res = dwmapi::DwmIsCompositionEnabled(&mut enabled);
+                if !SUCCEEDED(res) {
+                    return Err(ContextError::OsError(format!(
+                        "DwmIsCompositionEnabled failed: 0x{:x}",
+                        res
+                    )));
                 }
-                Context::Wgl(context) => {
-                    Context::HiddenWindowWgl(window, context)
+                if enabled == FALSE {
+                    return Ok(false);
                 }
-                _ => unreachable!(),
+
+                let hwnd = window.get_hwnd() as HWND;
+                let monitor = winuser::MonitorFromWindow(
+                    hwnd,
+                    winuser::MONITOR_DEFAULTTONEAREST,
+                );
+                let mut info: winuser::MONITORINFO = mem::zeroed();
+                info.cbSize = mem::size_of::<winuser::MONITORINFO>() as DWORD;
+                let mut rect: RECT = mem::zeroed();
+                if winuser::GetMonitorInfoW(monitor, &mut info) == 0
+                    || winuser::GetWindowRect(hwnd, &mut rect) == 0
+                {
+                    return Ok(true);
+                }
+                let monitor = info.rcMonitor;
+                let covers_monitor = rect.left <= monitor.left
+                    && rect.top <= monitor.top
+                    && rect.right >= monitor.right
+                    && rect.bottom >= monitor.bottom;
+                Ok(!covers_monitor)
+            },
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn display_token(&self) -> DisplayToken {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.display_token()
             }
-        })
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.display_token(),
+        }
     }
 
     #[inline]
-    pub fn resize(&self, _width: u32, _height: u32) {
-        // Method is for API consistency.
+    pub fn get_release_behavior(&self) -> ReleaseBehavior {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.get_release_behavior()
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.get_release_behavior(),
+        }
+    }
+
+    #[inline]
+    pub fn get_driver_workarounds(&self) -> DriverWorkarounds {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => {
+                DriverWorkarounds::none()
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.get_driver_workarounds(),
+        }
+    }
+
+    #[inline]
+    pub fn get_surface_downgrades(&self) -> Vec<SurfaceDowngrade> {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => Vec::new(),
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.get_surface_downgrades(),
+        }
+    }
+
+    #[inline]
+    pub fn destroy(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => Ok(()),
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.destroy(),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_behavior(
+        &self,
+        behavior: SwapBehavior,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => {
+                Err(ContextError::FunctionUnavailable)
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.set_swap_behavior(behavior),
+        }
+    }
+
+    #[inline]
+    pub fn is_render_buffer_mutable(&self) -> bool {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => false,
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.is_render_buffer_mutable(),
+        }
+    }
+
+    #[inline]
+    pub fn set_render_buffer(
+        &self,
+        render_buffer: RenderBuffer,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => {
+                Err(ContextError::FunctionUnavailable)
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.set_render_buffer(render_buffer),
+        }
+    }
+
+    #[inline]
+    pub fn set_hdr_metadata(
+        &self,
+        metadata: &::HdrMetadata,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => {
+                Err(ContextError::FunctionUnavailable)
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.set_hdr_metadata(metadata),
+        }
+    }
+
+    #[inline]
+    pub fn join_swap_group(
+        &self,
+        group: u32,
+        barrier: u32,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.join_swap_group(group, barrier)
+            }
+            Context::Egl(_)
+            | Context::HiddenWindowEgl(_, _)
+            | Context::EglPbuffer(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_msc(
+        &self,
+        _divisor: u32,
+        _remainder: u32,
+    ) -> Result<(), ContextError> {
+        Err(ContextError::FunctionUnavailable)
+    }
+
+    /// `DwmFlush` returns after the next composition, which happens on the
+    /// vertical blank. It fails while the composition is disabled, which
+    /// only Windows 7 allows.
+    #[inline]
+    pub fn wait_for_vsync(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(_) | Context::Wgl(_) => unsafe {
+                let res = dwmapi::DwmFlush();
+                if SUCCEEDED(res) {
+                    Ok(())
+                } else {
+                    Err(ContextError::FunctionUnavailable)
+                }
+            },
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    /// Since Windows 8.1 the window must be null, so the statistics are the
+    /// ones of the desktop composition rather than of the window alone.
+    #[inline]
+    pub fn get_present_stats(&self) -> Result<PresentStats, ContextError> {
+        match *self {
+            Context::Egl(_) | Context::Wgl(_) => unsafe {
+                let mut info: dwmapi::DWM_TIMING_INFO = mem::zeroed();
+                info.cbSize = mem::size_of::<dwmapi::DWM_TIMING_INFO>() as _;
+                let res = dwmapi::DwmGetCompositionTimingInfo(
+                    ptr::null_mut(),
+                    &mut info,
+                );
+                if !SUCCEEDED(res) {
+                    // fails when the desktop composition is disabled
+                    return Err(ContextError::FunctionUnavailable);
+                }
+                Ok(PresentStats {
+                    refresh_count: info.cRefresh as u64,
+                    frames_displayed: info.cFramesDisplayed as u64,
+                    frames_dropped: info.cFramesDropped as u64,
+                    frames_missed: info.cFramesMissed as u64,
+                })
+            },
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn set_pbuffer_mipmap_level(
+        &self,
+        level: i32,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::EglPbuffer(ref c) => c.set_pbuffer_mipmap_level(level),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn bind_pbuffer_tex_image(
+        &self,
+        bind: bool,
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::EglPbuffer(ref c) => c.bind_pbuffer_tex_image(bind),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_swap_interval_range(
+        &self,
+    ) -> Result<SwapIntervalRange, ContextError> {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => {
+                Err(ContextError::FunctionUnavailable)
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.get_swap_interval_range(),
+        }
+    }
+
+    #[inline]
+    pub fn set_swap_interval(&self, interval: u32) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.set_swap_interval(interval)
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.set_swap_interval(interval),
+        }
+    }
+
+    #[inline]
+    pub fn copy_state_from(
+        &self,
+        other: &Context,
+        mask: u32,
+    ) -> Result<(), ContextError> {
+        match (self, other) {
+            (&Context::Wgl(ref c), &Context::Wgl(ref other))
+            | (&Context::Wgl(ref c), &Context::HiddenWindowWgl(_, ref other))
+            | (&Context::HiddenWindowWgl(_, ref c), &Context::Wgl(ref other))
+            | (
+                &Context::HiddenWindowWgl(_, ref c),
+                &Context::HiddenWindowWgl(_, ref other),
+            ) => c.copy_state_from(other, mask),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn get_max_swap_groups(&self) -> Result<(u32, u32), ContextError> {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.get_max_swap_groups()
+            }
+            Context::Egl(_)
+            | Context::HiddenWindowEgl(_, _)
+            | Context::EglPbuffer(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn dx_open_device(
+        &self,
+        dx_device: *mut raw::c_void,
+    ) -> Result<DxInteropDevice, ContextError> {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.dx_open_device(dx_device)
+            }
+            Context::Egl(_)
+            | Context::HiddenWindowEgl(_, _)
+            | Context::EglPbuffer(_) => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub unsafe fn current_state(&self) -> CurrentState {
+        match *self {
+            Context::Wgl(_) | Context::HiddenWindowWgl(_, _) => {
+                CurrentState::Wgl(WglContext::current_state())
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => {
+                CurrentState::Egl(c.current_state())
+            }
+        }
+    }
+
+    #[inline]
+    pub unsafe fn restore_current(&self, state: &CurrentState) {
+        match (self, state) {
+            (Context::Wgl(c), CurrentState::Wgl(state))
+            | (Context::HiddenWindowWgl(_, c), CurrentState::Wgl(state)) => {
+                c.restore_current(state)
+            }
+            (Context::Egl(c), CurrentState::Egl(state))
+            | (Context::HiddenWindowEgl(_, c), CurrentState::Egl(state))
+            | (Context::EglPbuffer(c), CurrentState::Egl(state)) => {
+                c.restore_current(state)
+            }
+            _ => unreachable!(),
+        }
     }
 
     #[inline]
@@ -204,6 +769,18 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub unsafe fn make_not_current(&self) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(ref c) | Context::HiddenWindowWgl(_, ref c) => {
+                c.make_not_current()
+            }
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.make_not_current(),
+        }
+    }
+
     #[inline]
     pub fn is_current(&self) -> bool {
         match *self {
@@ -237,6 +814,25 @@ impl Context {
         }
     }
 
+    #[inline]
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        match *self {
+            Context::Wgl(ref c) => c.swap_buffers(),
+            Context::Egl(ref c) => c.swap_buffers_with_damage(rects),
+            _ => unreachable!(),
+        }
+    }
+
+    /// Waits for about `timeout`, in place of a swap while rendering is
+    /// paused. Swapping the buffers of minimized windows returns at once.
+    #[inline]
+    pub fn wait_paused_frame(&self, timeout: Duration) {
+        thread::sleep(timeout)
+    }
+
     #[inline]
     pub fn get_api(&self) -> Api {
         match *self {
@@ -279,4 +875,26 @@ impl Context {
             _ => None,
         }
     }
+
+    #[inline]
+    pub fn set_egl_label(&self, label: &str) -> Result<(), ContextError> {
+        match *self {
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => c.set_label(label),
+            _ => Err(ContextError::FunctionUnavailable),
+        }
+    }
+
+    #[inline]
+    pub fn egl_display_tracks_references(&self) -> Option<bool> {
+        match *self {
+            Context::Egl(ref c)
+            | Context::HiddenWindowEgl(_, ref c)
+            | Context::EglPbuffer(ref c) => {
+                Some(c.egl_display_tracks_references())
+            }
+            _ => None,
+        }
+    }
 }
@@ -0,0 +1,194 @@
+use super::*;
+
+use std::marker::PhantomData;
+use std::sync::{Condvar, Mutex};
+
+/// A pool of headless contexts sharing their objects with a main context,
+/// meant to be handed out to worker threads, for example to upload textures
+/// and buffers in the background.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// # use std::sync::Arc;
+/// # fn main() {
+/// let el = glutin::EventsLoop::new();
+/// let wb = glutin::WindowBuilder::new();
+/// let combined_context = glutin::ContextBuilder::new()
+///     .build_combined(wb, &el)
+///     .unwrap();
+///
+/// let pool = glutin::ContextPool::new(
+///     &el,
+///     glutin::ContextBuilder::new(),
+///     combined_context.context(),
+///     4,
+/// )
+/// .unwrap();
+/// let pool = Arc::new(pool);
+///
+/// let worker_pool = Arc::clone(&pool);
+/// std::thread::spawn(move || {
+///     let context = worker_pool.acquire().unwrap();
+///     // upload resources here, then wait for them with a fence before
+///     // using them from the main context
+///     drop(context);
+/// });
+/// # }
+/// ```
+pub struct ContextPool {
+    contexts: Mutex<Contexts>,
+    available: Condvar,
+}
+
+struct Contexts {
+    /// The contexts waiting in the pool.
+    idle: Vec<Context>,
+    /// The contexts that haven't been lost, whether idle or acquired.
+    live: usize,
+}
+
+impl ContextPool {
+    /// Builds `count` headless contexts with the attributes of `cb`, all
+    /// sharing their objects with `shared_with`. `count` can't be `0`.
+    ///
+    /// The contexts are built like those of `ContextBuilder::for_compute`:
+    /// with EGL, they have no surface if `EGL_KHR_surfaceless_context` is
+    /// supported and a 1x1 pbuffer otherwise. Where the backend can't share
+    /// objects with `shared_with` that way, such as with a GLX or Wayland
+    /// window on Linux, they fall back to hidden windows like any headless
+    /// context.
+    ///
+    /// Like every headless context, they must be built on the thread running
+    /// the events loop, but can then be used from any thread.
+    pub fn new(
+        el: &EventsLoop,
+        cb: ContextBuilder,
+        shared_with: &Context,
+        count: usize,
+    ) -> Result<Self, CreationError> {
+        if count == 0 {
+            return Err(CreationError::NotSupported(
+                "a context pool needs at least one context",
+            ));
+        }
+
+        let mut cb = cb.with_shared_lists(shared_with);
+        cb.pf_reqs.compute_only = true;
+        let idle = (0..count)
+            .map(|_| cb.clone().build_headless(el))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ContextPool {
+            contexts: Mutex::new(Contexts { idle, live: count }),
+            available: Condvar::new(),
+        })
+    }
+
+    /// Takes a context out of the pool and makes it current on the calling
+    /// thread, blocking until one is available.
+    ///
+    /// The context is made not current and returned to the pool when the
+    /// returned guard is dropped, so the guard must be dropped on the thread
+    /// that acquired it.
+    ///
+    /// Contexts that are lost, or that fail to be made not current, are
+    /// dropped instead of being returned to the pool. Once every context has
+    /// been dropped, `ContextError::ContextLost` is returned instead of
+    /// blocking forever.
+    pub fn acquire(&self) -> Result<PooledContext<'_>, ContextError> {
+        let mut contexts = self.contexts.lock().unwrap();
+        loop {
+            if let Some(context) = contexts.idle.pop() {
+                drop(contexts);
+                return self.make_current(context);
+            }
+            if contexts.live == 0 {
+                return Err(ContextError::ContextLost);
+            }
+            contexts = self.available.wait(contexts).unwrap();
+        }
+    }
+
+    /// Like `acquire`, but returns `None` instead of blocking if every
+    /// context is in use.
+    pub fn try_acquire(
+        &self,
+    ) -> Option<Result<PooledContext<'_>, ContextError>> {
+        let mut contexts = self.contexts.lock().unwrap();
+        match contexts.idle.pop() {
+            Some(context) => {
+                drop(contexts);
+                Some(self.make_current(context))
+            }
+            None if contexts.live == 0 => Some(Err(ContextError::ContextLost)),
+            None => None,
+        }
+    }
+
+    fn make_current(
+        &self,
+        context: Context,
+    ) -> Result<PooledContext<'_>, ContextError> {
+        match unsafe { context.make_current() } {
+            Ok(()) => Ok(PooledContext {
+                pool: self,
+                context: Some(context),
+                _not_send: PhantomData,
+            }),
+            Err(err @ ContextError::ContextLost)
+            | Err(err @ ContextError::DisplayLost) => {
+                self.discard(context);
+                Err(err)
+            }
+            Err(err) => {
+                self.release(context);
+                Err(err)
+            }
+        }
+    }
+
+    fn release(&self, context: Context) {
+        self.contexts.lock().unwrap().idle.push(context);
+        self.available.notify_one();
+    }
+
+    fn discard(&self, context: Context) {
+        drop(context);
+        self.contexts.lock().unwrap().live -= 1;
+        // every waiter has to return an error once the last context is gone
+        self.available.notify_all();
+    }
+}
+
+/// A context taken out of a `ContextPool`, current on the thread that
+/// acquired it.
+///
+/// Dereferences to the `Context`. It can't be sent to another thread, since
+/// the context has to be released by the thread it is current on.
+pub struct PooledContext<'a> {
+    pool: &'a ContextPool,
+    context: Option<Context>,
+    _not_send: PhantomData<*const ()>,
+}
+
+impl<'a> std::ops::Deref for PooledContext<'a> {
+    type Target = Context;
+    fn deref(&self) -> &Self::Target {
+        self.context.as_ref().unwrap()
+    }
+}
+
+impl<'a> Drop for PooledContext<'a> {
+    fn drop(&mut self) {
+        let context = self.context.take().unwrap();
+        // a context that is still current can't be made current on another
+        // thread, so never hand it out again
+        if unsafe { context.make_not_current() }.is_ok() {
+            self.pool.release(context);
+        } else {
+            self.pool.discard(context);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use super::*;
+
+/// An API used to create contexts, as reported by `probe`.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// EGL, used on Android, Wayland, and on X11 and Windows if requested.
+    Egl,
+
+    /// GLX, used on X11.
+    Glx,
+}
+
+/// What `probe` found out about an available backend.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackendInfo {
+    /// The backend.
+    pub backend: Backend,
+
+    /// The version of the backend implementation.
+    pub version: (u8, u8),
+
+    /// The extensions of the client library, independent of any display.
+    ///
+    /// Empty if the implementation can't report them, which is the case of
+    /// EGL before 1.5 without `EGL_EXT_client_extensions`.
+    pub client_extensions: Vec<String>,
+
+    /// The extensions supported on the default display.
+    pub display_extensions: Vec<String>,
+
+    /// The number of framebuffer configurations available on the default
+    /// display.
+    pub num_configs: usize,
+}
+
+/// Lists the backends available on the system, along with their versions,
+/// extensions and number of configurations.
+///
+/// Neither an events loop nor a window is needed, which makes this suitable
+/// for diagnostics and bug reports. Each backend is queried on the default
+/// display of the system; backends whose library can't be loaded or whose
+/// display can't be initialized are left out.
+///
+/// ## Platform-specific
+///
+/// EGL is probed on Linux, Windows and Android, GLX on Linux. An empty list
+/// is returned on other platforms.
+pub fn probe() -> Vec<BackendInfo> {
+    platform::probe()
+}
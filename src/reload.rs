@@ -0,0 +1,63 @@
+use super::*;
+
+/// Unloads and loads again the libraries glutin loads at runtime, resolving
+/// their entry points anew.
+///
+/// This lets long-running applications, such as kiosks, pick up a driver
+/// that was updated, or start afresh after a driver crash, without
+/// restarting. The functions of a context come from the libraries it was
+/// built with, so every context must be dropped beforehand, and the
+/// function pointers loaded through `get_proc_address` must be loaded again
+/// from the contexts built afterwards.
+///
+/// Returns `CreationError::PlatformSpecific` without reloading anything if
+/// contexts are still alive. A library that can't be loaded anymore is
+/// reported by the next attempt to build a context, like at startup.
+///
+/// ## Platform-specific
+///
+/// - On Linux, EGL, GLX and the client libraries of EGL are reloaded.
+/// - On Windows and macOS, EGL and its client libraries are reloaded. WGL,
+///   CGL and NSOpenGL come with the system, which keeps their drivers
+///   loaded, and aren't reloaded.
+/// - On Android, EGL is linked with the executable, and on iOS and
+///   Emscripten, nothing is loaded at runtime, so this does nothing.
+///
+/// # Safety
+///
+/// No context may be built on another thread in the meantime, and the
+/// function pointers loaded from the previous libraries must not be called
+/// anymore.
+pub unsafe fn reload_symbols() -> Result<(), CreationError> {
+    platform::reload_symbols().map_err(|live| {
+        CreationError::PlatformSpecific(format!(
+            "{} contexts are still alive, they must be dropped before \
+             reloading the libraries they use",
+            live
+        ))
+    })
+}
+
+/// Loads the libraries glutin loads at runtime and resolves their entry
+/// points, unless it already happened.
+///
+/// This otherwise happens when the first context is built, and can take a
+/// noticeable part of the startup of an application on some systems. It
+/// can be called early on a background thread, while the application does
+/// something else, to hide that latency. Calling it is never required.
+///
+/// The functions loaded through `get_proc_address` are also resolved once
+/// per process, and shared by the contexts that use the same library.
+///
+/// ## Platform-specific
+///
+/// - On Linux, EGL, GLX and the client libraries of EGL are loaded.
+/// - On Windows and macOS, EGL and its client libraries are loaded. WGL,
+///   CGL and NSOpenGL come with the system, and the functions of WGL
+///   depend on the context, so they are resolved for each of them.
+/// - On Android, EGL is linked with the executable, so only its entry
+///   points are resolved. On iOS and Emscripten, nothing is loaded at
+///   runtime, so this does nothing.
+pub fn preload() {
+    platform::preload()
+}
@@ -0,0 +1,325 @@
+use super::*;
+
+use gl::{
+    load, GL_COLOR_ATTACHMENT0, GL_COLOR_BUFFER_BIT, GL_DEPTH24_STENCIL8,
+    GL_DEPTH_STENCIL_ATTACHMENT, GL_DRAW_FRAMEBUFFER, GL_FRAMEBUFFER,
+    GL_FRAMEBUFFER_BINDING, GL_FRAMEBUFFER_COMPLETE, GL_MAX_SAMPLES,
+    GL_NEAREST, GL_READ_FRAMEBUFFER, GL_RENDERBUFFER, GL_RENDERBUFFER_BINDING,
+    GL_RGBA8,
+};
+
+use std::os::raw::{c_int, c_uint, c_void};
+
+/// The OpenGL functions needed to render to a multisampled framebuffer
+/// object and resolve it, which OpenGL 3.0, OpenGL ES 3.0 and
+/// `ARB_framebuffer_object` provide.
+#[derive(Clone)]
+struct ResolveGl {
+    get_integerv: Option<unsafe extern "system" fn(c_uint, *mut c_int)>,
+    gen_framebuffers: Option<unsafe extern "system" fn(c_int, *mut c_uint)>,
+    delete_framebuffers:
+        Option<unsafe extern "system" fn(c_int, *const c_uint)>,
+    bind_framebuffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    check_framebuffer_status:
+        Option<unsafe extern "system" fn(c_uint) -> c_uint>,
+    gen_renderbuffers: Option<unsafe extern "system" fn(c_int, *mut c_uint)>,
+    delete_renderbuffers:
+        Option<unsafe extern "system" fn(c_int, *const c_uint)>,
+    bind_renderbuffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    renderbuffer_storage_multisample: Option<
+        unsafe extern "system" fn(c_uint, c_int, c_uint, c_int, c_int),
+    >,
+    framebuffer_renderbuffer:
+        Option<unsafe extern "system" fn(c_uint, c_uint, c_uint, c_uint)>,
+    blit_framebuffer: Option<
+        unsafe extern "system" fn(
+            c_int,
+            c_int,
+            c_int,
+            c_int,
+            c_int,
+            c_int,
+            c_int,
+            c_int,
+            c_uint,
+            c_uint,
+        ),
+    >,
+}
+
+unsafe impl GlLoader for ResolveGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        ResolveGl {
+            get_integerv: load(loadfn("glGetIntegerv")),
+            gen_framebuffers: load(loadfn("glGenFramebuffers")),
+            delete_framebuffers: load(loadfn("glDeleteFramebuffers")),
+            bind_framebuffer: load(loadfn("glBindFramebuffer")),
+            check_framebuffer_status: load(loadfn(
+                "glCheckFramebufferStatus",
+            )),
+            gen_renderbuffers: load(loadfn("glGenRenderbuffers")),
+            delete_renderbuffers: load(loadfn("glDeleteRenderbuffers")),
+            bind_renderbuffer: load(loadfn("glBindRenderbuffer")),
+            renderbuffer_storage_multisample: load(loadfn(
+                "glRenderbufferStorageMultisample",
+            )),
+            framebuffer_renderbuffer: load(loadfn(
+                "glFramebufferRenderbuffer",
+            )),
+            blit_framebuffer: load(loadfn("glBlitFramebuffer")),
+        }
+    }
+}
+
+/// Renders to a multisampled framebuffer object owned by glutin and
+/// resolves it into the default framebuffer before swapping, for the
+/// platforms and pixel formats that have no multisampled default
+/// framebuffer.
+///
+/// Build the window without `with_multisampling` and render to
+/// `framebuffer` instead of the default framebuffer: `swap_buffers`
+/// resolves the samples into the default framebuffer and swaps it, so that
+/// multisampling behaves the same on every platform. `is_needed` tells
+/// whether the default framebuffer of a context already has the samples.
+///
+/// The framebuffer object has a color buffer and a packed depth and stencil
+/// buffer, and must be resized along with the window. It needs OpenGL 3.0,
+/// OpenGL ES 3.0 or `ARB_framebuffer_object`.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// # use glutin::ContextTrait;
+/// # fn main() {
+/// # let el = glutin::EventsLoop::new();
+/// # let wb = glutin::WindowBuilder::new();
+/// let context = glutin::ContextBuilder::new()
+///     .build_combined(wb, &el)
+///     .unwrap();
+/// unsafe { context.make_current().unwrap() };
+///
+/// let size = context
+///     .get_inner_size()
+///     .unwrap()
+///     .to_physical(context.get_hidpi_factor());
+/// let resolver =
+///     glutin::MultisampleResolver::new(context.context(), 4, size)
+///         .unwrap();
+///
+/// // draw to `resolver.framebuffer()`, which is bound
+/// resolver.swap_buffers(&context).unwrap();
+/// # }
+/// ```
+pub struct MultisampleResolver {
+    gl: ResolveGl,
+    samples: u16,
+    width: u32,
+    height: u32,
+    framebuffer: c_uint,
+    /// The color and depth-stencil renderbuffers.
+    renderbuffers: [c_uint; 2],
+}
+
+impl MultisampleResolver {
+    /// Returns whether the default framebuffer of `context` has less than
+    /// `samples` samples per pixel, so that a `MultisampleResolver` is
+    /// needed to render with them.
+    pub fn is_needed(context: &CombinedContext, samples: u16) -> bool {
+        context.get_pixel_format().multisampling.unwrap_or(0) < samples
+    }
+
+    /// Creates a framebuffer object of `size` pixels with `samples` samples
+    /// per pixel, and binds it.
+    ///
+    /// `context` must be current, and `samples` at most `GL_MAX_SAMPLES`.
+    pub fn new(
+        context: &Context,
+        samples: u16,
+        size: dpi::PhysicalSize,
+    ) -> Result<Self, ContextError> {
+        let gl = context.load_gl::<ResolveGl>()?;
+        if gl.get_integerv.is_none()
+            || gl.gen_framebuffers.is_none()
+            || gl.delete_framebuffers.is_none()
+            || gl.bind_framebuffer.is_none()
+            || gl.check_framebuffer_status.is_none()
+            || gl.gen_renderbuffers.is_none()
+            || gl.delete_renderbuffers.is_none()
+            || gl.bind_renderbuffer.is_none()
+            || gl.renderbuffer_storage_multisample.is_none()
+            || gl.framebuffer_renderbuffer.is_none()
+            || gl.blit_framebuffer.is_none()
+        {
+            return Err(ContextError::FunctionUnavailable);
+        }
+
+        let mut max_samples = 0;
+        unsafe { gl.get_integerv.unwrap()(GL_MAX_SAMPLES, &mut max_samples) };
+        if c_int::from(samples) > max_samples {
+            return Err(ContextError::OsError(format!(
+                "{} samples were requested, at most {} are supported",
+                samples, max_samples
+            )));
+        }
+
+        let mut framebuffer = 0;
+        let mut renderbuffers = [0; 2];
+        unsafe {
+            gl.gen_framebuffers.unwrap()(1, &mut framebuffer);
+            gl.gen_renderbuffers.unwrap()(2, renderbuffers.as_mut_ptr());
+        }
+        let (width, height) = size.into();
+        let resolver = MultisampleResolver {
+            gl,
+            samples,
+            width,
+            height,
+            framebuffer,
+            renderbuffers,
+        };
+        if let Err(err) = unsafe { resolver.allocate() } {
+            unsafe { resolver.delete_objects() };
+            return Err(err);
+        }
+        Ok(resolver)
+    }
+
+    /// Returns the name of the framebuffer object to render to.
+    pub fn framebuffer(&self) -> u32 {
+        self.framebuffer
+    }
+
+    /// Returns the number of samples per pixel of the framebuffer object.
+    pub fn samples(&self) -> u16 {
+        self.samples
+    }
+
+    /// Returns the size of the framebuffer object.
+    pub fn size(&self) -> dpi::PhysicalSize {
+        dpi::PhysicalSize::new(f64::from(self.width), f64::from(self.height))
+    }
+
+    /// Reallocates the framebuffer object with `size` pixels, such as when
+    /// the window is resized, and binds it. Its contents are lost.
+    ///
+    /// `context` must be current.
+    pub fn resize(
+        &mut self,
+        context: &Context,
+        size: dpi::PhysicalSize,
+    ) -> Result<(), ContextError> {
+        if !context.is_current() {
+            return Err(ContextError::NotCurrent);
+        }
+        let (width, height) = size.into();
+        self.width = width;
+        self.height = height;
+        unsafe { self.allocate() }
+    }
+
+    /// Resolves the samples of the framebuffer object into the default
+    /// framebuffer, then binds the framebuffer object again.
+    ///
+    /// `context` must be current.
+    pub fn resolve(&self, context: &Context) -> Result<(), ContextError> {
+        if !context.is_current() {
+            return Err(ContextError::NotCurrent);
+        }
+        let bind_framebuffer = self.gl.bind_framebuffer.unwrap();
+        let (width, height) = (self.width as c_int, self.height as c_int);
+        unsafe {
+            bind_framebuffer(GL_READ_FRAMEBUFFER, self.framebuffer);
+            bind_framebuffer(GL_DRAW_FRAMEBUFFER, 0);
+            self.gl.blit_framebuffer.unwrap()(
+                0,
+                0,
+                width,
+                height,
+                0,
+                0,
+                width,
+                height,
+                GL_COLOR_BUFFER_BIT,
+                GL_NEAREST,
+            );
+            bind_framebuffer(GL_FRAMEBUFFER, self.framebuffer);
+        }
+        Ok(())
+    }
+
+    /// Resolves the samples into the default framebuffer of `context` with
+    /// `resolve`, and swaps its buffers.
+    ///
+    /// `context` must be current.
+    pub fn swap_buffers(
+        &self,
+        context: &CombinedContext,
+    ) -> Result<(), ContextError> {
+        self.resolve(context.context())?;
+        context.swap_buffers()
+    }
+
+    /// Deletes the framebuffer object, `context` being current.
+    ///
+    /// Dropping the resolver instead leaks the objects, which are destroyed
+    /// along with the context.
+    pub fn delete(self, context: &Context) -> Result<(), ContextError> {
+        if !context.is_current() {
+            return Err(ContextError::NotCurrent);
+        }
+        unsafe { self.delete_objects() };
+        Ok(())
+    }
+
+    /// Allocates the storage of the renderbuffers and binds the framebuffer
+    /// object, the context being current.
+    unsafe fn allocate(&self) -> Result<(), ContextError> {
+        let gl = &self.gl;
+        let bind_renderbuffer = gl.bind_renderbuffer.unwrap();
+        let storage = gl.renderbuffer_storage_multisample.unwrap();
+        let attach = gl.framebuffer_renderbuffer.unwrap();
+        let (width, height) = (self.width as c_int, self.height as c_int);
+        let samples = c_int::from(self.samples);
+
+        let mut previous_rb = 0;
+        gl.get_integerv.unwrap()(GL_RENDERBUFFER_BINDING, &mut previous_rb);
+        gl.bind_framebuffer.unwrap()(GL_FRAMEBUFFER, self.framebuffer);
+        let formats = [GL_RGBA8, GL_DEPTH24_STENCIL8];
+        let attachments = [GL_COLOR_ATTACHMENT0, GL_DEPTH_STENCIL_ATTACHMENT];
+        for i in 0..2 {
+            bind_renderbuffer(GL_RENDERBUFFER, self.renderbuffers[i]);
+            storage(GL_RENDERBUFFER, samples, formats[i], width, height);
+            attach(
+                GL_FRAMEBUFFER,
+                attachments[i],
+                GL_RENDERBUFFER,
+                self.renderbuffers[i],
+            );
+        }
+        bind_renderbuffer(GL_RENDERBUFFER, previous_rb as c_uint);
+
+        let status = gl.check_framebuffer_status.unwrap()(GL_FRAMEBUFFER);
+        if status != GL_FRAMEBUFFER_COMPLETE {
+            gl.bind_framebuffer.unwrap()(GL_FRAMEBUFFER, 0);
+            return Err(ContextError::OsError(format!(
+                "the multisampled framebuffer is incomplete (0x{:x})",
+                status
+            )));
+        }
+        Ok(())
+    }
+
+    unsafe fn delete_objects(&self) {
+        let mut bound = 0;
+        self.gl.get_integerv.unwrap()(GL_FRAMEBUFFER_BINDING, &mut bound);
+        if bound as c_uint == self.framebuffer {
+            self.gl.bind_framebuffer.unwrap()(GL_FRAMEBUFFER, 0);
+        }
+        self.gl.delete_framebuffers.unwrap()(1, &self.framebuffer);
+        self.gl.delete_renderbuffers.unwrap()(2, self.renderbuffers.as_ptr());
+    }
+}
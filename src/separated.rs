@@ -1,5 +1,9 @@
 use super::*;
 
+use combined::PAUSED_FRAME_MS;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
 /// Represents an OpenGL context which has been associated with a preexisting
 /// window.
 ///
@@ -34,6 +38,10 @@ use super::*;
 /// ```
 pub struct SeparatedContext {
     context: Context,
+    /// Whether `resize` makes the context current again.
+    remake_current_on_resize: bool,
+    /// Whether `swap_buffers` waits instead of swapping.
+    paused: AtomicBool,
 }
 
 impl SeparatedContext {
@@ -54,13 +62,25 @@ impl SeparatedContext {
         el: &EventsLoop,
     ) -> Result<Self, CreationError> {
         let ContextBuilder { pf_reqs, gl_attr } = cb;
+        let sharing = gl_attr.sharing;
         let gl_attr = gl_attr.map_sharing(|ctx| &ctx.context);
 
-        platform::Context::new_separated(window, el, &pf_reqs, &gl_attr).map(
-            |context| SeparatedContext {
-                context: Context { context },
-            },
-        )
+        platform::Context::new_separated(window, el, &pf_reqs, &gl_attr)
+            .map(|context| {
+                SeparatedContext::from_platform(context, &pf_reqs, sharing)
+            })
+    }
+
+    pub(crate) fn from_platform(
+        context: platform::Context,
+        pf_reqs: &PixelFormatRequirements,
+        sharing: Option<&Context>,
+    ) -> Self {
+        SeparatedContext {
+            context: Context::from_platform(context, sharing),
+            remake_current_on_resize: pf_reqs.remake_current_on_resize,
+            paused: AtomicBool::new(false),
+        }
     }
 
     /// Borrow the inner GL `Context`.
@@ -68,6 +88,14 @@ impl SeparatedContext {
         &self.context
     }
 
+    /// Destroys the context and its surface, which must happen before the
+    /// window is dropped.
+    ///
+    /// See `Context::destroy` for details.
+    pub fn destroy(self) -> Result<(), ContextError> {
+        self.context.destroy()
+    }
+
     /// Swaps the buffers in case of double or triple buffering.
     ///
     /// You should call this function every time you have finished rendering, or
@@ -78,14 +106,118 @@ impl SeparatedContext {
     /// override your vsync settings, which means that you can't know in
     /// advance whether `swap_buffers` will block or not.
     pub fn swap_buffers(&self) -> Result<(), ContextError> {
+        if self.is_paused() {
+            self.wait_paused_frame();
+            return Ok(());
+        }
+        #[cfg(feature = "strict")]
+        strict::before_swap_buffers(&self.context);
         self.context.context.swap_buffers()
     }
 
+    /// Swaps the buffers, telling the compositor that only the given
+    /// rectangles changed since the last swap.
+    ///
+    /// See `CombinedContext::swap_buffers_with_damage` for details.
+    pub fn swap_buffers_with_damage(
+        &self,
+        rects: &[Rect],
+    ) -> Result<(), ContextError> {
+        if self.is_paused() {
+            self.wait_paused_frame();
+            return Ok(());
+        }
+        #[cfg(feature = "strict")]
+        strict::before_swap_buffers(&self.context);
+        self.context.context.swap_buffers_with_damage(rects)
+    }
+
+    /// Pauses or resumes the presentation of the frames.
+    ///
+    /// See `CombinedContext::set_paused` for details.
+    pub fn set_paused(&self, paused: bool) {
+        self.paused.store(paused, Ordering::Relaxed);
+    }
+
+    /// Returns whether rendering is paused. See `set_paused`.
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::Relaxed)
+    }
+
+    fn wait_paused_frame(&self) {
+        let timeout = Duration::from_millis(PAUSED_FRAME_MS);
+        self.context.context.wait_paused_frame(timeout)
+    }
+
+    /// Makes the context current if it isn't already, draws a frame with
+    /// `draw` and swaps the buffers.
+    ///
+    /// See `CombinedContext::render_frame` for details.
+    ///
+    /// # Safety
+    ///
+    /// Like `make_current`, this replaces the context that was current on
+    /// the calling thread.
+    pub unsafe fn render_frame<F, R>(&self, draw: F) -> Result<R, ContextError>
+    where
+        F: FnOnce(&Context) -> R,
+    {
+        if !self.context.is_current() {
+            self.context.make_current()?;
+        }
+        let ret = draw(&self.context);
+        self.swap_buffers()?;
+        Ok(ret)
+    }
+
     /// Returns the pixel format of the main framebuffer of the context.
     pub fn get_pixel_format(&self) -> PixelFormat {
         self.context.context.get_pixel_format()
     }
 
+    /// Reads the contents of the window back, which is `size` pixels big.
+    ///
+    /// See `CombinedContext::capture` for details.
+    pub fn capture(
+        &self,
+        size: dpi::PhysicalSize,
+    ) -> Result<Screenshot, ContextError> {
+        self.start_capture(size)?.finish()
+    }
+
+    /// Starts reading the contents of the window back, which is `size`
+    /// pixels big.
+    ///
+    /// See `CombinedContext::start_capture` for details.
+    pub fn start_capture(
+        &self,
+        size: dpi::PhysicalSize,
+    ) -> Result<PendingScreenshot<'_>, ContextError> {
+        PendingScreenshot::start(&self.context, &self.get_pixel_format(), size)
+    }
+
+    /// Returns whether `window`, which the context renders to, can currently
+    /// be seen.
+    ///
+    /// See `CombinedContext::get_surface_visibility` for details.
+    pub fn get_surface_visibility(
+        &self,
+        window: &Window,
+    ) -> Result<SurfaceVisibility, ContextError> {
+        self.context.context.get_surface_visibility(window)
+    }
+
+    /// Returns whether the frames of `window`, which the context renders to,
+    /// are synchronized to the vertical blank whatever the swap interval.
+    ///
+    /// See `CombinedContext::is_vsync_forced` for details.
+    pub fn is_vsync_forced(
+        &self,
+        window: &Window,
+    ) -> Result<bool, ContextError> {
+        self.context.context.is_vsync_forced(window)
+    }
+
     /// Resize the context.
     ///
     /// Some platforms (macOS, Wayland) require being manually updated when
@@ -93,10 +225,40 @@ impl SeparatedContext {
     ///
     /// The easiest way of doing this is to take every `Resized` window event
     /// that is received with a `LogicalSize` and convert it to a
-    /// `PhysicalSize` and pass it into this function.
+    /// `PhysicalSize` and pass it into this function. Calling it everywhere
+    /// is always correct: it does nothing where it isn't needed, and
+    /// `needs_manual_resize` tells whether that's the case.
+    ///
+    /// See `ContextBuilder::with_remake_current_on_resize` for the drivers
+    /// that also need the context to be made current again.
+    ///
+    /// ## Platform-specific
+    ///
+    /// - **Wayland:** resizes the `wl_egl_window`, which otherwise keeps the
+    ///   size it was created with.
+    /// - **macOS:** updates the `NSOpenGLContext`, except for ANGLE whose
+    ///   surfaces follow the bounds of the layer.
+    /// - **X11, Windows, DRM, Android, iOS, Emscripten:** a no-op, the
+    ///   surface follows the window by itself.
     pub fn resize(&self, size: dpi::PhysicalSize) {
         let (width, height) = size.into();
         self.context.context.resize(width, height);
+        self.resized();
+    }
+
+    /// Returns whether `resize` has to be called when the window is resized.
+    ///
+    /// See `CombinedContext::needs_manual_resize` for details.
+    pub fn needs_manual_resize(&self) -> bool {
+        self.remake_current_on_resize || self.context.context.needs_resize()
+    }
+
+    /// Finishes a resize once the platform context was resized.
+    pub(crate) fn resized(&self) {
+        if self.remake_current_on_resize && self.context.is_current() {
+            // a failure shows up again on the next swap
+            let _ = unsafe { self.context.make_current() };
+        }
     }
 }
 
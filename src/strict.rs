@@ -0,0 +1,144 @@
+#![cfg(feature = "strict")]
+
+//! Runtime checks of the usage of the API, enabled by the `strict` feature.
+//!
+//! Misuses that the drivers would turn into undefined behavior panic, while
+//! the ones that are only suspicious print a warning on the standard error.
+
+use super::*;
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::thread::{self, ThreadId};
+
+lazy_static! {
+    /// The thread each context is current on, keyed by the id of the
+    /// context.
+    static ref CURRENT_THREADS: Mutex<HashMap<usize, ThreadId>> =
+        Mutex::new(HashMap::new());
+}
+
+/// Prints a warning about a suspicious usage of the API.
+fn warn(message: &str) {
+    eprintln!("glutin (strict): {}", message);
+}
+
+/// Panics if the context is current on another thread, since it can only be
+/// current on one thread at a time.
+pub(crate) fn before_make_current(id: usize) {
+    let threads = CURRENT_THREADS.lock().unwrap();
+    if let Some(thread) = threads.get(&id) {
+        if *thread != thread::current().id() {
+            panic!(
+                "glutin (strict): making a context current while it is \
+                 current on thread {:?}, call `make_not_current` there first",
+                thread
+            );
+        }
+    }
+}
+
+/// Records that the context is now the one current on the calling thread.
+pub(crate) fn made_current(id: usize) {
+    let current = thread::current().id();
+    let mut threads = CURRENT_THREADS.lock().unwrap();
+    // the previous context of this thread was released implicitly
+    threads.retain(|_, thread| *thread != current);
+    threads.insert(id, current);
+}
+
+/// Records that the context isn't current anymore.
+pub(crate) fn made_not_current(id: usize) {
+    CURRENT_THREADS.lock().unwrap().remove(&id);
+}
+
+/// Checks that a context being dropped isn't current on another thread,
+/// which would leave that thread with a dangling context.
+///
+/// Dropping a context current on the calling thread releases it, but prints
+/// a warning since the OpenGL functions can't be called afterwards.
+pub(crate) fn on_drop(id: usize) {
+    let thread = CURRENT_THREADS.lock().unwrap().remove(&id);
+    match thread {
+        Some(thread) if thread != thread::current().id() => panic!(
+            "glutin (strict): dropping a context that is current on thread \
+             {:?}, call `make_not_current` there first",
+            thread
+        ),
+        Some(_) => warn("dropping a context that is current on this thread"),
+        None => (),
+    }
+}
+
+/// Warns if the buffers of a context are swapped while it isn't current,
+/// which fails or does nothing with EGL.
+pub(crate) fn before_swap_buffers(context: &Context) {
+    if !context.is_current() {
+        warn(
+            "swapping the buffers of a context that isn't current on this \
+             thread",
+        );
+    }
+}
+
+/// Whether the context of `window` must be resized along with it.
+fn requires_resize(window: &Window) -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        let _ = window;
+        true
+    }
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    {
+        use winit::os::unix::WindowExt;
+        window.get_wayland_surface().is_some()
+    }
+    #[cfg(not(any(
+        target_os = "macos",
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    {
+        let _ = window;
+        false
+    }
+}
+
+/// Warns once per size if `window` was resized without its context, on the
+/// platforms where `CombinedContext::resize` is required.
+///
+/// `last_size` is the size the context was last resized to, which is
+/// updated after warning.
+pub(crate) fn check_resized(
+    window: &Window,
+    last_size: &Mutex<Option<dpi::PhysicalSize>>,
+) {
+    if !requires_resize(window) {
+        return;
+    }
+    let dpi_factor = window.get_hidpi_factor();
+    let size = match window.get_inner_size() {
+        Some(size) => size.to_physical(dpi_factor),
+        None => return,
+    };
+
+    let mut last_size = last_size.lock().unwrap();
+    if *last_size != Some(size) {
+        if last_size.is_some() {
+            warn(
+                "the window was resized without calling `resize` on its \
+                 context, which keeps rendering at the previous size",
+            );
+        }
+        *last_size = Some(size);
+    }
+}
@@ -0,0 +1,398 @@
+use super::*;
+
+use gl::{
+    load, query_gl_version, GL_ACTIVE_TEXTURE, GL_ARRAY_BUFFER,
+    GL_ARRAY_BUFFER_BINDING, GL_BLEND, GL_CULL_FACE, GL_CURRENT_PROGRAM,
+    GL_DEPTH_TEST, GL_DRAW_FRAMEBUFFER, GL_FRAMEBUFFER, GL_FRAMEBUFFER_BINDING,
+    GL_READ_FRAMEBUFFER, GL_READ_FRAMEBUFFER_BINDING, GL_SCISSOR_BOX,
+    GL_SCISSOR_TEST, GL_STENCIL_TEST, GL_VERTEX_ARRAY_BINDING, GL_VIEWPORT,
+};
+
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
+use std::os::raw::{c_int, c_uint, c_void};
+
+/// The capabilities saved and restored when switching virtual contexts.
+const TRACKED_CAPS: [c_uint; 5] = [
+    GL_SCISSOR_TEST,
+    GL_BLEND,
+    GL_DEPTH_TEST,
+    GL_STENCIL_TEST,
+    GL_CULL_FACE,
+];
+
+/// The OpenGL functions needed to save and restore the tracked state.
+#[derive(Clone)]
+struct StateGl {
+    get_string: Option<unsafe extern "system" fn(c_uint) -> *const u8>,
+    get_integerv: Option<unsafe extern "system" fn(c_uint, *mut c_int)>,
+    is_enabled: Option<unsafe extern "system" fn(c_uint) -> u8>,
+    enable: Option<unsafe extern "system" fn(c_uint)>,
+    disable: Option<unsafe extern "system" fn(c_uint)>,
+    viewport: Option<unsafe extern "system" fn(c_int, c_int, c_int, c_int)>,
+    scissor: Option<unsafe extern "system" fn(c_int, c_int, c_int, c_int)>,
+    use_program: Option<unsafe extern "system" fn(c_uint)>,
+    active_texture: Option<unsafe extern "system" fn(c_uint)>,
+    bind_buffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+    bind_vertex_array: Option<unsafe extern "system" fn(c_uint)>,
+    bind_framebuffer: Option<unsafe extern "system" fn(c_uint, c_uint)>,
+}
+
+unsafe impl GlLoader for StateGl {
+    fn load_with<F>(mut loadfn: F) -> Self
+    where
+        F: FnMut(&'static str) -> *const c_void,
+    {
+        StateGl {
+            get_string: load(loadfn("glGetString")),
+            get_integerv: load(loadfn("glGetIntegerv")),
+            is_enabled: load(loadfn("glIsEnabled")),
+            enable: load(loadfn("glEnable")),
+            disable: load(loadfn("glDisable")),
+            viewport: load(loadfn("glViewport")),
+            scissor: load(loadfn("glScissor")),
+            use_program: load(loadfn("glUseProgram")),
+            active_texture: load(loadfn("glActiveTexture")),
+            bind_buffer: load(loadfn("glBindBuffer")),
+            bind_vertex_array: load(loadfn("glBindVertexArray")),
+            bind_framebuffer: load(loadfn("glBindFramebuffer")),
+        }
+    }
+}
+
+/// Which of the tracked state the native context has.
+#[derive(Debug, Copy, Clone)]
+struct Features {
+    programs: bool,
+    framebuffers: bool,
+    /// Separate draw and read framebuffers.
+    read_framebuffer: bool,
+    vertex_arrays: bool,
+}
+
+impl Features {
+    unsafe fn query(gl: &StateGl) -> Features {
+        let (es, major, _) = query_gl_version(gl.get_string);
+        Features {
+            programs: (es || major >= 2) && gl.use_program.is_some(),
+            framebuffers: (es || major >= 3) && gl.bind_framebuffer.is_some(),
+            read_framebuffer: major >= 3 && gl.bind_framebuffer.is_some(),
+            vertex_arrays: major >= 3 && gl.bind_vertex_array.is_some(),
+        }
+    }
+}
+
+/// The state of a virtual context while another one uses the native
+/// context.
+#[derive(Debug, Clone, Default)]
+struct TrackedState {
+    viewport: [c_int; 4],
+    scissor_box: [c_int; 4],
+    caps: [bool; 5],
+    program: c_int,
+    active_texture: c_int,
+    array_buffer: c_int,
+    vertex_array: c_int,
+    draw_framebuffer: c_int,
+    read_framebuffer: c_int,
+}
+
+impl TrackedState {
+    unsafe fn save(gl: &StateGl, features: Features) -> TrackedState {
+        let get_integerv = gl.get_integerv.unwrap();
+        let get = |pname| {
+            let mut value = 0;
+            get_integerv(pname, &mut value);
+            value
+        };
+
+        let mut state = TrackedState::default();
+        get_integerv(GL_VIEWPORT, state.viewport.as_mut_ptr());
+        get_integerv(GL_SCISSOR_BOX, state.scissor_box.as_mut_ptr());
+        let is_enabled = gl.is_enabled.unwrap();
+        for (enabled, &cap) in state.caps.iter_mut().zip(TRACKED_CAPS.iter())
+        {
+            *enabled = is_enabled(cap) != 0;
+        }
+        state.active_texture = get(GL_ACTIVE_TEXTURE);
+        state.array_buffer = get(GL_ARRAY_BUFFER_BINDING);
+        if features.programs {
+            state.program = get(GL_CURRENT_PROGRAM);
+        }
+        if features.vertex_arrays {
+            state.vertex_array = get(GL_VERTEX_ARRAY_BINDING);
+        }
+        if features.framebuffers {
+            state.draw_framebuffer = get(GL_FRAMEBUFFER_BINDING);
+        }
+        if features.read_framebuffer {
+            state.read_framebuffer = get(GL_READ_FRAMEBUFFER_BINDING);
+        }
+        state
+    }
+
+    unsafe fn restore(&self, gl: &StateGl, features: Features) {
+        let [x, y, w, h] = self.viewport;
+        gl.viewport.unwrap()(x, y, w, h);
+        let [x, y, w, h] = self.scissor_box;
+        gl.scissor.unwrap()(x, y, w, h);
+        for (&enabled, &cap) in self.caps.iter().zip(TRACKED_CAPS.iter()) {
+            if enabled {
+                gl.enable.unwrap()(cap);
+            } else {
+                gl.disable.unwrap()(cap);
+            }
+        }
+        if let Some(active_texture) = gl.active_texture {
+            active_texture(self.active_texture as c_uint);
+        }
+        // the array buffer binding is part of the vertex array on GL 3
+        if features.vertex_arrays {
+            gl.bind_vertex_array.unwrap()(self.vertex_array as c_uint);
+        }
+        gl.bind_buffer.unwrap()(GL_ARRAY_BUFFER, self.array_buffer as c_uint);
+        if features.programs {
+            gl.use_program.unwrap()(self.program as c_uint);
+        }
+        if features.read_framebuffer {
+            let bind = gl.bind_framebuffer.unwrap();
+            bind(GL_DRAW_FRAMEBUFFER, self.draw_framebuffer as c_uint);
+            bind(GL_READ_FRAMEBUFFER, self.read_framebuffer as c_uint);
+        } else if features.framebuffers {
+            gl.bind_framebuffer.unwrap()(
+                GL_FRAMEBUFFER,
+                self.draw_framebuffer as c_uint,
+            );
+        }
+    }
+}
+
+/// Counts what making virtual contexts current cost, see
+/// `VirtualContextHost::get_stats`.
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq)]
+pub struct VirtualContextStats {
+    /// The number of times a virtual context was made current.
+    pub make_current_calls: u64,
+
+    /// The number of times the native context was actually made current.
+    pub native_make_current_calls: u64,
+
+    /// The number of times the tracked state was swapped because another
+    /// virtual context became current.
+    pub state_switches: u64,
+}
+
+struct HostState {
+    gl: Option<(StateGl, Features)>,
+    /// The virtual context whose state is in the native context.
+    current: Option<usize>,
+    next_id: usize,
+    /// The state of the native context before any virtual context used it,
+    /// which new virtual contexts start with.
+    initial: Option<TrackedState>,
+    saved: HashMap<usize, TrackedState>,
+}
+
+/// Shares a single native context between several virtual contexts, so that
+/// switching between them doesn't go through the driver's `MakeCurrent`.
+///
+/// Plugin-style architectures often give each plugin its own context, all
+/// sharing their objects, and make them current in turn many times per
+/// frame. Making a native context current is expensive on most platforms,
+/// since it flushes the previous one. Virtual contexts instead save and
+/// restore a small set of state when switching:
+///
+/// - the viewport and the scissor box,
+/// - whether scissor testing, blending, depth testing, stencil testing and
+///   face culling are enabled,
+/// - the current program and active texture unit,
+/// - the bound vertex array, array buffer and framebuffers.
+///
+/// Any other state is shared by the virtual contexts, and must be set by
+/// each of them before use. Objects are shared too, like with
+/// `ContextBuilder::with_shared_lists`.
+///
+/// The host can only be used from one thread, on which the native context
+/// stays current until another context is made current.
+///
+/// # Example
+///
+/// ```no_run
+/// # extern crate glutin;
+/// # use glutin::ContextTrait;
+/// # fn main() {
+/// let el = glutin::EventsLoop::new();
+/// let context = glutin::ContextBuilder::new().build_headless(&el).unwrap();
+/// let host = glutin::VirtualContextHost::new(context);
+///
+/// let plugin_a = host.create_context();
+/// let plugin_b = host.create_context();
+/// for _ in 0..100 {
+///     unsafe { plugin_a.make_current().unwrap() };
+///     // draw plugin A here
+///     unsafe { plugin_b.make_current().unwrap() };
+///     // draw plugin B here
+/// }
+/// println!("{:?}", host.get_stats());
+/// # }
+/// ```
+pub struct VirtualContextHost {
+    context: Context,
+    state: RefCell<HostState>,
+    stats: Cell<VirtualContextStats>,
+}
+
+impl VirtualContextHost {
+    /// Builds a host for virtual contexts on top of `context`.
+    pub fn new(context: Context) -> Self {
+        VirtualContextHost {
+            context,
+            state: RefCell::new(HostState {
+                gl: None,
+                current: None,
+                next_id: 0,
+                initial: None,
+                saved: HashMap::new(),
+            }),
+            stats: Cell::new(VirtualContextStats::default()),
+        }
+    }
+
+    /// Creates a virtual context, which starts with the state the native
+    /// context had before any virtual context was made current.
+    pub fn create_context(&self) -> VirtualContext<'_> {
+        let mut state = self.state.borrow_mut();
+        let id = state.next_id;
+        state.next_id += 1;
+        VirtualContext { host: self, id }
+    }
+
+    /// Borrows the native context.
+    ///
+    /// The tracked state of the current virtual context is only saved when
+    /// another virtual context is made current, so making the native context
+    /// current directly and changing that state is a logic error.
+    pub fn context(&self) -> &Context {
+        &self.context
+    }
+
+    /// Returns how many times virtual contexts were made current, and how
+    /// many of those went through the driver.
+    pub fn get_stats(&self) -> VirtualContextStats {
+        self.stats.get()
+    }
+
+    /// Resets the counters returned by `get_stats`.
+    pub fn reset_stats(&self) {
+        self.stats.set(VirtualContextStats::default());
+    }
+
+    /// Unwraps the native context.
+    pub fn into_context(self) -> Context {
+        self.context
+    }
+
+    unsafe fn make_current(&self, id: usize) -> Result<(), ContextError> {
+        let mut stats = self.stats.get();
+        stats.make_current_calls += 1;
+        let native = !self.context.is_current();
+        if native {
+            stats.native_make_current_calls += 1;
+        }
+        self.stats.set(stats);
+        if native {
+            self.context.make_current()?;
+        }
+
+        let mut state = self.state.borrow_mut();
+        if state.current == Some(id) {
+            return Ok(());
+        }
+        if state.gl.is_none() {
+            let gl = self.context.load_gl::<StateGl>()?;
+            if gl.get_integerv.is_none()
+                || gl.is_enabled.is_none()
+                || gl.enable.is_none()
+                || gl.disable.is_none()
+                || gl.viewport.is_none()
+                || gl.scissor.is_none()
+                || gl.bind_buffer.is_none()
+            {
+                return Err(ContextError::FunctionUnavailable);
+            }
+            let features = Features::query(&gl);
+            state.gl = Some((gl, features));
+        }
+        let (gl, features) = state.gl.clone().unwrap();
+
+        let previous = TrackedState::save(&gl, features);
+        match state.current {
+            Some(current) => {
+                state.saved.insert(current, previous);
+            }
+            None => {
+                if state.initial.is_none() {
+                    state.initial = Some(previous);
+                }
+            }
+        }
+
+        let next = state.saved.remove(&id).or_else(|| state.initial.clone());
+        if let Some(next) = next {
+            next.restore(&gl, features);
+        }
+        state.current = Some(id);
+        let mut stats = self.stats.get();
+        stats.state_switches += 1;
+        self.stats.set(stats);
+        Ok(())
+    }
+}
+
+/// A context created by a `VirtualContextHost`, backed by its native
+/// context.
+///
+/// Making it current only goes through the driver if the native context
+/// isn't current on the thread. Its tracked state is forgotten when it is
+/// dropped.
+pub struct VirtualContext<'a> {
+    host: &'a VirtualContextHost,
+    id: usize,
+}
+
+impl<'a> VirtualContext<'a> {
+    /// Returns the host of the virtual context.
+    pub fn host(&self) -> &'a VirtualContextHost {
+        self.host
+    }
+}
+
+impl<'a> ContextTrait for VirtualContext<'a> {
+    unsafe fn make_current(&self) -> Result<(), ContextError> {
+        self.host.make_current(self.id)
+    }
+
+    fn is_current(&self) -> bool {
+        self.host.state.borrow().current == Some(self.id)
+            && self.host.context.is_current()
+    }
+
+    fn get_proc_address(&self, addr: &str) -> *const () {
+        self.host.context.get_proc_address(addr)
+    }
+
+    fn get_api(&self) -> Api {
+        self.host.context.get_api()
+    }
+}
+
+impl<'a> Drop for VirtualContext<'a> {
+    fn drop(&mut self) {
+        let mut state = self.host.state.borrow_mut();
+        state.saved.remove(&self.id);
+        if state.current == Some(self.id) {
+            // the state stays in the native context until the next switch
+            state.current = None;
+        }
+    }
+}
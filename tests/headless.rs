@@ -0,0 +1,1218 @@
+//! Renders with a software implementation of OpenGL and reads the results
+//! back, so that the FFI paths of each backend are exercised without a GPU.
+//!
+//! The contexts come from OSMesa on Linux, which doesn't need a display
+//! server, or from the surfaceless platform of Mesa when OSMesa is missing.
+//! Elsewhere, they are headless contexts of the software renderers of WGL
+//! (the GDI generic implementation) and CGL (the Apple software renderer).
+//! Tests are skipped when no context can be built.
+//!
+//! This runs with `harness = false`, on the main thread, since the events
+//! loop of macOS must live there.
+
+extern crate glutin;
+
+mod gl {
+    pub use self::Gles2 as Gl;
+    include!(concat!(env!("OUT_DIR"), "/test_gl_bindings.rs"));
+}
+
+use glutin::{
+    Api, CombinedContext, Context, ContextBuilder, ContextTrait, GlRequest,
+    GpuTimer, MultisampleResolver, OfflineRenderer,
+};
+
+use std::process;
+use std::time::Duration;
+
+const GL_DEPTH_BITS: gl::types::GLenum = 0x0D56;
+const GL_FRAMEBUFFER_SRGB: gl::types::GLenum = 0x8DB9;
+
+enum Outcome {
+    Passed,
+    Skipped(String),
+    Failed(String),
+}
+
+/// Builds the contexts the tests render with.
+struct Harness {
+    /// Whether OSMesa is available, otherwise the contexts are built on the
+    /// surfaceless platform of Mesa.
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    osmesa: bool,
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    el: glutin::EventsLoop,
+}
+
+impl Harness {
+    fn new() -> Self {
+        Harness {
+            #[cfg(any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            ))]
+            osmesa: {
+                use glutin::os::unix::OsMesaContextExt;
+                Context::new_osmesa(ContextBuilder::new()).is_ok()
+            },
+            #[cfg(not(any(
+                target_os = "linux",
+                target_os = "dragonfly",
+                target_os = "freebsd",
+                target_os = "netbsd",
+                target_os = "openbsd"
+            )))]
+            el: glutin::EventsLoop::new(),
+        }
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    fn build(&self, cb: ContextBuilder) -> Result<Context, String> {
+        use glutin::os::unix::{OsMesaContextExt, SurfacelessContextExt};
+        if self.osmesa {
+            Context::new_osmesa(cb)
+        } else {
+            Context::new_surfaceless(cb)
+        }
+        .map_err(|err| err.to_string())
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    fn build(&self, cb: ContextBuilder) -> Result<Context, String> {
+        cb.with_hardware_acceleration(Some(false))
+            .build_headless(&self.el)
+            .map_err(|err| err.to_string())
+    }
+
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    fn with_events_loop<F>(&self, test: F) -> Outcome
+    where
+        F: FnOnce(&glutin::EventsLoop) -> Outcome,
+    {
+        // winit panics without a display server
+        if std::env::var_os("DISPLAY").is_none()
+            && std::env::var_os("WAYLAND_DISPLAY").is_none()
+        {
+            return Outcome::Skipped("no display server".into());
+        }
+        test(&glutin::EventsLoop::new())
+    }
+
+    #[cfg(not(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    )))]
+    fn with_events_loop<F>(&self, test: F) -> Outcome
+    where
+        F: FnOnce(&glutin::EventsLoop) -> Outcome,
+    {
+        test(&self.el)
+    }
+
+    /// Builds a hidden window and its context, made current, or skips the
+    /// test.
+    fn window_or_skip(
+        &self,
+        el: &glutin::EventsLoop,
+        cb: ContextBuilder,
+    ) -> Result<CombinedContext, Outcome> {
+        let wb = glutin::WindowBuilder::new()
+            .with_visibility(false)
+            .with_dimensions((32, 32).into());
+        let combined = cb
+            .build_combined(wb, el)
+            .map_err(|err| Outcome::Skipped(err.to_string()))?;
+        unsafe { combined.make_current() }
+            .map_err(|err| Outcome::Failed(format!("{:?}", err)))?;
+        Ok(combined)
+    }
+
+    /// Builds a context, or skips the test.
+    fn context_or_skip(&self, cb: ContextBuilder) -> Result<Context, Outcome> {
+        self.build(cb).map_err(Outcome::Skipped)
+    }
+
+    /// Builds a context and makes it current, or skips the test.
+    fn current(
+        &self,
+        cb: ContextBuilder,
+    ) -> Result<(Context, gl::Gl), Outcome> {
+        let context = self.context_or_skip(cb)?;
+        unsafe { context.make_current() }
+            .map_err(|err| Outcome::Failed(format!("{:?}", err)))?;
+        let gl =
+            gl::Gl::load_with(|s| context.get_proc_address(s) as *const _);
+        Ok((context, gl))
+    }
+}
+
+/// Clears the framebuffer to `color` and reads the bottom-left pixel back.
+unsafe fn clear_and_read(gl: &gl::Gl, color: [f32; 4]) -> [u8; 4] {
+    gl.ClearColor(color[0], color[1], color[2], color[3]);
+    gl.Clear(gl::COLOR_BUFFER_BIT);
+    let mut pixel = [0u8; 4];
+    gl.ReadPixels(
+        0,
+        0,
+        1,
+        1,
+        gl::RGBA,
+        gl::UNSIGNED_BYTE,
+        pixel.as_mut_ptr() as *mut _,
+    );
+    pixel
+}
+
+fn close_to(pixel: [u8; 4], expected: [u8; 4]) -> bool {
+    pixel
+        .iter()
+        .zip(expected.iter())
+        .all(|(&a, &b)| (i16::from(a) - i16::from(b)).abs() <= 2)
+}
+
+fn clear_readback(harness: &Harness) -> Outcome {
+    let (_context, gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let pixel = unsafe { clear_and_read(&gl, [1.0, 0.5, 0.0, 1.0]) };
+    if close_to(pixel, [255, 128, 0, 255]) {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("read {:?} back", pixel))
+    }
+}
+
+/// `get_proc_address` must return the core functions as well as the
+/// extension ones.
+fn core_functions(harness: &Harness) -> Outcome {
+    let (_context, gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    if gl.Clear.is_loaded() && gl.GetString.is_loaded() {
+        Outcome::Passed
+    } else {
+        Outcome::Failed("glClear or glGetString wasn't returned".into())
+    }
+}
+
+fn config_selection(harness: &Harness) -> Outcome {
+    let cb = ContextBuilder::new()
+        .with_pixel_format(24, 8)
+        .with_depth_buffer(24);
+    let (_context, gl) = match harness.current(cb) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let mut depth_bits = 0;
+    unsafe {
+        gl.GetIntegerv(GL_DEPTH_BITS, &mut depth_bits);
+        // core profiles don't have `GL_DEPTH_BITS`
+        if gl.GetError() != gl::NO_ERROR {
+            return Outcome::Skipped("GL_DEPTH_BITS is unavailable".into());
+        }
+    }
+    if depth_bits < 24 {
+        return Outcome::Failed(format!("got {} depth bits", depth_bits));
+    }
+    let pixel = unsafe { clear_and_read(&gl, [0.0, 0.0, 1.0, 0.5]) };
+    if close_to(pixel, [0, 0, 255, 128]) {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("read {:?} back", pixel))
+    }
+}
+
+fn srgb(harness: &Harness) -> Outcome {
+    let cb = ContextBuilder::new().with_srgb(true);
+    let (_context, gl) = match harness.current(cb) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let pixel = unsafe {
+        gl.Enable(GL_FRAMEBUFFER_SRGB);
+        if gl.GetError() != gl::NO_ERROR {
+            let reason = "GL_FRAMEBUFFER_SRGB is unavailable";
+            return Outcome::Skipped(reason.into());
+        }
+        clear_and_read(&gl, [0.5, 0.5, 0.5, 1.0])
+    };
+    // the framebuffer is either linear or encodes the color in sRGB
+    if close_to(pixel, [128, 128, 128, 255])
+        || close_to(pixel, [188, 188, 188, 255])
+    {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("read {:?} back", pixel))
+    }
+}
+
+fn swap_interval(harness: &Harness) -> Outcome {
+    let (context, _gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let range = match context.get_swap_interval_range() {
+        Ok(range) if range.min <= range.max => range,
+        Ok(range) => {
+            return Outcome::Failed(format!("invalid range {:?}", range))
+        }
+        Err(glutin::ContextError::FunctionUnavailable) => {
+            return Outcome::Skipped("swap intervals are unavailable".into())
+        }
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    };
+    let strict = glutin::SwapIntervalPolicy::Strict;
+    match context.set_swap_interval_with_policy(range.max + 1, strict) {
+        Err(glutin::ContextError::OsError(_)) => Outcome::Passed,
+        res => Outcome::Failed(format!("{:?} past the range", res)),
+    }
+}
+
+fn swap_interval_caps(harness: &Harness) -> Outcome {
+    use glutin::{SwapIntervalQuirk, SwapIntervalReliability};
+
+    let (context, _gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let driver = match context.get_driver_info() {
+        Ok(driver) => driver,
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    };
+    if driver.renderer.is_empty() || driver.version.is_empty() {
+        return Outcome::Failed(format!("incomplete {:?}", driver));
+    }
+    let caps = match context.get_swap_interval_caps() {
+        Ok(caps) => caps,
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    };
+    let unsupported =
+        SwapIntervalReliability::Unreliable(SwapIntervalQuirk::Unsupported);
+    match (caps.range, caps.reliability) {
+        (None, reliability) if reliability != unsupported => {
+            Outcome::Failed(format!("{:?} without a range", reliability))
+        }
+        (_, SwapIntervalReliability::Reliable) if driver.is_software() => {
+            Outcome::Failed(format!("{} reported reliable", driver.renderer))
+        }
+        _ => Outcome::Passed,
+    }
+}
+
+fn swap_msc(harness: &Harness) -> Outcome {
+    let context = match harness.context_or_skip(ContextBuilder::new()) {
+        Ok(context) => context,
+        Err(outcome) => return outcome,
+    };
+    match context.set_swap_msc(2, 1) {
+        Ok(()) => (),
+        Err(glutin::ContextError::FunctionUnavailable) => {
+            return Outcome::Skipped("OML_sync_control is unavailable".into())
+        }
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    }
+    if context.set_swap_msc(2, 2).is_ok() {
+        return Outcome::Failed("accepted a remainder of 2 for 2".into());
+    }
+    match context.set_swap_msc(0, 0) {
+        Ok(()) => Outcome::Passed,
+        Err(err) => Outcome::Failed(format!("{:?}", err)),
+    }
+}
+
+/// Waits for the vertical blank of the display of a window, which needs a
+/// display server.
+fn wait_for_vsync(harness: &Harness) -> Outcome {
+    harness.with_events_loop(|el| {
+        let combined = match harness.window_or_skip(el, ContextBuilder::new()) {
+            Ok(combined) => combined,
+            Err(outcome) => return outcome,
+        };
+        match combined.wait_for_vsync() {
+            Ok(()) => Outcome::Passed,
+            Err(glutin::ContextError::FunctionUnavailable) => {
+                let reason = "waiting for the vertical blank is unavailable";
+                Outcome::Skipped(reason.into())
+            }
+            Err(err) => Outcome::Failed(format!("{:?}", err)),
+        }
+    })
+}
+
+/// Presents a few frames of a window, which needs a display server, and
+/// checks that the statistics only grow.
+fn present_stats(harness: &Harness) -> Outcome {
+    harness.with_events_loop(|el| {
+        let combined = match harness.window_or_skip(el, ContextBuilder::new()) {
+            Ok(combined) => combined,
+            Err(outcome) => return outcome,
+        };
+        let before = match combined.get_present_stats() {
+            Ok(stats) => stats,
+            Err(glutin::ContextError::FunctionUnavailable) => {
+                let reason = "present statistics are unavailable";
+                return Outcome::Skipped(reason.into());
+            }
+            Err(err) => return Outcome::Failed(format!("{:?}", err)),
+        };
+        for _ in 0..3 {
+            if let Err(err) = combined.swap_buffers() {
+                return Outcome::Failed(format!("{:?}", err));
+            }
+        }
+        let after = match combined.get_present_stats() {
+            Ok(stats) => stats,
+            Err(err) => return Outcome::Failed(format!("{:?}", err)),
+        };
+        if after.refresh_count < before.refresh_count
+            || after.frames_displayed < before.frames_displayed
+            || after.frames_dropped < before.frames_dropped
+            || after.frames_missed < before.frames_missed
+        {
+            Outcome::Failed(format!("went from {:?} to {:?}", before, after))
+        } else {
+            Outcome::Passed
+        }
+    })
+}
+
+fn pbuffer_texture(harness: &Harness) -> Outcome {
+    let context = match harness.context_or_skip(ContextBuilder::new()) {
+        Ok(context) => context,
+        Err(outcome) => return outcome,
+    };
+    match context.bind_pbuffer_to_texture() {
+        Err(glutin::ContextError::FunctionUnavailable) => (),
+        res => return Outcome::Failed(format!("bound without: {:?}", res)),
+    }
+
+    let texture = glutin::PbufferTexture {
+        alpha: true,
+        mipmap: false,
+    };
+    let cb = ContextBuilder::new().with_pbuffer_texture(Some(texture));
+    let context = match harness.context_or_skip(cb) {
+        Ok(context) => context,
+        Err(outcome) => return outcome,
+    };
+    // the level can only be chosen with mipmaps
+    match context.set_pbuffer_mipmap_level(0) {
+        Err(glutin::ContextError::FunctionUnavailable) => (),
+        res => return Outcome::Failed(format!("chose a level: {:?}", res)),
+    }
+    match context.bind_pbuffer_to_texture() {
+        Err(glutin::ContextError::NotCurrent) => (),
+        res => return Outcome::Failed(format!("bound to none: {:?}", res)),
+    }
+
+    if let Err(err) = unsafe { context.make_current() } {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+    match context
+        .bind_pbuffer_to_texture()
+        .and_then(|_| context.release_pbuffer_from_texture())
+    {
+        Ok(()) => Outcome::Passed,
+        Err(err) => Outcome::Failed(format!("{:?}", err)),
+    }
+}
+
+/// Switches a window to single buffering, which needs a display server and
+/// `EGL_KHR_mutable_render_buffer`.
+fn render_buffer(harness: &Harness) -> Outcome {
+    harness.with_events_loop(|el| {
+        let cb = ContextBuilder::new().with_mutable_render_buffer(true);
+        let combined = match harness.window_or_skip(el, cb) {
+            Ok(combined) => combined,
+            Err(outcome) => return outcome,
+        };
+        if !combined.is_render_buffer_mutable() {
+            let reason = "the render buffer isn't mutable";
+            return Outcome::Skipped(reason.into());
+        }
+        // the new render buffer takes effect after the next swap
+        let res = combined
+            .set_render_buffer(glutin::RenderBuffer::Single)
+            .and_then(|()| combined.swap_buffers())
+            .and_then(|()| combined.get_surface_info());
+        match res {
+            Ok(ref info)
+                if info.render_buffer == Some(glutin::RenderBuffer::Single) =>
+            {
+                Outcome::Passed
+            }
+            res => Outcome::Failed(format!("got {:?}", res)),
+        }
+    })
+}
+
+fn pbuffer_limits(harness: &Harness) -> Outcome {
+    let context = match harness.context_or_skip(ContextBuilder::new()) {
+        Ok(context) => context,
+        Err(outcome) => return outcome,
+    };
+    match context.get_pbuffer_limits() {
+        Ok(limits) if limits.max_width > 0 && limits.max_height > 0 => {
+            Outcome::Passed
+        }
+        Ok(limits) => Outcome::Failed(format!("got {:?}", limits)),
+        Err(glutin::ContextError::FunctionUnavailable) => {
+            Outcome::Skipped("pbuffer limits are unavailable".into())
+        }
+        Err(err) => Outcome::Failed(format!("{:?}", err)),
+    }
+}
+
+/// The tests of the backends that only exist on Linux and the BSDs, which
+/// build their own contexts.
+#[cfg(any(
+    target_os = "linux",
+    target_os = "dragonfly",
+    target_os = "freebsd",
+    target_os = "netbsd",
+    target_os = "openbsd"
+))]
+mod unix {
+    use super::*;
+
+    use glutin::os::unix::SurfacelessContextExt;
+
+    /// Builds a context on the surfaceless platform of Mesa, or skips the
+    /// test.
+    fn surfaceless_or_skip(cb: ContextBuilder) -> Result<Context, Outcome> {
+        Context::new_surfaceless(cb)
+            .map_err(|err| Outcome::Skipped(err.to_string()))
+    }
+
+    pub fn surfaceless(_: &Harness) -> Outcome {
+        let context = match surfaceless_or_skip(ContextBuilder::new()) {
+            Ok(context) => context,
+            Err(outcome) => return outcome,
+        };
+        if let Err(err) = unsafe { context.make_current() } {
+            return Outcome::Failed(format!("{:?}", err));
+        }
+        let gl = gl::Gl::load_with(|s| context.get_proc_address(s) as *const _);
+        let pixel = unsafe { clear_and_read(&gl, [0.0, 1.0, 0.0, 1.0]) };
+        if close_to(pixel, [0, 255, 0, 255]) {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(format!("read {:?} back", pixel))
+        }
+    }
+
+    pub fn x11_pixmap(_: &Harness) -> Outcome {
+        use glutin::dpi::PhysicalSize;
+        use glutin::os::unix::PixmapContextExt;
+        // winit panics without a display server
+        if std::env::var_os("DISPLAY").is_none() {
+            return Outcome::Skipped("no X server".into());
+        }
+        let el = glutin::EventsLoop::new();
+        let size = PhysicalSize::new(16.0, 16.0);
+        let context =
+            match Context::new_x11_pixmap(&el, ContextBuilder::new(), size) {
+                Ok(context) => context,
+                Err(err) => return Outcome::Skipped(err.to_string()),
+            };
+        if let Err(err) = unsafe { context.make_current() } {
+            return Outcome::Failed(format!("{:?}", err));
+        }
+        let gl = gl::Gl::load_with(|s| context.get_proc_address(s) as *const _);
+        let pixel = unsafe { clear_and_read(&gl, [0.0, 0.0, 1.0, 1.0]) };
+        if close_to(pixel, [0, 0, 255, 255]) {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(format!("read {:?} back", pixel))
+        }
+    }
+
+    /// Strict selection only applies to EGL, which the surfaceless platform of
+    /// Mesa provides without a display server.
+    pub fn strict_selection(_: &Harness) -> Outcome {
+        let cb = ContextBuilder::new()
+            .with_strict_selection(true)
+            .with_pixel_format(24, 8)
+            .with_depth_buffer(16);
+        let context = match surfaceless_or_skip(cb) {
+            Ok(context) => context,
+            Err(outcome) => return outcome,
+        };
+        if let Err(err) = unsafe { context.make_current() } {
+            return Outcome::Failed(format!("{:?}", err));
+        }
+        let gl = gl::Gl::load_with(|s| context.get_proc_address(s) as *const _);
+        // the alpha is only kept if the requested alpha bits were honored
+        let pixel = unsafe { clear_and_read(&gl, [1.0, 0.0, 1.0, 0.5]) };
+        if close_to(pixel, [255, 0, 255, 128]) {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(format!("read {:?} back", pixel))
+        }
+    }
+
+    /// The versions that don't exist are refused by EGL, which the surfaceless
+    /// platform of Mesa provides without a display server.
+    pub fn gl_versions(_: &Harness) -> Outcome {
+        use glutin::CreationError;
+        if let Err(outcome) = surfaceless_or_skip(ContextBuilder::new()) {
+            return outcome;
+        }
+        let invalid = [
+            (Api::OpenGlEs, (4, 0)),
+            (Api::OpenGlEs, (2, 1)),
+            (Api::OpenGlEs, (3, 3)),
+            (Api::OpenGl, (5, 0)),
+            (Api::WebGl, (1, 0)),
+        ];
+        for &(api, version) in &invalid {
+            let request = GlRequest::Specific(api, version);
+            let cb = ContextBuilder::new().with_gl(request);
+            match Context::new_surfaceless(cb) {
+                Err(CreationError::OpenGlVersionNotSupported) => (),
+                Err(err) => {
+                    return Outcome::Failed(format!("{:?}: {}", request, err))
+                }
+                Ok(_) => {
+                    return Outcome::Failed(format!(
+                        "{:?} was created",
+                        request
+                    ))
+                }
+            }
+        }
+        // OpenGL ES 3.1 exists, though the driver may not provide it
+        let request = GlRequest::Specific(Api::OpenGlEs, (3, 1));
+        let cb = ContextBuilder::new().with_gl(request);
+        match Context::new_surfaceless(cb) {
+            Ok(_) | Err(CreationError::OpenGlVersionNotSupported) => {
+                Outcome::Passed
+            }
+            Err(err) => Outcome::Failed(format!("{:?}: {}", request, err)),
+        }
+    }
+
+    pub fn selection_hook(_: &Harness) -> Outcome {
+        use glutin::CreationError;
+        use std::sync::{Arc, Mutex};
+
+        let rejected = ContextBuilder::new().with_selection_hook(|_| None);
+        match Context::new_surfaceless(rejected) {
+            Err(CreationError::NoAvailablePixelFormat) => (),
+            Err(err) => return Outcome::Skipped(err.to_string()),
+            Ok(_) => return Outcome::Failed("the hook was ignored".into()),
+        }
+
+        let given = Arc::new(Mutex::new(Vec::new()));
+        let recorded = given.clone();
+        let cb = ContextBuilder::new()
+            .with_depth_buffer(16)
+            .with_selection_hook(move |formats| {
+                *recorded.lock().unwrap() = formats.to_vec();
+                Some(formats.len() - 1)
+            });
+        if let Err(err) = Context::new_surfaceless(cb) {
+            return Outcome::Failed(err.to_string());
+        }
+        let given = given.lock().unwrap();
+        if given.is_empty() {
+            Outcome::Failed("the hook wasn't called".into())
+        } else if given.iter().any(|format| format.depth_bits < 16) {
+            Outcome::Failed(format!("was given {:?}", *given))
+        } else {
+            Outcome::Passed
+        }
+    }
+
+    /// Saves the format of a context that isn't the best match and requests it
+    /// again.
+    pub fn saved_pixel_format(_: &Harness) -> Outcome {
+        use std::sync::{Arc, Mutex};
+
+        let chosen = Arc::new(Mutex::new(None));
+        let recorded = chosen.clone();
+        let cb = ContextBuilder::new()
+            .with_depth_buffer(16)
+            .with_selection_hook(move |formats| {
+                *recorded.lock().unwrap() = formats.last().cloned();
+                Some(formats.len() - 1)
+            });
+        if let Err(outcome) = surfaceless_or_skip(cb) {
+            return outcome;
+        }
+        let saved = match chosen.lock().unwrap().take() {
+            Some(format) => format,
+            None => return Outcome::Failed("the hook wasn't called".into()),
+        };
+        if saved.native_id.is_none() {
+            return Outcome::Failed("EGL configs have an identifier".into());
+        }
+
+        let cb = ContextBuilder::new().with_saved_pixel_format(&saved);
+        match Context::new_surfaceless(cb) {
+            Ok(_) => Outcome::Passed,
+            Err(err) => Outcome::Failed(format!("{:?}: {}", saved, err)),
+        }
+    }
+
+    /// Filters the configs by their native renderability, which the selection
+    /// hook then sees.
+    pub fn native_renderable(_: &Harness) -> Outcome {
+        use std::sync::{Arc, Mutex};
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let recorded = seen.clone();
+        let cb = ContextBuilder::new()
+            .with_native_renderable(Some(true))
+            .with_x11_visual_type(Some(glutin::X11VisualType::TrueColor))
+            .with_selection_hook(move |formats| {
+                recorded.lock().unwrap().extend_from_slice(formats);
+                Some(0)
+            });
+        if let Err(outcome) = surfaceless_or_skip(cb) {
+            return outcome;
+        }
+        // the visual type only filters the configs of X11 displays
+        let seen = seen.lock().unwrap();
+        match seen.iter().find(|format| {
+            !format.native_renderable || format.x11_visual_type.is_some()
+        }) {
+            Some(format) => {
+                Outcome::Failed(format!("{:?} was offered", format))
+            }
+            None if seen.is_empty() => {
+                Outcome::Failed("the hook wasn't called".into())
+            }
+            None => Outcome::Passed,
+        }
+    }
+
+    /// Makes EGL report an error about a labelled context.
+    pub fn egl_debug(_: &Harness) -> Outcome {
+        use glutin::os::unix::RawHandle;
+        use glutin::os::ContextTraitExt;
+        use glutin::EglMessageType;
+        use std::mem;
+        use std::os::raw::{c_int, c_void};
+        use std::sync::{Arc, Mutex};
+
+        type QueryContextFn = unsafe extern "system" fn(
+            *const c_void,
+            *const c_void,
+            c_int,
+            *mut c_int,
+        ) -> u32;
+
+        let context = match surfaceless_or_skip(ContextBuilder::new()) {
+            Ok(context) => context,
+            Err(outcome) => return outcome,
+        };
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let recorded = messages.clone();
+        let res =
+            glutin::set_egl_debug_callback(EglMessageType::Error, move |m| {
+                recorded.lock().unwrap().push(m.clone())
+            });
+        if let Err(err) = res {
+            return Outcome::Skipped(format!("{:?}", err));
+        }
+        if let Err(err) = context.set_egl_label("surfaceless") {
+            return Outcome::Failed(format!("{:?}", err));
+        }
+
+        // `eglQueryContext` with an invalid attribute raises
+        // `EGL_BAD_ATTRIBUTE`
+        let query = context.get_proc_address("eglQueryContext");
+        let (display, handle) = unsafe {
+            match (context.get_egl_display(), context.raw_handle()) {
+                (Some(display), RawHandle::Egl(handle)) => (display, handle),
+                _ => unreachable!(),
+            }
+        };
+        unsafe {
+            let query: QueryContextFn = mem::transmute(query);
+            let mut value = 0;
+            query(display, handle, 0, &mut value);
+        }
+        let _ = glutin::remove_egl_debug_callback();
+
+        let messages = messages.lock().unwrap();
+        let reported = messages.iter().any(|m| {
+            m.command == "eglQueryContext"
+                && m.error_name() == Some("EGL_BAD_ATTRIBUTE")
+                && m.object_label.as_ref().map(|l| &l[..])
+                    == Some("surfaceless")
+        });
+        if reported {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(format!("reported {:?}", *messages))
+        }
+    }
+
+    /// Reloads EGL between two contexts of the surfaceless platform of Mesa.
+    pub fn reload_symbols(_: &Harness) -> Outcome {
+        let context = match surfaceless_or_skip(ContextBuilder::new()) {
+            Ok(context) => context,
+            Err(outcome) => return outcome,
+        };
+        if unsafe { glutin::reload_symbols() }.is_ok() {
+            return Outcome::Failed("reloaded under a live context".into());
+        }
+        drop(context);
+        if let Err(err) = unsafe { glutin::reload_symbols() } {
+            return Outcome::Failed(err.to_string());
+        }
+
+        let context = match Context::new_surfaceless(ContextBuilder::new()) {
+            Ok(context) => context,
+            Err(err) => return Outcome::Failed(err.to_string()),
+        };
+        if let Err(err) = unsafe { context.make_current() } {
+            return Outcome::Failed(format!("{:?}", err));
+        }
+        let gl = gl::Gl::load_with(|s| context.get_proc_address(s) as *const _);
+        let pixel = unsafe { clear_and_read(&gl, [0.0, 0.0, 1.0, 1.0]) };
+        if close_to(pixel, [0, 0, 255, 255]) {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(format!("read {:?} back", pixel))
+        }
+    }
+}
+
+fn context_sharing(harness: &Harness) -> Outcome {
+    let (first, gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let mut texture = 0;
+    unsafe {
+        gl.GenTextures(1, &mut texture);
+        gl.BindTexture(gl::TEXTURE_2D, texture);
+        gl.Finish();
+    }
+
+    let cb = ContextBuilder::new().with_shared_lists(&first);
+    let (second, gl) = match harness.current(cb) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    if unsafe { gl.IsTexture(texture) } != gl::TRUE {
+        return Outcome::Failed("the texture isn't shared".into());
+    }
+    if second.share_group_id() != first.share_group_id()
+        || second.share_parent() != Some(first.id())
+    {
+        return Outcome::Failed(format!(
+            "wrong share group: {:?} sharing with {:?}",
+            second, first
+        ));
+    }
+    if second.display_token() != first.display_token() {
+        return Outcome::Failed(format!(
+            "shared contexts have the tokens {:?} and {:?}",
+            second.display_token(),
+            first.display_token()
+        ));
+    }
+    Outcome::Passed
+}
+
+fn offline_render(harness: &Harness) -> Outcome {
+    let (context, gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let size = glutin::dpi::PhysicalSize::new(16.0, 8.0);
+    let mut renderer = OfflineRenderer::from_context(context, size, 25.0);
+    let mut frames = Vec::new();
+    let res = unsafe {
+        renderer.render(
+            3,
+            |_, time| {
+                let red = time.index as f32 / 2.0;
+                gl.ClearColor(red, 0.0, 1.0, 1.0);
+                gl.Clear(gl::COLOR_BUFFER_BIT);
+            },
+            |time, frame| frames.push((time, frame)),
+        )
+    };
+    match res {
+        Ok(()) => (),
+        Err(glutin::ContextError::FunctionUnavailable) => {
+            let reason = "framebuffer objects are unavailable";
+            return Outcome::Skipped(reason.into());
+        }
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    }
+
+    let expected = [[0, 0, 255, 255], [128, 0, 255, 255], [255, 0, 255, 255]];
+    for (i, &(time, ref frame)) in frames.iter().enumerate() {
+        if time.index != i as u64
+            || time.time != Duration::from_millis(40 * i as u64)
+        {
+            return Outcome::Failed(format!("frame {} at {:?}", i, time));
+        }
+        let pixel = [
+            frame.pixels[0],
+            frame.pixels[1],
+            frame.pixels[2],
+            frame.pixels[3],
+        ];
+        if frame.pixels.len() != 16 * 8 * 4 || !close_to(pixel, expected[i]) {
+            return Outcome::Failed(format!("frame {}: read {:?}", i, pixel));
+        }
+    }
+    if frames.len() == 3 {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("got {} frames", frames.len()))
+    }
+}
+
+fn gpu_timer(harness: &Harness) -> Outcome {
+    let (context, gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let mut timer = match GpuTimer::new(&context) {
+        Ok(timer) => timer,
+        Err(glutin::ContextError::FunctionUnavailable) => {
+            let reason = "timer queries are unavailable";
+            return Outcome::Skipped(reason.into());
+        }
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    };
+
+    let res = timer.scope(&context, "frame", || unsafe {
+        clear_and_read(&gl, [0.0, 0.0, 1.0, 1.0]);
+    });
+    let res = res.and_then(|()| timer.scope(&context, "second", || ()));
+    if let Err(err) = res {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+    unsafe { gl.Finish() };
+
+    let timings = match timer.poll(&context) {
+        Ok(timings) => timings,
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    };
+    let labels: Vec<_> = timings.iter().map(|t| t.label.as_str()).collect();
+    if labels != ["frame", "second"] || timings[0].end < timings[0].start {
+        return Outcome::Failed(format!("got {:?}", timings));
+    }
+    if timer.end(&context).is_ok() {
+        return Outcome::Failed("ended a scope that never began".into());
+    }
+    match timer.delete(&context) {
+        Ok(()) => Outcome::Passed,
+        Err(err) => Outcome::Failed(format!("{:?}", err)),
+    }
+}
+
+fn destroy(harness: &Harness) -> Outcome {
+    // destroyed while current, which releases it first
+    let (context, _) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    if let Err(err) = context.destroy() {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+
+    let (_context, gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let pixel = unsafe { clear_and_read(&gl, [0.0, 1.0, 0.0, 1.0]) };
+    if pixel == [0, 255, 0, 255] {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("read {:?} after a destroy", pixel))
+    }
+}
+
+fn mixed_apis(harness: &Harness) -> Outcome {
+    let request = |api| GlRequest::Specific(api, (2, 0));
+    let cb = ContextBuilder::new().with_gl(request(Api::OpenGl));
+    let (desktop, desktop_gl) = match harness.current(cb) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let cb = ContextBuilder::new().with_gl(request(Api::OpenGlEs));
+    let (es, es_gl) = match harness.current(cb) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    if desktop.get_api() != Api::OpenGl || es.get_api() != Api::OpenGlEs {
+        return Outcome::Failed(format!(
+            "got {:?} and {:?}",
+            desktop.get_api(),
+            es.get_api()
+        ));
+    }
+
+    // each context renders after the other one was made current
+    let checks = [
+        (&desktop, &desktop_gl, [255, 0, 0, 255]),
+        (&es, &es_gl, [0, 255, 0, 255]),
+    ];
+    for &(context, gl, expected) in &checks {
+        if let Err(err) = unsafe { context.make_current() } {
+            return Outcome::Failed(format!("{:?}", err));
+        }
+        let color = [
+            f32::from(expected[0]) / 255.0,
+            f32::from(expected[1]) / 255.0,
+            0.0,
+            1.0,
+        ];
+        let pixel = unsafe { clear_and_read(gl, color) };
+        if !context.is_current() || !close_to(pixel, expected) {
+            return Outcome::Failed(format!(
+                "{:?} read {:?}",
+                context.get_api(),
+                pixel
+            ));
+        }
+    }
+    Outcome::Passed
+}
+
+fn multisample_resolve(harness: &Harness) -> Outcome {
+    let (context, gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let size = glutin::dpi::PhysicalSize::new(16.0, 16.0);
+    let resolver = match MultisampleResolver::new(&context, 4, size) {
+        Ok(resolver) => resolver,
+        Err(glutin::ContextError::FunctionUnavailable) => {
+            return Outcome::Skipped("framebuffer blits are unavailable".into())
+        }
+        Err(err) => return Outcome::Failed(format!("{:?}", err)),
+    };
+    let mut bound = 0;
+    unsafe {
+        gl.GetIntegerv(gl::FRAMEBUFFER_BINDING, &mut bound);
+        gl.ClearColor(0.0, 0.0, 1.0, 1.0);
+        gl.Clear(gl::COLOR_BUFFER_BIT);
+    }
+    if bound as u32 != resolver.framebuffer() {
+        return Outcome::Failed(format!("framebuffer {} is bound", bound));
+    }
+    if let Err(err) = resolver.resolve(&context) {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+
+    let mut pixel = [0u8; 4];
+    unsafe {
+        gl.BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl.ReadPixels(
+            0,
+            0,
+            1,
+            1,
+            gl::RGBA,
+            gl::UNSIGNED_BYTE,
+            pixel.as_mut_ptr() as *mut _,
+        );
+    }
+    if let Err(err) = resolver.delete(&context) {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+    if close_to(pixel, [0, 0, 255, 255]) {
+        Outcome::Passed
+    } else {
+        Outcome::Failed(format!("read {:?} after the resolve", pixel))
+    }
+}
+
+fn flush(harness: &Harness) -> Outcome {
+    let cb = ContextBuilder::new()
+        .with_release_behavior(glutin::ReleaseBehavior::None);
+    let (context, _gl) = match harness.current(cb) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    if let Err(err) = context.flush().and_then(|_| context.finish()) {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+    context.set_flush_on_release(true);
+    if let Err(err) = unsafe { context.make_not_current() } {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+    match context.flush() {
+        Err(glutin::ContextError::NotCurrent) => Outcome::Passed,
+        res => Outcome::Failed(format!("flushed without a context: {:?}", res)),
+    }
+}
+
+/// A current context stays current, with the size of its window, after a
+/// `resize` that makes it current again. This needs a window, so it is
+/// skipped without a display server.
+fn remake_current_on_resize(harness: &Harness) -> Outcome {
+    harness.with_events_loop(|el| {
+        let cb = ContextBuilder::new().with_remake_current_on_resize(true);
+        let combined = match harness.window_or_skip(el, cb) {
+            Ok(combined) => combined,
+            Err(outcome) => return outcome,
+        };
+        let window = combined.window();
+        let dpi_factor = window.get_hidpi_factor();
+        let size = match window.get_inner_size() {
+            Some(size) => size.to_physical(dpi_factor),
+            None => return Outcome::Skipped("the window is gone".into()),
+        };
+
+        combined.resize(size);
+        if !combined.is_current() {
+            return Outcome::Failed("the context isn't current".into());
+        }
+        let new_size =
+            window.get_inner_size().map(|s| s.to_physical(dpi_factor));
+        if new_size == Some(size) {
+            Outcome::Passed
+        } else {
+            Outcome::Failed(format!("resized to {:?}", new_size))
+        }
+    })
+}
+
+fn current_scoped(harness: &Harness) -> Outcome {
+    let (host, _gl) = match harness.current(ContextBuilder::new()) {
+        Ok(current) => current,
+        Err(outcome) => return outcome,
+    };
+    let library = match harness.context_or_skip(ContextBuilder::new()) {
+        Ok(context) => context,
+        Err(outcome) => return outcome,
+    };
+    {
+        let _guard = match unsafe { library.make_current_scoped() } {
+            Ok(guard) => guard,
+            Err(err) => return Outcome::Failed(format!("{:?}", err)),
+        };
+        if !library.is_current() || host.is_current() {
+            return Outcome::Failed("the guard's context isn't current".into());
+        }
+    }
+    if !host.is_current() {
+        return Outcome::Failed("the previous context wasn't restored".into());
+    }
+
+    if let Err(err) = unsafe { host.make_not_current() } {
+        return Outcome::Failed(format!("{:?}", err));
+    }
+    drop(unsafe { library.make_current_scoped() });
+    if library.is_current() {
+        return Outcome::Failed("the guard's context wasn't released".into());
+    }
+    Outcome::Passed
+}
+
+fn main() {
+    // only extended on Linux and the BSDs
+    #[allow(unused_mut)]
+    let mut tests: Vec<(&str, fn(&Harness) -> Outcome)> = vec![
+        ("clear_readback", clear_readback),
+        ("core_functions", core_functions),
+        ("config_selection", config_selection),
+        ("srgb", srgb),
+        ("swap_interval", swap_interval),
+        ("swap_interval_caps", swap_interval_caps),
+        ("swap_msc", swap_msc),
+        ("wait_for_vsync", wait_for_vsync),
+        ("present_stats", present_stats),
+        ("pbuffer_texture", pbuffer_texture),
+        ("render_buffer", render_buffer),
+        ("pbuffer_limits", pbuffer_limits),
+        ("context_sharing", context_sharing),
+        ("offline_render", offline_render),
+        ("gpu_timer", gpu_timer),
+        ("destroy", destroy),
+        ("mixed_apis", mixed_apis),
+        ("multisample_resolve", multisample_resolve),
+        ("flush", flush),
+        ("remake_current_on_resize", remake_current_on_resize),
+        ("current_scoped", current_scoped),
+    ];
+    #[cfg(any(
+        target_os = "linux",
+        target_os = "dragonfly",
+        target_os = "freebsd",
+        target_os = "netbsd",
+        target_os = "openbsd"
+    ))]
+    tests.extend_from_slice(&[
+        ("surfaceless", unix::surfaceless),
+        ("x11_pixmap", unix::x11_pixmap),
+        ("strict_selection", unix::strict_selection),
+        ("gl_versions", unix::gl_versions),
+        ("selection_hook", unix::selection_hook),
+        ("saved_pixel_format", unix::saved_pixel_format),
+        ("native_renderable", unix::native_renderable),
+        ("egl_debug", unix::egl_debug),
+        ("reload_symbols", unix::reload_symbols),
+    ]);
+
+    let harness = Harness::new();
+    let mut failed = 0;
+    println!("running {} tests", tests.len());
+    for &(name, test) in &tests {
+        match test(&harness) {
+            Outcome::Passed => println!("test {} ... ok", name),
+            Outcome::Skipped(reason) => {
+                println!("test {} ... skipped: {}", name, reason)
+            }
+            Outcome::Failed(reason) => {
+                println!("test {} ... FAILED: {}", name, reason);
+                failed += 1;
+            }
+        }
+    }
+
+    if failed > 0 {
+        println!("{} tests failed", failed);
+        process::exit(1);
+    }
+}